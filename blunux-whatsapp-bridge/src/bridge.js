@@ -36,6 +36,7 @@ class WhatsAppBridge {
         this.ipc = new IpcClient(config.socketPath);
         this.rateLimiter = new RateLimiter(config.maxMessagesPerMinute);
         this.client = null;
+        this._polling = false;
     }
 
     async start() {
@@ -134,12 +135,13 @@ class WhatsAppBridge {
                 timestamp: new Date().toISOString(),
             }, 120_000); // 2-minute timeout for long commands
 
-            const reply = response.body || '(no response)';
-            // WhatsApp limits messages to ~65535 chars; truncate if needed
-            const truncated = reply.length > 4000
-                ? reply.slice(0, 3997) + '...'
-                : reply;
-            await msg.reply(truncated);
+            // The daemon splits long replies: `body` is the first part and
+            // `notifications` carries the rest, in order.
+            await msg.reply(response.body || '(no response)');
+            const rest = Array.isArray(response.notifications) ? response.notifications : [];
+            for (const part of rest) {
+                if (part.body) await msg.reply(part.body);
+            }
         } catch (err) {
             console.error('[bridge] Error forwarding message:', err.message);
             await msg.reply(`Error: ${err.message}`);
@@ -149,36 +151,49 @@ class WhatsAppBridge {
     /**
      * Start polling the daemon every 15 seconds for automation notifications.
      * When the daemon has pending notifications, they are sent via WhatsApp.
+     * If the daemon reports more queued than one batch, poll again right away.
      */
     _startNotificationPoller() {
         const POLL_INTERVAL_MS = 15_000;
+        const MAX_BATCHES_PER_TICK = 20;
 
         const poll = async () => {
-            if (!this.ipc.connected) return;
+            if (!this.ipc.connected || this._polling) return;
+            this._polling = true;
             try {
-                const items = await this.ipc.pollNotifications();
-                for (const item of items) {
-                    const phone = item.to;
-                    const body  = item.body || '';
-                    if (!phone || !body) continue;
-
-                    // Format as WhatsApp JID: strip leading + and append @c.us
-                    const jid = phone.replace(/^\+/, '') + '@c.us';
-                    try {
-                        await this.client.sendMessage(jid, body);
-                        console.log(`[bridge] Notification sent to ${phone}: ${body.slice(0, 60)}...`);
-                    } catch (err) {
-                        console.error(`[bridge] Failed to send notification to ${phone}:`, err.message);
-                    }
+                for (let batch = 0; batch < MAX_BATCHES_PER_TICK; batch++) {
+                    const { items, remaining } = await this.ipc.pollNotifications();
+                    await this._sendNotifications(items);
+                    if (remaining <= 0 || items.length === 0) break;
                 }
             } catch (err) {
                 console.error('[bridge] Notification poll error:', err.message);
+            } finally {
+                this._polling = false;
             }
         };
 
         this._pollTimer = setInterval(poll, POLL_INTERVAL_MS);
     }
 
+    /** Deliver a batch of {to, body} notifications via WhatsApp. */
+    async _sendNotifications(items) {
+        for (const item of items) {
+            const phone = item.to;
+            const body  = item.body || '';
+            if (!phone || !body) continue;
+
+            // Format as WhatsApp JID: strip leading + and append @c.us
+            const jid = phone.replace(/^\+/, '') + '@c.us';
+            try {
+                await this.client.sendMessage(jid, body);
+                console.log(`[bridge] Notification sent to ${phone}: ${body.slice(0, 60)}...`);
+            } catch (err) {
+                console.error(`[bridge] Failed to send notification to ${phone}:`, err.message);
+            }
+        }
+    }
+
     /** Strip WhatsApp suffix and non-digits for whitelist comparison. */
     _normalisePhone(from) {
         return from.replace('@c.us', '').replace(/\D/g, '');
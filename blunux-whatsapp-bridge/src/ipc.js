@@ -127,8 +127,9 @@ class IpcClient extends EventEmitter {
 
     /**
      * Poll the daemon for pending automation notifications.
-     * Returns an array of {to, body} objects (may be empty).
-     * @returns {Promise<Array<{to: string, body: string}>>}
+     * Returns the batch of {to, body} objects (may be empty) and how many
+     * notifications are still queued on the daemon side.
+     * @returns {Promise<{items: Array<{to: string, body: string}>, remaining: number}>}
      */
     async pollNotifications() {
         try {
@@ -136,9 +137,12 @@ class IpcClient extends EventEmitter {
                 { type: 'action', action: 'poll_notifications', from: '__poll__' },
                 10000
             );
-            return Array.isArray(resp.notifications) ? resp.notifications : [];
+            return {
+                items: Array.isArray(resp.notifications) ? resp.notifications : [],
+                remaining: Number.isInteger(resp.remaining) ? resp.remaining : 0,
+            };
         } catch {
-            return [];
+            return { items: [], remaining: 0 };
         }
     }
 
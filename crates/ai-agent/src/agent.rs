@@ -1,48 +1,223 @@
-use std::collections::HashMap;
-use std::io::{self, BufRead, Write};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use crate::config::{AgentConfig, Language};
+use tokio::sync::Notify;
+
+use blunux_config::{BlunuxConfig, Packages};
+use serde::{Deserialize, Serialize};
+
+use crate::capabilities::Capabilities;
+use crate::config::{AgentConfig, Language, ModelId, WhatsAppConfig};
 use crate::error::AgentError;
 use crate::memory::Memory;
+use crate::pending::{is_affirmative, PendingAction, PendingActions, Resolution};
+use crate::prompts::{self, PromptVars};
+use crate::readline::{Completions, LineEditor, HISTORY_FILE};
 use crate::providers::{
-    build_provider, CompletionResult, ContentBlock, Message, Provider, StopReason,
+    build_provider, estimate_tokens, CompletionResult, ContentBlock, Message, Provider, Role, StopReason, Usage,
 };
+use crate::render;
+use crate::spinner::Spinner;
 use crate::strings;
-use crate::tools::{PermissionLevel, SafetyChecker, SafetyResult, ToolRegistry};
+use crate::tools::{
+    packages, PermissionLevel, SafetyChecker, SafetyResult, ToolDefinition, ToolRegistry,
+};
 
 const MAX_TOOL_LOOP_ITERATIONS: usize = 10;
 const MAX_TOKENS: u32 = 4096;
+/// Interactive session saved for `--resume`, relative to the config dir.
+const SESSION_FILE: &str = "last_session.json";
+/// Longest tool result shown by `print_tool_event`.
+const TOOL_EVENT_PREVIEW_CHARS: usize = 500;
+/// Input budget per request; older turns are dropped once a conversation exceeds it.
+const MAX_CONTEXT_TOKENS: u32 = 150_000;
+/// Version of the [`AgentState`] document; bump on incompatible changes.
+pub const STATE_SCHEMA_VERSION: u32 = 1;
 
 pub struct Agent {
     provider: Box<dyn Provider>,
+    model: ModelId,
     tools: ToolRegistry,
     memory: Memory,
     safety: SafetyChecker,
     conversation: Vec<Message>,
-    /// Per-user conversation history for daemon mode (keyed by phone number).
-    user_conversations: HashMap<String, Vec<Message>>,
     lang: Language,
     /// When true, skip interactive confirmation prompts (daemon / WhatsApp mode).
     auto_confirm: bool,
+    /// When false, nothing is printed to stdout for a confirmation and any
+    /// that would prompt is declined (`blunux-ai ask --json`).
+    interactive: bool,
+    /// Observer mode: only `Safe` tool calls run (see [`AgentConfig::read_only`]).
+    read_only: bool,
+    /// Which tools remote turns ([`Agent::chat_as_user`]) may call, and
+    /// whether they confirm by reply.
+    whatsapp: WhatsAppConfig,
+    /// Remote tool calls waiting for a YES reply (`confirm_via_reply`).
+    pending: PendingActions,
+    /// Render interactive replies as styled terminal output.
+    color: bool,
+    /// Shown while a chat turn is in flight; paused for prompts and tool output.
+    spinner: Spinner,
+    /// Blunux `config.toml` this system was installed from, if known.
+    blunux_config: Option<PathBuf>,
+    /// Called after every tool execution (e.g. `--verbose` printing).
+    tool_observers: Vec<ToolObserver>,
+    /// Token usage summed over every completion this agent has made.
+    usage: std::sync::Mutex<Usage>,
+    /// Configured canned prompts (see [`crate::prompts`]).
+    prompts: BTreeMap<String, String>,
+    session_path: PathBuf,
+    /// Interactive prompt history (see [`crate::readline`]).
+    history_path: PathBuf,
+}
+
+/// Where a chat turn came from. Remote turns are held to the `[whatsapp]`
+/// tool policy, and every logged command records its origin.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecutionContext {
+    /// The trusted local CLI: chat, `ask`, `run` and automations.
+    Local,
+    /// A WhatsApp message relayed by the daemon.
+    Remote { phone: String },
+}
+
+impl std::fmt::Display for ExecutionContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Local => f.write_str("local"),
+            Self::Remote { phone } => write!(f, "remote {phone}"),
+        }
+    }
+}
+
+/// A finished tool call, as reported to the agent's tool observer.
+pub struct ToolEvent<'a> {
+    pub name: &'a str,
+    pub input: &'a serde_json::Value,
+    pub result: &'a str,
+    pub is_error: bool,
+}
+
+pub type ToolObserver = Box<dyn Fn(&ToolEvent) + Send + Sync>;
+
+/// On-disk form of the interactive conversation restored by `--resume`.
+#[derive(Serialize, Deserialize)]
+struct SavedSession {
+    conversation: Vec<Message>,
+    usage: Usage,
+}
+
+/// Result of one scripted turn, printed by `blunux-ai ask --json`.
+#[derive(Debug, Clone, Serialize)]
+pub struct AskReport {
+    pub reply: String,
+    /// Names of the tools called during the turn, in call order.
+    pub tools_used: Vec<String>,
+    /// Tokens spent on this turn alone.
+    pub usage: Usage,
+}
+
+/// Full agent state for external UIs, from [`Agent::export_state`].
+/// Serializes as a stable JSON document tagged with `schema_version`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentState {
+    pub schema_version: u32,
+    /// API name of the model the conversation was held with.
+    pub model: String,
+    pub conversation: Vec<Message>,
+    pub usage: Usage,
 }
 
 impl Agent {
     pub fn new(config: &AgentConfig) -> Result<Self, AgentError> {
         let provider = build_provider(config).map_err(AgentError::Config)?;
-        let tools = ToolRegistry::default_tools();
-        let memory = Memory::new(config.config_dir.clone());
-        let safety = SafetyChecker::new();
+        let agent = Self::with_provider(config, provider);
+        // Old daily logs are only tidied when an agent starts, never in the background
+        let _ = agent.memory.apply_retention(&config.memory);
+        Ok(agent)
+    }
 
-        Ok(Self {
+    /// Create an agent around an already-constructed provider.
+    pub fn with_provider(config: &AgentConfig, provider: Box<dyn Provider>) -> Self {
+        Self {
             provider,
-            tools,
-            memory,
-            safety,
+            model: config.model.clone(),
+            tools: ToolRegistry::for_agent(&config.config_dir),
+            memory: Memory::new(config.config_dir.clone())
+                .with_carry_over(config.memory.carry_over_minutes),
+            safety: SafetyChecker::new(),
             conversation: Vec::new(),
-            user_conversations: HashMap::new(),
             lang: config.language.clone(),
             auto_confirm: false,
-        })
+            interactive: true,
+            read_only: config.read_only,
+            whatsapp: config.whatsapp.clone(),
+            pending: PendingActions::new(Duration::from_secs(config.whatsapp.confirm_timeout_secs)),
+            color: false,
+            spinner: Spinner::new(strings::thinking(&config.language)),
+            blunux_config: None,
+            tool_observers: Vec::new(),
+            usage: std::sync::Mutex::new(Usage::default()),
+            prompts: config.prompts.clone(),
+            session_path: config.config_dir.join(SESSION_FILE),
+            history_path: config.config_dir.join(HISTORY_FILE),
+        }
+    }
+
+    /// Model this agent's provider was built for.
+    pub fn model(&self) -> &ModelId {
+        &self.model
+    }
+
+    /// The `[whatsapp]` settings this agent was built with.
+    pub fn whatsapp(&self) -> &WhatsAppConfig {
+        &self.whatsapp
+    }
+
+    /// Render interactive replies with ANSI styling (see [`render::render_markdown`]).
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Report every tool call and its result to `observer` as the loop runs.
+    pub fn with_tool_observer(
+        mut self,
+        observer: impl Fn(&ToolEvent) + Send + Sync + 'static,
+    ) -> Self {
+        self.tool_observers.push(Box::new(observer));
+        self
+    }
+
+    /// With `false`, decline confirmations instead of prompting for them and
+    /// keep stdout free for the reply (`blunux-ai ask --json`).
+    pub fn with_interactive(mut self, interactive: bool) -> Self {
+        self.interactive = interactive;
+        self
+    }
+
+    /// Point the agent at the blunux `config.toml` used to install this system.
+    /// Adds its identity to the system prompt, its recorded hardware to
+    /// SYSTEM.md, and enables `check_blunux_packages`.
+    pub fn with_blunux_config(mut self, path: &Path) -> Self {
+        self.tools
+            .register(Box::new(packages::CheckBlunuxPackagesTool::new(
+                path.to_path_buf(),
+            )))
+            .expect("with_blunux_config is called at most once");
+        self.memory = self.memory.with_blunux_config(path);
+        self.blunux_config = Some(path.to_path_buf());
+        self
+    }
+
+    /// Run tools that need confirmation without asking (`blunux-ai ask --yes`).
+    pub fn with_auto_confirm(mut self, auto_confirm: bool) -> Self {
+        self.auto_confirm = auto_confirm;
+        self
     }
 
     /// Create an agent configured for daemon / WhatsApp mode (auto-confirms all prompts).
@@ -53,15 +228,161 @@ impl Agent {
     }
 
     pub async fn chat(&mut self, user_message: &str) -> Result<String, AgentError> {
+        let mut conversation = std::mem::take(&mut self.conversation);
+        let result = self
+            .run_turn(&mut conversation, user_message, &ExecutionContext::Local)
+            .await;
+        self.conversation = conversation;
+        result
+    }
+
+    /// Run one chat turn, abandoning it if `cancel` completes first (Ctrl+C).
+    /// A cancelled turn leaves the conversation exactly as it was before.
+    pub async fn chat_cancellable(
+        &mut self,
+        user_message: &str,
+        cancel: impl std::future::Future<Output = ()>,
+    ) -> Result<String, AgentError> {
+        let mut conversation = std::mem::take(&mut self.conversation);
+        let before = conversation.len();
+        let finished = tokio::select! {
+            result = self.chat_with(&mut conversation, user_message) => Some(result),
+            () = cancel => None,
+        };
+        let result = finished.unwrap_or_else(|| {
+            conversation.truncate(before);
+            Err(AgentError::UserCancelled)
+        });
+        self.conversation = conversation;
+        result
+    }
+
+    /// Run one chat turn against a caller-owned conversation history.
+    /// Takes `&self`, so independent conversations can run concurrently.
+    pub async fn chat_with(
+        &self,
+        conversation: &mut Vec<Message>,
+        user_message: &str,
+    ) -> Result<String, AgentError> {
+        self.run_turn(conversation, user_message, &ExecutionContext::Local)
+            .await
+    }
+
+    /// [`Agent::chat_with`] for a message from the remote (WhatsApp) user
+    /// `phone`: tool calls are also checked against the `[whatsapp]` tool
+    /// policy, and logged as coming from `phone`.
+    ///
+    /// With `confirm_via_reply`, a tool call that needs confirmation is
+    /// parked and the reply ends with a "reply YES to run" prompt; a YES as
+    /// the user's next message runs it.
+    pub async fn chat_as_user(
+        &self,
+        conversation: &mut Vec<Message>,
+        phone: &str,
+        user_message: &str,
+    ) -> Result<String, AgentError> {
+        let context = ExecutionContext::Remote {
+            phone: phone.to_string(),
+        };
+        let log = |status: &str, command: &str| {
+            let _ = self.memory.log_command(status, command, &context.to_string());
+        };
+
+        match self.pending.resolve(phone, user_message, Instant::now()) {
+            Resolution::NoneWaiting => {}
+            Resolution::Confirmed(action) => {
+                let reply = self.run_confirmed(action, &context).await;
+                conversation.push(Message::user(user_message));
+                conversation.push(Message::assistant_text(reply.clone()));
+                let _ = self.memory.append_today(user_message);
+                let _ = self.memory.append_today(&format!("AI: {reply}"));
+                return Ok(reply);
+            }
+            Resolution::Expired(action) => {
+                log("EXPIRED", &action.preview);
+                if is_affirmative(user_message) {
+                    return Ok(strings::remote_confirm_expired(&self.lang).to_string());
+                }
+            }
+            Resolution::Declined(action) => log("CANCELLED", &action.preview),
+        }
+
+        let mut reply = self.run_turn(conversation, user_message, &context).await?;
+        if let Some(preview) = self.pending.preview(phone) {
+            reply.push_str("\n\n");
+            reply.push_str(&strings::remote_confirm_prompt(&self.lang, &preview));
+        }
+        Ok(reply)
+    }
+
+    /// Run a parked tool call the user has now confirmed. Safety checks
+    /// already passed when it was parked.
+    async fn run_confirmed(&self, action: PendingAction, context: &ExecutionContext) -> String {
+        let origin = context.to_string();
+        let Some(tool) = self.tools.get(&action.tool) else {
+            return format!("Unknown tool: {}", action.tool);
+        };
+        match tool.execute(action.input).await {
+            Ok(output) => {
+                let _ = self.memory.log_command("CONFIRMED", &action.preview, &origin);
+                output
+            }
+            Err(e) => {
+                let _ = self.memory.log_command("FAILED", &action.preview, &origin);
+                format!("Error: {e}")
+            }
+        }
+    }
+
+    /// With `confirm_via_reply`, park a remote tool call that needs
+    /// confirmation instead of auto-confirming it. Returns the tool result
+    /// telling the model it hasn't run yet, or that it was refused because
+    /// another call is already waiting.
+    fn defer_to_reply(
+        &self,
+        context: &ExecutionContext,
+        tool_use_id: &str,
+        name: &str,
+        input: &serde_json::Value,
+        preview: &str,
+    ) -> Option<ContentBlock> {
+        let ExecutionContext::Remote { phone } = context else {
+            return None;
+        };
+        if !self.whatsapp.confirm_via_reply {
+            return None;
+        }
+        let action = PendingAction::new(name, input.clone(), preview.to_string(), Instant::now());
+        if !self.pending.stash(phone, action) {
+            // One confirmation per reply: the first call stays the one on offer
+            let _ = self.memory.log_command("CANCELLED", preview, &context.to_string());
+            return Some(ContentBlock::tool_result_err(
+                tool_use_id,
+                strings::remote_confirm_busy(&self.lang),
+            ));
+        }
+        let _ = self.memory.log_command("PENDING", preview, &context.to_string());
+        Some(ContentBlock::tool_result_ok(
+            tool_use_id,
+            strings::remote_confirm_pending(&self.lang),
+        ))
+    }
+
+    async fn run_turn(
+        &self,
+        conversation: &mut Vec<Message>,
+        user_message: &str,
+        context: &ExecutionContext,
+    ) -> Result<String, AgentError> {
         // Add user message
-        self.conversation.push(Message::user(user_message));
+        conversation.push(Message::user(user_message));
 
         // Log to daily memory
         let _ = self.memory.append_today(user_message);
 
         // Build system prompt
         let system_prompt = self.build_system_prompt()?;
-        let tool_defs = self.tools.definitions();
+        let tool_defs = self.tool_definitions();
 
         // Tool-use loop
         let mut iterations = 0;
@@ -71,15 +392,18 @@ impl Agent {
                 break;
             }
 
+            self.trim_context(&system_prompt, conversation, &tool_defs).await;
+
             let result = self
                 .provider
-                .complete(&system_prompt, &self.conversation, &tool_defs, MAX_TOKENS)
+                .complete(&system_prompt, conversation, &tool_defs, MAX_TOKENS)
                 .await
                 .map_err(AgentError::Provider)?;
+            self.usage.lock().unwrap().add(&result.usage);
 
             // Add assistant response to conversation
-            self.conversation.push(Message {
-                role: crate::providers::Role::Assistant,
+            conversation.push(Message {
+                role: Role::Assistant,
                 content: result.content.clone(),
             });
 
@@ -90,9 +414,9 @@ impl Agent {
                     return Ok(text);
                 }
                 StopReason::ToolUse => {
-                    let tool_results = self.process_tool_calls(&result).await?;
+                    let tool_results = self.process_tool_calls(&result, context).await?;
                     if !tool_results.is_empty() {
-                        self.conversation.push(Message::tool_results(tool_results));
+                        conversation.push(Message::tool_results(tool_results));
                     }
                     // Continue loop for next completion
                 }
@@ -100,8 +424,7 @@ impl Agent {
         }
 
         // If we exhausted iterations, return whatever text we have
-        let last_text = self
-            .conversation
+        let last_text = conversation
             .last()
             .map(|m| {
                 m.content
@@ -118,7 +441,74 @@ impl Agent {
         Ok(last_text)
     }
 
-    pub async fn run_interactive(&mut self) -> Result<(), AgentError> {
+    /// Total token usage so far, including any restored session.
+    pub fn usage(&self) -> Usage {
+        self.usage.lock().unwrap().clone()
+    }
+
+    /// Restore the conversation saved by [`save_session`](Self::save_session).
+    /// Returns the number of restored messages; 0 if there was no saved session.
+    pub fn resume_session(&mut self) -> Result<usize, AgentError> {
+        let content = match std::fs::read_to_string(&self.session_path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(AgentError::Io(e)),
+        };
+        let session: SavedSession = serde_json::from_str(&content)
+            .map_err(|e| AgentError::Io(io::Error::new(io::ErrorKind::InvalidData, e)))?;
+        self.conversation = session.conversation;
+        *self.usage.lock().unwrap() = session.usage;
+        Ok(self.conversation.len())
+    }
+
+    /// Restore the saved session if `resume` is set, as the interactive loop
+    /// does on startup. Returns the number of restored messages.
+    fn start_session(&mut self, resume: bool) -> Result<usize, AgentError> {
+        if resume {
+            self.resume_session()
+        } else {
+            Ok(0)
+        }
+    }
+
+    /// Write the interactive conversation and token totals for a later `--resume`.
+    pub fn save_session(&self) -> Result<(), AgentError> {
+        let session = SavedSession {
+            conversation: self.conversation.clone(),
+            usage: self.usage(),
+        };
+        let json = serde_json::to_string(&session)
+            .map_err(|e| AgentError::Io(io::Error::new(io::ErrorKind::InvalidData, e)))?;
+        blunux_config::write_atomic(&self.session_path, json).map_err(AgentError::Io)
+    }
+
+    /// Snapshot the conversation, token totals and model as an [`AgentState`].
+    pub fn export_state(&self) -> AgentState {
+        AgentState {
+            schema_version: STATE_SCHEMA_VERSION,
+            model: self.model.api_name().to_string(),
+            conversation: self.conversation.clone(),
+            usage: self.usage(),
+        }
+    }
+
+    /// Replace the conversation and token totals with `state`. The agent keeps
+    /// its own provider and model; `state.model` is informational.
+    pub fn import_state(&mut self, state: AgentState) -> Result<(), AgentError> {
+        if state.schema_version != STATE_SCHEMA_VERSION {
+            return Err(AgentError::UnsupportedStateVersion {
+                found: state.schema_version,
+                expected: STATE_SCHEMA_VERSION,
+            });
+        }
+        self.conversation = state.conversation;
+        *self.usage.lock().unwrap() = state.usage;
+        Ok(())
+    }
+
+    /// Run the interactive chat loop. With `resume`, the previous session is
+    /// restored first and the conversation is saved after every turn.
+    pub async fn run_interactive(&mut self, resume: bool) -> Result<(), AgentError> {
         // Refresh system info on startup
         let _ = self.memory.refresh_system_info();
 
@@ -129,43 +519,84 @@ impl Agent {
             env!("CARGO_PKG_VERSION")
         );
         println!(
-            "   {} ({}) | {}",
+            "   {} (AI Agent) | {}",
             self.provider.name(),
-            "AI Agent",
             strings::exit_hint(&self.lang)
         );
+        match self.start_session(resume) {
+            Ok(0) => {}
+            Ok(n) => println!("   {}", strings::session_resumed(&self.lang, n)),
+            Err(e) => println!("   {}: {e}", strings::error_prefix(&self.lang)),
+        }
         println!();
 
-        let stdin = io::stdin();
-        let mut stdout = io::stdout();
+        // Ctrl+C cancels the turn in flight; at the prompt it exits.
+        let in_turn = Arc::new(AtomicBool::new(false));
+        let interrupt = Arc::new(Notify::new());
+        {
+            let in_turn = Arc::clone(&in_turn);
+            let interrupt = Arc::clone(&interrupt);
+            let lang = self.lang.clone();
+            tokio::spawn(async move {
+                while tokio::signal::ctrl_c().await.is_ok() {
+                    if in_turn.load(Ordering::SeqCst) {
+                        interrupt.notify_waiters();
+                    } else {
+                        println!("\n\n  {}", strings::goodbye(&lang));
+                        std::process::exit(130);
+                    }
+                }
+            });
+        }
 
-        loop {
-            // Prompt
-            print!("{}: ", strings::prompt(&self.lang));
-            stdout.flush().map_err(AgentError::Io)?;
-
-            // Read line
-            let mut input = String::new();
-            match stdin.lock().read_line(&mut input) {
-                Ok(0) => break, // EOF
-                Ok(_) => {}
-                Err(_) => break,
-            }
+        let mut editor = LineEditor::new(
+            self.history_path.clone(),
+            Completions::new(prompts::names(&self.prompts)),
+        )
+            .map_err(|e| AgentError::Io(io::Error::other(e)))?;
+        let prompt = format!("{}: ", strings::prompt(&self.lang));
 
-            let input = input.trim();
+        // EOF (Ctrl+D) or Ctrl+C at the prompt ends the session
+        while let Some(line) = editor.read_line(&prompt) {
+            let input = line.trim();
             if input.is_empty() {
                 continue;
             }
+            editor.remember(input);
+            // `/run <name>` sends a canned prompt instead
+            let input = match input.strip_prefix("/run") {
+                Some(name) if name.is_empty() || name.starts_with(' ') => {
+                    match self.expand_prompt(name.trim()) {
+                        Ok(prompt) => {
+                            println!("  → {prompt}");
+                            prompt
+                        }
+                        Err(e) => {
+                            println!("\n  {}: {e}\n", strings::error_prefix(&self.lang));
+                            continue;
+                        }
+                    }
+                }
+                _ => input.to_string(),
+            };
 
-            // Thinking indicator
-            print!("\n  {} ", strings::thinking(&self.lang));
-            stdout.flush().map_err(AgentError::Io)?;
+            println!();
+            self.spinner.start();
+            in_turn.store(true, Ordering::SeqCst);
+            let reply = self.chat_cancellable(&input, interrupt.notified()).await;
+            in_turn.store(false, Ordering::SeqCst);
+            self.spinner.stop();
 
-            match self.chat(input).await {
+            match reply {
                 Ok(response) => {
-                    // Clear thinking line and print response
-                    print!("\r");
-                    println!("\nAI: {response}\n");
+                    if self.color {
+                        println!("\nAI: {}\n", render::render_markdown(&response));
+                    } else {
+                        println!("\nAI: {response}\n");
+                    }
+                    if resume {
+                        let _ = self.save_session();
+                    }
                 }
                 Err(AgentError::UserCancelled) => {
                     println!("\n  {}\n", strings::cancelled(&self.lang));
@@ -179,6 +610,9 @@ impl Agent {
             }
         }
 
+        if resume {
+            let _ = self.save_session();
+        }
         println!("\n  {}", strings::goodbye(&self.lang));
         Ok(())
     }
@@ -187,46 +621,94 @@ impl Agent {
         self.conversation.clear();
     }
 
-    /// Clear the stored conversation history for a specific user (daemon mode).
-    pub fn reset_user_conversation(&mut self, phone: &str) {
-        self.user_conversations.remove(phone);
+    /// Drop the oldest turns until the request fits in `MAX_CONTEXT_TOKENS`.
+    /// Whole turns are removed so the history always starts at a user message
+    /// and tool results are never separated from their tool calls. The latest
+    /// turn, the one being answered, is always kept.
+    ///
+    /// This runs before every completion, so the local estimate comes first.
+    /// Only when it is over half the budget is the provider asked for a
+    /// count, which may be a request of its own; each dropped turn is then
+    /// subtracted by its share of the estimate. If counting fails, the
+    /// estimate is used instead of failing the turn.
+    async fn trim_context(
+        &self,
+        system_prompt: &str,
+        conversation: &mut Vec<Message>,
+        tools: &[ToolDefinition],
+    ) {
+        let estimate = estimate_tokens(system_prompt, conversation, tools);
+        // The estimate runs low for Korean text, hence the wide margin
+        if estimate <= MAX_CONTEXT_TOKENS / 2 {
+            return;
+        }
+        let mut tokens = self
+            .provider
+            .count_tokens(system_prompt, conversation, tools)
+            .await
+            .unwrap_or(estimate);
+        let estimated = u64::from(estimate.max(1));
+        while tokens > MAX_CONTEXT_TOKENS {
+            // Only the latest turn left: nothing more can go
+            let Some(end) = first_turn_len(conversation) else {
+                break;
+            };
+            let dropped = u64::from(estimate_tokens("", &conversation[..end], &[]));
+            let share = (u64::from(tokens) * dropped / estimated).max(1);
+            tokens = tokens.saturating_sub(share.try_into().unwrap_or(u32::MAX));
+            drop_oldest_turn(conversation);
+        }
     }
 
-    /// Process a message on behalf of a specific user (daemon / WhatsApp mode).
-    /// Each phone number maintains its own conversation history.
-    pub async fn chat_as_user(
-        &mut self,
-        phone: &str,
-        user_message: &str,
-    ) -> Result<String, AgentError> {
-        // Restore per-user conversation
-        let mut conv = self
-            .user_conversations
-            .remove(phone)
-            .unwrap_or_default();
-
-        // Swap in the user's conversation
-        std::mem::swap(&mut self.conversation, &mut conv);
-
-        let result = self.chat(user_message).await;
+    /// Run a scheduled automation action without a user phone number.
+    /// The action string is treated as a system-initiated instruction to the AI;
+    /// the reply is returned as the notification body. `/run <name>` uses a
+    /// canned prompt as the action.
+    pub async fn run_automation(&self, action: &str) -> Result<String, AgentError> {
+        let action = match action.strip_prefix("/run ") {
+            Some(name) => self.expand_prompt(name.trim())?,
+            None => action.to_string(),
+        };
+        // Use a fresh isolated conversation so automations don't pollute
+        // any active user conversation history.
+        let mut conversation = Vec::new();
+        self.chat_with(&mut conversation, &action).await
+    }
 
-        // Swap back and store
-        std::mem::swap(&mut self.conversation, &mut conv);
-        self.user_conversations.insert(phone.to_string(), conv);
+    /// The canned prompt `name`, expanded for today and this machine.
+    pub fn expand_prompt(&self, name: &str) -> Result<String, AgentError> {
+        prompts::render(&self.prompts, &self.lang, name, &PromptVars::current())
+            .map_err(AgentError::Config)
+    }
 
-        result
+    /// Answer a single scripted prompt (`blunux-ai ask`) in a fresh
+    /// conversation that is not kept afterwards.
+    pub async fn ask(&self, prompt: &str) -> Result<String, AgentError> {
+        self.chat_with(&mut Vec::new(), prompt).await
     }
 
-    /// Run a scheduled automation action without a user phone number.
-    /// The action string is treated as a system-initiated instruction to the AI;
-    /// the reply is returned as the notification body.
-    pub async fn run_automation(&mut self, action: &str) -> Result<String, AgentError> {
-        // Use a temporary isolated conversation so automations don't pollute
-        // any active user conversation history.
-        let saved = std::mem::take(&mut self.conversation);
-        let result = self.chat(action).await;
-        self.conversation = saved;
-        result
+    /// Like [`ask`](Self::ask), also reporting the tools called and the
+    /// tokens used.
+    pub async fn ask_report(&mut self, prompt: &str) -> Result<AskReport, AgentError> {
+        let tools_used = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = std::sync::Arc::clone(&tools_used);
+        self.tool_observers.push(Box::new(move |event: &ToolEvent| {
+            sink.lock().unwrap().push(event.name.to_string());
+        }));
+        let before = self.usage();
+        let reply = self.chat_with(&mut Vec::new(), prompt).await;
+        let after = self.usage();
+        self.tool_observers.pop();
+
+        let tools_used = std::mem::take(&mut *tools_used.lock().unwrap());
+        Ok(AskReport {
+            reply: reply?,
+            tools_used,
+            usage: Usage {
+                input_tokens: after.input_tokens.saturating_sub(before.input_tokens),
+                output_tokens: after.output_tokens.saturating_sub(before.output_tokens),
+            },
+        })
     }
 
     fn build_system_prompt(&self) -> Result<String, AgentError> {
@@ -237,36 +719,83 @@ impl Agent {
             Language::English => "Respond in English.",
         };
 
-        let tool_names: Vec<String> = self.tools.definitions().iter().map(|t| t.name.clone()).collect();
+        // Without tool support the model can only advise, so don't promise tools
+        let (tools_line, first_rule) = if self.provider.supports_tools() {
+            let tool_names: Vec<String> =
+                self.tools.definitions().iter().map(|t| t.name.clone()).collect();
+            let mut tools_line = format!("Available tools: {}\n", tool_names.join(", "));
+            let missing = Capabilities::get().missing();
+            if !missing.is_empty() {
+                tools_line.push_str(&format!("Not installed here: {}\n", missing.join(", ")));
+            }
+            (
+                tools_line,
+                "- Use the provided tools to execute system commands",
+            )
+        } else {
+            (
+                String::new(),
+                "- You cannot run commands yourself; describe the exact commands for the user to run, step by step",
+            )
+        };
+
+        let identity = self
+            .blunux_config
+            .as_deref()
+            .and_then(install_identity)
+            .unwrap_or_default();
 
         Ok(format!(
             "You are Blunux AI Agent, a Linux system management assistant for Blunux (Arch-based).\n\
              You help users manage their system using natural language.\n\
              {lang_instruction}\n\
              \n\
-             Available tools: {tool_list}\n\
+             {tools_line}\
              Safe mode: enabled\n\
              \n\
              Rules:\n\
-             - Use the provided tools to execute system commands\n\
+             {first_rule}\n\
              - Explain what you're doing before executing commands\n\
              - For package names, use the exact Arch Linux / AUR package name\n\
              - Never run destructive commands without user confirmation\n\
              - Report results clearly and concisely\n\
              \n\
+             {identity}\
              {memory_ctx}",
-            tool_list = tool_names.join(", "),
         ))
     }
 
+    /// Tools offered to the provider; empty if it can't call them.
+    fn tool_definitions(&self) -> Vec<ToolDefinition> {
+        if self.provider.supports_tools() {
+            self.tools.definitions()
+        } else {
+            Vec::new()
+        }
+    }
+
     async fn process_tool_calls(
         &self,
         result: &CompletionResult,
+        context: &ExecutionContext,
     ) -> Result<Vec<ContentBlock>, AgentError> {
         let mut tool_results = Vec::new();
 
         for (id, name, input) in result.tool_uses() {
-            let tool_result = self.execute_tool(id, name, input.clone()).await?;
+            let tool_result = self.execute_tool(id, name, input.clone(), context).await?;
+            if let ContentBlock::ToolResult { content, is_error, .. } = &tool_result {
+                let event = ToolEvent {
+                    name,
+                    input,
+                    result: content,
+                    is_error: *is_error,
+                };
+                self.with_spinner_paused(|| {
+                    for observer in &self.tool_observers {
+                        observer(&event);
+                    }
+                });
+            }
             tool_results.push(tool_result);
         }
 
@@ -278,15 +807,15 @@ impl Agent {
         tool_use_id: &str,
         name: &str,
         input: serde_json::Value,
+        context: &ExecutionContext,
     ) -> Result<ContentBlock, AgentError> {
         let tool = match self.tools.get(name) {
             Some(t) => t,
             None => {
-                return Ok(ContentBlock::ToolResult {
-                    tool_use_id: tool_use_id.to_string(),
-                    content: format!("Unknown tool: {name}"),
-                    is_error: true,
-                });
+                return Ok(ContentBlock::tool_result_err(
+                    tool_use_id,
+                    format!("Unknown tool: {name}"),
+                ));
             }
         };
 
@@ -297,8 +826,39 @@ impl Agent {
             None
         };
 
+        let log = |status: &str, command: &str| {
+            let _ = self.memory.log_command(status, command, &context.to_string());
+        };
+
+        // Remote users are limited to the [whatsapp] tool policy
+        let remote = matches!(context, ExecutionContext::Remote { .. });
+        if remote && !self.whatsapp.permits_tool(name) {
+            log("BLOCKED", name);
+            return Ok(ContentBlock::tool_result_err(
+                tool_use_id,
+                strings::remote_tool_blocked(&self.lang, name),
+            ));
+        }
+
+        // Read-only mode blocks anything that isn't Safe, before any prompt
+        let permission = tool.permission_for(&input);
+        if self.read_only && permission != PermissionLevel::Safe {
+            log("BLOCKED", name);
+            return Ok(ContentBlock::tool_result_err(
+                tool_use_id,
+                strings::read_only_blocked(&self.lang),
+            ));
+        }
+
+        // Memory is shared by every session, so remote writes to it are confirmed
+        let permission = if remote && tool.writes_memory() && permission == PermissionLevel::Safe {
+            PermissionLevel::RequiresConfirmation
+        } else {
+            permission
+        };
+
         // Check permission level
-        match tool.permission_level() {
+        match permission {
             PermissionLevel::Safe => {
                 // Auto-execute
             }
@@ -306,54 +866,64 @@ impl Agent {
                 // Check safety for run_command specifically
                 if let Some(ref cmd) = command_str {
                     match self.safety.check(cmd) {
-                        SafetyResult::Blocked { reason } => {
-                            let _ = self.memory.log_command("BLOCKED", cmd);
-                            return Ok(ContentBlock::ToolResult {
-                                tool_use_id: tool_use_id.to_string(),
-                                content: format!(
-                                    "{}: {reason}",
-                                    strings::blocked(&self.lang)
-                                ),
-                                is_error: true,
-                            });
+                        SafetyResult::Blocked { reason, suggestion } => {
+                            log("BLOCKED", cmd);
+                            let mut content = format!(
+                                "{}: {}",
+                                strings::blocked(&self.lang),
+                                strings::safety_reason(&self.lang, reason)
+                            );
+                            if let Some(alternative) = suggestion {
+                                content.push('\n');
+                                content.push_str(strings::safe_alternative(&self.lang, alternative));
+                            }
+                            return Ok(ContentBlock::tool_result_err(tool_use_id, content));
                         }
                         SafetyResult::RequiresConfirmation { reason } => {
+                            if let Some(deferred) =
+                                self.defer_to_reply(context, tool_use_id, name, &input, cmd)
+                            {
+                                return Ok(deferred);
+                            }
                             let description =
                                 strings::confirm_command(&self.lang, cmd);
-                            println!("\n  {description}");
-                            println!("  ({reason})");
-                            if !self.prompt_confirmation() {
-                                let _ = self.memory.log_command("CANCELLED", cmd);
-                                return Ok(ContentBlock::ToolResult {
-                                    tool_use_id: tool_use_id.to_string(),
-                                    content: strings::cancelled(&self.lang).to_string(),
-                                    is_error: false,
-                                });
+                            let reason = strings::safety_reason(&self.lang, reason);
+                            let confirmed = self.confirm(&description, Some(reason));
+                            if !confirmed {
+                                log("CANCELLED", cmd);
+                                return Ok(ContentBlock::tool_result_ok(
+                                    tool_use_id,
+                                    strings::cancelled(&self.lang),
+                                ));
                             }
                         }
                         SafetyResult::Safe => {}
                     }
                 } else {
                     // Non-run_command tool requiring confirmation
+                    let preview = format!("{name} {input}");
+                    if let Some(deferred) =
+                        self.defer_to_reply(context, tool_use_id, name, &input, &preview)
+                    {
+                        return Ok(deferred);
+                    }
                     let description = strings::tool_executing(&self.lang, name);
-                    println!("\n  {description}");
-                    if !self.prompt_confirmation() {
-                        let _ = self.memory.log_command("CANCELLED", name);
-                        return Ok(ContentBlock::ToolResult {
-                            tool_use_id: tool_use_id.to_string(),
-                            content: strings::cancelled(&self.lang).to_string(),
-                            is_error: false,
-                        });
+                    let confirmed = self.confirm(&description, None);
+                    if !confirmed {
+                        log("CANCELLED", name);
+                        return Ok(ContentBlock::tool_result_ok(
+                            tool_use_id,
+                            strings::cancelled(&self.lang),
+                        ));
                     }
                 }
             }
             PermissionLevel::Blocked => {
-                let _ = self.memory.log_command("BLOCKED", name);
-                return Ok(ContentBlock::ToolResult {
-                    tool_use_id: tool_use_id.to_string(),
-                    content: strings::blocked(&self.lang).to_string(),
-                    is_error: true,
-                });
+                log("BLOCKED", name);
+                return Ok(ContentBlock::tool_result_err(
+                    tool_use_id,
+                    strings::blocked(&self.lang),
+                ));
             }
         }
 
@@ -361,29 +931,47 @@ impl Agent {
         let log_cmd = command_str.as_deref().unwrap_or(name);
         match tool.execute(input).await {
             Ok(output) => {
-                let status = if tool.permission_level() == PermissionLevel::Safe {
+                let status = if permission == PermissionLevel::Safe {
                     "SAFE"
                 } else {
                     "CONFIRMED"
                 };
-                let _ = self.memory.log_command(status, log_cmd);
-                Ok(ContentBlock::ToolResult {
-                    tool_use_id: tool_use_id.to_string(),
-                    content: output,
-                    is_error: false,
-                })
+                log(status, log_cmd);
+                Ok(ContentBlock::tool_result_ok(tool_use_id, output))
             }
             Err(e) => {
-                let _ = self.memory.log_command("FAILED", log_cmd);
-                Ok(ContentBlock::ToolResult {
-                    tool_use_id: tool_use_id.to_string(),
-                    content: format!("Error: {e}"),
-                    is_error: true,
-                })
+                log("FAILED", log_cmd);
+                Ok(ContentBlock::tool_result_err(tool_use_id, format!("Error: {e}")))
             }
         }
     }
 
+    /// Run `f` with the spinner line erased, restarting it afterwards if it was running.
+    fn with_spinner_paused<T>(&self, f: impl FnOnce() -> T) -> T {
+        let was_running = self.spinner.stop();
+        let result = f();
+        if was_running {
+            self.spinner.start();
+        }
+        result
+    }
+
+    /// Show `description` (and `reason`) for a tool call and ask whether to
+    /// run it. Non-interactive agents print nothing and only run it with
+    /// auto-confirm.
+    fn confirm(&self, description: &str, reason: Option<&str>) -> bool {
+        if !self.interactive {
+            return self.auto_confirm;
+        }
+        self.with_spinner_paused(|| {
+            println!("\n  {description}");
+            if let Some(reason) = reason {
+                println!("  ({reason})");
+            }
+            self.prompt_confirmation()
+        })
+    }
+
     fn prompt_confirmation(&self) -> bool {
         if self.auto_confirm {
             return true;
@@ -401,3 +989,824 @@ impl Agent {
         input == "y" || input == "yes"
     }
 }
+
+/// Prompt for `blunux-ai ask`: the argument if given, otherwise everything on
+/// `stdin`. `None` if both are blank.
+pub fn read_prompt(arg: Option<String>, mut stdin: impl io::Read) -> io::Result<Option<String>> {
+    let text = match arg {
+        Some(arg) => arg,
+        None => {
+            let mut buf = String::new();
+            stdin.read_to_string(&mut buf)?;
+            buf
+        }
+    };
+    let text = text.trim();
+    Ok((!text.is_empty()).then(|| text.to_string()))
+}
+
+/// Print a tool call and a truncated result inline (`blunux-ai --verbose`).
+/// With `color`, the output is dimmed so it stands apart from replies.
+pub fn print_tool_event(event: &ToolEvent, color: bool) {
+    let _ = write_tool_event(&mut io::stdout(), event, color);
+}
+
+/// [`print_tool_event`] to any writer, e.g. stderr when stdout carries JSON.
+pub fn write_tool_event(out: &mut impl Write, event: &ToolEvent, color: bool) -> io::Result<()> {
+    let marker = if event.is_error { "✗" } else { "→" };
+    writeln!(out, "\n  [tool] {}({})", event.name, event.input)?;
+    let mut preview: String = event.result.chars().take(TOOL_EVENT_PREVIEW_CHARS).collect();
+    if preview.len() < event.result.len() {
+        preview.push_str(" …");
+    }
+    for line in preview.lines() {
+        let line = format!("  {marker} {line}");
+        if color {
+            writeln!(out, "{}", render::dim(&line))?;
+        } else {
+            writeln!(out, "{line}")?;
+        }
+    }
+    Ok(())
+}
+
+/// Whether `msg` opens a turn: a user message typed by a person rather than
+/// tool results sent back on their behalf.
+fn starts_turn(msg: &Message) -> bool {
+    msg.role == Role::User
+        && msg.content.iter().any(|b| matches!(b, ContentBlock::Text { .. }))
+}
+
+/// Number of messages in the first turn, or `None` if it is the only turn
+/// (the latest one, which is never dropped).
+fn first_turn_len(conversation: &[Message]) -> Option<usize> {
+    (1..conversation.len()).find(|&i| starts_turn(&conversation[i]))
+}
+
+/// Remove the first turn: its opening message and everything up to the next
+/// turn, so tool results are never separated from their tool calls. The
+/// latest turn is kept however long it is; returns whether anything went.
+pub(crate) fn drop_oldest_turn(conversation: &mut Vec<Message>) -> bool {
+    let Some(end) = first_turn_len(conversation) else {
+        return false;
+    };
+    conversation.drain(..end);
+    true
+}
+
+/// Describe what the installer chose in the blunux `config.toml`, so the agent
+/// can answer questions like "what did I install?". `None` if it can't be read.
+fn install_identity(path: &Path) -> Option<String> {
+    let cfg = BlunuxConfig::load(path).ok()?;
+    let packages = selected_packages(&cfg.packages);
+    Some(format!(
+        "## Installed Configuration\n\
+         - Hostname: {hostname}\n\
+         - Username: {username}\n\
+         - Timezone: {timezone}\n\
+         - Selected packages: {packages}\n\
+         \n",
+        hostname = cfg.install.hostname,
+        username = cfg.install.username,
+        timezone = cfg.locale.timezone,
+        packages = if packages.is_empty() { "none".to_string() } else { packages.join(", ") },
+    ))
+}
+
+/// Enabled `[packages.*]` options as `group.option` names, e.g. `browser.firefox`.
+fn selected_packages(packages: &Packages) -> Vec<String> {
+    let Ok(toml::Value::Table(groups)) = toml::Value::try_from(packages) else {
+        return Vec::new();
+    };
+    let mut selected = Vec::new();
+    for (group, options) in &groups {
+        let Some(options) = options.as_table() else { continue };
+        for (name, enabled) in options {
+            if enabled.as_bool() == Some(true) {
+                selected.push(format!("{group}.{name}"));
+            }
+        }
+    }
+    selected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ModelId;
+    use crate::error::ProviderError;
+    use crate::test_support::{test_config, NullProvider, ScriptedProvider};
+
+    fn test_agent(dir: &Path) -> Agent {
+        Agent::with_provider(&test_config(dir), Box::new(NullProvider))
+    }
+
+    #[test]
+    fn test_system_prompt_includes_blunux_identity() {
+        let tmp = tempfile::tempdir().unwrap();
+        let blunux_toml = tmp.path().join("config.toml");
+        std::fs::write(&blunux_toml, include_str!("../../../config.toml")).unwrap();
+
+        let agent = test_agent(tmp.path()).with_blunux_config(&blunux_toml);
+        let prompt = agent.build_system_prompt().unwrap();
+        assert!(prompt.contains("- Hostname: nux"));
+        assert!(prompt.contains("- Username: blu"));
+        assert!(prompt.contains("- Timezone: Europe/Stockholm"));
+        assert!(prompt.contains("desktop.kde"));
+        assert!(prompt.contains("check_blunux_packages"));
+    }
+
+    #[test]
+    fn test_system_prompt_without_tool_support() {
+        let tmp = tempfile::tempdir().unwrap();
+        let with_tools = test_agent(tmp.path()).build_system_prompt().unwrap();
+        assert!(with_tools.contains("Available tools: "));
+        assert!(with_tools.contains("Use the provided tools"));
+
+        let oauth = Agent::with_provider(
+            &test_config(tmp.path()),
+            Box::new(crate::providers::ClaudeOAuthProvider::new(ModelId::ClaudeSonnet46)),
+        );
+        let prompt = oauth.build_system_prompt().unwrap();
+        assert!(!prompt.contains("Available tools"), "{prompt}");
+        assert!(!prompt.contains("Use the provided tools"));
+        assert!(!prompt.contains("run_command"));
+        assert!(prompt.contains("describe the exact commands"));
+        assert!(oauth.tool_definitions().is_empty());
+    }
+
+    /// Reports one token per character so tests can size conversations
+    /// exactly, or fails to count at all with `fail`.
+    #[derive(Default)]
+    struct CharCountProvider {
+        fail: bool,
+        counts: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    #[async_trait::async_trait]
+    impl Provider for CharCountProvider {
+        fn name(&self) -> &str {
+            "char-count"
+        }
+
+        async fn complete(
+            &self,
+            _system_prompt: &str,
+            _messages: &[Message],
+            _tools: &[ToolDefinition],
+            _max_tokens: u32,
+        ) -> Result<CompletionResult, ProviderError> {
+            unreachable!("trimming tests never call complete")
+        }
+
+        async fn count_tokens(
+            &self,
+            _system_prompt: &str,
+            messages: &[Message],
+            _tools: &[ToolDefinition],
+        ) -> Result<u32, ProviderError> {
+            self.counts.fetch_add(1, Ordering::SeqCst);
+            if self.fail {
+                return Err(ProviderError::Timeout);
+            }
+            let chars: usize = messages
+                .iter()
+                .flat_map(|m| &m.content)
+                .map(|b| match b {
+                    ContentBlock::Text { text } => text.len(),
+                    ContentBlock::ToolResult { content, .. } => content.len(),
+                    ContentBlock::ToolUse { .. } => 0,
+                })
+                .sum();
+            Ok(chars as u32)
+        }
+    }
+
+    struct EchoTool;
+
+    #[async_trait::async_trait]
+    impl crate::tools::SystemTool for EchoTool {
+        fn name(&self) -> &str {
+            "echo"
+        }
+        fn description(&self) -> &str {
+            "Echo the input text"
+        }
+        fn input_schema(&self) -> serde_json::Value {
+            serde_json::json!({"type": "object", "properties": {"text": {"type": "string"}}})
+        }
+        fn permission_level(&self) -> PermissionLevel {
+            PermissionLevel::Safe
+        }
+        async fn execute(&self, input: serde_json::Value) -> Result<String, crate::error::ToolError> {
+            Ok(input["text"].as_str().unwrap_or_default().to_string())
+        }
+    }
+
+    /// Echo that needs confirmation, to exercise the confirm paths.
+    struct ConfirmEchoTool;
+
+    #[async_trait::async_trait]
+    impl crate::tools::SystemTool for ConfirmEchoTool {
+        fn name(&self) -> &str {
+            "confirm_echo"
+        }
+        fn description(&self) -> &str {
+            "Echo the input text after confirmation"
+        }
+        fn input_schema(&self) -> serde_json::Value {
+            serde_json::json!({"type": "object", "properties": {"text": {"type": "string"}}})
+        }
+        fn permission_level(&self) -> PermissionLevel {
+            PermissionLevel::RequiresConfirmation
+        }
+        async fn execute(&self, input: serde_json::Value) -> Result<String, crate::error::ToolError> {
+            Ok(format!("ran {}", input["text"].as_str().unwrap_or_default()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_remote_confirm_via_reply() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = test_config(tmp.path());
+        config.whatsapp.confirm_via_reply = true;
+        let mut agent = Agent::with_provider(&config, Box::new(NullProvider)).with_auto_confirm(true);
+        agent.tools.register(Box::new(ConfirmEchoTool)).unwrap();
+        agent.provider = Box::new(ScriptedProvider {
+            replies: std::sync::Mutex::new(vec![
+                CompletionResult {
+                    content: vec![ContentBlock::ToolUse {
+                        id: "t1".into(),
+                        name: "confirm_echo".into(),
+                        input: serde_json::json!({"text": "hi"}),
+                    }],
+                    stop_reason: StopReason::ToolUse,
+                    usage: Usage::default(),
+                },
+                CompletionResult {
+                    content: vec![ContentBlock::Text { text: "This will echo hi.".into() }],
+                    stop_reason: StopReason::EndTurn,
+                    usage: Usage::default(),
+                },
+            ]),
+        });
+        let phone = "+821012345678";
+        let mut conversation = Vec::new();
+
+        let reply = agent.chat_as_user(&mut conversation, phone, "echo hi").await.unwrap();
+        assert_eq!(
+            reply,
+            "This will echo hi.\n\nReply YES to run: confirm_echo {\"text\":\"hi\"}"
+        );
+
+        // The YES is handled without asking the model again
+        let reply = agent.chat_as_user(&mut conversation, phone, "yes").await.unwrap();
+        assert_eq!(reply, "ran hi");
+        assert!(
+            matches!(&conversation.last().unwrap().content[..], [ContentBlock::Text { text }] if text == "ran hi")
+        );
+
+        let log = std::fs::read_to_string(tmp.path().join("logs/commands.log")).unwrap();
+        let statuses: Vec<&str> = log.lines().filter_map(|l| l.split_whitespace().nth(1)).collect();
+        assert_eq!(statuses, ["PENDING", "CONFIRMED"], "{log}");
+    }
+
+    #[tokio::test]
+    async fn test_second_deferral_in_a_turn_is_refused() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = test_config(tmp.path());
+        config.whatsapp.confirm_via_reply = true;
+        let mut agent = Agent::with_provider(&config, Box::new(NullProvider)).with_auto_confirm(true);
+        agent.tools.register(Box::new(ConfirmEchoTool)).unwrap();
+        let call = |id: &str, text: &str| ContentBlock::ToolUse {
+            id: id.into(),
+            name: "confirm_echo".into(),
+            input: serde_json::json!({ "text": text }),
+        };
+        agent.provider = Box::new(ScriptedProvider {
+            replies: std::sync::Mutex::new(vec![
+                CompletionResult {
+                    content: vec![call("t1", "first"), call("t2", "second")],
+                    stop_reason: StopReason::ToolUse,
+                    usage: Usage::default(),
+                },
+                CompletionResult {
+                    content: vec![ContentBlock::Text { text: "Waiting.".into() }],
+                    stop_reason: StopReason::EndTurn,
+                    usage: Usage::default(),
+                },
+            ]),
+        });
+        let phone = "+821012345678";
+        let mut conversation = Vec::new();
+
+        let reply = agent.chat_as_user(&mut conversation, phone, "echo twice").await.unwrap();
+        assert!(reply.ends_with("confirm_echo {\"text\":\"first\"}"), "{reply}");
+        let results: Vec<bool> = conversation
+            .iter()
+            .flat_map(|m| &m.content)
+            .filter_map(|b| match b {
+                ContentBlock::ToolResult { is_error, .. } => Some(*is_error),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(results, [false, true], "the second call is refused");
+
+        let reply = agent.chat_as_user(&mut conversation, phone, "yes").await.unwrap();
+        assert_eq!(reply, "ran first");
+
+        let log = std::fs::read_to_string(tmp.path().join("logs/commands.log")).unwrap();
+        let statuses: Vec<&str> = log.lines().filter_map(|l| l.split_whitespace().nth(1)).collect();
+        assert_eq!(statuses, ["PENDING", "CANCELLED", "CONFIRMED"], "{log}");
+    }
+
+    #[tokio::test]
+    async fn test_tool_observer_sees_tool_calls() {
+        let tmp = tempfile::tempdir().unwrap();
+        let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = std::sync::Arc::clone(&events);
+        let mut agent = test_agent(tmp.path()).with_tool_observer(move |e: &ToolEvent| {
+            sink.lock()
+                .unwrap()
+                .push(format!("{}({}) = {}", e.name, e.input, e.result));
+        });
+        agent.tools.register(Box::new(EchoTool)).unwrap();
+        agent.provider = Box::new(ScriptedProvider {
+            replies: std::sync::Mutex::new(vec![
+                CompletionResult {
+                    content: vec![ContentBlock::ToolUse {
+                        id: "t1".into(),
+                        name: "echo".into(),
+                        input: serde_json::json!({"text": "pong"}),
+                    }],
+                    stop_reason: StopReason::ToolUse,
+                    usage: Usage::default(),
+                },
+                CompletionResult {
+                    content: vec![ContentBlock::Text { text: "done".into() }],
+                    stop_reason: StopReason::EndTurn,
+                    usage: Usage::default(),
+                },
+            ]),
+        });
+
+        let reply = agent.chat("ping").await.unwrap();
+        assert_eq!(reply, "done");
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![r#"echo({"text":"pong"}) = pong"#.to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ask_returns_scripted_reply() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut agent = test_agent(tmp.path()).with_auto_confirm(true);
+        agent.provider = Box::new(ScriptedProvider {
+            replies: std::sync::Mutex::new(vec![CompletionResult {
+                content: vec![ContentBlock::Text { text: "/ is 42% full.".into() }],
+                stop_reason: StopReason::EndTurn,
+                usage: Usage::default(),
+            }]),
+        });
+
+        assert_eq!(agent.ask("disk usage?").await.unwrap(), "/ is 42% full.");
+        assert!(agent.conversation.is_empty(), "one-shot turns are not kept");
+    }
+
+    #[tokio::test]
+    async fn test_ask_report_json_lists_tools() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut agent = test_agent(tmp.path());
+        agent.tools.register(Box::new(EchoTool)).unwrap();
+        agent.provider = Box::new(ScriptedProvider {
+            replies: std::sync::Mutex::new(vec![
+                CompletionResult {
+                    content: vec![ContentBlock::ToolUse {
+                        id: "t1".into(),
+                        name: "echo".into(),
+                        input: serde_json::json!({"text": "pong"}),
+                    }],
+                    stop_reason: StopReason::ToolUse,
+                    usage: Usage {
+                        input_tokens: 100,
+                        output_tokens: 10,
+                    },
+                },
+                CompletionResult {
+                    content: vec![ContentBlock::Text { text: "pong received".into() }],
+                    stop_reason: StopReason::EndTurn,
+                    usage: Usage {
+                        input_tokens: 120,
+                        output_tokens: 5,
+                    },
+                },
+            ]),
+        });
+
+        let report = agent.ask_report("ping").await.unwrap();
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["reply"], "pong received");
+        assert_eq!(json["tools_used"], serde_json::json!(["echo"]));
+        assert_eq!(json["usage"]["input_tokens"], 220);
+        assert_eq!(json["usage"]["output_tokens"], 15);
+    }
+
+    #[tokio::test]
+    async fn test_non_interactive_ask_declines_confirmations() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut agent = test_agent(tmp.path()).with_interactive(false);
+        agent.tools.register(Box::new(ConfirmEchoTool)).unwrap();
+        agent.provider = Box::new(ScriptedProvider {
+            replies: std::sync::Mutex::new(vec![
+                CompletionResult {
+                    content: vec![ContentBlock::ToolUse {
+                        id: "t1".into(),
+                        name: "confirm_echo".into(),
+                        input: serde_json::json!({"text": "hi"}),
+                    }],
+                    stop_reason: StopReason::ToolUse,
+                    usage: Usage::default(),
+                },
+                CompletionResult {
+                    content: vec![ContentBlock::Text { text: "skipped".into() }],
+                    stop_reason: StopReason::EndTurn,
+                    usage: Usage::default(),
+                },
+            ]),
+        });
+
+        // No stdin is read: the call is declined and still reported
+        let report = agent.ask_report("echo hi").await.unwrap();
+        assert_eq!(report.tools_used, ["confirm_echo"]);
+        assert!(agent.tool_observers.is_empty(), "the recorder is removed again");
+        let log = std::fs::read_to_string(tmp.path().join("logs/commands.log")).unwrap();
+        assert!(log.contains("CANCELLED"), "{log}");
+    }
+
+    #[test]
+    fn test_read_prompt_from_arg_or_stdin() {
+        let prompt = read_prompt(Some("  disk usage?\n".into()), io::empty()).unwrap();
+        assert_eq!(prompt.as_deref(), Some("disk usage?"));
+        let prompt = read_prompt(None, "why is boot slow?\n".as_bytes()).unwrap();
+        assert_eq!(prompt.as_deref(), Some("why is boot slow?"));
+        assert_eq!(read_prompt(None, " \n".as_bytes()).unwrap(), None);
+    }
+
+    /// A provider whose completions never finish.
+    struct HangingProvider;
+
+    #[async_trait::async_trait]
+    impl Provider for HangingProvider {
+        fn name(&self) -> &str {
+            "hanging"
+        }
+
+        async fn complete(
+            &self,
+            _system_prompt: &str,
+            _messages: &[Message],
+            _tools: &[ToolDefinition],
+            _max_tokens: u32,
+        ) -> Result<CompletionResult, ProviderError> {
+            std::future::pending().await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_turn_restores_conversation() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut agent = test_agent(tmp.path());
+        agent.provider = Box::new(HangingProvider);
+        agent.conversation = vec![Message::user("earlier"), Message::assistant_text("reply")];
+
+        let cancel = tokio::time::sleep(std::time::Duration::from_millis(20));
+        let result = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            agent.chat_cancellable("never answered", cancel),
+        )
+        .await
+        .expect("cancellation should end the turn");
+
+        assert!(matches!(result, Err(AgentError::UserCancelled)));
+        assert_eq!(agent.conversation.len(), 2, "cancelled message must not linger");
+    }
+
+    #[tokio::test]
+    async fn test_trim_context_drops_whole_turns() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut agent = test_agent(tmp.path());
+        let counts = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        agent.provider = Box::new(CharCountProvider {
+            counts: counts.clone(),
+            ..Default::default()
+        });
+
+        let big = "x".repeat(MAX_CONTEXT_TOKENS as usize * 2);
+        let mut conversation = vec![
+            Message::user(big.clone()),
+            Message {
+                role: Role::Assistant,
+                content: vec![ContentBlock::ToolUse {
+                    id: "t1".into(),
+                    name: "check_disk".into(),
+                    input: serde_json::json!({}),
+                }],
+            },
+            Message::single_tool_result(ContentBlock::tool_result_ok("t1", big.clone())),
+            Message::assistant_text("ok"),
+            Message::user("y".repeat(MAX_CONTEXT_TOKENS as usize / 2)),
+            Message::assistant_text("done"),
+            Message::user("latest"),
+        ];
+
+        agent.trim_context("", &mut conversation, &[]).await;
+
+        // The first turn (including its tool call/result) no longer fits
+        assert_eq!(conversation.len(), 3);
+        assert!(starts_turn(&conversation[0]));
+        assert!(matches!(&conversation[2].content[0], ContentBlock::Text { text } if text == "latest"));
+        assert_eq!(counts.load(Ordering::SeqCst), 1, "counted once");
+    }
+
+    #[tokio::test]
+    async fn test_trim_context_skips_count_for_small_context() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut agent = test_agent(tmp.path());
+        let counts = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        agent.provider = Box::new(CharCountProvider {
+            counts: counts.clone(),
+            ..Default::default()
+        });
+
+        let mut conversation = vec![
+            Message::user("how full is my disk?"),
+            Message::assistant_text("42%"),
+            Message::user("latest"),
+        ];
+        for _ in 0..MAX_TOOL_LOOP_ITERATIONS {
+            agent.trim_context("system", &mut conversation, &[]).await;
+        }
+        assert_eq!(conversation.len(), 3);
+        assert_eq!(counts.load(Ordering::SeqCst), 0, "no count_tokens request");
+    }
+
+    #[tokio::test]
+    async fn test_trim_context_keeps_latest_turn_and_survives_count_errors() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut agent = test_agent(tmp.path());
+        agent.provider = Box::new(CharCountProvider {
+            fail: true,
+            ..Default::default()
+        });
+
+        // Far too big, even for the estimate, but it is the turn being answered
+        let huge = "x".repeat(MAX_CONTEXT_TOKENS as usize * 8);
+        let mut conversation = vec![
+            Message::user("old question"),
+            Message::assistant_text("old answer"),
+            Message::user("latest"),
+            Message {
+                role: Role::Assistant,
+                content: vec![ContentBlock::ToolUse {
+                    id: "t1".into(),
+                    name: "read_logs".into(),
+                    input: serde_json::json!({}),
+                }],
+            },
+            Message::single_tool_result(ContentBlock::tool_result_ok("t1", huge)),
+        ];
+
+        agent.trim_context("", &mut conversation, &[]).await;
+
+        assert_eq!(conversation.len(), 3, "only the older turn goes");
+        assert!(matches!(&conversation[0].content[0], ContentBlock::Text { text } if text == "latest"));
+    }
+
+    #[test]
+    fn test_session_restored_only_when_resuming() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut first = test_agent(tmp.path());
+        first.conversation = vec![Message::user("hello"), Message::assistant_text("hi there")];
+        first.usage.lock().unwrap().add(&Usage {
+            input_tokens: 120,
+            output_tokens: 30,
+        });
+        first.save_session().unwrap();
+        assert!(tmp.path().join(SESSION_FILE).exists());
+
+        // Without --resume the saved session is left alone
+        let mut fresh = test_agent(tmp.path());
+        assert_eq!(fresh.start_session(false).unwrap(), 0);
+        assert!(fresh.conversation.is_empty());
+        assert_eq!(fresh.usage(), Usage::default());
+
+        let mut resumed = test_agent(tmp.path());
+        assert_eq!(resumed.start_session(true).unwrap(), 2);
+        assert_eq!(resumed.conversation.len(), 2);
+        assert_eq!(resumed.usage().input_tokens, 120);
+        assert_eq!(resumed.usage().output_tokens, 30);
+    }
+
+    #[test]
+    fn test_state_round_trip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut agent = test_agent(tmp.path());
+        agent.conversation = vec![
+            Message::user("how full is my disk?"),
+            Message {
+                role: Role::Assistant,
+                content: vec![ContentBlock::ToolUse {
+                    id: "t1".into(),
+                    name: "check_disk".into(),
+                    input: serde_json::json!({"path": "/"}),
+                }],
+            },
+            Message::single_tool_result(ContentBlock::tool_result_ok("t1", "/ 42% used")),
+            Message::assistant_text("Your root partition is 42% full."),
+        ];
+        agent.usage.lock().unwrap().add(&Usage {
+            input_tokens: 900,
+            output_tokens: 75,
+        });
+
+        let json = serde_json::to_string(&agent.export_state()).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["schema_version"], STATE_SCHEMA_VERSION);
+        assert_eq!(value["model"], "claude-sonnet-4-6");
+
+        let mut restored = test_agent(tmp.path());
+        restored.import_state(serde_json::from_str(&json).unwrap()).unwrap();
+        assert_eq!(restored.usage(), agent.usage());
+        assert_eq!(
+            serde_json::to_value(&restored.conversation).unwrap(),
+            serde_json::to_value(&agent.conversation).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_import_rejects_unknown_schema_version() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut agent = test_agent(tmp.path());
+        let mut state = agent.export_state();
+        state.schema_version = STATE_SCHEMA_VERSION + 1;
+        state.conversation.push(Message::user("from the future"));
+        let err = agent.import_state(state).unwrap_err();
+        assert!(matches!(err, AgentError::UnsupportedStateVersion { .. }), "{err}");
+        assert!(agent.conversation.is_empty());
+    }
+
+    #[test]
+    fn test_resume_without_saved_session() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut agent = test_agent(tmp.path());
+        assert_eq!(agent.resume_session().unwrap(), 0);
+        assert!(agent.conversation.is_empty());
+    }
+
+    #[test]
+    fn test_system_prompt_without_blunux_config() {
+        let tmp = tempfile::tempdir().unwrap();
+        let agent = test_agent(tmp.path()).with_blunux_config(&tmp.path().join("missing.toml"));
+        let prompt = agent.build_system_prompt().unwrap();
+        assert!(!prompt.contains("Installed Configuration"));
+    }
+
+    #[tokio::test]
+    async fn test_read_only_blocks_changes_but_not_checks() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = AgentConfig {
+            read_only: true,
+            ..test_config(tmp.path())
+        };
+        let agent = Agent::with_provider(&config, Box::new(NullProvider));
+
+        // Blocked without a confirmation prompt, even though auto_confirm is off
+        let result = agent
+            .execute_tool("t1", "install_package", serde_json::json!({"package": "vlc"}), &ExecutionContext::Local)
+            .await
+            .unwrap();
+        let ContentBlock::ToolResult { content, is_error, .. } = result else {
+            panic!("expected a tool result");
+        };
+        assert!(is_error);
+        assert_eq!(content, strings::read_only_blocked(&Language::English));
+
+        let result = agent
+            .execute_tool("t2", "check_disk", serde_json::json!({}), &ExecutionContext::Local)
+            .await
+            .unwrap();
+        let ContentBlock::ToolResult { content, is_error, .. } = result else {
+            panic!("expected a tool result");
+        };
+        assert!(!is_error, "{content}");
+        assert!(content.contains("Filesystem"), "{content}");
+    }
+
+    #[tokio::test]
+    async fn test_remote_tool_policy_limits_daemon_requests() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = test_config(tmp.path());
+        config.whatsapp.allowed_tools = vec!["check_disk".into(), "check_memory".into()];
+        let agent = Agent::with_provider(&config, Box::new(NullProvider)).with_auto_confirm(true);
+        let remote = ExecutionContext::Remote {
+            phone: "+821012345678".into(),
+        };
+
+        let result = agent
+            .execute_tool("t1", "remove_package", serde_json::json!({"package": "vlc"}), &remote)
+            .await
+            .unwrap();
+        let ContentBlock::ToolResult { content, is_error, .. } = result else {
+            panic!("expected a tool result");
+        };
+        assert!(is_error);
+        assert_eq!(content, strings::remote_tool_blocked(&Language::English, "remove_package"));
+
+        let result = agent
+            .execute_tool("t2", "check_disk", serde_json::json!({}), &remote)
+            .await
+            .unwrap();
+        let ContentBlock::ToolResult { content, is_error, .. } = result else {
+            panic!("expected a tool result");
+        };
+        assert!(!is_error, "{content}");
+        assert!(content.contains("Filesystem"), "{content}");
+    }
+
+    #[tokio::test]
+    async fn test_remote_memory_writes_need_confirmation() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = test_config(tmp.path());
+        config.whatsapp.confirm_via_reply = true;
+        let agent = Agent::with_provider(&config, Box::new(NullProvider)).with_auto_confirm(true);
+        let remote = ExecutionContext::Remote {
+            phone: "+821012345678".into(),
+        };
+        let input = serde_json::json!({"fact": "backups go to /mnt/usb"});
+
+        let result = agent.execute_tool("t1", "remember", input.clone(), &remote).await.unwrap();
+        let ContentBlock::ToolResult { content, .. } = result else {
+            panic!("expected a tool result");
+        };
+        assert_eq!(content, strings::remote_confirm_pending(&Language::English));
+        assert!(!agent.memory.load_long_term().unwrap().contains("/mnt/usb"));
+
+        // Locally it is saved straight away
+        agent.execute_tool("t2", "remember", input, &ExecutionContext::Local).await.unwrap();
+        assert!(agent.memory.load_long_term().unwrap().contains("/mnt/usb"));
+    }
+
+    #[tokio::test]
+    async fn test_remote_commands_are_tagged_in_log() {
+        let tmp = tempfile::tempdir().unwrap();
+        let agent = test_agent(tmp.path());
+        let remote = ExecutionContext::Remote {
+            phone: "+821012345678".into(),
+        };
+        agent
+            .execute_tool("t1", "check_disk", serde_json::json!({}), &remote)
+            .await
+            .unwrap();
+        agent
+            .execute_tool("t2", "check_disk", serde_json::json!({}), &ExecutionContext::Local)
+            .await
+            .unwrap();
+
+        let log = std::fs::read_to_string(tmp.path().join("logs/commands.log")).unwrap();
+        let lines: Vec<&str> = log.lines().collect();
+        assert_eq!(lines.len(), 2, "{log}");
+        assert!(lines[0].ends_with("SAFE        [remote +821012345678] check_disk"), "{log}");
+        assert!(lines[1].ends_with("SAFE        [local] check_disk"), "{log}");
+    }
+
+    #[tokio::test]
+    async fn test_blocked_reason_rendered_in_korean() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = AgentConfig {
+            language: Language::Korean,
+            ..test_config(tmp.path())
+        };
+        let agent = Agent::with_provider(&config, Box::new(NullProvider));
+        let result = agent
+            .execute_tool("t1", "run_command", serde_json::json!({"command": "rm -rf /"}), &ExecutionContext::Local)
+            .await
+            .unwrap();
+
+        let ContentBlock::ToolResult { content, is_error, .. } = result else {
+            panic!("expected a tool result");
+        };
+        assert!(is_error);
+        let expected = format!(
+            "{}: {}\n{}",
+            strings::blocked(&Language::Korean),
+            strings::safety_reason(&Language::Korean, crate::tools::SafetyReason::RootDeletion),
+            strings::safe_alternative(
+                &Language::Korean,
+                crate::tools::SafeAlternative::DeleteSpecificPath
+            )
+        );
+        assert_eq!(content, expected);
+        assert!(content.contains("루트 파일시스템"), "{content}");
+    }
+}
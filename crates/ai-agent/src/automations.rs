@@ -5,8 +5,8 @@ use std::sync::Arc;
 use chrono::{Datelike, Local, Timelike};
 use tokio::sync::Mutex;
 
-use crate::agent::Agent;
 use crate::config::WhatsAppConfig;
+use crate::daemon::SharedAgent;
 
 // ─── Automation config ────────────────────────────────────────────────────────
 
@@ -28,10 +28,29 @@ pub struct Automation {
     pub enabled: bool,
 }
 
+/// Dead-man's-switch heartbeat: a periodic "agent alive" notification that is
+/// sent without invoking the LLM.
+#[derive(Debug, Clone)]
+pub struct HeartbeatConfig {
+    pub enabled: bool,
+    /// Minutes between heartbeats, aligned to midnight local time.
+    pub interval_minutes: u32,
+}
+
+impl Default for HeartbeatConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_minutes: 360,
+        }
+    }
+}
+
 /// All automations loaded from `~/.config/blunux-ai/automations.toml`.
 #[derive(Debug, Default)]
 pub struct AutomationsConfig {
     pub automations: Vec<Automation>,
+    pub heartbeat: HeartbeatConfig,
 }
 
 impl AutomationsConfig {
@@ -52,9 +71,23 @@ impl AutomationsConfig {
             }
         };
 
+        let heartbeat = table
+            .get("heartbeat")
+            .map(|hb| HeartbeatConfig {
+                enabled: hb.get("enabled").and_then(|v| v.as_bool()).unwrap_or(false),
+                interval_minutes: match hb.get("interval_minutes").and_then(|v| v.as_integer()) {
+                    Some(v) => u32::try_from(v).unwrap_or_else(|_| {
+                        eprintln!("[automations] heartbeat interval_minutes {v} is out of range, using 360");
+                        360
+                    }),
+                    None => 360,
+                },
+            })
+            .unwrap_or_default();
+
         let entries = match table.get("automation").and_then(|v| v.as_array()) {
-            Some(arr) => arr,
-            None => return Self::default(),
+            Some(arr) => arr.as_slice(),
+            None => &[],
         };
 
         let mut automations = Vec::new();
@@ -100,7 +133,23 @@ impl AutomationsConfig {
             });
         }
 
-        Self { automations }
+        Self {
+            automations,
+            heartbeat,
+        }
+    }
+
+    /// True if an enabled automation or the heartbeat fires at any minute
+    /// from `now` through `now + minutes`.
+    pub fn due_within(&self, now: &chrono::DateTime<Local>, minutes: u32) -> bool {
+        (0..=minutes).any(|m| {
+            let at = *now + chrono::Duration::minutes(m.into());
+            heartbeat_due(&self.heartbeat, &at)
+                || self
+                    .automations
+                    .iter()
+                    .any(|a| a.enabled && cron_matches(&a.schedule, &at))
+        })
     }
 
     /// Write the default automations.toml template if none exists yet.
@@ -110,7 +159,7 @@ impl AutomationsConfig {
             return Ok(());
         }
         std::fs::create_dir_all(config_dir)?;
-        std::fs::write(&path, DEFAULT_AUTOMATIONS_TOML)?;
+        blunux_config::write_atomic(&path, DEFAULT_AUTOMATIONS_TOML)?;
         Ok(())
     }
 }
@@ -122,6 +171,8 @@ impl AutomationsConfig {
 ///   `*`     — any value
 ///   `N`     — exact integer match
 ///   `*/N`   — every N units (value % N == 0)
+///   `A-B`   — inclusive range
+///   `X,Y,Z` — list of any of the above
 pub fn cron_matches(schedule: &str, now: &chrono::DateTime<Local>) -> bool {
     let fields: Vec<&str> = schedule.split_whitespace().collect();
     if fields.len() != 5 {
@@ -145,31 +196,355 @@ pub fn cron_matches(schedule: &str, now: &chrono::DateTime<Local>) -> bool {
 }
 
 fn field_matches(field: &str, value: u32) -> bool {
+    if field.contains(',') {
+        return field.split(',').any(|part| field_matches(part, value));
+    }
     if field == "*" {
         return true;
     }
     if let Some(step) = field.strip_prefix("*/") {
         if let Ok(n) = step.parse::<u32>() {
-            return n > 0 && value % n == 0;
+            return n > 0 && value.is_multiple_of(n);
         }
         return false;
     }
+    if let Some((start, end)) = parse_range(field) {
+        return (start..=end).contains(&value);
+    }
     if let Ok(n) = field.parse::<u32>() {
         return n == value;
     }
     false
 }
 
+/// Parse an `A-B` range field.  Reversed ranges are rejected.
+fn parse_range(field: &str) -> Option<(u32, u32)> {
+    let (a, b) = field.split_once('-')?;
+    let (a, b) = (a.parse::<u32>().ok()?, b.parse::<u32>().ok()?);
+    (a <= b).then_some((a, b))
+}
+
+/// Translate a 5-field cron schedule into systemd `OnCalendar=` syntax.
+/// Returns `None` when the expression can't be represented faithfully.
+pub fn cron_to_oncalendar(schedule: &str) -> Option<String> {
+    let fields: Vec<&str> = schedule.split_whitespace().collect();
+    if fields.len() != 5 {
+        return None;
+    }
+
+    let minute = calendar_field(fields[0], 0, 59)?;
+    let hour = calendar_field(fields[1], 0, 23)?;
+    let dom = calendar_field(fields[2], 1, 31)?;
+    let month = calendar_field(fields[3], 1, 12)?;
+    let dow = weekday_field(fields[4])?;
+
+    let date = format!("*-{month}-{dom}");
+    let time = format!("{hour}:{minute}:00");
+    Some(match dow {
+        Some(days) if date == "*-*-*" => format!("{days} {time}"),
+        Some(days) => format!("{days} {date} {time}"),
+        None => format!("{date} {time}"),
+    })
+}
+
+/// Convert one numeric cron field. `*/N` keeps the scheduler's "multiple of N"
+/// semantics, so the repetition starts at the first multiple inside the range.
+fn calendar_field(field: &str, min: u32, max: u32) -> Option<String> {
+    if field.contains(',') {
+        let parts = field
+            .split(',')
+            .map(|part| calendar_field(part, min, max))
+            .collect::<Option<Vec<_>>>()?;
+        return Some(parts.join(","));
+    }
+    if field == "*" {
+        return Some("*".into());
+    }
+    if let Some(step) = field.strip_prefix("*/") {
+        let n = step.parse::<u32>().ok().filter(|&n| n > 0)?;
+        let start = if min == 0 { 0 } else { n };
+        if start > max {
+            return None;
+        }
+        return Some(format!("{start:02}/{n}"));
+    }
+    if let Some((a, b)) = parse_range(field) {
+        if a < min || b > max {
+            return None;
+        }
+        return Some(format!("{a:02}..{b:02}"));
+    }
+    let n = field.parse::<u32>().ok().filter(|n| (min..=max).contains(n))?;
+    Some(format!("{n:02}"))
+}
+
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+/// Convert the day-of-week field.  `Some(None)` means any day (`*`).
+fn weekday_field(field: &str) -> Option<Option<String>> {
+    if field == "*" {
+        return Some(None);
+    }
+    let parts = field
+        .split(',')
+        .map(weekday_part)
+        .collect::<Option<Vec<_>>>()?;
+    Some(Some(parts.join(",")))
+}
+
+fn weekday_part(part: &str) -> Option<String> {
+    if let Some(step) = part.strip_prefix("*/") {
+        let n = step.parse::<usize>().ok().filter(|&n| n > 0)?;
+        let days: Vec<&str> = WEEKDAYS.iter().step_by(n).copied().collect();
+        return Some(days.join(","));
+    }
+    if let Some((a, b)) = parse_range(part) {
+        if b >= 7 {
+            return None;
+        }
+        return Some(format!("{}..{}", WEEKDAYS[a as usize], WEEKDAYS[b as usize]));
+    }
+    let n = part.parse::<usize>().ok().filter(|&n| n < 7)?;
+    Some(WEEKDAYS[n].to_string())
+}
+
+// ─── systemd timers ──────────────────────────────────────────────────────────
+
+/// Unit file prefix for generated automation timers.
+pub const TIMER_UNIT_PREFIX: &str = "blunux-ai-automation-";
+
+/// Where `install-timers` writes its units: the systemd user unit dir.
+pub fn timer_unit_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("/tmp"))
+        .join("systemd/user")
+}
+
+/// Stable id for the automation called `name`, used in its unit names so
+/// reordering automations.toml doesn't rename units: FNV-1a of the name.
+pub fn automation_id(name: &str) -> String {
+    let hash = name.bytes().fold(0x811c_9dc5_u32, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    });
+    format!("{hash:08x}")
+}
+
+/// Names (without suffix) of the automation timers installed in `unit_dir`.
+pub fn installed_timers(unit_dir: &Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(unit_dir) else {
+        return Vec::new();
+    };
+    let mut units: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let unit = name.strip_suffix(".timer")?;
+            unit.starts_with(TIMER_UNIT_PREFIX).then(|| unit.to_string())
+        })
+        .collect();
+    units.sort();
+    units
+}
+
+/// A generated `.service` / `.timer` pair for one automation.
+#[derive(Debug, Clone)]
+pub struct TimerUnits {
+    /// Unit name without suffix, e.g. `blunux-ai-automation-1b2c3d4e`.
+    pub unit_name: String,
+    pub service: String,
+    pub timer: String,
+}
+
+/// Build the systemd user units that run `exe automation run <name>` on the
+/// automation's schedule.  Returns `None` if the schedule has no `OnCalendar=`
+/// equivalent.
+pub fn timer_units(auto: &Automation, exe: &Path) -> Option<TimerUnits> {
+    let on_calendar = cron_to_oncalendar(&auto.schedule)?;
+    let unit_name = format!("{TIMER_UNIT_PREFIX}{}", automation_id(&auto.name));
+
+    // ExecStart= takes C-style quoting and expands `%` specifiers.
+    let quoted_name = auto
+        .name
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%");
+
+    let service = format!(
+        "# Auto-generated by blunux-ai install-timers from automations.toml\n\
+         [Unit]\n\
+         Description=Blunux AI automation: {name}\n\
+         \n\
+         [Service]\n\
+         Type=oneshot\n\
+         ExecStart={exe} automation run \"{quoted_name}\"\n",
+        name = auto.name,
+        exe = exe.display(),
+    );
+
+    let timer = format!(
+        "# Auto-generated by blunux-ai install-timers from automations.toml\n\
+         # schedule = \"{schedule}\"\n\
+         [Unit]\n\
+         Description=Blunux AI automation timer: {name}\n\
+         \n\
+         [Timer]\n\
+         OnCalendar={on_calendar}\n\
+         Persistent=true\n\
+         \n\
+         [Install]\n\
+         WantedBy=timers.target\n",
+        schedule = auto.schedule,
+        name = auto.name,
+    );
+
+    Some(TimerUnits {
+        unit_name,
+        service,
+        timer,
+    })
+}
+
+/// Write timer units for every enabled automation into `unit_dir`, removing
+/// previously generated units first.  Returns the unit names written.
+///
+/// Disable the old units (see [`installed_timers`]) before calling this, or
+/// systemd keeps them loaded with dangling `timers.target.wants` links.
+pub fn install_timers(
+    config: &AutomationsConfig,
+    unit_dir: &Path,
+    exe: &Path,
+) -> std::io::Result<Vec<String>> {
+    std::fs::create_dir_all(unit_dir)?;
+
+    for entry in std::fs::read_dir(unit_dir)?.flatten() {
+        let name = entry.file_name();
+        if name.to_string_lossy().starts_with(TIMER_UNIT_PREFIX) {
+            std::fs::remove_file(entry.path())?;
+        }
+    }
+
+    let mut written = Vec::new();
+    for auto in &config.automations {
+        if !auto.enabled {
+            continue;
+        }
+        let Some(units) = timer_units(auto, exe) else {
+            eprintln!(
+                "[automations] Skipping '{}': schedule '{}' has no OnCalendar equivalent",
+                auto.name, auto.schedule
+            );
+            continue;
+        };
+        // `automation run <name>` only ever runs the first one
+        if written.contains(&units.unit_name) {
+            eprintln!("[automations] Skipping duplicate automation name '{}'", auto.name);
+            continue;
+        }
+        std::fs::write(
+            unit_dir.join(format!("{}.service", units.unit_name)),
+            &units.service,
+        )?;
+        std::fs::write(
+            unit_dir.join(format!("{}.timer", units.unit_name)),
+            &units.timer,
+        )?;
+        written.push(units.unit_name);
+    }
+
+    Ok(written)
+}
+
+// ─── Notify queue ────────────────────────────────────────────────────────────
+
+/// Upper bound on pending notifications; the oldest entry is dropped beyond it.
+pub const MAX_QUEUED_NOTIFICATIONS: usize = 200;
+
+/// Push `(phone, body)` onto the notify queue.
+///
+/// A message identical to the most recent one still queued for the same phone
+/// is collapsed (returns false), and the queue is capped at
+/// `MAX_QUEUED_NOTIFICATIONS` by dropping the oldest entry.
+pub fn enqueue_notification(
+    queue: &mut VecDeque<(String, String)>,
+    phone: &str,
+    body: &str,
+) -> bool {
+    let duplicate = queue
+        .iter()
+        .rev()
+        .find(|(p, _)| p == phone)
+        .is_some_and(|(_, b)| b == body);
+    if duplicate {
+        return false;
+    }
+
+    if queue.len() >= MAX_QUEUED_NOTIFICATIONS {
+        if let Some((dropped_to, _)) = queue.pop_front() {
+            eprintln!(
+                "[scheduler] Notification queue full ({MAX_QUEUED_NOTIFICATIONS}) — dropped oldest message for {dropped_to}"
+            );
+        }
+    }
+    queue.push_back((phone.to_string(), body.to_string()));
+    true
+}
+
+// ─── Heartbeat ───────────────────────────────────────────────────────────────
+
+/// True when `now` falls on a heartbeat boundary (minutes since midnight is a
+/// multiple of the interval).
+pub fn heartbeat_due(heartbeat: &HeartbeatConfig, now: &chrono::DateTime<Local>) -> bool {
+    if !heartbeat.enabled || heartbeat.interval_minutes == 0 {
+        return false;
+    }
+    let minute_of_day = now.hour() * 60 + now.minute();
+    minute_of_day.is_multiple_of(heartbeat.interval_minutes)
+}
+
+fn heartbeat_message(now: &chrono::DateTime<Local>) -> String {
+    let load = std::fs::read_to_string("/proc/loadavg")
+        .ok()
+        .and_then(|s| s.split_whitespace().next().map(str::to_string));
+    let mut msg = format!(
+        "💓 Blunux AI Agent — heartbeat\n\n에이전트 정상 작동 중 / agent alive ({})",
+        now.format("%Y-%m-%d %H:%M")
+    );
+    if let Some(load) = load {
+        msg.push_str(&format!("\nload: {load}"));
+    }
+    msg
+}
+
+/// Enqueue a heartbeat for every allowed number if one is due at `now`.
+/// Returns true when a heartbeat was queued.
+pub async fn push_heartbeat(
+    heartbeat: &HeartbeatConfig,
+    now: &chrono::DateTime<Local>,
+    notify_queue: &Mutex<VecDeque<(String, String)>>,
+    whatsapp_cfg: &WhatsAppConfig,
+) -> bool {
+    if !heartbeat_due(heartbeat, now) || whatsapp_cfg.allowed_numbers.is_empty() {
+        return false;
+    }
+    let message = heartbeat_message(now);
+    let mut queue = notify_queue.lock().await;
+    for phone in &whatsapp_cfg.allowed_numbers {
+        enqueue_notification(&mut queue, phone, &message);
+    }
+    true
+}
+
 // ─── Scheduler ───────────────────────────────────────────────────────────────
 
 /// Background task: wakes at the top of every minute, evaluates all
 /// automations, and pushes triggered notifications into `notify_queue`.
+/// Recipients come from the current agent's `[whatsapp]` config, so a
+/// SIGHUP reload applies from the next tick.
 ///
 /// Each item in the queue is `(phone_number, message_body)`.
 pub async fn run_scheduler(
-    agent: Arc<Mutex<Agent>>,
+    agent: SharedAgent,
     notify_queue: Arc<Mutex<VecDeque<(String, String)>>>,
-    whatsapp_cfg: WhatsAppConfig,
     config_dir: PathBuf,
 ) {
     // Keep track of the last minute we processed to avoid double-firing.
@@ -192,6 +567,12 @@ pub async fn run_scheduler(
 
         // Reload config each minute so changes take effect without restart
         let cfg = AutomationsConfig::load(&config_dir);
+        let current = Arc::clone(&*agent.read().await);
+        let whatsapp_cfg = current.whatsapp();
+
+        if push_heartbeat(&cfg.heartbeat, &now, &notify_queue, whatsapp_cfg).await {
+            eprintln!("[scheduler] Heartbeat queued");
+        }
 
         for auto in &cfg.automations {
             if !auto.enabled {
@@ -204,10 +585,7 @@ pub async fn run_scheduler(
             eprintln!("[scheduler] Triggering automation: {}", auto.name);
 
             // Run through the AI agent
-            let reply = {
-                let mut locked = agent.lock().await;
-                locked.run_automation(&auto.action).await
-            };
+            let reply = current.run_automation(&auto.action).await;
 
             let message = match reply {
                 Ok(text) => format!(
@@ -224,7 +602,9 @@ pub async fn run_scheduler(
             if auto.notify == "whatsapp" && !whatsapp_cfg.allowed_numbers.is_empty() {
                 let mut queue = notify_queue.lock().await;
                 for phone in &whatsapp_cfg.allowed_numbers {
-                    queue.push_back((phone.clone(), message.clone()));
+                    if !enqueue_notification(&mut queue, phone, &message) {
+                        eprintln!("[scheduler] Skipped duplicate notification for {phone}");
+                    }
                 }
             }
         }
@@ -240,23 +620,30 @@ const DEFAULT_AUTOMATIONS_TOML: &str = r#"# ~/.config/blunux-ai/automations.toml
 #   분(0-59) 시(0-23) 일(1-31) 월(1-12) 요일(0-6, 0=일요일)
 #   *  = 모두 일치 / any value
 #   */N = N마다 / every N units
+#   A-B = 범위 / inclusive range
+#   X,Y = 목록 / list of values
 #
 # Examples:
 #   "0 9 * * *"   → 매일 오전 9시   / every day at 09:00
 #   "0 */6 * * *" → 6시간마다       / every 6 hours
 #   "0 0 * * *"   → 매일 자정       / every day at midnight
+#   "0 9 * * 1-5" → 평일 오전 9시   / weekdays at 09:00
+#
+# action 은 자연어 지시문이거나 "/run health" 처럼 저장된 질문입니다 (config.toml [prompts]).
+# action is a natural-language instruction, or "/run health" for a canned prompt
+# (built-in or config.toml [prompts]).
 
 [[automation]]
 name = "시스템 헬스체크"
 schedule = "0 9 * * *"
-action = "시스템 전체 상태를 확인하고 CPU, RAM, 디스크, 업타임, 보류 중인 업데이트 수를 요약해줘. 문제가 있으면 명확히 알려줘."
+action = "/run health"
 notify = "whatsapp"
 enabled = true
 
 [[automation]]
 name = "보안 업데이트 확인"
 schedule = "0 */6 * * *"
-action = "보안 업데이트가 있는지 확인해줘. 있으면 패키지 목록과 함께 알려줘. 없으면 '보안 업데이트 없음'이라고 짧게 답해줘."
+action = "/run security"
 notify = "whatsapp"
 auto_apply = false
 enabled = true
@@ -264,9 +651,15 @@ enabled = true
 [[automation]]
 name = "디스크 공간 경고"
 schedule = "0 0 * * *"
-action = "디스크 사용률을 확인해서 80% 이상인 파티션이 있으면 경고해줘. 모두 안전하면 '디스크 공간 정상'이라고 짧게 답해줘."
+action = "/run disk"
 notify = "whatsapp"
 enabled = true
+
+# 하트비트: LLM 호출 없이 주기적으로 "정상 작동 중" 알림 전송
+# Heartbeat: periodic "agent alive" notification without calling the LLM
+[heartbeat]
+enabled = false
+interval_minutes = 360           # 자정 기준 간격 / interval aligned to midnight
 "#;
 
 // ─── Tests ────────────────────────────────────────────────────────────────────
@@ -321,6 +714,128 @@ mod tests {
         assert!(!cron_matches("0 9 * *", &dt(9, 0, 1))); // only 4 fields
     }
 
+    #[test]
+    fn test_cron_to_oncalendar_basic() {
+        assert_eq!(
+            cron_to_oncalendar("0 9 * * *").as_deref(),
+            Some("*-*-* 09:00:00")
+        );
+        assert_eq!(
+            cron_to_oncalendar("30 0 1 * *").as_deref(),
+            Some("*-*-01 00:30:00")
+        );
+        assert_eq!(
+            cron_to_oncalendar("0 8 * * 1").as_deref(),
+            Some("Mon 08:00:00")
+        );
+        assert_eq!(cron_to_oncalendar("61 9 * * *"), None);
+        assert_eq!(cron_to_oncalendar("0 9 * *"), None);
+    }
+
+    #[test]
+    fn test_cron_ranges_and_lists() {
+        // 2026-02-23 is a Monday, 2026-02-22 a Sunday
+        assert!(cron_matches("0 9 * * 1-5", &dt(9, 0, 23)));
+        assert!(!cron_matches("0 9 * * 1-5", &dt(9, 0, 22)));
+        assert!(cron_matches("0,30 * * * *", &dt(14, 30, 21)));
+        assert!(!cron_matches("0,30 * * * *", &dt(14, 15, 21)));
+        assert!(!cron_matches("0 17-9 * * *", &dt(18, 0, 21)));
+    }
+
+    #[test]
+    fn test_cron_to_oncalendar_weekdays() {
+        assert_eq!(
+            cron_to_oncalendar("0 9 * * 1-5").as_deref(),
+            Some("Mon..Fri 09:00:00")
+        );
+        assert_eq!(
+            cron_to_oncalendar("0 9 * * 1,3,5").as_deref(),
+            Some("Mon,Wed,Fri 09:00:00")
+        );
+    }
+
+    #[test]
+    fn test_cron_to_oncalendar_step_and_list() {
+        assert_eq!(
+            cron_to_oncalendar("0 */6 * * *").as_deref(),
+            Some("*-*-* 00/6:00:00")
+        );
+        assert_eq!(
+            cron_to_oncalendar("0,30 9-17 * * *").as_deref(),
+            Some("*-*-* 09..17:00,30:00")
+        );
+        assert_eq!(cron_to_oncalendar("0 9 * * 5-1"), None);
+        assert_eq!(cron_to_oncalendar("0 9 * * 1-7"), None);
+    }
+
+    #[test]
+    fn test_timer_units_on_calendar() {
+        let auto = Automation {
+            name: "헬스체크".into(),
+            schedule: "0 */6 * * *".into(),
+            action: "check".into(),
+            notify: "whatsapp".into(),
+            auto_apply: false,
+            enabled: true,
+        };
+        let units = timer_units(&auto, Path::new("/usr/bin/blunux-ai")).unwrap();
+        assert_eq!(
+            units.unit_name,
+            format!("blunux-ai-automation-{}", automation_id("헬스체크"))
+        );
+        assert!(units.timer.contains("\nOnCalendar=*-*-* 00/6:00:00\n"));
+        assert!(units
+            .service
+            .contains("ExecStart=/usr/bin/blunux-ai automation run \"헬스체크\""));
+    }
+
+    #[test]
+    fn test_install_timers_writes_enabled_only() {
+        let tmp = tempfile::tempdir().unwrap();
+        AutomationsConfig::write_defaults(tmp.path()).unwrap();
+        let mut cfg = AutomationsConfig::load(tmp.path());
+        cfg.automations[1].enabled = false;
+        let unit = |i: usize| {
+            format!("{TIMER_UNIT_PREFIX}{}", automation_id(&cfg.automations[i].name))
+        };
+
+        let unit_dir = tmp.path().join("units");
+        std::fs::create_dir_all(&unit_dir).unwrap();
+        std::fs::write(unit_dir.join("blunux-ai-automation-9.timer"), "stale").unwrap();
+        std::fs::write(unit_dir.join("blunux-ai-automation-9.service"), "stale").unwrap();
+        assert_eq!(installed_timers(&unit_dir), ["blunux-ai-automation-9"]);
+
+        let written = install_timers(&cfg, &unit_dir, Path::new("/usr/bin/blunux-ai")).unwrap();
+        assert_eq!(written, vec![unit(0), unit(2)]);
+        assert!(unit_dir.join(format!("{}.timer", unit(0))).exists());
+        assert!(unit_dir.join(format!("{}.service", unit(0))).exists());
+        assert!(!unit_dir.join(format!("{}.timer", unit(1))).exists());
+        assert!(!unit_dir.join("blunux-ai-automation-9.timer").exists());
+
+        let mut expected = vec![unit(0), unit(2)];
+        expected.sort();
+        assert_eq!(installed_timers(&unit_dir), expected);
+    }
+
+    #[test]
+    fn test_unit_names_survive_reordering() {
+        let tmp = tempfile::tempdir().unwrap();
+        AutomationsConfig::write_defaults(tmp.path()).unwrap();
+        let mut cfg = AutomationsConfig::load(tmp.path());
+        let exe = Path::new("/usr/bin/blunux-ai");
+        let before = install_timers(&cfg, &tmp.path().join("a"), exe).unwrap();
+        cfg.automations.reverse();
+        let mut after = install_timers(&cfg, &tmp.path().join("b"), exe).unwrap();
+        after.reverse();
+        assert_eq!(before, after);
+
+        // Two automations with one name would share a unit
+        let first = cfg.automations[0].clone();
+        cfg.automations.push(first);
+        assert_eq!(install_timers(&cfg, &tmp.path().join("c"), exe).unwrap().len(), 3);
+        assert_ne!(automation_id("a"), automation_id("b"));
+    }
+
     #[test]
     fn test_automations_load_defaults_on_missing_file() {
         let tmp = tempfile::tempdir().unwrap();
@@ -337,6 +852,114 @@ mod tests {
         assert_eq!(cfg.automations[0].name, "시스템 헬스체크");
         assert_eq!(cfg.automations[1].name, "보안 업데이트 확인");
         assert_eq!(cfg.automations[2].name, "디스크 공간 경고");
+        // The defaults send the built-in canned prompts rather than copies of them
+        let actions: Vec<&str> = cfg.automations.iter().map(|a| a.action.as_str()).collect();
+        assert_eq!(actions, ["/run health", "/run security", "/run disk"]);
+        for action in actions {
+            let name = action.strip_prefix("/run ").unwrap();
+            assert!(crate::prompts::names(&Default::default()).contains(&name.to_string()));
+        }
+    }
+
+    fn wa_cfg(numbers: &[&str]) -> WhatsAppConfig {
+        WhatsAppConfig {
+            allowed_numbers: numbers.iter().map(|n| n.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_enqueue_collapses_duplicates() {
+        let mut queue = VecDeque::new();
+        assert!(enqueue_notification(&mut queue, "+821011112222", "disk ok"));
+        assert!(enqueue_notification(&mut queue, "+821033334444", "disk ok"));
+        // Same body again for both phones — interleaved, but still a repeat
+        assert!(!enqueue_notification(&mut queue, "+821011112222", "disk ok"));
+        assert!(!enqueue_notification(&mut queue, "+821033334444", "disk ok"));
+        assert_eq!(queue.len(), 2);
+
+        // A different body is kept, and then the old body is no longer the latest
+        assert!(enqueue_notification(&mut queue, "+821011112222", "disk full"));
+        assert!(enqueue_notification(&mut queue, "+821011112222", "disk ok"));
+        assert_eq!(queue.len(), 4);
+    }
+
+    #[test]
+    fn test_enqueue_caps_queue_length() {
+        let mut queue = VecDeque::new();
+        for i in 0..MAX_QUEUED_NOTIFICATIONS + 5 {
+            enqueue_notification(&mut queue, "+821011112222", &format!("msg {i}"));
+        }
+        assert_eq!(queue.len(), MAX_QUEUED_NOTIFICATIONS);
+        assert_eq!(queue.front().unwrap().1, "msg 5");
+        assert_eq!(
+            queue.back().unwrap().1,
+            format!("msg {}", MAX_QUEUED_NOTIFICATIONS + 4)
+        );
+    }
+
+    #[test]
+    fn test_heartbeat_defaults_disabled() {
+        let tmp = tempfile::tempdir().unwrap();
+        AutomationsConfig::write_defaults(tmp.path()).unwrap();
+        let cfg = AutomationsConfig::load(tmp.path());
+        assert!(!cfg.heartbeat.enabled);
+        assert_eq!(cfg.heartbeat.interval_minutes, 360);
+    }
+
+    #[test]
+    fn test_heartbeat_interval_out_of_range_uses_default() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("automations.toml");
+        for (value, expected) in [("90", 90), ("4294967386", 360), ("-1", 360)] {
+            std::fs::write(&path, format!("[heartbeat]\nenabled = true\ninterval_minutes = {value}\n")).unwrap();
+            let cfg = AutomationsConfig::load(tmp.path());
+            assert_eq!(cfg.heartbeat.interval_minutes, expected, "{value}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_enqueued_at_interval_boundary() {
+        let heartbeat = HeartbeatConfig {
+            enabled: true,
+            interval_minutes: 120,
+        };
+        let queue = Mutex::new(VecDeque::new());
+        let wa = wa_cfg(&["+821012345678"]);
+
+        assert!(!push_heartbeat(&heartbeat, &dt(3, 0, 21), &queue, &wa).await);
+        assert!(queue.lock().await.is_empty());
+
+        assert!(push_heartbeat(&heartbeat, &dt(4, 0, 21), &queue, &wa).await);
+        let queue = queue.lock().await;
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue[0].0, "+821012345678");
+        assert!(queue[0].1.contains("heartbeat"));
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_disabled_never_enqueues() {
+        let heartbeat = HeartbeatConfig::default();
+        let queue = Mutex::new(VecDeque::new());
+        let wa = wa_cfg(&["+821012345678"]);
+        assert!(!push_heartbeat(&heartbeat, &dt(0, 0, 21), &queue, &wa).await);
+        assert!(queue.lock().await.is_empty());
+    }
+
+    #[test]
+    fn test_due_within_window() {
+        let tmp = tempfile::tempdir().unwrap();
+        AutomationsConfig::write_defaults(tmp.path()).unwrap();
+        let mut cfg = AutomationsConfig::load(tmp.path());
+
+        // The default disk check runs at 00:00
+        assert!(cfg.due_within(&dt(23, 55, 21), 10));
+        assert!(!cfg.due_within(&dt(23, 40, 21), 10));
+
+        for auto in &mut cfg.automations {
+            auto.enabled = false;
+        }
+        assert!(!cfg.due_within(&dt(23, 55, 21), 10));
     }
 
     #[test]
@@ -0,0 +1,113 @@
+use std::io;
+use std::sync::OnceLock;
+
+use crate::error::ToolError;
+
+// ── External commands ────────────────────────────────────────────────────────
+//
+// Tools and the OAuth provider shell out to these. Which ones exist is probed
+// once per process, and a command that fails to spawn because it isn't
+// installed becomes a `ToolError::MissingCommand` with a hint on how to get
+// it, rather than a bare "No such file or directory".
+
+/// Commands the agent's tools and providers may run.
+const PROBED: [&str; 9] = [
+    "claude",
+    "journalctl",
+    "nmcli",
+    "paccache",
+    "pacman",
+    "sudo",
+    "systemctl",
+    "systemd-analyze",
+    "yay",
+];
+
+/// Which of the [`PROBED`] commands are installed.
+#[derive(Debug, Clone)]
+pub struct Capabilities {
+    missing: Vec<&'static str>,
+}
+
+impl Capabilities {
+    /// Probe every command with `exists`.
+    pub fn detect_with(exists: impl Fn(&str) -> bool) -> Self {
+        Self {
+            missing: PROBED.into_iter().filter(|cmd| !exists(cmd)).collect(),
+        }
+    }
+
+    /// The capabilities of this machine, probed on first use.
+    pub fn get() -> &'static Self {
+        static CAPABILITIES: OnceLock<Capabilities> = OnceLock::new();
+        CAPABILITIES.get_or_init(|| Self::detect_with(blunux_config::util::command_exists))
+    }
+
+    /// Probed commands that are not installed.
+    pub fn missing(&self) -> &[&'static str] {
+        &self.missing
+    }
+}
+
+/// How to get `cmd` on a Blunux system.
+pub fn install_hint(cmd: &str) -> &'static str {
+    match cmd {
+        "yay" => "run blunux-setup to install it",
+        "claude" => {
+            "install it with `npm install -g @anthropic-ai/claude-code`, then run `claude login`"
+        }
+        "paccache" => "install the pacman-contrib package",
+        "nmcli" => "install and enable the networkmanager package",
+        "systemctl" | "journalctl" | "systemd-analyze" => "this needs a systemd-based system",
+        "pacman" => "this needs an Arch-based system",
+        "sudo" => "install sudo as root and add your user to the wheel group",
+        _ => "install it and try again",
+    }
+}
+
+/// The error for `cmd` failing to spawn with `err`: an actionable
+/// [`ToolError::MissingCommand`] if it isn't installed, otherwise the I/O error.
+pub fn spawn_error(cmd: &str, err: io::Error) -> ToolError {
+    if err.kind() == io::ErrorKind::NotFound {
+        ToolError::MissingCommand {
+            command: cmd.to_string(),
+            hint: install_hint(cmd).to_string(),
+        }
+    } else {
+        ToolError::Io(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_lists_missing_commands() {
+        let caps = Capabilities::detect_with(|cmd| cmd != "yay" && cmd != "paccache");
+        assert_eq!(caps.missing(), ["paccache", "yay"]);
+    }
+
+    #[test]
+    fn test_missing_yay_error_is_actionable() {
+        let err = spawn_error("yay", io::Error::from(io::ErrorKind::NotFound));
+        assert_eq!(err.to_string(), "`yay` is not installed — run blunux-setup to install it");
+
+        let err = spawn_error("yay", io::Error::from(io::ErrorKind::PermissionDenied));
+        assert!(matches!(err, ToolError::Io(_)));
+    }
+
+    #[tokio::test]
+    async fn test_runner_reports_missing_binary() {
+        use crate::tools::runner::{CommandRunner, RealRunner};
+
+        let err = RealRunner
+            .run("blunux-no-such-command", &[], std::time::Duration::from_secs(5))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, ToolError::MissingCommand { ref command, .. } if command == "blunux-no-such-command"),
+            "{err}"
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 use crate::error::ConfigError;
@@ -10,8 +11,96 @@ pub struct AgentConfig {
     pub whatsapp_enabled: bool,
     pub language: Language,
     pub safe_mode: bool,
+    /// Restore the previous interactive session on `blunux-ai chat`.
+    pub resume_session: bool,
+    /// Observer mode: every tool that could change the system is blocked,
+    /// confirmed or not. Default: false.
+    pub read_only: bool,
+    /// Seconds to wait for the `claude` CLI in OAuth mode before killing it.
+    /// Default: 120.
+    pub oauth_timeout_secs: u64,
     pub config_dir: PathBuf,
     pub whatsapp: WhatsAppConfig,
+    pub daemon: DaemonConfig,
+    pub memory: MemoryConfig,
+    /// `[prompts]`: canned prompt templates by name, adding to or overriding
+    /// the built-ins in [`crate::prompts`].
+    pub prompts: BTreeMap<String, String>,
+}
+
+/// The values [`AgentConfig::load`] uses for keys missing from config.toml,
+/// with the default config directory.
+impl Default for AgentConfig {
+    fn default() -> Self {
+        Self {
+            provider: ProviderType::Claude,
+            claude_mode: ClaudeMode::OAuth,
+            model: ModelId::ClaudeSonnet46,
+            whatsapp_enabled: false,
+            language: Language::Korean,
+            safe_mode: true,
+            resume_session: false,
+            read_only: false,
+            oauth_timeout_secs: 120,
+            config_dir: Self::default_config_dir(),
+            whatsapp: WhatsAppConfig::default(),
+            daemon: DaemonConfig::default(),
+            memory: MemoryConfig::default(),
+            prompts: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DaemonConfig {
+    /// Longest accepted IPC line in bytes. Longer lines are rejected without
+    /// being buffered. Default: 65536 (64 KB).
+    pub max_message_bytes: usize,
+    /// Exit after this many seconds without IPC activity, unless an
+    /// automation is due soon. 0 disables. Default: 0.
+    pub idle_shutdown_secs: u64,
+    /// Most WhatsApp users whose conversations are kept; the least recently
+    /// active one is forgotten beyond this. Default: 100.
+    pub max_users: usize,
+    /// Most messages kept per user conversation; the oldest turns are
+    /// dropped beyond this. Default: 200.
+    pub max_messages_per_user: usize,
+    /// Longest reply or notification, in characters, handed to the WhatsApp
+    /// bridge as one message; longer ones are split. Default: 4000.
+    pub max_reply_chars: usize,
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        Self {
+            max_message_bytes: 64 * 1024,
+            idle_shutdown_secs: 0,
+            max_users: 100,
+            max_messages_per_user: 200,
+            max_reply_chars: 4000,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MemoryConfig {
+    /// Daily logs older than this many days are gzip-compressed. Default: 30.
+    pub compress_after_days: u32,
+    /// Daily logs older than this many days are deleted. Default: 365.
+    pub delete_after_days: u32,
+    /// For this many minutes after midnight the end of yesterday's log is
+    /// still included in the context. 0 disables it. Default: 60.
+    pub carry_over_minutes: u32,
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self {
+        Self {
+            compress_after_days: 30,
+            delete_after_days: 365,
+            carry_over_minutes: 60,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -26,6 +115,39 @@ pub struct WhatsAppConfig {
     /// Seconds of inactivity before a user's conversation is reset.
     /// Default: 3600 (1 hour).
     pub session_timeout: u32,
+    /// Tools WhatsApp users may call; empty allows every tool.
+    pub allowed_tools: Vec<String>,
+    /// Tools WhatsApp users may never call, even if listed in `allowed_tools`.
+    pub blocked_tools: Vec<String>,
+    /// Instead of auto-confirming, ask the WhatsApp user to reply YES before
+    /// a tool that needs confirmation runs. Default: false.
+    pub confirm_via_reply: bool,
+    /// Seconds a YES reply is accepted for. Default: 300.
+    pub confirm_timeout_secs: u64,
+}
+
+impl Default for WhatsAppConfig {
+    fn default() -> Self {
+        Self {
+            allowed_numbers: Vec::new(),
+            max_messages_per_minute: 5,
+            require_prefix: false,
+            session_timeout: 3600,
+            allowed_tools: Vec::new(),
+            blocked_tools: Vec::new(),
+            confirm_via_reply: false,
+            confirm_timeout_secs: 300,
+        }
+    }
+}
+
+impl WhatsAppConfig {
+    /// Whether a remote (WhatsApp) request may call `tool`. Local CLI use is
+    /// not affected by this policy.
+    pub fn permits_tool(&self, tool: &str) -> bool {
+        let listed = |list: &[String]| list.iter().any(|t| t == tool);
+        !listed(&self.blocked_tools) && (self.allowed_tools.is_empty() || listed(&self.allowed_tools))
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -44,26 +166,99 @@ pub enum ClaudeMode {
 pub enum ModelId {
     ClaudeSonnet46,
     ClaudeOpus46,
+    ClaudeHaiku45,
     DeepSeekChat,
+    DeepSeekReasoner,
     DeepSeekCoder,
+    /// A model name this build doesn't know, passed to the API as-is so a
+    /// newly released model works without an update.
+    Custom(String),
 }
 
 impl ModelId {
-    pub fn api_name(&self) -> &'static str {
+    /// Every known model, in the order the setup wizard offers them
+    /// (recommended first for each provider).
+    pub fn all() -> Vec<Self> {
+        vec![
+            Self::ClaudeSonnet46,
+            Self::ClaudeOpus46,
+            Self::ClaudeHaiku45,
+            Self::DeepSeekChat,
+            Self::DeepSeekReasoner,
+            Self::DeepSeekCoder,
+        ]
+    }
+
+    /// The models served by `provider`.
+    pub fn all_for(provider: &ProviderType) -> Vec<Self> {
+        Self::all()
+            .into_iter()
+            .filter(|m| m.provider().as_ref() == Some(provider))
+            .collect()
+    }
+
+    /// Inverse of [`api_name`](Self::api_name) for the known models.
+    pub fn from_api_name(name: &str) -> Option<Self> {
+        Self::all().into_iter().find(|m| m.api_name() == name)
+    }
+
+    /// A [`Custom`](Self::Custom) model, if `name` looks like a model id:
+    /// 1-100 characters of ASCII letters, digits, `.`, `-`, `_`, `:` or `/`.
+    pub fn custom(name: &str) -> Option<Self> {
+        let plausible = (1..=100).contains(&name.len())
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | ':' | '/'));
+        plausible.then(|| Self::Custom(name.to_string()))
+    }
+
+    /// The provider serving this model; `None` for custom models.
+    pub fn provider(&self) -> Option<ProviderType> {
+        match self {
+            Self::ClaudeSonnet46 | Self::ClaudeOpus46 | Self::ClaudeHaiku45 => {
+                Some(ProviderType::Claude)
+            }
+            Self::DeepSeekChat | Self::DeepSeekReasoner | Self::DeepSeekCoder => {
+                Some(ProviderType::DeepSeek)
+            }
+            Self::Custom(_) => None,
+        }
+    }
+
+    pub fn api_name(&self) -> &str {
         match self {
             Self::ClaudeSonnet46 => "claude-sonnet-4-6",
             Self::ClaudeOpus46 => "claude-opus-4-6",
+            Self::ClaudeHaiku45 => "claude-haiku-4-5",
             Self::DeepSeekChat => "deepseek-chat",
+            Self::DeepSeekReasoner => "deepseek-reasoner",
             Self::DeepSeekCoder => "deepseek-coder",
+            Self::Custom(name) => name,
         }
     }
 
-    pub fn display_name(&self) -> &'static str {
+    pub fn display_name(&self) -> &str {
         match self {
             Self::ClaudeSonnet46 => "Claude Sonnet 4.6",
             Self::ClaudeOpus46 => "Claude Opus 4.6",
+            Self::ClaudeHaiku45 => "Claude Haiku 4.5",
             Self::DeepSeekChat => "DeepSeek Chat",
+            Self::DeepSeekReasoner => "DeepSeek Reasoner",
             Self::DeepSeekCoder => "DeepSeek Coder",
+            Self::Custom(name) => name,
+        }
+    }
+
+    /// One-line description shown next to the model in the setup wizard.
+    pub fn tagline(&self) -> &'static str {
+        match self {
+            Self::ClaudeSonnet46 => "Fast & balanced (Recommended)",
+            Self::ClaudeOpus46 => "More capable, slower",
+            Self::ClaudeHaiku45 => "Fastest, lowest cost",
+            Self::DeepSeekChat => "General purpose (Recommended)",
+            Self::DeepSeekReasoner => "Step-by-step reasoning, slower",
+            Self::DeepSeekCoder => "Code-focused",
+            Self::Custom(_) => "Custom model",
         }
     }
 }
@@ -93,13 +288,19 @@ impl AgentConfig {
 
     /// Load AgentConfig from the agent's own config.toml inside config_dir.
     pub fn load(config_dir: &Path) -> Result<Self, ConfigError> {
-        let config_path = config_dir.join("config.toml");
+        Self::load_file(&config_dir.join("config.toml"))
+    }
+
+    /// Load AgentConfig from an arbitrary TOML file. Its directory becomes
+    /// config_dir, so credentials and memory are looked up next to it.
+    pub fn load_file(config_path: &Path) -> Result<Self, ConfigError> {
+        let config_dir = config_path.parent().unwrap_or(Path::new("."));
         if !config_path.exists() {
             return Err(ConfigError::NotFound {
                 path: config_path.display().to_string(),
             });
         }
-        let content = std::fs::read_to_string(&config_path).map_err(ConfigError::Io)?;
+        let content = std::fs::read_to_string(config_path).map_err(ConfigError::Io)?;
         let table: toml::Table =
             toml::from_str(&content).map_err(|e| ConfigError::Parse(e.to_string()))?;
 
@@ -143,16 +344,15 @@ impl AgentConfig {
             .get("model")
             .and_then(|v| v.as_str())
             .unwrap_or("claude-sonnet-4-6");
-        let model = match model_str {
-            "claude-sonnet-4-6" => ModelId::ClaudeSonnet46,
-            "claude-opus-4-6" => ModelId::ClaudeOpus46,
-            "deepseek-chat" => ModelId::DeepSeekChat,
-            "deepseek-coder" => ModelId::DeepSeekCoder,
-            other => {
-                return Err(ConfigError::InvalidValue {
+        let model = match ModelId::from_api_name(model_str) {
+            Some(model) => model,
+            None => {
+                let model = ModelId::custom(model_str).ok_or_else(|| ConfigError::InvalidValue {
                     field: "model".into(),
-                    value: other.into(),
-                })
+                    value: model_str.into(),
+                })?;
+                eprintln!("[config] Unknown model '{model_str}', passing it to the API as-is");
+                model
             }
         };
 
@@ -171,6 +371,22 @@ impl AgentConfig {
             .and_then(|v| v.as_bool())
             .unwrap_or(true);
 
+        let resume_session = agent
+            .get("resume_session")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let read_only = agent
+            .get("read_only")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let oauth_timeout_secs = agent
+            .get("oauth_timeout_secs")
+            .and_then(|v| v.as_integer())
+            .map(|v| v.max(1) as u64)
+            .unwrap_or(120);
+
         let whatsapp_enabled = agent
             .get("whatsapp_enabled")
             .and_then(|v| v.as_bool())
@@ -178,15 +394,39 @@ impl AgentConfig {
 
         // [whatsapp] section — optional, defaults to empty
         let wa_section = table.get("whatsapp");
-        let allowed_numbers: Vec<String> = wa_section
-            .and_then(|s| s.get("allowed_numbers"))
-            .and_then(|v| v.as_array())
-            .map(|arr| {
-                arr.iter()
-                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                    .collect()
-            })
-            .unwrap_or_default();
+        let string_list = |key: &str| -> Vec<String> {
+            wa_section
+                .and_then(|s| s.get(key))
+                .and_then(|v| v.as_array())
+                .map(|arr| {
+                    arr.iter()
+                        .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let allowed_numbers = string_list("allowed_numbers");
+        let allowed_tools = string_list("allowed_tools");
+        let blocked_tools = string_list("blocked_tools");
+        // A misspelt name would silently allow or fail to block a tool
+        let known_tools = crate::tools::ToolRegistry::builtin_names();
+        for (field, tools) in [("allowed_tools", &allowed_tools), ("blocked_tools", &blocked_tools)] {
+            if let Some(unknown) = tools.iter().find(|t| !known_tools.contains(t)) {
+                return Err(ConfigError::InvalidValue {
+                    field: format!("whatsapp.{field}"),
+                    value: unknown.clone(),
+                });
+            }
+        }
+        let confirm_via_reply = wa_section
+            .and_then(|s| s.get("confirm_via_reply"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let confirm_timeout_secs = wa_section
+            .and_then(|s| s.get("confirm_timeout_secs"))
+            .and_then(|v| v.as_integer())
+            .map(|v| v.max(1) as u64)
+            .unwrap_or(300);
         let max_messages_per_minute = wa_section
             .and_then(|s| s.get("max_messages_per_minute"))
             .and_then(|v| v.as_integer())
@@ -202,6 +442,73 @@ impl AgentConfig {
             .map(|v| v as u32)
             .unwrap_or(3600);
 
+        // [daemon] section — optional
+        let daemon_section = table.get("daemon");
+        let mut daemon = DaemonConfig::default();
+        if let Some(v) = daemon_section
+            .and_then(|s| s.get("max_message_bytes"))
+            .and_then(|v| v.as_integer())
+        {
+            daemon.max_message_bytes = v.max(1) as usize;
+        }
+        if let Some(v) = daemon_section
+            .and_then(|s| s.get("idle_shutdown_secs"))
+            .and_then(|v| v.as_integer())
+        {
+            daemon.idle_shutdown_secs = v.max(0) as u64;
+        }
+        if let Some(v) = daemon_section
+            .and_then(|s| s.get("max_users"))
+            .and_then(|v| v.as_integer())
+        {
+            daemon.max_users = v.max(1) as usize;
+        }
+        if let Some(v) = daemon_section
+            .and_then(|s| s.get("max_messages_per_user"))
+            .and_then(|v| v.as_integer())
+        {
+            daemon.max_messages_per_user = v.max(2) as usize;
+        }
+        if let Some(v) = daemon_section
+            .and_then(|s| s.get("max_reply_chars"))
+            .and_then(|v| v.as_integer())
+        {
+            daemon.max_reply_chars = v.max(100) as usize;
+        }
+
+        // [memory] section — optional
+        let memory_section = table.get("memory");
+        let mut memory = MemoryConfig::default();
+        if let Some(v) = memory_section
+            .and_then(|s| s.get("compress_after_days"))
+            .and_then(|v| v.as_integer())
+        {
+            memory.compress_after_days = v.max(1) as u32;
+        }
+        if let Some(v) = memory_section
+            .and_then(|s| s.get("delete_after_days"))
+            .and_then(|v| v.as_integer())
+        {
+            memory.delete_after_days = v.max(1) as u32;
+        }
+        if let Some(v) = memory_section
+            .and_then(|s| s.get("carry_over_minutes"))
+            .and_then(|v| v.as_integer())
+        {
+            memory.carry_over_minutes = v.clamp(0, 24 * 60) as u32;
+        }
+
+        // [prompts] section — optional name → template map
+        let prompts: BTreeMap<String, String> = table
+            .get("prompts")
+            .and_then(|v| v.as_table())
+            .map(|t| {
+                t.iter()
+                    .filter_map(|(name, v)| Some((name.clone(), v.as_str()?.to_string())))
+                    .collect()
+            })
+            .unwrap_or_default();
+
         Ok(Self {
             provider,
             claude_mode,
@@ -209,16 +516,33 @@ impl AgentConfig {
             whatsapp_enabled,
             language,
             safe_mode,
+            resume_session,
+            read_only,
+            oauth_timeout_secs,
             config_dir: config_dir.to_path_buf(),
             whatsapp: WhatsAppConfig {
                 allowed_numbers,
                 max_messages_per_minute,
                 require_prefix,
                 session_timeout,
+                allowed_tools,
+                blocked_tools,
+                confirm_via_reply,
+                confirm_timeout_secs,
             },
+            daemon,
+            memory,
+            prompts,
         })
     }
 
+    /// Re-read config_dir/config.toml in place. On error the current values
+    /// are kept, so a broken edit can't take down a running daemon.
+    pub fn reload(&mut self) -> Result<(), ConfigError> {
+        *self = Self::load(&self.config_dir)?;
+        Ok(())
+    }
+
     /// Save the current config to config_dir/config.toml.
     pub fn save(&self) -> Result<(), ConfigError> {
         std::fs::create_dir_all(&self.config_dir).map_err(ConfigError::Io)?;
@@ -234,20 +558,26 @@ impl AgentConfig {
             Language::Korean => "ko",
             Language::English => "en",
         };
-        let allowed_numbers_toml = self
-            .whatsapp
-            .allowed_numbers
-            .iter()
-            .map(|n| format!("\"{n}\""))
-            .collect::<Vec<_>>()
-            .join(", ");
-        let content = format!(
+        let toml_list = |items: &[String]| {
+            items
+                .iter()
+                .map(|n| format!("\"{n}\""))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let allowed_numbers_toml = toml_list(&self.whatsapp.allowed_numbers);
+        let allowed_tools_toml = toml_list(&self.whatsapp.allowed_tools);
+        let blocked_tools_toml = toml_list(&self.whatsapp.blocked_tools);
+        let mut content = format!(
             r#"[agent]
 provider = "{provider_str}"
 claude_mode = "{claude_mode_str}"
 model = "{model}"
 language = "{language_str}"
 safe_mode = {safe_mode}
+resume_session = {resume_session}
+read_only = {read_only}
+oauth_timeout_secs = {oauth_timeout_secs}
 whatsapp_enabled = {whatsapp}
 
 [whatsapp]
@@ -255,16 +585,54 @@ allowed_numbers = [{allowed_numbers_toml}]
 max_messages_per_minute = {max_mpm}
 require_prefix = {require_prefix}
 session_timeout = {session_timeout}
+allowed_tools = [{allowed_tools_toml}]
+blocked_tools = [{blocked_tools_toml}]
+confirm_via_reply = {confirm_via_reply}
+confirm_timeout_secs = {confirm_timeout_secs}
+
+[daemon]
+max_message_bytes = {max_message_bytes}
+idle_shutdown_secs = {idle_shutdown_secs}
+max_users = {max_users}
+max_messages_per_user = {max_messages_per_user}
+max_reply_chars = {max_reply_chars}
+
+[memory]
+compress_after_days = {compress_after_days}
+delete_after_days = {delete_after_days}
+carry_over_minutes = {carry_over_minutes}
 "#,
             model = self.model.api_name(),
             safe_mode = self.safe_mode,
+            resume_session = self.resume_session,
+            read_only = self.read_only,
+            oauth_timeout_secs = self.oauth_timeout_secs,
             whatsapp = self.whatsapp_enabled,
             max_mpm = self.whatsapp.max_messages_per_minute,
             require_prefix = self.whatsapp.require_prefix,
             session_timeout = self.whatsapp.session_timeout,
+            confirm_via_reply = self.whatsapp.confirm_via_reply,
+            confirm_timeout_secs = self.whatsapp.confirm_timeout_secs,
+            max_message_bytes = self.daemon.max_message_bytes,
+            idle_shutdown_secs = self.daemon.idle_shutdown_secs,
+            max_users = self.daemon.max_users,
+            max_messages_per_user = self.daemon.max_messages_per_user,
+            max_reply_chars = self.daemon.max_reply_chars,
+            compress_after_days = self.memory.compress_after_days,
+            delete_after_days = self.memory.delete_after_days,
+            carry_over_minutes = self.memory.carry_over_minutes,
         );
+        if !self.prompts.is_empty() {
+            let prompts: toml::Table = self
+                .prompts
+                .iter()
+                .map(|(name, template)| (name.clone(), toml::Value::String(template.clone())))
+                .collect();
+            let prompts = toml::to_string(&prompts).map_err(|e| ConfigError::Parse(e.to_string()))?;
+            content.push_str(&format!("\n[prompts]\n{prompts}"));
+        }
         let path = self.config_dir.join("config.toml");
-        std::fs::write(&path, content).map_err(ConfigError::Io)?;
+        blunux_config::write_atomic(&path, content).map_err(ConfigError::Io)?;
         Ok(())
     }
 }
@@ -316,6 +684,49 @@ mod tests {
         assert_eq!(ModelId::DeepSeekCoder.api_name(), "deepseek-coder");
     }
 
+    #[test]
+    fn test_model_id_round_trips_api_name() {
+        for model in ModelId::all() {
+            assert_eq!(ModelId::from_api_name(model.api_name()), Some(model));
+        }
+        assert_eq!(ModelId::from_api_name("gpt-4"), None);
+    }
+
+    #[test]
+    fn test_unknown_model_loads_as_custom() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(
+            tmp.path().join("config.toml"),
+            "[agent]\nmodel = \"claude-sonnet-5-0\"\n",
+        )
+        .unwrap();
+        let loaded = AgentConfig::load(tmp.path()).unwrap();
+        assert_eq!(loaded.model, ModelId::Custom("claude-sonnet-5-0".into()));
+        assert_eq!(loaded.model.api_name(), "claude-sonnet-5-0");
+
+        loaded.save().unwrap();
+        assert_eq!(AgentConfig::load(tmp.path()).unwrap().model, loaded.model);
+
+        std::fs::write(
+            tmp.path().join("config.toml"),
+            "[agent]\nmodel = \"not a model\"\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            AgentConfig::load(tmp.path()),
+            Err(ConfigError::InvalidValue { field, .. }) if field == "model"
+        ));
+    }
+
+    #[test]
+    fn test_models_grouped_by_provider() {
+        let claude = ModelId::all_for(&ProviderType::Claude);
+        let deepseek = ModelId::all_for(&ProviderType::DeepSeek);
+        assert_eq!(claude[0], ModelId::ClaudeSonnet46);
+        assert_eq!(deepseek[0], ModelId::DeepSeekChat);
+        assert_eq!(claude.len() + deepseek.len(), ModelId::all().len());
+    }
+
     #[test]
     fn test_config_save_and_load() {
         let tmp = tempfile::tempdir().unwrap();
@@ -326,13 +737,36 @@ mod tests {
             whatsapp_enabled: false,
             language: Language::Korean,
             safe_mode: true,
+            resume_session: true,
+            read_only: true,
+            oauth_timeout_secs: 45,
             config_dir: tmp.path().to_path_buf(),
             whatsapp: WhatsAppConfig {
                 allowed_numbers: vec![],
                 max_messages_per_minute: 5,
                 require_prefix: false,
                 session_timeout: 3600,
+                allowed_tools: vec!["check_disk".into(), "check_memory".into()],
+                blocked_tools: vec!["remove_package".into()],
+                confirm_via_reply: true,
+                confirm_timeout_secs: 120,
+            },
+            daemon: DaemonConfig {
+                max_message_bytes: 4096,
+                idle_shutdown_secs: 900,
+                max_users: 20,
+                max_messages_per_user: 50,
+                max_reply_chars: 1500,
             },
+            memory: MemoryConfig {
+                compress_after_days: 14,
+                delete_after_days: 90,
+                carry_over_minutes: 15,
+            },
+            prompts: BTreeMap::from([(
+                "weekly report".to_string(),
+                "Summarize \"{hostname}\" for {date}".to_string(),
+            )]),
         };
         cfg.save().unwrap();
         let loaded = AgentConfig::load(tmp.path()).unwrap();
@@ -341,5 +775,62 @@ mod tests {
         assert_eq!(loaded.model, ModelId::ClaudeSonnet46);
         assert_eq!(loaded.language, Language::Korean);
         assert!(loaded.safe_mode);
+        assert!(loaded.resume_session);
+        assert!(loaded.read_only);
+        assert_eq!(loaded.prompts, cfg.prompts);
+        assert_eq!(loaded.whatsapp.allowed_tools, cfg.whatsapp.allowed_tools);
+        assert_eq!(loaded.whatsapp.blocked_tools, cfg.whatsapp.blocked_tools);
+        assert!(loaded.whatsapp.confirm_via_reply);
+        assert_eq!(loaded.whatsapp.confirm_timeout_secs, 120);
+        assert_eq!(loaded.oauth_timeout_secs, 45);
+        assert_eq!(loaded.daemon.max_message_bytes, 4096);
+        assert_eq!(loaded.daemon.idle_shutdown_secs, 900);
+        assert_eq!(loaded.daemon.max_users, 20);
+        assert_eq!(loaded.daemon.max_messages_per_user, 50);
+        assert_eq!(loaded.daemon.max_reply_chars, 1500);
+        assert_eq!(loaded.memory.compress_after_days, 14);
+        assert_eq!(loaded.memory.delete_after_days, 90);
+        assert_eq!(loaded.memory.carry_over_minutes, 15);
+    }
+
+    #[test]
+    fn test_unknown_whatsapp_tool_is_rejected() {
+        let tmp = tempfile::tempdir().unwrap();
+        let write = |whatsapp: &str| {
+            let toml = format!("[agent]\nprovider = \"claude\"\n\n[whatsapp]\n{whatsapp}\n");
+            std::fs::write(tmp.path().join("config.toml"), toml).unwrap();
+        };
+
+        write("blocked_tools = [\"remove_pakage\"]");
+        assert!(matches!(
+            AgentConfig::load(tmp.path()),
+            Err(ConfigError::InvalidValue { field, value })
+                if field == "whatsapp.blocked_tools" && value == "remove_pakage"
+        ));
+
+        write("allowed_tools = [\"check_disk\", \"check_blunux_packages\", \"remember\"]");
+        let loaded = AgentConfig::load(tmp.path()).unwrap();
+        assert_eq!(loaded.whatsapp.allowed_tools.len(), 3);
+    }
+
+    #[test]
+    fn test_load_file_with_custom_name() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("work-account.toml");
+        std::fs::write(
+            &path,
+            "[agent]\nprovider = \"deepseek\"\nmodel = \"deepseek-chat\"\nlanguage = \"en\"\n",
+        )
+        .unwrap();
+
+        let loaded = AgentConfig::load_file(&path).unwrap();
+        assert_eq!(loaded.provider, ProviderType::DeepSeek);
+        assert_eq!(loaded.model, ModelId::DeepSeekChat);
+        assert_eq!(loaded.language, Language::English);
+        assert_eq!(loaded.config_dir, tmp.path());
+        assert!(matches!(
+            AgentConfig::load_file(&tmp.path().join("missing.toml")),
+            Err(ConfigError::NotFound { .. })
+        ));
     }
 }
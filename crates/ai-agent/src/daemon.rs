@@ -1,20 +1,257 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
+use std::os::unix::io::{FromRawFd, OwnedFd, RawFd};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use chrono::Local;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
 use tokio::net::UnixListener;
-use tokio::sync::Mutex;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::{Mutex, RwLock};
 
-use crate::agent::Agent;
-use crate::automations::{run_scheduler, AutomationsConfig};
-use crate::config::AgentConfig;
+use crate::agent::{drop_oldest_turn, Agent};
+use crate::automations::{installed_timers, run_scheduler, timer_unit_dir, AutomationsConfig};
+use crate::config::{AgentConfig, DaemonConfig};
 use crate::error::AgentError;
-use crate::ipc::{socket_path, IpcMessage, IpcMessageType};
+use crate::ipc::{socket_path, IpcErrorCode, IpcMessage, IpcMessageType};
+use crate::lock::{chat_running, DaemonLock};
+use crate::providers::Message;
+use crate::whatsapp::{split_message, to_whatsapp};
 
 /// Pending outbound notifications queued by the automation scheduler.
 /// Each entry is `(phone_number, message_body)`.
 type NotifyQueue = Arc<Mutex<VecDeque<(String, String)>>>;
 
+/// The daemon's current agent. Replaced wholesale on SIGHUP reload; requests
+/// already running keep the `Arc<Agent>` they started with.
+pub type SharedAgent = Arc<RwLock<Arc<Agent>>>;
+
+/// `[daemon]` limits read per connection, so a SIGHUP reload applies to new
+/// connections without restarting the daemon.
+pub struct IpcLimits {
+    max_message_bytes: AtomicUsize,
+    max_reply_chars: AtomicUsize,
+}
+
+impl IpcLimits {
+    pub fn new(daemon: &DaemonConfig) -> Self {
+        Self {
+            max_message_bytes: AtomicUsize::new(daemon.max_message_bytes),
+            max_reply_chars: AtomicUsize::new(daemon.max_reply_chars),
+        }
+    }
+
+    fn update(&self, daemon: &DaemonConfig) {
+        self.max_message_bytes.store(daemon.max_message_bytes, Ordering::Relaxed);
+        self.max_reply_chars.store(daemon.max_reply_chars, Ordering::Relaxed);
+    }
+}
+
+/// With idle shutdown on, the daemon stays up if an automation fires within
+/// this many minutes.
+const IDLE_AUTOMATION_WINDOW_MINUTES: u32 = 10;
+
+/// Time since the last IPC activity, for `idle_shutdown_secs`. Never
+/// expires while a request is being processed.
+/// Takes `now` explicitly so expiry can be tested without sleeping.
+pub struct IdleTimer {
+    timeout: Option<Duration>,
+    last_activity: std::sync::Mutex<Instant>,
+    in_flight: AtomicUsize,
+}
+
+/// A request in progress; see [`IdleTimer::busy`].
+pub struct Busy<'a>(&'a IdleTimer);
+
+impl Drop for Busy<'_> {
+    fn drop(&mut self) {
+        self.0.touch(Instant::now());
+        self.0.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl IdleTimer {
+    /// `timeout_secs == 0` gives a timer that never expires.
+    pub fn new(timeout_secs: u64, now: Instant) -> Self {
+        Self {
+            timeout: (timeout_secs > 0).then(|| Duration::from_secs(timeout_secs)),
+            last_activity: std::sync::Mutex::new(now),
+            in_flight: AtomicUsize::new(0),
+        }
+    }
+
+    /// Hold off expiry until the returned guard is dropped, which counts as
+    /// activity.
+    pub fn busy(&self) -> Busy<'_> {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        Busy(self)
+    }
+
+    pub fn touch(&self, now: Instant) {
+        *self.last_activity.lock().unwrap() = now;
+    }
+
+    /// Time left before expiry, or `None` if idle shutdown is disabled.
+    /// The full timeout while a request is in flight.
+    pub fn remaining(&self, now: Instant) -> Option<Duration> {
+        let timeout = self.timeout?;
+        if self.in_flight.load(Ordering::SeqCst) > 0 {
+            return Some(timeout);
+        }
+        let idle = now.saturating_duration_since(*self.last_activity.lock().unwrap());
+        Some(timeout.saturating_sub(idle))
+    }
+}
+
+/// Messages accepted per phone number in the last minute, for
+/// `[whatsapp] max_messages_per_minute`. Takes `now` explicitly so the
+/// window can be tested without sleeping.
+#[derive(Default)]
+struct RateLimiter {
+    recent: std::sync::Mutex<HashMap<String, VecDeque<Instant>>>,
+}
+
+impl RateLimiter {
+    const WINDOW: Duration = Duration::from_secs(60);
+
+    /// Record a message from `phone` at `now` unless it already sent
+    /// `max_per_minute` in the last minute. 0 means no limit.
+    fn allow(&self, phone: &str, max_per_minute: u32, now: Instant) -> bool {
+        if max_per_minute == 0 {
+            return true;
+        }
+        let mut recent = self.recent.lock().unwrap();
+        recent.retain(|_, times| {
+            while times.front().is_some_and(|&t| now.saturating_duration_since(t) >= Self::WINDOW) {
+                times.pop_front();
+            }
+            !times.is_empty()
+        });
+        let times = recent.entry(phone.to_string()).or_default();
+        if times.len() >= max_per_minute as usize {
+            return false;
+        }
+        times.push_back(now);
+        true
+    }
+}
+
+/// Per-user conversation histories for daemon mode, keyed by phone number.
+///
+/// The outer lock is only held to look up a user's slot.  Each slot has its
+/// own lock, so different users are processed concurrently while messages
+/// from the same user are serialized.
+///
+/// At most `max_users` conversations are kept (least recently used evicted
+/// first) and each is capped at `max_messages`, oldest turns dropped first.
+/// Each user's recent message times are tracked for the rate limit too.
+pub struct ConversationStore {
+    users: Mutex<UserSlots>,
+    rate_limiter: RateLimiter,
+    max_users: usize,
+    max_messages: usize,
+}
+
+#[derive(Default)]
+struct UserSlots {
+    slots: HashMap<String, UserSlot>,
+    /// Incremented on every lookup; orders slots by recency.
+    clock: u64,
+}
+
+struct UserSlot {
+    conversation: Arc<Mutex<Vec<Message>>>,
+    last_used: u64,
+}
+
+impl Default for ConversationStore {
+    fn default() -> Self {
+        let daemon = DaemonConfig::default();
+        Self::new(daemon.max_users, daemon.max_messages_per_user)
+    }
+}
+
+impl ConversationStore {
+    pub fn new(max_users: usize, max_messages: usize) -> Self {
+        Self {
+            users: Mutex::new(UserSlots::default()),
+            rate_limiter: RateLimiter::default(),
+            max_users: max_users.max(1),
+            max_messages,
+        }
+    }
+
+    async fn slot(&self, phone: &str) -> Arc<Mutex<Vec<Message>>> {
+        let mut users = self.users.lock().await;
+        users.clock += 1;
+        let now = users.clock;
+        let conversation = match users.slots.get_mut(phone) {
+            Some(slot) => {
+                slot.last_used = now;
+                Arc::clone(&slot.conversation)
+            }
+            None => {
+                let conversation = Arc::new(Mutex::new(Vec::new()));
+                users.slots.insert(
+                    phone.to_string(),
+                    UserSlot {
+                        conversation: Arc::clone(&conversation),
+                        last_used: now,
+                    },
+                );
+                conversation
+            }
+        };
+
+        while users.slots.len() > self.max_users {
+            let Some(oldest) = users
+                .slots
+                .iter()
+                .min_by_key(|(_, slot)| slot.last_used)
+                .map(|(phone, _)| phone.clone())
+            else {
+                break;
+            };
+            users.slots.remove(&oldest);
+            eprintln!(
+                "[blunux-ai daemon] Evicted conversation for {oldest} (over {} users)",
+                self.max_users
+            );
+        }
+        conversation
+    }
+
+    /// Run one chat turn for `phone`, holding only that user's lock.
+    pub async fn chat(
+        &self,
+        agent: &Agent,
+        phone: &str,
+        user_message: &str,
+    ) -> Result<String, AgentError> {
+        let slot = self.slot(phone).await;
+        let mut conversation = slot.lock().await;
+        let reply = agent.chat_as_user(&mut conversation, phone, user_message).await;
+
+        let before = conversation.len();
+        // The turn that just finished stays even if it alone is over the cap
+        while conversation.len() > self.max_messages && drop_oldest_turn(&mut conversation) {}
+        if conversation.len() < before {
+            eprintln!(
+                "[blunux-ai daemon] Dropped {} old messages for {phone}",
+                before - conversation.len()
+            );
+        }
+        reply
+    }
+
+    /// Forget the stored conversation history for `phone`.
+    pub async fn reset(&self, phone: &str) {
+        self.users.lock().await.slots.remove(phone);
+    }
+}
+
 /// Run the AI agent daemon, listening on a Unix domain socket.
 ///
 /// Incoming messages are newline-delimited JSON `IpcMessage` objects.
@@ -24,72 +261,343 @@ type NotifyQueue = Arc<Mutex<VecDeque<(String, String)>>>;
 /// A background scheduler task fires automations on their cron schedules and
 /// pushes results to `notify_queue`.  The WhatsApp bridge polls the queue via
 /// the `poll_notifications` IPC action.
-pub async fn run_daemon(config: &AgentConfig) -> Result<(), AgentError> {
-    let path = socket_path();
-
-    // Remove stale socket file if present
-    if path.exists() {
-        std::fs::remove_file(&path).map_err(AgentError::Io)?;
-    }
-
-    let listener = UnixListener::bind(&path).map_err(AgentError::Io)?;
-
-    // Set socket permissions so only the current user can connect
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))
-            .map_err(AgentError::Io)?;
+///
+/// The socket is `socket` if given, else [`socket_path`]; `activated`, the
+/// socket passed by systemd (see [`take_activated_socket`]), takes
+/// precedence over both.
+pub async fn run_daemon(
+    config: &AgentConfig,
+    blunux_config: &Path,
+    socket: Option<&Path>,
+    activated: Option<OwnedFd>,
+) -> Result<(), AgentError> {
+    let _lock = DaemonLock::acquire(&config.config_dir)?;
+    if chat_running(&config.config_dir) {
+        eprintln!(
+            "[blunux-ai daemon] An interactive chat is running; both will write memory for this config"
+        );
     }
-
-    eprintln!("[blunux-ai daemon] Listening on {}", path.display());
+    let listener = open_listener(socket, activated)?;
 
     // Write default automations.toml if not present
     let _ = AutomationsConfig::write_defaults(&config.config_dir);
+    if !installed_timers(&timer_unit_dir()).is_empty() {
+        eprintln!(
+            "[blunux-ai daemon] Automation timers are installed; automations will also run from systemd (remove them from {})",
+            timer_unit_dir().display()
+        );
+    }
 
-    let agent = Arc::new(Mutex::new(Agent::new_daemon(config)?));
+    let agent: SharedAgent = Arc::new(RwLock::new(Arc::new(
+        Agent::new_daemon(config)?.with_blunux_config(blunux_config),
+    )));
+    let limits = Arc::new(IpcLimits::new(&config.daemon));
+    spawn_reload_on_sighup(
+        config.clone(),
+        blunux_config.to_path_buf(),
+        Arc::clone(&agent),
+        Arc::clone(&limits),
+    )?;
+    serve(listener, agent, config, limits).await
+}
+
+/// Accept IPC connections on `listener` and run the automation scheduler
+/// until idle shutdown. `limits` are re-read for each connection so a reload
+/// can change them.
+pub async fn serve(
+    listener: UnixListener,
+    agent: SharedAgent,
+    config: &AgentConfig,
+    limits: Arc<IpcLimits>,
+) -> Result<(), AgentError> {
+    let conversations = Arc::new(ConversationStore::new(
+        config.daemon.max_users,
+        config.daemon.max_messages_per_user,
+    ));
     let notify_queue: NotifyQueue = Arc::new(Mutex::new(VecDeque::new()));
+    let idle = Arc::new(IdleTimer::new(config.daemon.idle_shutdown_secs, Instant::now()));
 
     // Spawn automation scheduler as a background task
     let sched_agent = Arc::clone(&agent);
     let sched_queue = Arc::clone(&notify_queue);
-    let sched_wa_cfg = config.whatsapp.clone();
     let sched_config_dir = config.config_dir.clone();
     tokio::spawn(async move {
-        run_scheduler(sched_agent, sched_queue, sched_wa_cfg, sched_config_dir).await;
+        run_scheduler(sched_agent, sched_queue, sched_config_dir).await;
     });
 
     loop {
-        let (stream, _addr) = listener.accept().await.map_err(AgentError::Io)?;
+        let accepted = match idle.remaining(Instant::now()) {
+            None => listener.accept().await,
+            Some(left) => tokio::select! {
+                accepted = listener.accept() => accepted,
+                _ = tokio::time::sleep(left) => {
+                    if idle.remaining(Instant::now()).is_some_and(|d| d.is_zero()) {
+                        let automations = AutomationsConfig::load(&config.config_dir);
+                        if !automations.due_within(&Local::now(), IDLE_AUTOMATION_WINDOW_MINUTES) {
+                            eprintln!(
+                                "[blunux-ai daemon] No activity for {}s, shutting down",
+                                config.daemon.idle_shutdown_secs
+                            );
+                            return Ok(());
+                        }
+                        idle.touch(Instant::now());
+                    }
+                    continue;
+                }
+            },
+        };
+        let (stream, _addr) = accepted.map_err(AgentError::Io)?;
+        idle.touch(Instant::now());
         let agent = Arc::clone(&agent);
+        let conversations = Arc::clone(&conversations);
         let queue = Arc::clone(&notify_queue);
+        let idle = Arc::clone(&idle);
+        let max_message_bytes = limits.max_message_bytes.load(Ordering::Relaxed);
+        let max_reply_chars = limits.max_reply_chars.load(Ordering::Relaxed);
 
         tokio::spawn(async move {
-            if let Err(e) = handle_connection(stream, agent, queue).await {
+            if let Err(e) = handle_connection(
+                stream,
+                agent,
+                conversations,
+                queue,
+                idle,
+                max_message_bytes,
+                max_reply_chars,
+            )
+            .await
+            {
                 eprintln!("[blunux-ai daemon] connection error: {e}");
             }
         });
     }
 }
 
+/// First file descriptor passed by systemd socket activation (SD_LISTEN_FDS_START).
+const SD_LISTEN_FDS_START: RawFd = 3;
+
+/// The fd to adopt if this process was socket-activated: `LISTEN_PID` must
+/// name us and `LISTEN_FDS` must pass at least one socket.
+fn activated_fd(listen_pid: Option<&str>, listen_fds: Option<&str>, pid: u32) -> Option<RawFd> {
+    let for_us = listen_pid?.trim().parse::<u32>().ok()? == pid;
+    let count = listen_fds?.trim().parse::<u32>().ok()?;
+    (for_us && count >= 1).then_some(SD_LISTEN_FDS_START)
+}
+
+/// Take the socket passed by systemd socket activation, if any, and clear
+/// the `LISTEN_*` variables so nothing we spawn (tools, the claude CLI)
+/// thinks it was socket-activated or inherits the socket.
+///
+/// Changes the environment, so call it before starting any threads — in
+/// particular before building the tokio runtime.
+pub fn take_activated_socket() -> std::io::Result<Option<OwnedFd>> {
+    let fd = activated_fd(
+        std::env::var("LISTEN_PID").ok().as_deref(),
+        std::env::var("LISTEN_FDS").ok().as_deref(),
+        std::process::id(),
+    );
+    let Some(fd) = fd else {
+        return Ok(None);
+    };
+    for var in ["LISTEN_PID", "LISTEN_FDS", "LISTEN_FDNAMES"] {
+        std::env::remove_var(var);
+    }
+    // SAFETY: systemd hands us ownership of fd 3 and nothing else in this
+    // process opens it before main runs.
+    let inherited = unsafe { OwnedFd::from_raw_fd(fd) };
+    // The inherited fd isn't close-on-exec; the dup is (F_DUPFD_CLOEXEC),
+    // and dropping the original closes fd 3.
+    inherited.try_clone().map(Some)
+}
+
+/// Listen on `activated` (from [`take_activated_socket`]), or bind `socket`
+/// (default `socket_path()`) ourselves.
+fn open_listener(
+    socket: Option<&Path>,
+    activated: Option<OwnedFd>,
+) -> Result<UnixListener, AgentError> {
+    if let Some(fd) = activated {
+        let listener = std::os::unix::net::UnixListener::from(fd);
+        listener.set_nonblocking(true).map_err(AgentError::Io)?;
+        eprintln!("[blunux-ai daemon] Listening on socket from systemd");
+        return UnixListener::from_std(listener).map_err(AgentError::Io);
+    }
+
+    let path = socket.map_or_else(socket_path, Path::to_path_buf);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(AgentError::Io)?;
+    }
+
+    // Remove stale socket file if present
+    if path.exists() {
+        std::fs::remove_file(&path).map_err(AgentError::Io)?;
+    }
+
+    let listener = UnixListener::bind(&path).map_err(AgentError::Io)?;
+
+    // Set socket permissions so only the current user can connect
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))
+            .map_err(AgentError::Io)?;
+    }
+
+    eprintln!("[blunux-ai daemon] Listening on {}", path.display());
+    Ok(listener)
+}
+
+/// Reload config.toml on SIGHUP, swapping in a freshly built agent.
+/// Conversations, the socket and the notification queue are untouched.
+fn spawn_reload_on_sighup(
+    mut config: AgentConfig,
+    blunux_config: PathBuf,
+    agent: SharedAgent,
+    limits: Arc<IpcLimits>,
+) -> Result<(), AgentError> {
+    let mut hangup = signal(SignalKind::hangup()).map_err(AgentError::Io)?;
+    tokio::spawn(async move {
+        while hangup.recv().await.is_some() {
+            match reload_agent(&mut config, &blunux_config, &agent).await {
+                Ok(()) => {
+                    limits.update(&config.daemon);
+                    eprintln!(
+                        "[blunux-ai daemon] Reloaded config ({})",
+                        config.model.display_name()
+                    );
+                }
+                Err(e) => {
+                    eprintln!("[blunux-ai daemon] Reload failed, keeping previous config: {e}");
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Re-read `config` from disk and replace the shared agent.
+/// Nothing changes unless both the config and the new agent are valid.
+async fn reload_agent(
+    config: &mut AgentConfig,
+    blunux_config: &Path,
+    agent: &SharedAgent,
+) -> Result<(), AgentError> {
+    let mut fresh = config.clone();
+    fresh.reload()?;
+    let rebuilt = Agent::new_daemon(&fresh)?.with_blunux_config(blunux_config);
+    *agent.write().await = Arc::new(rebuilt);
+    *config = fresh;
+    Ok(())
+}
+
+/// Result of reading one newline-delimited IPC message.
+#[derive(Debug, PartialEq)]
+enum LineRead {
+    Line(String),
+    /// The line exceeded the size limit and was discarded.
+    TooLong,
+    Eof,
+}
+
+/// Read one line of at most `max_len` bytes (excluding the newline).
+/// Oversized lines are skipped through to the next newline without being
+/// buffered, so a misbehaving client can't grow the daemon's memory.
+async fn read_line_bounded<R>(reader: &mut R, max_len: usize) -> std::io::Result<LineRead>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut buf = Vec::new();
+    let n = (&mut *reader)
+        .take(max_len as u64 + 1)
+        .read_until(b'\n', &mut buf)
+        .await?;
+    if n == 0 {
+        return Ok(LineRead::Eof);
+    }
+
+    if buf.last() == Some(&b'\n') {
+        buf.pop();
+    } else if buf.len() > max_len {
+        skip_line(reader).await?;
+        return Ok(LineRead::TooLong);
+    }
+    Ok(LineRead::Line(String::from_utf8_lossy(&buf).into_owned()))
+}
+
+/// Consume input up to and including the next newline (or EOF).
+async fn skip_line<R>(reader: &mut R) -> std::io::Result<()>
+where
+    R: AsyncBufRead + Unpin,
+{
+    loop {
+        let available = reader.fill_buf().await?;
+        if available.is_empty() {
+            return Ok(());
+        }
+        if let Some(pos) = available.iter().position(|&b| b == b'\n') {
+            reader.consume(pos + 1);
+            return Ok(());
+        }
+        let len = available.len();
+        reader.consume(len);
+    }
+}
+
+/// True if `phone` looks like a phone number: an optional leading `+`
+/// followed by 5–15 digits (E.164 allows at most 15).
+fn is_valid_phone(phone: &str) -> bool {
+    let digits = phone.strip_prefix('+').unwrap_or(phone);
+    (5..=15).contains(&digits.len()) && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+/// True if `phone` may use the agent: `allowed` is empty, or has an entry
+/// with the same digits (so "+82 10-1234-5678" matches "821012345678").
+fn is_allowed(phone: &str, allowed: &[String]) -> bool {
+    let digits = |s: &str| s.chars().filter(char::is_ascii_digit).collect::<String>();
+    allowed.is_empty() || allowed.iter().any(|n| digits(n) == digits(phone))
+}
+
 async fn handle_connection(
     stream: tokio::net::UnixStream,
-    agent: Arc<Mutex<Agent>>,
+    agent: SharedAgent,
+    conversations: Arc<ConversationStore>,
     notify_queue: NotifyQueue,
+    idle: Arc<IdleTimer>,
+    max_message_bytes: usize,
+    max_reply_chars: usize,
 ) -> Result<(), AgentError> {
     let (reader, mut writer) = stream.into_split();
-    let mut lines = BufReader::new(reader).lines();
+    let mut reader = BufReader::new(reader);
 
-    while let Some(line) = lines.next_line().await.map_err(AgentError::Io)? {
-        let line = line.trim().to_string();
+    loop {
+        let read = read_line_bounded(&mut reader, max_message_bytes)
+            .await
+            .map_err(AgentError::Io)?;
+        idle.touch(Instant::now());
+        let line = match read {
+            LineRead::Eof => break,
+            LineRead::TooLong => {
+                let err_resp = error_response(
+                    None,
+                    IpcErrorCode::BadRequest,
+                    &format!("Message exceeds {max_message_bytes} bytes"),
+                );
+                let mut json = serde_json::to_string(&err_resp).unwrap_or_default();
+                json.push('\n');
+                writer.write_all(json.as_bytes()).await.map_err(AgentError::Io)?;
+                continue;
+            }
+            LineRead::Line(line) => line,
+        };
+        let line = line.trim();
         if line.is_empty() {
             continue;
         }
 
-        let msg: IpcMessage = match serde_json::from_str(&line) {
+        let msg: IpcMessage = match serde_json::from_str(line) {
             Ok(m) => m,
             Err(e) => {
-                let err_resp = error_response(None, &format!("Invalid JSON: {e}"));
+                let err_resp = error_response(None, IpcErrorCode::BadRequest, &format!("Invalid JSON: {e}"));
                 let mut json = serde_json::to_string(&err_resp).unwrap_or_default();
                 json.push('\n');
                 let _ = writer.write_all(json.as_bytes()).await;
@@ -97,7 +605,13 @@ async fn handle_connection(
             }
         };
 
-        let response = process_ipc_message(msg, &agent, &notify_queue).await;
+        // A long agent turn keeps the daemon up until it finishes
+        let busy = idle.busy();
+        let current = Arc::clone(&*agent.read().await);
+        let response =
+            process_ipc_message(msg, &current, &conversations, &notify_queue, max_reply_chars)
+                .await;
+        drop(busy);
         let mut json = serde_json::to_string(&response).unwrap_or_default();
         json.push('\n');
         writer.write_all(json.as_bytes()).await.map_err(AgentError::Io)?;
@@ -108,37 +622,56 @@ async fn handle_connection(
 
 async fn process_ipc_message(
     msg: IpcMessage,
-    agent: &Arc<Mutex<Agent>>,
+    agent: &Agent,
+    conversations: &ConversationStore,
     notify_queue: &NotifyQueue,
+    max_reply_chars: usize,
 ) -> IpcMessage {
     match msg.msg_type {
         IpcMessageType::Message => {
             let phone = match &msg.from {
-                Some(p) => p.clone(),
+                Some(p) if is_valid_phone(p) => p.clone(),
+                Some(_) => {
+                    return error_response(
+                        None,
+                        IpcErrorCode::BadRequest,
+                        "Invalid 'from' field: expected a phone number",
+                    );
+                }
                 None => {
-                    return error_response(None, "Missing 'from' field");
+                    return error_response(None, IpcErrorCode::BadRequest, "Missing 'from' field");
                 }
             };
             let body = match &msg.body {
                 Some(b) => b.clone(),
                 None => {
-                    return error_response(Some(&phone), "Missing 'body' field");
+                    return error_response(
+                        Some(&phone),
+                        IpcErrorCode::BadRequest,
+                        "Missing 'body' field",
+                    );
                 }
             };
+            let whatsapp = agent.whatsapp();
+            if !is_allowed(&phone, &whatsapp.allowed_numbers) {
+                return error_response(
+                    Some(&phone),
+                    IpcErrorCode::Unauthorized,
+                    "Sender is not in allowed_numbers",
+                );
+            }
+            let limit = whatsapp.max_messages_per_minute;
+            if !conversations.rate_limiter.allow(&phone, limit, Instant::now()) {
+                return error_response(
+                    Some(&phone),
+                    IpcErrorCode::RateLimited,
+                    "Too many messages. Please wait a minute before sending more.",
+                );
+            }
 
-            let mut locked = agent.lock().await;
-            match locked.chat_as_user(&phone, &body).await {
-                Ok(reply) => IpcMessage {
-                    msg_type: IpcMessageType::Response,
-                    from: None,
-                    body: Some(reply),
-                    to: Some(phone),
-                    actions: None,
-                    action: None,
-                    notifications: None,
-                    timestamp: Some(utc_now()),
-                },
-                Err(e) => error_response(Some(&phone), &e.to_string()),
+            match conversations.chat(agent, &phone, &body).await {
+                Ok(reply) => reply_response(phone, &reply, max_reply_chars),
+                Err(e) => error_response(Some(&phone), IpcErrorCode::Internal, &e.to_string()),
             }
         }
         IpcMessageType::Action => {
@@ -152,13 +685,14 @@ async fn process_ipc_message(
                     actions: None,
                     action: None,
                     notifications: None,
+                    remaining: None,
+                    error_code: None,
                     timestamp: Some(utc_now()),
                 },
                 "reset" => {
                     let phone = msg.from.as_deref().unwrap_or("");
-                    if !phone.is_empty() {
-                        let mut locked = agent.lock().await;
-                        locked.reset_user_conversation(phone);
+                    if is_valid_phone(phone) {
+                        conversations.reset(phone).await;
                     }
                     IpcMessage {
                         msg_type: IpcMessageType::Response,
@@ -168,45 +702,97 @@ async fn process_ipc_message(
                         actions: None,
                         action: None,
                         notifications: None,
+                        remaining: None,
+                        error_code: None,
                         timestamp: Some(utc_now()),
                     }
                 }
                 "poll_notifications" => {
-                    // Drain up to 10 pending notifications per poll to avoid
-                    // sending a huge payload in one response.
-                    let mut queue = notify_queue.lock().await;
-                    let take = queue.len().min(10);
-                    let batch: Vec<(String, String)> = queue.drain(..take).collect();
-                    drop(queue);
-
-                    let items: Vec<serde_json::Value> = batch
-                        .into_iter()
-                        .map(|(to, body)| {
-                            serde_json::json!({ "to": to, "body": body })
-                        })
-                        .collect();
-
-                    IpcMessage {
-                        msg_type: IpcMessageType::Response,
-                        from: None,
-                        body: None,
-                        to: msg.from.clone(),
-                        actions: None,
-                        action: None,
-                        notifications: Some(items),
-                        timestamp: Some(utc_now()),
-                    }
+                    poll_notifications(notify_queue, msg.from.clone(), max_reply_chars).await
                 }
-                other => error_response(msg.from.as_deref(), &format!("Unknown action: {other}")),
+                other => error_response(
+                    msg.from.as_deref(),
+                    IpcErrorCode::BadRequest,
+                    &format!("Unknown action: {other}"),
+                ),
             }
         }
         IpcMessageType::Response => {
-            error_response(None, "Unexpected message type 'response' from client")
+            error_response(
+                None,
+                IpcErrorCode::BadRequest,
+                "Unexpected message type 'response' from client",
+            )
         }
     }
 }
 
-fn error_response(to: Option<&str>, reason: &str) -> IpcMessage {
+/// The `Response` to a WhatsApp message, in WhatsApp formatting. A reply over
+/// `max_reply_chars` is split: `body` holds the first part and
+/// `notifications` the rest, in order.
+fn reply_response(phone: String, reply: &str, max_reply_chars: usize) -> IpcMessage {
+    let mut chunks = split_message(&to_whatsapp(reply), max_reply_chars).into_iter();
+    let first = chunks.next().unwrap_or_default();
+    let rest: Vec<serde_json::Value> = chunks
+        .map(|body| serde_json::json!({ "to": phone, "body": body }))
+        .collect();
+
+    IpcMessage {
+        msg_type: IpcMessageType::Response,
+        from: None,
+        body: Some(first),
+        to: Some(phone),
+        actions: None,
+        action: None,
+        notifications: (!rest.is_empty()).then_some(rest),
+        remaining: None,
+        error_code: None,
+        timestamp: Some(utc_now()),
+    }
+}
+
+/// Maximum notifications returned by a single `poll_notifications` call.
+const POLL_BATCH_SIZE: usize = 10;
+
+/// Drain up to `POLL_BATCH_SIZE` pending notifications, reporting how many
+/// are still queued so the bridge can poll again without waiting. Bodies are
+/// put in WhatsApp formatting; those over `max_reply_chars` come back as
+/// several consecutive items.
+async fn poll_notifications(
+    notify_queue: &NotifyQueue,
+    to: Option<String>,
+    max_reply_chars: usize,
+) -> IpcMessage {
+    let mut queue = notify_queue.lock().await;
+    let take = queue.len().min(POLL_BATCH_SIZE);
+    let batch: Vec<(String, String)> = queue.drain(..take).collect();
+    let remaining = queue.len();
+    drop(queue);
+
+    let items: Vec<serde_json::Value> = batch
+        .into_iter()
+        .flat_map(|(to, body)| {
+            split_message(&to_whatsapp(&body), max_reply_chars)
+                .into_iter()
+                .map(move |chunk| serde_json::json!({ "to": to, "body": chunk }))
+        })
+        .collect();
+
+    IpcMessage {
+        msg_type: IpcMessageType::Response,
+        from: None,
+        body: None,
+        to,
+        actions: None,
+        action: None,
+        notifications: Some(items),
+        remaining: Some(remaining),
+        error_code: None,
+        timestamp: Some(utc_now()),
+    }
+}
+
+fn error_response(to: Option<&str>, code: IpcErrorCode, reason: &str) -> IpcMessage {
     IpcMessage {
         msg_type: IpcMessageType::Response,
         from: None,
@@ -215,6 +801,8 @@ fn error_response(to: Option<&str>, reason: &str) -> IpcMessage {
         actions: None,
         action: None,
         notifications: None,
+        remaining: None,
+        error_code: Some(code),
         timestamp: Some(utc_now()),
     }
 }
@@ -222,3 +810,527 @@ fn error_response(to: Option<&str>, reason: &str) -> IpcMessage {
 fn utc_now() -> String {
     chrono::Utc::now().to_rfc3339()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ModelId;
+    use crate::test_support::{test_config, FailingProvider};
+    use crate::error::ProviderError;
+    use crate::providers::{CompletionResult, ContentBlock, Provider, StopReason, Usage};
+    use crate::tools::ToolDefinition;
+
+    /// Provider whose completions only finish once `barrier` is full (a
+    /// barrier of 1 never blocks). Replies with the conversation length.
+    struct BarrierProvider {
+        barrier: tokio::sync::Barrier,
+    }
+
+    #[async_trait::async_trait]
+    impl Provider for BarrierProvider {
+        fn name(&self) -> &str {
+            "barrier"
+        }
+
+        async fn complete(
+            &self,
+            _system_prompt: &str,
+            messages: &[Message],
+            _tools: &[ToolDefinition],
+            _max_tokens: u32,
+        ) -> Result<CompletionResult, ProviderError> {
+            self.barrier.wait().await;
+            Ok(CompletionResult {
+                content: vec![ContentBlock::Text {
+                    text: format!("turns: {}", messages.len()),
+                }],
+                stop_reason: StopReason::EndTurn,
+                usage: Usage::default(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_two_users_processed_concurrently() {
+        let tmp = tempfile::tempdir().unwrap();
+        let provider = BarrierProvider {
+            barrier: tokio::sync::Barrier::new(2),
+        };
+        let agent = Arc::new(Agent::with_provider(&test_config(tmp.path()), Box::new(provider)));
+        let store = Arc::new(ConversationStore::default());
+
+        // Each request blocks in the provider until the other one arrives, so
+        // this only completes if the two users are not serialized.
+        let spawn_chat = |phone: &'static str| {
+            let agent = Arc::clone(&agent);
+            let store = Arc::clone(&store);
+            tokio::spawn(async move { store.chat(&agent, phone, "hello").await })
+        };
+        let a = spawn_chat("+821011112222");
+        let b = spawn_chat("+821033334444");
+
+        let (a, b) = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            (a.await.unwrap(), b.await.unwrap())
+        })
+        .await
+        .expect("users were serialized — requests deadlocked");
+        assert_eq!(a.unwrap(), "turns: 1");
+        assert_eq!(b.unwrap(), "turns: 1");
+
+        // Histories are kept per user
+        let slot = store.slot("+821011112222").await;
+        assert_eq!(slot.lock().await.len(), 2);
+        store.reset("+821011112222").await;
+        assert!(store.slot("+821011112222").await.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_store_evicts_least_recently_used_user() {
+        let tmp = tempfile::tempdir().unwrap();
+        let provider = BarrierProvider {
+            barrier: tokio::sync::Barrier::new(1),
+        };
+        let agent = Agent::with_provider(&test_config(tmp.path()), Box::new(provider));
+        let store = ConversationStore::new(2, 100);
+
+        store.chat(&agent, "+821011110000", "hi").await.unwrap();
+        store.chat(&agent, "+821022220000", "hi").await.unwrap();
+        // Touch the first user so the second becomes least recently used
+        store.chat(&agent, "+821011110000", "again").await.unwrap();
+        store.chat(&agent, "+821033330000", "hi").await.unwrap();
+
+        let users = store.users.lock().await;
+        assert_eq!(users.slots.len(), 2);
+        assert!(users.slots.contains_key("+821011110000"));
+        assert!(!users.slots.contains_key("+821022220000"));
+        assert!(users.slots.contains_key("+821033330000"));
+    }
+
+    #[tokio::test]
+    async fn test_store_caps_messages_per_user() {
+        let tmp = tempfile::tempdir().unwrap();
+        let provider = BarrierProvider {
+            barrier: tokio::sync::Barrier::new(1),
+        };
+        let agent = Agent::with_provider(&test_config(tmp.path()), Box::new(provider));
+        let store = ConversationStore::new(10, 4);
+
+        for i in 0..5 {
+            store.chat(&agent, "+821011110000", &format!("msg {i}")).await.unwrap();
+        }
+        let slot = store.slot("+821011110000").await;
+        let conversation = slot.lock().await;
+        assert_eq!(conversation.len(), 4);
+        // The oldest turns went first; the history still opens on a user turn
+        assert_eq!(conversation[0].role, crate::providers::Role::User);
+        assert!(format!("{:?}", conversation[0].content).contains("msg 3"));
+    }
+
+    /// Calls `search_memory` until the conversation has `until` messages,
+    /// then answers, so one turn can be made as long as needed.
+    struct ToolLoopProvider {
+        until: usize,
+    }
+
+    #[async_trait::async_trait]
+    impl Provider for ToolLoopProvider {
+        fn name(&self) -> &str {
+            "tool-loop"
+        }
+
+        async fn complete(
+            &self,
+            _system_prompt: &str,
+            messages: &[Message],
+            _tools: &[ToolDefinition],
+            _max_tokens: u32,
+        ) -> Result<CompletionResult, ProviderError> {
+            let (content, stop_reason) = if messages.len() < self.until {
+                let id = format!("t{}", messages.len());
+                let input = serde_json::json!({"query": "x"});
+                let call = ContentBlock::ToolUse { id, name: "search_memory".into(), input };
+                (vec![call], StopReason::ToolUse)
+            } else {
+                let text = ContentBlock::Text { text: format!("turns: {}", messages.len()) };
+                (vec![text], StopReason::EndTurn)
+            };
+            Ok(CompletionResult {
+                content,
+                stop_reason,
+                usage: Usage::default(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_store_keeps_latest_turn_over_cap() {
+        let tmp = tempfile::tempdir().unwrap();
+        let agent = Agent::with_provider(
+            &test_config(tmp.path()),
+            Box::new(ToolLoopProvider { until: 11 }),
+        );
+        let store = ConversationStore::new(10, 4);
+        let phone = "+821011110000";
+        store.slot(phone).await.lock().await.extend([
+            Message::user("earlier question"),
+            Message::assistant_text("earlier answer"),
+        ]);
+
+        // user, 4 × (tool call, result), answer: 10 messages in one turn
+        store.chat(&agent, phone, "check everything").await.unwrap();
+
+        let slot = store.slot(phone).await;
+        let conversation = slot.lock().await;
+        assert_eq!(conversation.len(), 10, "the latest turn is kept whole");
+        assert!(format!("{:?}", conversation[0].content).contains("check everything"));
+    }
+
+    #[tokio::test]
+    async fn test_reload_swaps_model_and_keeps_old_on_error() {
+        let tmp = tempfile::tempdir().unwrap();
+        let blunux_config = tmp.path().join("config.toml.blunux");
+        let mut config = test_config(tmp.path());
+        config.save().unwrap();
+        let agent: SharedAgent = Arc::new(RwLock::new(Arc::new(
+            Agent::new_daemon(&config).unwrap().with_blunux_config(&blunux_config),
+        )));
+
+        let mut edited = config.clone();
+        edited.model = ModelId::ClaudeOpus46;
+        edited.whatsapp.allowed_numbers = vec!["+821055550000".into()];
+        edited.save().unwrap();
+        reload_agent(&mut config, &blunux_config, &agent).await.unwrap();
+        assert_eq!(agent.read().await.model(), &ModelId::ClaudeOpus46);
+        // The scheduler reads its recipients from the swapped-in agent
+        assert_eq!(agent.read().await.whatsapp().allowed_numbers, ["+821055550000"]);
+        assert_eq!(config.model, ModelId::ClaudeOpus46);
+
+        // A broken file leaves the running agent and config untouched
+        std::fs::write(tmp.path().join("config.toml"), "[agent\nprovider = ").unwrap();
+        assert!(reload_agent(&mut config, &blunux_config, &agent).await.is_err());
+        assert_eq!(agent.read().await.model(), &ModelId::ClaudeOpus46);
+        assert_eq!(config.model, ModelId::ClaudeOpus46);
+    }
+
+    #[tokio::test]
+    async fn test_error_responses_carry_codes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = test_config(tmp.path());
+        config.daemon.max_message_bytes = 256;
+        config.whatsapp.allowed_numbers = vec!["+821012345678".into()];
+        config.whatsapp.max_messages_per_minute = 2;
+        let agent: SharedAgent = Arc::new(RwLock::new(Arc::new(Agent::with_provider(
+            &config,
+            Box::new(FailingProvider),
+        ))));
+        let sock = tmp.path().join("daemon.sock");
+        let listener = UnixListener::bind(&sock).unwrap();
+        let limits = Arc::new(IpcLimits::new(&config.daemon));
+        tokio::spawn(async move { serve(listener, agent, &config, limits).await });
+
+        let stream = tokio::net::UnixStream::connect(&sock).await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+
+        let cases = [
+            ("{not json".to_string(), IpcErrorCode::BadRequest),
+            ("x".repeat(300), IpcErrorCode::BadRequest),
+            (r#"{"type":"message","body":"hi"}"#.into(), IpcErrorCode::BadRequest),
+            (r#"{"type":"message","from":"+821012345678"}"#.into(), IpcErrorCode::BadRequest),
+            (r#"{"type":"action","action":"explode"}"#.into(), IpcErrorCode::BadRequest),
+            (r#"{"type":"response"}"#.into(), IpcErrorCode::BadRequest),
+            (
+                r#"{"type":"message","from":"+821099990000","body":"hi"}"#.into(),
+                IpcErrorCode::Unauthorized,
+            ),
+            (
+                r#"{"type":"message","from":"+821012345678","body":"hi"}"#.into(),
+                IpcErrorCode::Internal,
+            ),
+            (
+                r#"{"type":"message","from":"+821012345678","body":"hi"}"#.into(),
+                IpcErrorCode::Internal,
+            ),
+            (
+                r#"{"type":"message","from":"+821012345678","body":"hi"}"#.into(),
+                IpcErrorCode::RateLimited,
+            ),
+        ];
+        for (line, code) in cases {
+            writer.write_all(format!("{line}\n").as_bytes()).await.unwrap();
+            let resp = lines.next_line().await.unwrap().unwrap();
+            let resp: IpcMessage = serde_json::from_str(&resp).unwrap();
+            assert_eq!(resp.error_code, Some(code), "for {line}");
+            assert!(resp.body.unwrap().starts_with("Error: "));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_client_against_running_daemon() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = test_config(tmp.path());
+        let provider = BarrierProvider {
+            barrier: tokio::sync::Barrier::new(1),
+        };
+        let agent: SharedAgent = Arc::new(RwLock::new(Arc::new(Agent::with_provider(
+            &config,
+            Box::new(provider),
+        ))));
+
+        let sock = tmp.path().join("daemon.sock");
+        let listener = UnixListener::bind(&sock).unwrap();
+        let limits = Arc::new(IpcLimits::new(&config.daemon));
+        tokio::spawn(async move { serve(listener, agent, &config, limits).await });
+
+        let mut client = crate::ipc::Client::connect(&sock).await.unwrap();
+        let pong = client.ping().await.unwrap();
+        assert_eq!(pong.msg_type, IpcMessageType::Response);
+        assert_eq!(pong.body.as_deref(), Some("pong"));
+
+        let reply = client.message("+821012345678", "hello").await.unwrap();
+        assert_eq!(reply.body.as_deref(), Some("turns: 1"));
+        assert_eq!(reply.to.as_deref(), Some("+821012345678"));
+        let reply = client.message("+821012345678", "again").await.unwrap();
+        assert_eq!(reply.body.as_deref(), Some("turns: 3"));
+
+        let polled = client.poll_notifications().await.unwrap();
+        assert_eq!(polled.notifications.map(|n| n.len()), Some(0));
+        assert_eq!(polled.remaining, Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_send_once_to_spawned_daemon() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = test_config(tmp.path());
+        let provider = BarrierProvider {
+            barrier: tokio::sync::Barrier::new(1),
+        };
+        let agent: SharedAgent = Arc::new(RwLock::new(Arc::new(Agent::with_provider(
+            &config,
+            Box::new(provider),
+        ))));
+        let sock = tmp.path().join("daemon.sock");
+        let listener = UnixListener::bind(&sock).unwrap();
+        let limits = Arc::new(IpcLimits::new(&config.daemon));
+        tokio::spawn(async move { serve(listener, agent, &config, limits).await });
+
+        let send = |msg: IpcMessage| {
+            let sock = sock.clone();
+            async move { crate::ipc::send_once(&sock, &msg).await.unwrap() }
+        };
+        assert_eq!(send(IpcMessage::action("ping", None)).await.body.as_deref(), Some("pong"));
+        let reply = send(IpcMessage::message("+821012345678", "health check")).await;
+        assert_eq!(reply.body.as_deref(), Some("turns: 1"));
+        let reset = send(IpcMessage::action("reset", Some("+821012345678"))).await;
+        assert_eq!(reset.body.as_deref(), Some("Conversation reset."));
+        let unknown = send(IpcMessage::action("explode", None)).await;
+        assert_eq!(unknown.body.as_deref(), Some("Error: Unknown action: explode"));
+        assert_eq!(unknown.error_code, Some(IpcErrorCode::BadRequest));
+        assert_eq!(reset.error_code, None);
+    }
+
+    #[test]
+    fn test_idle_timer_expires_after_timeout() {
+        let t0 = Instant::now();
+        let timer = IdleTimer::new(30, t0);
+        assert_eq!(timer.remaining(t0), Some(Duration::from_secs(30)));
+        assert_eq!(timer.remaining(t0 + Duration::from_secs(10)), Some(Duration::from_secs(20)));
+
+        // Activity resets the countdown
+        timer.touch(t0 + Duration::from_secs(20));
+        assert_eq!(timer.remaining(t0 + Duration::from_secs(45)), Some(Duration::from_secs(5)));
+        assert_eq!(timer.remaining(t0 + Duration::from_secs(50)), Some(Duration::ZERO));
+        assert_eq!(timer.remaining(t0 + Duration::from_secs(90)), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_idle_timer_waits_for_requests_in_flight() {
+        let t0 = Instant::now();
+        let timer = IdleTimer::new(30, t0);
+        let busy = timer.busy();
+        let second = timer.busy();
+        assert_eq!(timer.remaining(t0 + Duration::from_secs(90)), Some(Duration::from_secs(30)));
+        drop(busy);
+        assert_eq!(timer.remaining(t0 + Duration::from_secs(90)), Some(Duration::from_secs(30)));
+        drop(second);
+        // The countdown restarts when the last request finishes
+        assert!(timer.remaining(Instant::now()).is_some_and(|d| d > Duration::from_secs(29)));
+    }
+
+    /// Provider that takes `delay` to answer.
+    struct SlowProvider {
+        delay: Duration,
+    }
+
+    #[async_trait::async_trait]
+    impl Provider for SlowProvider {
+        fn name(&self) -> &str {
+            "slow"
+        }
+
+        async fn complete(
+            &self,
+            _system_prompt: &str,
+            _messages: &[Message],
+            _tools: &[ToolDefinition],
+            _max_tokens: u32,
+        ) -> Result<CompletionResult, ProviderError> {
+            tokio::time::sleep(self.delay).await;
+            Ok(CompletionResult {
+                content: vec![ContentBlock::Text { text: "done".into() }],
+                stop_reason: StopReason::EndTurn,
+                usage: Usage::default(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_idle_shutdown_waits_for_slow_turn() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = test_config(tmp.path());
+        config.daemon.idle_shutdown_secs = 1;
+        let provider = SlowProvider {
+            delay: Duration::from_millis(2500),
+        };
+        let agent: SharedAgent = Arc::new(RwLock::new(Arc::new(Agent::with_provider(
+            &config,
+            Box::new(provider),
+        ))));
+        let sock = tmp.path().join("daemon.sock");
+        let listener = UnixListener::bind(&sock).unwrap();
+        let limits = Arc::new(IpcLimits::new(&config.daemon));
+        let server = tokio::spawn(async move { serve(listener, agent, &config, limits).await });
+
+        let mut client = crate::ipc::Client::connect(&sock).await.unwrap();
+        let reply = client.message("+821012345678", "slow question").await.unwrap();
+        assert_eq!(reply.body.as_deref(), Some("done"));
+        assert!(!server.is_finished(), "shut down with a turn in flight");
+
+        // Once idle again, it does shut down
+        let stopped = tokio::time::timeout(Duration::from_secs(5), server).await;
+        assert!(matches!(stopped, Ok(Ok(Ok(())))));
+    }
+
+    #[test]
+    fn test_activated_fd_detection() {
+        assert_eq!(activated_fd(Some("4242"), Some("1"), 4242), Some(3));
+        assert_eq!(activated_fd(Some("4242"), Some("2"), 4242), Some(3));
+        // Variables meant for another process (e.g. inherited by a child)
+        assert_eq!(activated_fd(Some("1111"), Some("1"), 4242), None);
+        assert_eq!(activated_fd(Some("4242"), Some("0"), 4242), None);
+        assert_eq!(activated_fd(None, Some("1"), 4242), None);
+        assert_eq!(activated_fd(Some("4242"), None, 4242), None);
+        assert_eq!(activated_fd(Some("abc"), Some("1"), 4242), None);
+    }
+
+    #[test]
+    fn test_idle_timer_disabled() {
+        let t0 = Instant::now();
+        let timer = IdleTimer::new(0, t0);
+        assert_eq!(timer.remaining(t0 + Duration::from_secs(86_400)), None);
+    }
+
+    #[tokio::test]
+    async fn test_read_line_bounded_rejects_over_length() {
+        let long = "x".repeat(100);
+        let input = format!("{{\"type\":\"action\"}}\n{long}\nnext\n");
+        let mut reader = input.as_bytes();
+
+        assert_eq!(
+            read_line_bounded(&mut reader, 32).await.unwrap(),
+            LineRead::Line("{\"type\":\"action\"}".into())
+        );
+        assert_eq!(
+            read_line_bounded(&mut reader, 32).await.unwrap(),
+            LineRead::TooLong
+        );
+        // The rest of the oversized line is discarded, not parsed as a message
+        assert_eq!(
+            read_line_bounded(&mut reader, 32).await.unwrap(),
+            LineRead::Line("next".into())
+        );
+        assert_eq!(read_line_bounded(&mut reader, 32).await.unwrap(), LineRead::Eof);
+    }
+
+    #[tokio::test]
+    async fn test_read_line_bounded_exact_limit() {
+        let mut reader = "abcd\nabcde".as_bytes();
+        assert_eq!(
+            read_line_bounded(&mut reader, 4).await.unwrap(),
+            LineRead::Line("abcd".into())
+        );
+        assert_eq!(read_line_bounded(&mut reader, 4).await.unwrap(), LineRead::TooLong);
+    }
+
+    #[test]
+    fn test_rate_limiter_window() {
+        let limiter = RateLimiter::default();
+        let t0 = Instant::now();
+        assert!(limiter.allow("+821012345678", 2, t0));
+        assert!(limiter.allow("+821012345678", 2, t0 + Duration::from_secs(10)));
+        assert!(!limiter.allow("+821012345678", 2, t0 + Duration::from_secs(20)));
+        assert!(limiter.allow("+821099990000", 2, t0 + Duration::from_secs(20)));
+        // The first message leaves the window after a minute
+        assert!(limiter.allow("+821012345678", 2, t0 + Duration::from_secs(60)));
+        assert!(limiter.allow("+821012345678", 0, t0 + Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn test_is_allowed() {
+        assert!(is_allowed("+821012345678", &[]));
+        let allowed = vec!["+82 10-1234-5678".to_string()];
+        assert!(is_allowed("821012345678", &allowed));
+        assert!(!is_allowed("+821099990000", &allowed));
+    }
+
+    #[test]
+    fn test_is_valid_phone() {
+        assert!(is_valid_phone("+821012345678"));
+        assert!(is_valid_phone("821012345678"));
+        assert!(!is_valid_phone("__poll__"));
+        assert!(!is_valid_phone("+82 10 1234"));
+        assert!(!is_valid_phone("+1234567890123456"));
+        assert!(!is_valid_phone(""));
+    }
+
+    #[tokio::test]
+    async fn test_poll_notifications_reports_remaining() {
+        let queue: NotifyQueue = Arc::new(Mutex::new(VecDeque::new()));
+        {
+            let mut q = queue.lock().await;
+            for i in 0..25 {
+                q.push_back(("+821012345678".into(), format!("msg {i}")));
+            }
+        }
+
+        let first = poll_notifications(&queue, Some("__poll__".into()), 4000).await;
+        assert_eq!(first.notifications.as_ref().unwrap().len(), 10);
+        assert_eq!(first.remaining, Some(15));
+        assert_eq!(first.to.as_deref(), Some("__poll__"));
+
+        let second = poll_notifications(&queue, None, 4000).await;
+        assert_eq!(second.remaining, Some(5));
+        let third = poll_notifications(&queue, None, 4000).await;
+        assert_eq!(third.notifications.as_ref().unwrap().len(), 5);
+        assert_eq!(third.remaining, Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_long_notification_is_split_for_whatsapp() {
+        let queue: NotifyQueue = Arc::new(Mutex::new(VecDeque::new()));
+        let long = format!("{}\n\n", "disk ".repeat(40)).repeat(6);
+        queue.lock().await.push_back(("+821012345678".into(), long));
+
+        let polled = poll_notifications(&queue, None, 500).await;
+        let items = polled.notifications.unwrap();
+        assert_eq!(items.len(), 3);
+        assert!(items[0]["body"].as_str().unwrap().starts_with("(1/3)\n"));
+        assert!(items.iter().all(|i| i["to"] == "+821012345678"));
+
+        let reply = reply_response("+821012345678".into(), &"word ".repeat(300), 500);
+        assert!(reply.body.unwrap().starts_with("(1/"));
+        assert_eq!(reply.notifications.map(|n| n.len()), Some(3));
+        let short = reply_response("+821012345678".into(), "ok", 500);
+        assert_eq!((short.body.as_deref(), short.notifications), (Some("ok"), None));
+    }
+}
@@ -0,0 +1,326 @@
+use std::ffi::OsStr;
+use std::path::Path;
+
+use blunux_config::util::find_in_path;
+
+use crate::config::{AgentConfig, ClaudeMode, Language, ProviderType};
+use crate::providers::build_provider;
+use crate::strings;
+
+// ── Check results ────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CheckStatus {
+    Pass,
+    /// Something will degrade but the agent still works.
+    Warn,
+    /// The agent can't work until this is fixed.
+    Fail,
+}
+
+/// What a [`CheckResult`] is about; its display name comes from
+/// [`strings::doctor_check_name`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Check {
+    Config,
+    Credentials,
+    Provider,
+    Yay,
+    Distro,
+}
+
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    /// Display name of the check, in the user's language.
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+    /// What to do about a warning or failure.
+    pub hint: Option<String>,
+}
+
+impl CheckResult {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Pass,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Warn,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Fail,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    /// One line per check, plus an indented hint line when there is one.
+    pub fn render(&self) -> String {
+        let mark = match self.status {
+            CheckStatus::Pass => "✓",
+            CheckStatus::Warn => "!",
+            CheckStatus::Fail => "✗",
+        };
+        let mut out = format!("  [{mark}] {}: {}", self.name, self.detail);
+        if let Some(hint) = &self.hint {
+            out.push_str(&format!("\n      → {hint}"));
+        }
+        out
+    }
+}
+
+// ── Individual checks ────────────────────────────────────────────────────────
+
+/// The agent config exists and parses.
+pub fn check_config(config_dir: &Path, lang: &Language) -> (CheckResult, Option<AgentConfig>) {
+    let name = strings::doctor_check_name(lang, Check::Config);
+    match AgentConfig::load(config_dir) {
+        Ok(cfg) => (
+            CheckResult::pass(name, config_dir.join("config.toml").display().to_string()),
+            Some(cfg),
+        ),
+        Err(e) => (
+            CheckResult::fail(name, e.to_string(), strings::doctor_run_setup(lang)),
+            None,
+        ),
+    }
+}
+
+/// The API key file exists for API providers, or the `claude` CLI is on
+/// `path` for OAuth mode.
+pub fn check_credentials(cfg: &AgentConfig, path: Option<&OsStr>, lang: &Language) -> CheckResult {
+    let name = strings::doctor_check_name(lang, Check::Credentials);
+    let key_file = match (&cfg.provider, &cfg.claude_mode) {
+        (ProviderType::Claude, ClaudeMode::OAuth) => {
+            return match find_in_path("claude", path) {
+                Some(bin) => CheckResult::pass(name, strings::doctor_claude_cli_found(lang, &bin)),
+                None => CheckResult::fail(
+                    name,
+                    strings::doctor_claude_cli_missing(lang),
+                    strings::doctor_claude_cli_hint(lang),
+                ),
+            };
+        }
+        (ProviderType::Claude, ClaudeMode::Api) => cfg.config_dir.join("credentials/claude"),
+        (ProviderType::DeepSeek, _) => cfg.config_dir.join("credentials/deepseek"),
+    };
+    match crate::config::load_credential(&key_file) {
+        Ok(_) => CheckResult::pass(name, key_file.display().to_string()),
+        Err(e) => CheckResult::fail(name, e.to_string(), strings::doctor_save_api_key(lang)),
+    }
+}
+
+/// The provider answers a health check with these credentials.
+pub async fn check_provider(cfg: &AgentConfig, lang: &Language) -> CheckResult {
+    let name = strings::doctor_check_name(lang, Check::Provider);
+    let provider = match build_provider(cfg) {
+        Ok(p) => p,
+        Err(e) => return CheckResult::fail(name, e.to_string(), strings::doctor_run_setup(lang)),
+    };
+    match provider.health_check().await {
+        Ok(()) => CheckResult::pass(name, strings::doctor_provider_reachable(lang, provider.name())),
+        Err(e) => CheckResult::fail(
+            name,
+            format!("{}: {e}", provider.name()),
+            strings::doctor_check_network(lang),
+        ),
+    }
+}
+
+/// `yay` is on `path`; the package tools install through it.
+pub fn check_yay(path: Option<&OsStr>, lang: &Language) -> CheckResult {
+    let name = strings::doctor_check_name(lang, Check::Yay);
+    match find_in_path("yay", path) {
+        Some(bin) => CheckResult::pass(name, bin.display().to_string()),
+        None => CheckResult::warn(
+            name,
+            strings::doctor_yay_missing(lang),
+            strings::doctor_yay_hint(lang),
+        ),
+    }
+}
+
+/// `/etc/os-release` names Arch or an Arch derivative (`ID` or `ID_LIKE`).
+pub fn check_distro(os_release: Option<&str>, lang: &Language) -> CheckResult {
+    let check = strings::doctor_check_name(lang, Check::Distro);
+    let Some(os_release) = os_release else {
+        return CheckResult::warn(
+            check,
+            strings::doctor_os_release_unreadable(lang),
+            strings::doctor_arch_hint(lang),
+        );
+    };
+    let field = |key: &str| {
+        os_release.lines().find_map(|line| {
+            line.strip_prefix(key)
+                .and_then(|v| v.strip_prefix('='))
+                .map(|v| v.trim_matches('"').to_string())
+        })
+    };
+    let id = field("ID").unwrap_or_default();
+    let like = field("ID_LIKE").unwrap_or_default();
+    let name = field("PRETTY_NAME").unwrap_or_else(|| id.clone());
+
+    if id == "arch" || like.split_whitespace().any(|l| l == "arch") {
+        CheckResult::pass(check, name)
+    } else {
+        CheckResult::warn(
+            check,
+            strings::doctor_not_arch(lang, &name),
+            strings::doctor_arch_hint(lang),
+        )
+    }
+}
+
+// ── Checklist ────────────────────────────────────────────────────────────────
+
+/// Run every check against the live system. Checks that need a valid config
+/// are skipped when it fails to load.
+pub async fn run_checks(config_dir: &Path, lang: &Language) -> Vec<CheckResult> {
+    let path = std::env::var_os("PATH");
+    let (config_check, cfg) = check_config(config_dir, lang);
+    let mut results = vec![config_check];
+
+    if let Some(cfg) = cfg {
+        let credentials = check_credentials(&cfg, path.as_deref(), lang);
+        let credentials_ok = credentials.status == CheckStatus::Pass;
+        results.push(credentials);
+        if credentials_ok {
+            results.push(check_provider(&cfg, lang).await);
+        }
+    }
+
+    results.push(check_yay(path.as_deref(), lang));
+    let os_release = std::fs::read_to_string("/etc/os-release").ok();
+    results.push(check_distro(os_release.as_deref(), lang));
+    results
+}
+
+pub fn has_failures(results: &[CheckResult]) -> bool {
+    results.iter().any(|r| r.status == CheckStatus::Fail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EN: Language = Language::English;
+
+    fn test_config(dir: &Path, provider: ProviderType, claude_mode: ClaudeMode) -> AgentConfig {
+        AgentConfig {
+            provider,
+            claude_mode,
+            ..crate::test_support::test_config(dir)
+        }
+    }
+
+    #[test]
+    fn test_check_config_missing_fails() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (result, cfg) = check_config(tmp.path(), &EN);
+        assert_eq!(result.status, CheckStatus::Fail);
+        assert!(result.hint.unwrap().contains("blunux-ai setup"));
+        assert!(cfg.is_none());
+    }
+
+    #[test]
+    fn test_check_config_present_passes() {
+        let tmp = tempfile::tempdir().unwrap();
+        test_config(tmp.path(), ProviderType::Claude, ClaudeMode::OAuth)
+            .save()
+            .unwrap();
+        let (result, cfg) = check_config(tmp.path(), &EN);
+        assert_eq!(result.status, CheckStatus::Pass);
+        assert!(cfg.is_some());
+    }
+
+    #[test]
+    fn test_check_credentials_api_key() {
+        let tmp = tempfile::tempdir().unwrap();
+        let cfg = test_config(tmp.path(), ProviderType::DeepSeek, ClaudeMode::Api);
+        assert_eq!(check_credentials(&cfg, None, &EN).status, CheckStatus::Fail);
+
+        std::fs::create_dir_all(tmp.path().join("credentials")).unwrap();
+        std::fs::write(tmp.path().join("credentials/deepseek"), "sk-test\n").unwrap();
+        assert_eq!(check_credentials(&cfg, None, &EN).status, CheckStatus::Pass);
+    }
+
+    fn write_executable(path: &Path) {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::write(path, "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[test]
+    fn test_check_credentials_oauth_needs_cli() {
+        let tmp = tempfile::tempdir().unwrap();
+        let cfg = test_config(tmp.path(), ProviderType::Claude, ClaudeMode::OAuth);
+        let path = tmp.path().as_os_str();
+        assert_eq!(check_credentials(&cfg, Some(path), &EN).status, CheckStatus::Fail);
+
+        write_executable(&tmp.path().join("claude"));
+        assert_eq!(check_credentials(&cfg, Some(path), &EN).status, CheckStatus::Pass);
+    }
+
+    #[test]
+    fn test_check_yay() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().as_os_str();
+        let missing = check_yay(Some(path), &EN);
+        assert_eq!(missing.status, CheckStatus::Warn);
+        assert!(missing.hint.is_some());
+
+        write_executable(&tmp.path().join("yay"));
+        assert_eq!(check_yay(Some(path), &EN).status, CheckStatus::Pass);
+    }
+
+    #[test]
+    fn test_check_distro() {
+        let arch = "NAME=\"Arch Linux\"\nPRETTY_NAME=\"Arch Linux\"\nID=arch\n";
+        assert_eq!(check_distro(Some(arch), &EN).status, CheckStatus::Pass);
+
+        let derivative = "PRETTY_NAME=\"EndeavourOS\"\nID=\"endeavouros\"\nID_LIKE=\"arch\"\n";
+        assert_eq!(check_distro(Some(derivative), &EN).status, CheckStatus::Pass);
+
+        let ubuntu = "PRETTY_NAME=\"Ubuntu 24.04\"\nID=ubuntu\nID_LIKE=debian\n";
+        let result = check_distro(Some(ubuntu), &EN);
+        assert_eq!(result.status, CheckStatus::Warn);
+        assert!(result.detail.contains("Ubuntu 24.04"));
+
+        assert_eq!(check_distro(None, &EN).status, CheckStatus::Warn);
+    }
+
+    #[test]
+    fn test_has_failures_ignores_warnings() {
+        let warn = CheckResult::warn("yay", "missing", "install it");
+        let fail = CheckResult::fail("Config", "missing", "run setup");
+        assert!(!has_failures(std::slice::from_ref(&warn)));
+        assert!(has_failures(&[warn, fail.clone()]));
+        assert!(fail.render().contains("[✗] Config: missing\n      → run setup"));
+    }
+
+    #[test]
+    fn test_checks_rendered_in_korean() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (result, _) = check_config(tmp.path(), &Language::Korean);
+        assert_eq!(result.name, "설정");
+        assert_eq!(result.hint.as_deref(), Some(strings::doctor_run_setup(&Language::Korean)));
+
+        let ubuntu = "PRETTY_NAME=\"Ubuntu 24.04\"\nID=ubuntu\n";
+        let result = check_distro(Some(ubuntu), &Language::Korean);
+        assert!(result.render().contains("[!] 배포판: Ubuntu 24.04은(는) Arch 기반이 아닙니다"));
+    }
+}
@@ -14,12 +14,15 @@ pub enum AgentError {
     #[error("Config error: {0}")]
     Config(#[from] ConfigError),
 
-    #[error("Safety block: {reason}")]
-    SafetyBlock { reason: String },
-
     #[error("User cancelled")]
     UserCancelled,
 
+    #[error("Another blunux-ai daemon is already running (lock held on {})", lock.display())]
+    AlreadyRunning { lock: std::path::PathBuf },
+
+    #[error("Unsupported agent state schema version {found} (expected {expected})")]
+    UnsupportedStateVersion { found: u32, expected: u32 },
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 }
@@ -36,7 +39,10 @@ pub enum ProviderError {
     AuthenticationFailed,
 
     #[error("Network error: {0}")]
-    Network(#[from] reqwest::Error),
+    Network(reqwest::Error),
+
+    #[error("Request timed out — the model took too long to respond")]
+    Timeout,
 
     #[error("OAuth subprocess exited {exit_code}: {stderr}")]
     SubprocessError { exit_code: i32, stderr: String },
@@ -48,6 +54,17 @@ pub enum ProviderError {
     EmptyResponse,
 }
 
+impl From<reqwest::Error> for ProviderError {
+    /// Timeouts get their own variant so they aren't mistaken for a network outage.
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            Self::Timeout
+        } else {
+            Self::Network(e)
+        }
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum ToolError {
     #[error("Command `{command}` failed (exit {exit_code}): {stderr}")]
@@ -60,9 +77,18 @@ pub enum ToolError {
     #[error("Command timed out after {secs}s")]
     Timeout { secs: u64 },
 
+    #[error("`{command}` is not installed — {hint}")]
+    MissingCommand { command: String, hint: String },
+
     #[error("Invalid tool input: {0}")]
     InvalidInput(String),
 
+    #[error("Invalid tool name `{name}`: use only a-z, 0-9 and _")]
+    InvalidName { name: String },
+
+    #[error("Tool `{name}` is already registered")]
+    DuplicateName { name: String },
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 }
@@ -90,6 +116,9 @@ pub enum ConfigError {
     #[error("Invalid value for {field}: {value}")]
     InvalidValue { field: String, value: String },
 
+    #[error("Unknown prompt `{name}` (available: {available})")]
+    UnknownPrompt { name: String, available: String },
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 }
@@ -141,4 +170,35 @@ mod tests {
         let ae: AgentError = pe.into();
         assert!(format!("{ae}").contains("Authentication failed"));
     }
+
+    #[tokio::test]
+    async fn test_reqwest_timeout_maps_to_timeout() {
+        // Accepts the connection but never answers.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_millis(100))
+            .build()
+            .unwrap();
+
+        let err: ProviderError = client.get(url).send().await.unwrap_err().into();
+        assert!(matches!(err, ProviderError::Timeout), "{err}");
+        assert!(format!("{err}").contains("took too long"));
+        drop(listener);
+    }
+
+    #[tokio::test]
+    async fn test_reqwest_connect_error_stays_network() {
+        // Bind then drop to get a port nothing listens on.
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let err: ProviderError = reqwest::get(format!("http://127.0.0.1:{port}/"))
+            .await
+            .unwrap_err()
+            .into();
+        assert!(matches!(err, ProviderError::Network(_)), "{err}");
+    }
 }
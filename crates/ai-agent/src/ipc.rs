@@ -1,6 +1,28 @@
+use std::io;
+use std::path::Path;
+
 use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::UnixStream;
+
+/// Environment variable overriding the daemon socket path (also read by the
+/// WhatsApp bridge).
+pub const SOCKET_ENV: &str = "BLUNUX_AI_SOCKET";
 
+/// Daemon socket: `$BLUNUX_AI_SOCKET` if set, else `/run/user/<uid>/blunux-ai.sock`.
 pub fn socket_path() -> std::path::PathBuf {
+    socket_path_from(std::env::var_os(SOCKET_ENV))
+}
+
+fn socket_path_from(env_override: Option<std::ffi::OsString>) -> std::path::PathBuf {
+    match env_override {
+        Some(path) if !path.is_empty() => path.into(),
+        _ => default_socket_path(),
+    }
+}
+
+fn default_socket_path() -> std::path::PathBuf {
     let uid = std::process::Command::new("id")
         .arg("-u")
         .output()
@@ -37,6 +59,16 @@ pub struct IpcMessage {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub notifications: Option<Vec<serde_json::Value>>,
 
+    /// Notifications still queued after this batch.  Present only in
+    /// `poll_notifications` responses; the bridge polls again while > 0.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remaining: Option<usize>,
+
+    /// Why a request failed. Set only on error responses, whose `body` still
+    /// carries the human-readable `Error: ...` text.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<IpcErrorCode>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub timestamp: Option<String>,
 }
@@ -49,10 +81,127 @@ pub enum IpcMessageType {
     Action,
 }
 
+/// Why a request failed.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum IpcErrorCode {
+    /// Sender is not in `[whatsapp] allowed_numbers`.
+    Unauthorized,
+    /// Sender exceeded `[whatsapp] max_messages_per_minute`.
+    RateLimited,
+    /// Malformed, oversized or otherwise invalid request.
+    BadRequest,
+    /// The request was valid but the agent failed to handle it.
+    Internal,
+}
+
+impl IpcMessage {
+    /// A message of `msg_type` with every optional field unset.
+    pub fn new(msg_type: IpcMessageType) -> Self {
+        Self {
+            msg_type,
+            from: None,
+            body: None,
+            to: None,
+            actions: None,
+            action: None,
+            notifications: None,
+            remaining: None,
+            error_code: None,
+            timestamp: None,
+        }
+    }
+
+    /// An `action` request, e.g. `ping` or `poll_notifications`.
+    pub fn action(action: &str, from: Option<&str>) -> Self {
+        Self {
+            action: Some(action.into()),
+            from: from.map(str::to_string),
+            ..Self::new(IpcMessageType::Action)
+        }
+    }
+
+    /// A chat message from `from` (a phone number).
+    pub fn message(from: &str, body: &str) -> Self {
+        Self {
+            from: Some(from.into()),
+            body: Some(body.into()),
+            ..Self::new(IpcMessageType::Message)
+        }
+    }
+}
+
+// ── Client ───────────────────────────────────────────────────────────────────
+
+/// Connect, send a single message and return the daemon's response.
+pub async fn send_once(socket: &Path, msg: &IpcMessage) -> io::Result<IpcMessage> {
+    Client::connect(socket).await?.send(msg).await
+}
+
+/// Newline-delimited JSON client for the daemon socket. Requests are answered
+/// in order, one response line per request.
+pub struct Client {
+    reader: BufReader<OwnedReadHalf>,
+    writer: OwnedWriteHalf,
+}
+
+impl Client {
+    pub async fn connect(path: &Path) -> io::Result<Self> {
+        let (reader, writer) = UnixStream::connect(path).await?.into_split();
+        Ok(Self {
+            reader: BufReader::new(reader),
+            writer,
+        })
+    }
+
+    /// Send one message and wait for its response.
+    pub async fn send(&mut self, msg: &IpcMessage) -> io::Result<IpcMessage> {
+        let mut line = serde_json::to_string(msg).map_err(io::Error::other)?;
+        line.push('\n');
+        self.writer.write_all(line.as_bytes()).await?;
+
+        let mut response = String::new();
+        if self.reader.read_line(&mut response).await? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "daemon closed the connection",
+            ));
+        }
+        serde_json::from_str(&response).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub async fn ping(&mut self) -> io::Result<IpcMessage> {
+        self.send(&IpcMessage::action("ping", None)).await
+    }
+
+    pub async fn message(&mut self, phone: &str, body: &str) -> io::Result<IpcMessage> {
+        self.send(&IpcMessage::message(phone, body)).await
+    }
+
+    pub async fn poll_notifications(&mut self) -> io::Result<IpcMessage> {
+        self.send(&IpcMessage::action("poll_notifications", Some("__poll__")))
+            .await
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_socket_path_override() {
+        assert_eq!(
+            socket_path_from(Some("/tmp/blunux-test/ai.sock".into())),
+            std::path::PathBuf::from("/tmp/blunux-test/ai.sock")
+        );
+        // Unset or empty falls back to the per-user runtime dir
+        for unset in [None, Some("".into())] {
+            let path = socket_path_from(unset);
+            assert!(path.starts_with("/run/user"), "{}", path.display());
+            assert!(path.ends_with("blunux-ai.sock"));
+        }
+    }
+
     #[test]
     fn test_ipc_message_serde_roundtrip() {
         let msg = IpcMessage {
@@ -63,6 +212,8 @@ mod tests {
             actions: None,
             action: None,
             notifications: None,
+            remaining: None,
+            error_code: None,
             timestamp: Some("2026-02-20T09:00:00Z".into()),
         };
 
@@ -83,6 +234,8 @@ mod tests {
             actions: Some(vec!["OK".into(), "Show logs".into()]),
             action: None,
             notifications: None,
+            remaining: None,
+            error_code: None,
             timestamp: None,
         };
 
@@ -91,6 +244,32 @@ mod tests {
         assert!(json.contains("actions"));
     }
 
+    #[test]
+    fn test_ipc_error_code_serde() {
+        for (code, wire) in [
+            (IpcErrorCode::Unauthorized, "unauthorized"),
+            (IpcErrorCode::RateLimited, "rate_limited"),
+            (IpcErrorCode::BadRequest, "bad_request"),
+            (IpcErrorCode::Internal, "internal"),
+        ] {
+            let msg = IpcMessage {
+                body: Some("Error: nope".into()),
+                error_code: Some(code),
+                ..IpcMessage::new(IpcMessageType::Response)
+            };
+            let json = serde_json::to_string(&msg).unwrap();
+            assert!(json.contains(&format!("\"error_code\":\"{wire}\"")), "{json}");
+            let parsed: IpcMessage = serde_json::from_str(&json).unwrap();
+            assert_eq!(parsed.error_code, Some(code));
+        }
+
+        // Absent on success, and optional when parsing
+        let ok = serde_json::to_string(&IpcMessage::new(IpcMessageType::Response)).unwrap();
+        assert!(!ok.contains("error_code"));
+        let parsed: IpcMessage = serde_json::from_str(r#"{"type":"response"}"#).unwrap();
+        assert_eq!(parsed.error_code, None);
+    }
+
     #[test]
     fn test_ipc_notifications_field() {
         let msg = IpcMessage {
@@ -103,11 +282,14 @@ mod tests {
             notifications: Some(vec![
                 serde_json::json!({"to": "+821012345678", "body": "헬스체크 결과"}),
             ]),
+            remaining: Some(0),
+            error_code: None,
             timestamp: None,
         };
 
         let json = serde_json::to_string(&msg).unwrap();
         assert!(json.contains("notifications"));
         assert!(json.contains("헬스체크"));
+        assert!(json.contains("\"remaining\":0"));
     }
 }
@@ -1,17 +1,25 @@
-// lib.rs — re-exports all modules so integration tests in tests/ can import
-// them as `ai_agent::module::Type`.
-//
-// The [[bin]] target (main.rs) declares its own `mod` tree independently;
-// both compile from the same source files but as separate compilation units.
+// lib.rs — re-exports all modules so integration tests in tests/ and the
+// [[bin]] target (main.rs) can import them as `ai_agent::module::Type`.
 
 pub mod agent;
 pub mod automations;
+pub mod capabilities;
 pub mod config;
 pub mod daemon;
+pub mod doctor;
 pub mod error;
 pub mod ipc;
+pub mod lock;
 pub mod memory;
+pub mod pending;
+pub mod prompts;
 pub mod providers;
+pub mod readline;
+pub mod render;
 pub mod setup;
+pub mod spinner;
 pub mod strings;
+#[cfg(test)]
+mod test_support;
 pub mod tools;
+pub mod whatsapp;
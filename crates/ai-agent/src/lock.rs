@@ -0,0 +1,113 @@
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io;
+use std::path::Path;
+
+use crate::error::AgentError;
+
+// ── Instance locks ───────────────────────────────────────────────────────────
+//
+// Advisory `flock`s in `config_dir`, released when the holder exits (even on a
+// crash). Only one daemon may run per config dir; chat may run alongside it
+// but both write memory, so each side warns when it sees the other.
+
+const DAEMON_LOCK: &str = "daemon.lock";
+const CHAT_LOCK: &str = "chat.lock";
+
+/// Exclusive lock held by the daemon for its whole lifetime.
+#[derive(Debug)]
+pub struct DaemonLock {
+    _file: File,
+}
+
+impl DaemonLock {
+    /// Take the daemon lock, failing with [`AgentError::AlreadyRunning`] if
+    /// another daemon holds it.
+    pub fn acquire(config_dir: &Path) -> Result<Self, AgentError> {
+        let path = config_dir.join(DAEMON_LOCK);
+        let file = open_lock_file(&path)?;
+        match file.try_lock() {
+            Ok(()) => Ok(Self { _file: file }),
+            Err(TryLockError::WouldBlock) => Err(AgentError::AlreadyRunning { lock: path }),
+            Err(TryLockError::Error(e)) => Err(AgentError::Io(e)),
+        }
+    }
+}
+
+/// Shared lock held by an interactive chat session, so the daemon can tell
+/// one is running. Several chats can hold it at once.
+#[derive(Debug)]
+pub struct ChatLock {
+    _file: File,
+}
+
+impl ChatLock {
+    pub fn acquire(config_dir: &Path) -> Result<Self, AgentError> {
+        let file = open_lock_file(&config_dir.join(CHAT_LOCK))?;
+        file.lock_shared().map_err(AgentError::Io)?;
+        Ok(Self { _file: file })
+    }
+}
+
+/// Whether a daemon currently holds the daemon lock in `config_dir`.
+pub fn daemon_running(config_dir: &Path) -> bool {
+    is_held(&config_dir.join(DAEMON_LOCK))
+}
+
+/// Whether any chat session currently holds the chat lock in `config_dir`.
+pub fn chat_running(config_dir: &Path) -> bool {
+    is_held(&config_dir.join(CHAT_LOCK))
+}
+
+/// Probe a lock without keeping it: taking it exclusively fails only if
+/// someone else holds it.
+fn is_held(path: &Path) -> bool {
+    let Ok(file) = OpenOptions::new().write(true).open(path) else {
+        return false;
+    };
+    matches!(file.try_lock(), Err(TryLockError::WouldBlock))
+}
+
+fn open_lock_file(path: &Path) -> io::Result<File> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_second_daemon_lock_fails() {
+        let tmp = tempfile::tempdir().unwrap();
+        let first = DaemonLock::acquire(tmp.path()).unwrap();
+        assert!(daemon_running(tmp.path()));
+
+        let err = DaemonLock::acquire(tmp.path()).unwrap_err();
+        assert!(matches!(err, AgentError::AlreadyRunning { .. }), "{err}");
+
+        drop(first);
+        assert!(!daemon_running(tmp.path()));
+        assert!(DaemonLock::acquire(tmp.path()).is_ok());
+    }
+
+    #[test]
+    fn test_chat_lock_is_shared_and_visible() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert!(!chat_running(tmp.path()));
+
+        let a = ChatLock::acquire(tmp.path()).unwrap();
+        let b = ChatLock::acquire(tmp.path()).unwrap();
+        assert!(chat_running(tmp.path()));
+        // Chat doesn't stop a daemon from starting
+        assert!(DaemonLock::acquire(tmp.path()).is_ok());
+
+        drop((a, b));
+        assert!(!chat_running(tmp.path()));
+    }
+}
@@ -1,24 +1,21 @@
+
 use std::io::Write as _;
+use std::os::unix::io::OwnedFd;
 
-mod agent;
-mod automations;
-mod config;
-mod daemon;
-mod error;
-mod ipc;
-mod memory;
-mod providers;
-mod setup;
-mod strings;
-mod tools;
+use ai_agent::{
+    agent, automations, capabilities, config, daemon, doctor, ipc, lock, memory, providers,
+    render, setup, strings, tools,
+};
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use blunux_config::util::command_exists;
 use clap::{Parser, Subcommand};
 
 use automations::AutomationsConfig;
 use config::{AgentConfig, Language};
-use memory::Memory;
+use memory::{Memory, MemoryFile};
+use tools::ToolRegistry;
 
 #[derive(Parser)]
 #[command(name = "blunux-ai", version, about = "Blunux AI Agent — natural language Linux system management")]
@@ -27,6 +24,14 @@ struct Cli {
     #[arg(long, default_value = "/usr/share/blunux/config.toml")]
     blunux_config: PathBuf,
 
+    /// Show each tool call and its (truncated) output during chat
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
+    /// Styled terminal output for chat replies (respects NO_COLOR in auto mode)
+    #[arg(long, value_enum, default_value_t = render::ColorMode::Auto, global = true)]
+    color: render::ColorMode,
+
     #[command(subcommand)]
     command: Option<Command>,
 }
@@ -34,18 +39,106 @@ struct Cli {
 #[derive(Subcommand)]
 enum Command {
     /// Start interactive AI chat
-    Chat,
+    Chat {
+        /// Continue the previous chat session (saved in last_session.json)
+        #[arg(long)]
+        resume: bool,
+        /// Use this agent config file instead of <config dir>/config.toml.
+        /// Credentials are read from the credentials/ directory beside it.
+        #[arg(long)]
+        config_file: Option<PathBuf>,
+    },
+    /// Answer one prompt (from the argument or stdin), print the reply and exit
+    Ask {
+        /// The question; read from stdin when omitted
+        prompt: Option<String>,
+        /// Run tools that need confirmation without asking. Without it, a
+        /// prompt read from stdin can't be followed by a confirmation, so
+        /// those tools are declined
+        #[arg(short, long)]
+        yes: bool,
+        /// Print {"reply", "tools_used", "usage"} as JSON instead of the plain
+        /// reply. Nothing else goes to stdout: tools that need confirmation are
+        /// declined unless --yes is given, and --verbose output goes to stderr
+        #[arg(long)]
+        json: bool,
+        /// Use this agent config file instead of <config dir>/config.toml
+        #[arg(long)]
+        config_file: Option<PathBuf>,
+    },
+    /// Send a canned prompt (built-in or from [prompts] in config.toml) and print the reply
+    Run {
+        /// Prompt name, e.g. health, security, disk
+        name: String,
+        /// Run tools that need confirmation without asking
+        #[arg(short, long)]
+        yes: bool,
+        /// Use this agent config file instead of <config dir>/config.toml
+        #[arg(long)]
+        config_file: Option<PathBuf>,
+    },
     /// First-time setup wizard
-    Setup,
+    Setup {
+        /// Change an existing setup: current values are the defaults and a
+        /// saved API key is kept unless you choose to replace it
+        #[arg(long)]
+        reconfigure: bool,
+    },
+    /// Diagnose common setup problems (config, credentials, provider, yay, distro)
+    Doctor,
     /// Show agent status and configuration
-    Status,
+    Status {
+        /// Also verify the provider is reachable and the credentials work
+        #[arg(long)]
+        check: bool,
+    },
     /// Memory management
     Memory {
         #[command(subcommand)]
         action: MemoryAction,
     },
     /// Run as background daemon (Unix socket, for WhatsApp bridge)
-    Daemon,
+    Daemon {
+        /// Socket path [default: $BLUNUX_AI_SOCKET or /run/user/<uid>/blunux-ai.sock]
+        #[arg(long)]
+        socket: Option<PathBuf>,
+    },
+    /// Write systemd user timers for automations.toml (runs without the daemon;
+    /// results go to the journal, notify = "whatsapp" is not sent)
+    InstallTimers,
+    /// Send one IPC request to the running daemon and print its JSON response
+    #[command(group(clap::ArgGroup::new("request").required(true).args(["action", "message"])))]
+    Send {
+        /// Action to run, e.g. ping, reset, poll_notifications
+        #[arg(long)]
+        action: Option<String>,
+        /// Chat message to process as if it came from --from
+        #[arg(long, requires = "from")]
+        message: Option<String>,
+        /// Sender phone number, e.g. +821012345678
+        #[arg(long)]
+        from: Option<String>,
+        /// Socket path [default: $BLUNUX_AI_SOCKET or /run/user/<uid>/blunux-ai.sock]
+        #[arg(long)]
+        socket: Option<PathBuf>,
+    },
+    /// List the tools the agent exposes, with permission levels and input schemas
+    Tools,
+    /// Automation management
+    Automation {
+        #[command(subcommand)]
+        action: AutomationAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum AutomationAction {
+    /// Run a single automation once by name and print the result
+    /// (notify = "whatsapp" is ignored: only the daemon queues notifications)
+    Run {
+        /// Automation name as written in automations.toml
+        name: String,
+    },
 }
 
 #[derive(Subcommand)]
@@ -56,9 +149,41 @@ enum MemoryAction {
     Clear,
     /// Refresh SYSTEM.md with current system info
     Refresh,
+    /// Open a memory file in $EDITOR (created if missing)
+    Edit {
+        #[arg(value_enum, ignore_case = true)]
+        which: MemoryFile,
+    },
+}
+
+/// Open `path` in $EDITOR, falling back to nano, then vi.
+fn open_in_editor(path: &Path) -> anyhow::Result<()> {
+    let editor = std::env::var("EDITOR")
+        .ok()
+        .filter(|e| !e.trim().is_empty())
+        .or_else(|| {
+            ["nano", "vi"]
+                .into_iter()
+                .find(|cmd| command_exists(cmd))
+                .map(String::from)
+        })
+        .ok_or_else(|| anyhow::anyhow!("no editor found; set $EDITOR"))?;
+
+    // $EDITOR may carry arguments, e.g. "code --wait"
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+    let status = std::process::Command::new(program)
+        .args(parts)
+        .arg(path)
+        .status()
+        .map_err(|e| anyhow::anyhow!("failed to start {program}: {e}"))?;
+    if !status.success() {
+        anyhow::bail!("{program} exited with {status}");
+    }
+    Ok(())
 }
 
-fn detect_language(blunux_config_path: &PathBuf) -> Language {
+fn detect_language(blunux_config_path: &Path) -> Language {
     // Try loading blunux config for locale detection
     if let Ok(cfg) = blunux_config::BlunuxConfig::load(blunux_config_path) {
         return Language::from_locale(&cfg.locale.language);
@@ -71,7 +196,7 @@ fn detect_language(blunux_config_path: &PathBuf) -> Language {
     Language::Korean
 }
 
-fn run_status(config_dir: &PathBuf, lang: &Language) -> anyhow::Result<()> {
+fn run_status(config_dir: &Path, lang: &Language) -> anyhow::Result<()> {
     match AgentConfig::load(config_dir) {
         Ok(cfg) => {
             let provider_name = match (&cfg.provider, &cfg.claude_mode) {
@@ -83,27 +208,29 @@ fn run_status(config_dir: &PathBuf, lang: &Language) -> anyhow::Result<()> {
                 Language::Korean => "한국어",
                 Language::English => "English",
             };
-            let safe_str = if cfg.safe_mode {
-                match lang {
-                    Language::Korean => "활성화",
-                    Language::English => "Enabled",
-                }
-            } else {
-                match lang {
-                    Language::Korean => "비활성화",
-                    Language::English => "Disabled",
-                }
+            let on_off = |enabled: bool| match (enabled, lang) {
+                (true, Language::Korean) => "활성화",
+                (true, Language::English) => "Enabled",
+                (false, Language::Korean) => "비활성화",
+                (false, Language::English) => "Disabled",
             };
+            let safe_str = on_off(cfg.safe_mode);
 
             println!("\n  Blunux AI Agent v{}\n", env!("CARGO_PKG_VERSION"));
             println!("  Provider:    {provider_name}");
             println!("  Model:       {}", cfg.model.display_name());
             println!("  Language:    {lang_name}");
             println!("  Safe Mode:   {safe_str}");
-            println!("  Config:      {}\n", config_dir.display());
+            println!("  Read-only:   {}", on_off(cfg.read_only));
+            println!("  Config:      {}", config_dir.display());
+            let missing = capabilities::Capabilities::get().missing();
+            if !missing.is_empty() {
+                println!("  Missing:     {}", missing.join(", "));
+            }
+            println!();
 
             // Memory stats
-            let mem = Memory::new(config_dir.clone());
+            let mem = Memory::new(config_dir.to_path_buf());
             let system = mem.load_system().unwrap_or_default();
             let user = mem.load_user().unwrap_or_default();
             let long_term = mem.load_long_term().unwrap_or_default();
@@ -159,19 +286,126 @@ fn run_status(config_dir: &PathBuf, lang: &Language) -> anyhow::Result<()> {
     Ok(())
 }
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
+fn run_install_timers(config_dir: &Path, lang: &Language) -> anyhow::Result<()> {
+    // The daemon's own scheduler would run every automation a second time
+    if lock::daemon_running(config_dir) {
+        anyhow::bail!("{}", strings::timers_daemon_running(lang));
+    }
+    AutomationsConfig::write_defaults(config_dir)?;
+    let auto_cfg = AutomationsConfig::load(config_dir);
+
+    let unit_dir = automations::timer_unit_dir();
+    let exe = std::env::current_exe().unwrap_or_else(|_| PathBuf::from("/usr/bin/blunux-ai"));
+
+    // Stop the old timers before their unit files disappear
+    for unit in automations::installed_timers(&unit_dir) {
+        let _ = std::process::Command::new("systemctl")
+            .args(["--user", "disable", "--now", &format!("{unit}.timer")])
+            .status();
+    }
+    let units = automations::install_timers(&auto_cfg, &unit_dir, &exe)?;
+
+    let _ = std::process::Command::new("systemctl")
+        .args(["--user", "daemon-reload"])
+        .status();
+    for unit in &units {
+        let timer = format!("{unit}.timer");
+        match std::process::Command::new("systemctl")
+            .args(["--user", "enable", "--now", &timer])
+            .status()
+        {
+            Ok(s) if s.success() => println!("  • {timer}"),
+            _ => eprintln!("  {}: systemctl --user enable {timer}", strings::error_prefix(lang)),
+        }
+    }
+
+    println!("\n  {}\n", strings::timers_installed(lang, units.len(), &unit_dir));
+    if auto_cfg
+        .automations
+        .iter()
+        .any(|a| a.enabled && a.notify == "whatsapp")
+    {
+        println!("  {}\n", strings::timers_no_whatsapp(lang));
+    }
+    Ok(())
+}
+
+async fn run_provider_check(config_dir: &Path, lang: &Language) -> anyhow::Result<()> {
+    let cfg = AgentConfig::load(config_dir)?;
+    let provider = providers::build_provider(&cfg)?;
+    provider
+        .health_check()
+        .await
+        .map_err(|e| anyhow::anyhow!("{}: {e}", strings::provider_check_failed(lang)))?;
+    println!("  {}\n", strings::provider_check_ok(lang, provider.name()));
+    Ok(())
+}
+
+async fn run_automation_once(
+    config_dir: &Path,
+    blunux_config: &Path,
+    lang: &Language,
+    name: &str,
+) -> anyhow::Result<()> {
+    let cfg = AgentConfig::load(config_dir)?;
+    let auto_cfg = AutomationsConfig::load(config_dir);
+    let Some(auto) = auto_cfg.automations.iter().find(|a| a.name == name) else {
+        anyhow::bail!("{}", strings::automation_not_found(lang, name));
+    };
+
+    let agent = agent::Agent::new_daemon(&cfg)?.with_blunux_config(blunux_config);
+    let reply = agent.run_automation(&auto.action).await?;
+    println!("🤖 Blunux AI Agent — {}\n\n{reply}", auto.name);
+    Ok(())
+}
+
+fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
+    // Before the runtime starts any threads: this edits the environment
+    let activated = match cli.command {
+        Some(Command::Daemon { .. }) => daemon::take_activated_socket()?,
+        _ => None,
+    };
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?
+        .block_on(run(cli, activated))
+}
+
+async fn run(cli: Cli, activated: Option<OwnedFd>) -> anyhow::Result<()> {
     let lang = detect_language(&cli.blunux_config);
     let config_dir = AgentConfig::default_config_dir();
 
     match cli.command {
-        None | Some(Command::Chat) => {
-            // Load config, start interactive chat
-            match AgentConfig::load(&config_dir) {
+        None | Some(Command::Chat { .. }) => {
+            let (resume_flag, config_file) = match cli.command {
+                Some(Command::Chat {
+                    resume,
+                    config_file,
+                }) => (resume, config_file),
+                _ => (false, None),
+            };
+            // Load config, start interactive chat. An explicit --config-file
+            // that fails to load is an error, not a prompt to run setup.
+            let loaded = match &config_file {
+                Some(path) => Ok(AgentConfig::load_file(path)?),
+                None => AgentConfig::load(&config_dir),
+            };
+            match loaded {
                 Ok(cfg) => {
-                    let mut agent = agent::Agent::new(&cfg)?;
-                    agent.run_interactive().await?;
+                    let _chat_lock = lock::ChatLock::acquire(&cfg.config_dir)?;
+                    if lock::daemon_running(&cfg.config_dir) {
+                        println!("\n  {}", strings::daemon_running_warning(&cfg.language));
+                    }
+                    let color = cli.color.enabled();
+                    let mut agent = agent::Agent::new(&cfg)?
+                        .with_blunux_config(&cli.blunux_config)
+                        .with_color(color);
+                    if cli.verbose {
+                        agent = agent
+                            .with_tool_observer(move |event| agent::print_tool_event(event, color));
+                    }
+                    agent.run_interactive(resume_flag || cfg.resume_session).await?;
                 }
                 Err(_) => {
                     let msg = match lang {
@@ -184,17 +418,92 @@ async fn main() -> anyhow::Result<()> {
                 }
             }
         }
-        Some(Command::Setup) => {
-            let wizard = setup::SetupWizard::new(lang, config_dir);
+        Some(Command::Ask {
+            prompt,
+            yes,
+            json,
+            config_file,
+        }) => {
+            let cfg = match &config_file {
+                Some(path) => AgentConfig::load_file(path)?,
+                None => AgentConfig::load(&config_dir)?,
+            };
+            let stdin = std::io::stdin();
+            // stdin is used up by the prompt, so confirmations can't be asked there
+            let interactive = !json && prompt.is_some();
+            if prompt.is_none() && std::io::IsTerminal::is_terminal(&stdin) {
+                anyhow::bail!("{}", strings::ask_no_prompt(&cfg.language));
+            }
+            let Some(prompt) = agent::read_prompt(prompt, stdin.lock())? else {
+                anyhow::bail!("{}", strings::ask_no_prompt(&cfg.language));
+            };
+            let _chat_lock = lock::ChatLock::acquire(&cfg.config_dir)?;
+            let mut agent = agent::Agent::new(&cfg)?
+                .with_blunux_config(&cli.blunux_config)
+                .with_auto_confirm(yes)
+                .with_interactive(interactive);
+            if cli.verbose && json {
+                // stdout carries only the JSON document
+                agent = agent.with_tool_observer(|event| {
+                    let _ = agent::write_tool_event(&mut std::io::stderr(), event, false);
+                });
+            } else if cli.verbose {
+                agent = agent.with_tool_observer(|event| agent::print_tool_event(event, false));
+            }
+            if json {
+                println!("{}", serde_json::to_string(&agent.ask_report(&prompt).await?)?);
+            } else {
+                println!("{}", agent.ask(&prompt).await?);
+            }
+        }
+        Some(Command::Run {
+            name,
+            yes,
+            config_file,
+        }) => {
+            let cfg = match &config_file {
+                Some(path) => AgentConfig::load_file(path)?,
+                None => AgentConfig::load(&config_dir)?,
+            };
+            let _chat_lock = lock::ChatLock::acquire(&cfg.config_dir)?;
+            let mut agent = agent::Agent::new(&cfg)?
+                .with_blunux_config(&cli.blunux_config)
+                .with_auto_confirm(yes);
+            if cli.verbose {
+                agent = agent.with_tool_observer(|event| agent::print_tool_event(event, false));
+            }
+            let prompt = agent.expand_prompt(&name)?;
+            println!("{}", agent.ask(&prompt).await?);
+        }
+        Some(Command::Setup { reconfigure }) => {
+            let mut wizard = setup::SetupWizard::new(lang, config_dir.clone());
+            if reconfigure {
+                wizard = wizard.reconfigure(AgentConfig::load(&config_dir)?);
+            }
             wizard.run()?;
         }
-        Some(Command::Status) => {
+        Some(Command::Doctor) => {
+            let results = doctor::run_checks(&config_dir, &lang).await;
+            println!("\n  {}\n", strings::doctor_title(&lang));
+            for result in &results {
+                println!("{}", result.render());
+            }
+            println!();
+            if doctor::has_failures(&results) {
+                std::process::exit(1);
+            }
+        }
+        Some(Command::Status { check }) => {
             run_status(&config_dir, &lang)?;
+            if check {
+                run_provider_check(&config_dir, &lang).await?;
+            }
         }
-        Some(Command::Daemon) => {
+        Some(Command::Daemon { socket }) => {
             match AgentConfig::load(&config_dir) {
                 Ok(cfg) => {
-                    daemon::run_daemon(&cfg).await?;
+                    daemon::run_daemon(&cfg, &cli.blunux_config, socket.as_deref(), activated)
+                        .await?;
                 }
                 Err(_) => {
                     let msg = match lang {
@@ -207,8 +516,40 @@ async fn main() -> anyhow::Result<()> {
                 }
             }
         }
+        Some(Command::InstallTimers) => {
+            run_install_timers(&config_dir, &lang)?;
+        }
+        Some(Command::Send {
+            action,
+            message,
+            from,
+            socket,
+        }) => {
+            let request = match (action, message) {
+                (_, Some(body)) => ipc::IpcMessage::message(from.as_deref().unwrap_or(""), &body),
+                (Some(action), None) => ipc::IpcMessage::action(&action, from.as_deref()),
+                (None, None) => unreachable!("clap requires --action or --message"),
+            };
+            let socket = socket.unwrap_or_else(ipc::socket_path);
+            let response = ipc::send_once(&socket, &request)
+                .await
+                .map_err(|e| anyhow::anyhow!("{}: {e}", socket.display()))?;
+            println!("{}", serde_json::to_string_pretty(&response)?);
+        }
+        Some(Command::Tools) => {
+            let mut tools = ToolRegistry::for_agent(&config_dir);
+            tools.register(Box::new(tools::packages::CheckBlunuxPackagesTool::new(
+                cli.blunux_config.clone(),
+            )))?;
+            print!("{}", tools.catalog());
+        }
+        Some(Command::Automation { action }) => match action {
+            AutomationAction::Run { name } => {
+                run_automation_once(&config_dir, &cli.blunux_config, &lang, &name).await?;
+            }
+        },
         Some(Command::Memory { action }) => {
-            let mem = Memory::new(config_dir);
+            let mem = Memory::new(config_dir).with_blunux_config(&cli.blunux_config);
             match action {
                 MemoryAction::Show => {
                     let output = mem.show_all().map_err(|e| anyhow::anyhow!("{e}"))?;
@@ -249,6 +590,10 @@ async fn main() -> anyhow::Result<()> {
                     };
                     println!("  {done}");
                 }
+                MemoryAction::Edit { which } => {
+                    let path = mem.ensure_file(which).map_err(|e| anyhow::anyhow!("{e}"))?;
+                    open_in_editor(&path)?;
+                }
             }
         }
     }
@@ -1,7 +1,15 @@
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 
-use chrono::Local;
+use blunux_config::util::human_size;
+use blunux_config::{BlunuxConfig, Hardware};
+use chrono::{Duration, Local, NaiveDate, NaiveDateTime, NaiveTime};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
 
+use crate::config::MemoryConfig;
 use crate::error::MemoryError;
 
 #[derive(Debug, Default)]
@@ -11,21 +19,73 @@ pub struct SystemInfo {
     pub desktop_env: String,
     pub shell: String,
     pub cpu: String,
-    pub memory_total_gb: f64,
-    pub memory_used_gb: f64,
-    pub disk_total_gb: f64,
-    pub disk_used_gb: f64,
+    pub memory_total_bytes: u64,
+    pub memory_used_bytes: u64,
+    pub disk_total_bytes: u64,
+    pub disk_used_bytes: u64,
     pub hostname: String,
     pub username: String,
 }
 
+const MIB: u64 = 1024 * 1024;
+/// How much of yesterday's log is carried into the context after midnight.
+const CARRY_OVER_LINES: usize = 20;
+
+/// Serializes appends within the process so the daemon's concurrent requests
+/// can't interleave a daily-log header with another entry, or drop a line
+/// another request added to USER.md or MEMORY.md in the meantime.
+static APPEND_LOCK: Mutex<()> = Mutex::new(());
+
 pub struct Memory {
     base_dir: PathBuf,
+    /// How long after midnight yesterday's log still appears in the context.
+    carry_over: Duration,
+    /// Blunux `config.toml` whose `[hardware]` section goes into SYSTEM.md.
+    blunux_config: Option<PathBuf>,
+}
+
+/// A memory file that can be opened for editing with `blunux-ai memory edit`.
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum MemoryFile {
+    /// USER.md — user preferences
+    User,
+    /// MEMORY.md — long-term memory
+    Memory,
+    /// SYSTEM.md — detected system information
+    System,
+    /// Today's daily log
+    Today,
+}
+
+/// A retention step for one old daily log.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RetentionAction {
+    Compress(PathBuf),
+    Delete(PathBuf),
 }
 
 impl Memory {
     pub fn new(base_dir: PathBuf) -> Self {
-        Self { base_dir }
+        Self {
+            base_dir,
+            carry_over: Duration::zero(),
+            blunux_config: None,
+        }
+    }
+
+    /// Add the GPU and drivers recorded in the blunux `config.toml` at `path`
+    /// to SYSTEM.md on [`refresh_system_info`](Self::refresh_system_info).
+    pub fn with_blunux_config(mut self, path: &Path) -> Self {
+        self.blunux_config = Some(path.to_path_buf());
+        self
+    }
+
+    /// For `minutes` after midnight, end [`build_context`](Self::build_context)
+    /// with the tail of yesterday's log too, so a conversation that runs past
+    /// midnight keeps its thread. See `[memory] carry_over_minutes`.
+    pub fn with_carry_over(mut self, minutes: u32) -> Self {
+        self.carry_over = Duration::minutes(i64::from(minutes));
+        self
     }
 
     fn memory_dir(&self) -> PathBuf {
@@ -74,12 +134,67 @@ impl Memory {
                 source: e,
             })?;
         }
-        std::fs::write(path, content).map_err(|e| MemoryError::Write {
+        blunux_config::write_atomic(path, content).map_err(|e| MemoryError::Write {
             path: path.display().to_string(),
             source: e,
         })
     }
 
+    /// Path of `which`, without touching the filesystem.
+    pub fn file_path(&self, which: MemoryFile) -> PathBuf {
+        match which {
+            MemoryFile::User => self.memory_dir().join("USER.md"),
+            MemoryFile::Memory => self.memory_dir().join("MEMORY.md"),
+            MemoryFile::System => self.memory_dir().join("SYSTEM.md"),
+            MemoryFile::Today => {
+                let today = Local::now().format("%Y-%m-%d");
+                self.daily_dir().join(format!("{today}.md"))
+            }
+        }
+    }
+
+    /// Path of `which`, creating the file (and today's header) if it is absent.
+    pub fn ensure_file(&self, which: MemoryFile) -> Result<PathBuf, MemoryError> {
+        let path = self.file_path(which);
+        if !path.exists() {
+            let initial = match which {
+                MemoryFile::Today => format!("# {}\n\n", Local::now().format("%Y-%m-%d")),
+                _ => String::new(),
+            };
+            self.write_file(&path, &initial)?;
+        }
+        Ok(path)
+    }
+
+    /// Append `entry` to `path` with O_APPEND, writing `header` first if the
+    /// file is new or empty. Never rewrites existing content.
+    fn append_file(&self, path: &Path, header: &str, entry: &str) -> Result<(), MemoryError> {
+        let write_err = |e| MemoryError::Write {
+            path: path.display().to_string(),
+            source: e,
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| MemoryError::Write {
+                path: parent.display().to_string(),
+                source: e,
+            })?;
+        }
+
+        let _guard = APPEND_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(write_err)?;
+        let is_empty = file.metadata().map_err(write_err)?.len() == 0;
+        let chunk = if is_empty {
+            format!("{header}{entry}")
+        } else {
+            entry.to_string()
+        };
+        file.write_all(chunk.as_bytes()).map_err(write_err)
+    }
+
     pub fn load_system(&self) -> Result<String, MemoryError> {
         self.read_file(&self.memory_dir().join("SYSTEM.md"))
     }
@@ -93,31 +208,173 @@ impl Memory {
     }
 
     pub fn load_today(&self) -> Result<String, MemoryError> {
-        let today = Local::now().format("%Y-%m-%d").to_string();
-        let path = self.daily_dir().join(format!("{today}.md"));
-        self.read_file(&path)
+        self.load_daily(Local::now().date_naive())
+    }
+
+    /// Read one day's log, whether it is still plain or already compressed.
+    fn load_daily(&self, date: NaiveDate) -> Result<String, MemoryError> {
+        let plain = self.daily_dir().join(format!("{}.md", date.format("%Y-%m-%d")));
+        let gz = plain.with_extension("md.gz");
+        if plain.exists() || !gz.exists() {
+            return self.read_file(&plain);
+        }
+        let read_err = |e| MemoryError::Read {
+            path: gz.display().to_string(),
+            source: e,
+        };
+        let file = std::fs::File::open(&gz).map_err(read_err)?;
+        let mut content = String::new();
+        GzDecoder::new(file)
+            .read_to_string(&mut content)
+            .map_err(read_err)?;
+        Ok(content)
     }
 
+    /// Append `HH:MM - content` to today's log. If `content` already starts
+    /// with a `[HH:MM]` timestamp, that time is used instead of the current one.
     pub fn append_today(&self, content: &str) -> Result<(), MemoryError> {
         let today = Local::now().format("%Y-%m-%d").to_string();
         let path = self.daily_dir().join(format!("{today}.md"));
-        let time = Local::now().format("%H:%M").to_string();
+        let (time, content) = match split_timestamp(content) {
+            Some((time, rest)) => (time.to_string(), rest),
+            None => (Local::now().format("%H:%M").to_string(), content),
+        };
 
-        let existing = self.read_file(&path)?;
-        let new_content = if existing.is_empty() {
-            format!("# {today}\n\n{time} - {content}\n")
-        } else {
-            format!("{existing}{time} - {content}\n")
+        self.append_file(&path, &format!("# {today}\n\n"), &format!("{time} - {content}\n"))
+    }
+
+    /// Lines from the last `days` daily logs (today included) that contain
+    /// `query`, case-insensitively. Oldest first; missing days are skipped.
+    pub fn search(&self, query: &str, days: u32) -> Vec<(NaiveDate, String)> {
+        self.search_from(Local::now().date_naive(), query, days)
+    }
+
+    fn search_from(&self, today: NaiveDate, query: &str, days: u32) -> Vec<(NaiveDate, String)> {
+        let needle = query.to_lowercase();
+        let mut hits = Vec::new();
+        for offset in (0..days.max(1)).rev() {
+            let Some(date) = today.checked_sub_days(chrono::Days::new(offset.into())) else {
+                continue;
+            };
+            let Ok(content) = self.load_daily(date) else {
+                continue;
+            };
+            hits.extend(
+                content
+                    .lines()
+                    .filter(|line| !line.starts_with('#') && line.to_lowercase().contains(&needle))
+                    .map(|line| (date, line.to_string())),
+            );
+        }
+        hits
+    }
+
+    /// Compress and delete old daily logs according to `policy`.
+    /// Returns the number of files touched.
+    pub fn apply_retention(&self, policy: &MemoryConfig) -> Result<usize, MemoryError> {
+        let actions = self.retention_plan(Local::now().date_naive(), policy)?;
+        for action in &actions {
+            match action {
+                RetentionAction::Compress(path) => gzip_file(path)?,
+                RetentionAction::Delete(path) => {
+                    std::fs::remove_file(path).map_err(|e| MemoryError::Write {
+                        path: path.display().to_string(),
+                        source: e,
+                    })?
+                }
+            }
+        }
+        Ok(actions.len())
+    }
+
+    /// Decide what to do with each daily log, judging age by the date in its
+    /// file name. Files that don't look like `YYYY-MM-DD.md[.gz]` are left alone.
+    fn retention_plan(
+        &self,
+        today: NaiveDate,
+        policy: &MemoryConfig,
+    ) -> Result<Vec<RetentionAction>, MemoryError> {
+        let dir = self.daily_dir();
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(MemoryError::Read {
+                    path: dir.display().to_string(),
+                    source: e,
+                })
+            }
         };
-        self.write_file(&path, &new_content)
+
+        let mut actions = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            let (stem, compressed) = match name.strip_suffix(".md.gz") {
+                Some(stem) => (stem, true),
+                None => match name.strip_suffix(".md") {
+                    Some(stem) => (stem, false),
+                    None => continue,
+                },
+            };
+            let Ok(date) = NaiveDate::parse_from_str(stem, "%Y-%m-%d") else {
+                continue;
+            };
+            let age = (today - date).num_days();
+            if age > i64::from(policy.delete_after_days) {
+                actions.push(RetentionAction::Delete(path));
+            } else if age > i64::from(policy.compress_after_days) && !compressed {
+                actions.push(RetentionAction::Compress(path));
+            }
+        }
+        actions.sort_by(|a, b| retention_path(a).cmp(retention_path(b)));
+        Ok(actions)
     }
 
+    /// Replace USER.md entirely. Use [`Memory::append_user`] to add a single preference.
     pub fn update_user(&self, content: &str) -> Result<(), MemoryError> {
         self.write_file(&self.memory_dir().join("USER.md"), content)
     }
 
+    /// Add `line` to the end of USER.md unless an identical line is already
+    /// there. Returns whether the file changed.
+    pub fn append_user(&self, line: &str) -> Result<bool, MemoryError> {
+        self.append_unique(&self.memory_dir().join("USER.md"), line)
+    }
+
+    /// Add `fact` to MEMORY.md as a `- ` bullet unless it is already there,
+    /// so it is part of every later [`build_context`](Self::build_context).
+    /// Returns whether the file changed.
+    pub fn append_long_term(&self, fact: &str) -> Result<bool, MemoryError> {
+        let fact = fact.trim();
+        let fact = fact.strip_prefix("- ").unwrap_or(fact).trim();
+        if fact.is_empty() {
+            return Ok(false);
+        }
+        self.append_unique(&self.memory_dir().join("MEMORY.md"), &format!("- {fact}"))
+    }
+
+    fn append_unique(&self, path: &Path, line: &str) -> Result<bool, MemoryError> {
+        let line = line.trim();
+        // Held across the read and the write so concurrent appends aren't lost
+        let _guard = APPEND_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let existing = self.read_file(path)?;
+        if line.is_empty() || existing.lines().any(|l| l.trim() == line) {
+            return Ok(false);
+        }
+        let separator = if existing.is_empty() || existing.ends_with('\n') { "" } else { "\n" };
+        self.write_file(path, &format!("{existing}{separator}{line}\n"))?;
+        Ok(true)
+    }
+
     pub fn build_context(&self) -> Result<String, MemoryError> {
-        let today = Local::now().format("%Y-%m-%d").to_string();
+        self.build_context_at(Local::now().naive_local())
+    }
+
+    fn build_context_at(&self, now: NaiveDateTime) -> Result<String, MemoryError> {
+        let today = now.date();
         let mut ctx = String::new();
 
         let system = self.load_system()?;
@@ -141,7 +398,19 @@ impl Memory {
             ctx.push_str("\n\n");
         }
 
-        let daily = self.load_today()?;
+        let since_midnight = now - today.and_time(NaiveTime::MIN);
+        if since_midnight < self.carry_over {
+            if let Some(yesterday) = today.pred_opt() {
+                let tail = log_tail(&self.load_daily(yesterday)?, CARRY_OVER_LINES);
+                if !tail.is_empty() {
+                    ctx.push_str(&format!("## Yesterday's Session ({yesterday}, last entries)\n"));
+                    ctx.push_str(&tail);
+                    ctx.push_str("\n\n");
+                }
+            }
+        }
+
+        let daily = self.load_daily(today)?;
         if !daily.is_empty() {
             ctx.push_str(&format!("## Today's Session ({today})\n"));
             ctx.push_str(&daily);
@@ -162,8 +431,8 @@ impl Memory {
              - Desktop: {}\n\
              - Shell: {}\n\
              - CPU: {}\n\
-             - RAM: {:.1} GB total, {:.1} GB used\n\
-             - Disk: {:.1} GB total, {:.1} GB used\n",
+             - RAM: {} total, {} used\n\
+             - Disk: {} total, {} used\n",
             info.hostname,
             info.username,
             info.distro,
@@ -171,23 +440,33 @@ impl Memory {
             info.desktop_env,
             info.shell,
             info.cpu,
-            info.memory_total_gb,
-            info.memory_used_gb,
-            info.disk_total_gb,
-            info.disk_used_gb,
+            human_size(info.memory_total_bytes),
+            human_size(info.memory_used_bytes),
+            human_size(info.disk_total_bytes),
+            human_size(info.disk_used_bytes),
         );
+        let md = match self.recorded_hardware() {
+            Some(hardware) => md + &hardware_lines(&hardware),
+            None => md,
+        };
         self.write_file(&self.memory_dir().join("SYSTEM.md"), &md)
     }
 
-    pub fn detect_system_info(&self) -> SystemInfo {
-        let mut info = SystemInfo::default();
+    /// The `[hardware]` section of the blunux config, if there is one.
+    fn recorded_hardware(&self) -> Option<Hardware> {
+        BlunuxConfig::load(self.blunux_config.as_deref()?).ok()?.hardware
+    }
 
-        info.hostname = cmd_output("hostname").unwrap_or_else(|| "unknown".into());
-        info.username = std::env::var("USER").unwrap_or_else(|_| "unknown".into());
-        info.kernel = cmd_output_args("uname", &["-r"]).unwrap_or_else(|| "unknown".into());
-        info.shell = std::env::var("SHELL").unwrap_or_else(|_| "unknown".into());
-        info.desktop_env =
-            std::env::var("XDG_CURRENT_DESKTOP").unwrap_or_else(|_| "unknown".into());
+    pub fn detect_system_info(&self) -> SystemInfo {
+        let mut info = SystemInfo {
+            hostname: cmd_output("hostname").unwrap_or_else(|| "unknown".into()),
+            username: std::env::var("USER").unwrap_or_else(|_| "unknown".into()),
+            kernel: cmd_output_args("uname", &["-r"]).unwrap_or_else(|| "unknown".into()),
+            shell: std::env::var("SHELL").unwrap_or_else(|_| "unknown".into()),
+            desktop_env: std::env::var("XDG_CURRENT_DESKTOP")
+                .unwrap_or_else(|_| "unknown".into()),
+            ..Default::default()
+        };
 
         // Distro from /etc/os-release
         if let Ok(content) = std::fs::read_to_string("/etc/os-release") {
@@ -215,20 +494,9 @@ impl Memory {
         }
 
         // Memory from /proc/meminfo
-        if let Ok(content) = std::fs::read_to_string("/proc/meminfo") {
-            for line in content.lines() {
-                if let Some(val) = line.strip_prefix("MemTotal:") {
-                    if let Some(kb) = parse_kb(val) {
-                        info.memory_total_gb = kb as f64 / 1_048_576.0;
-                    }
-                } else if let Some(val) = line.strip_prefix("MemAvailable:") {
-                    if let Some(kb) = parse_kb(val) {
-                        let avail_gb = kb as f64 / 1_048_576.0;
-                        info.memory_used_gb = info.memory_total_gb - avail_gb;
-                    }
-                }
-            }
-        }
+        let mem = blunux_config::hwdetect::read_meminfo();
+        info.memory_total_bytes = mem.total_mb * MIB;
+        info.memory_used_bytes = mem.total_mb.saturating_sub(mem.available_mb) * MIB;
 
         // Disk from df
         if let Some(df_out) = cmd_output_args("df", &["--output=size,used", "-B1", "/"]) {
@@ -237,10 +505,10 @@ impl Memory {
                 let parts: Vec<&str> = lines[1].split_whitespace().collect();
                 if parts.len() >= 2 {
                     if let Ok(total) = parts[0].parse::<u64>() {
-                        info.disk_total_gb = total as f64 / 1_073_741_824.0;
+                        info.disk_total_bytes = total;
                     }
                     if let Ok(used) = parts[1].parse::<u64>() {
-                        info.disk_used_gb = used as f64 / 1_073_741_824.0;
+                        info.disk_used_bytes = used;
                     }
                 }
             }
@@ -249,14 +517,13 @@ impl Memory {
         info
     }
 
-    /// Append a command log entry.
-    pub fn log_command(&self, status: &str, command: &str) -> Result<(), MemoryError> {
+    /// Append a command log entry. `origin` says who asked for it, e.g.
+    /// `local` or `remote +821012345678`.
+    pub fn log_command(&self, status: &str, command: &str, origin: &str) -> Result<(), MemoryError> {
         let path = self.logs_dir().join("commands.log");
         let timestamp = Local::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
-        let entry = format!("[{timestamp}] {status:<12}{command}\n");
-
-        let existing = self.read_file(&path)?;
-        self.write_file(&path, &format!("{existing}{entry}"))
+        let entry = format!("[{timestamp}] {status:<12}[{origin}] {command}\n");
+        self.append_file(&path, "", &entry)
     }
 
     /// Clear daily logs and long-term memory.
@@ -320,6 +587,62 @@ impl Memory {
     }
 }
 
+/// SYSTEM.md lines for what the installer recorded; empty fields are skipped.
+fn hardware_lines(hardware: &Hardware) -> String {
+    let mut lines = String::new();
+    if !hardware.gpu.is_empty() {
+        lines.push_str(&format!("- GPU (recorded at install): {}\n", hardware.gpu));
+    }
+    if !hardware.drivers.is_empty() {
+        lines.push_str(&format!("- GPU drivers: {}\n", hardware.drivers.join(", ")));
+    }
+    lines
+}
+
+/// The last `lines` entries of a daily log, without its `# date` header.
+fn log_tail(log: &str, lines: usize) -> String {
+    let entries: Vec<&str> = log
+        .lines()
+        .filter(|l| !l.trim().is_empty() && !l.starts_with('#'))
+        .collect();
+    entries[entries.len().saturating_sub(lines)..].join("\n")
+}
+
+/// Split a leading `[HH:MM]` off a log entry.
+fn split_timestamp(content: &str) -> Option<(&str, &str)> {
+    let rest = content.strip_prefix('[')?;
+    let (time, rest) = rest.split_once(']')?;
+    chrono::NaiveTime::parse_from_str(time, "%H:%M").ok()?;
+    Some((time, rest.trim_start()))
+}
+
+fn retention_path(action: &RetentionAction) -> &Path {
+    match action {
+        RetentionAction::Compress(path) | RetentionAction::Delete(path) => path,
+    }
+}
+
+/// Replace `path` with `path.gz`.
+fn gzip_file(path: &Path) -> Result<(), MemoryError> {
+    let content = std::fs::read(path).map_err(|e| MemoryError::Read {
+        path: path.display().to_string(),
+        source: e,
+    })?;
+    let gz = path.with_extension("md.gz");
+    let write_err = |e| MemoryError::Write {
+        path: gz.display().to_string(),
+        source: e,
+    };
+    let file = std::fs::File::create(&gz).map_err(write_err)?;
+    let mut encoder = GzEncoder::new(file, Compression::default());
+    encoder.write_all(&content).map_err(write_err)?;
+    encoder.finish().map_err(write_err)?;
+    std::fs::remove_file(path).map_err(|e| MemoryError::Write {
+        path: path.display().to_string(),
+        source: e,
+    })
+}
+
 fn cmd_output(cmd: &str) -> Option<String> {
     std::process::Command::new(cmd)
         .output()
@@ -337,14 +660,6 @@ fn cmd_output_args(cmd: &str, args: &[&str]) -> Option<String> {
         .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
 }
 
-fn parse_kb(val: &str) -> Option<u64> {
-    val.trim()
-        .trim_end_matches("kB")
-        .trim()
-        .parse::<u64>()
-        .ok()
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -366,6 +681,46 @@ mod tests {
         assert_eq!(mem.load_user().unwrap(), "browser=firefox");
     }
 
+    #[test]
+    fn test_memory_append_user_dedups() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mem = Memory::new(tmp.path().to_path_buf());
+        mem.init_dirs().unwrap();
+
+        mem.update_user("## Preferences\n- browser=firefox").unwrap();
+        assert!(mem.append_user("- editor=vim").unwrap());
+        assert!(!mem.append_user("  - editor=vim ").unwrap());
+        assert!(!mem.append_user("- browser=firefox").unwrap());
+        assert_eq!(
+            mem.load_user().unwrap(),
+            "## Preferences\n- browser=firefox\n- editor=vim\n"
+        );
+
+        // update_user still replaces the whole file
+        mem.update_user("- theme=dark\n").unwrap();
+        assert_eq!(mem.load_user().unwrap(), "- theme=dark\n");
+    }
+
+    #[test]
+    fn test_memory_append_long_term_dedups() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mem = Memory::new(tmp.path().to_path_buf());
+        mem.init_dirs().unwrap();
+
+        assert!(mem.append_long_term("main disk is /dev/nvme0n1").unwrap());
+        assert!(!mem.append_long_term("  - main disk is /dev/nvme0n1 ").unwrap());
+        assert!(!mem.append_long_term("  ").unwrap());
+        assert!(mem.append_long_term("- uses a Korean keyboard").unwrap());
+        assert_eq!(
+            mem.load_long_term().unwrap(),
+            "- main disk is /dev/nvme0n1\n- uses a Korean keyboard\n"
+        );
+
+        // Remembered facts are in the next context
+        let ctx = mem.build_context().unwrap();
+        assert!(ctx.contains("## Long-term Memory\n- main disk is /dev/nvme0n1\n"), "{ctx}");
+    }
+
     #[test]
     fn test_memory_append_today() {
         let tmp = tempfile::tempdir().unwrap();
@@ -381,6 +736,139 @@ mod tests {
         assert!(today.contains("Installed chrome"));
     }
 
+    #[test]
+    fn test_memory_search_across_days() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mem = Memory::new(tmp.path().to_path_buf());
+        mem.init_dirs().unwrap();
+
+        let daily = tmp.path().join("memory/daily");
+        let write = |date: &str, body: &str| {
+            std::fs::write(daily.join(format!("{date}.md")), format!("# {date}\n\n{body}")).unwrap();
+        };
+        write("2026-03-01", "09:00 - Installed vlc\n");
+        write("2026-03-05", "10:15 - Checked disk\n10:20 - INSTALLED steam\n");
+        write("2026-03-08", "08:00 - installed firefox\n");
+
+        let today = NaiveDate::from_ymd_opt(2026, 3, 8).unwrap();
+        let hits = mem.search_from(today, "installed", 7);
+        assert_eq!(
+            hits,
+            vec![
+                (NaiveDate::from_ymd_opt(2026, 3, 5).unwrap(), "10:20 - INSTALLED steam".into()),
+                (today, "08:00 - installed firefox".into()),
+            ]
+        );
+
+        // The 2026-03-01 entry is 8 days back
+        assert_eq!(mem.search_from(today, "vlc", 7), vec![]);
+        assert_eq!(mem.search_from(today, "vlc", 8).len(), 1);
+        // Date headers are not matched
+        assert!(mem.search_from(today, "2026", 30).is_empty());
+    }
+
+    #[test]
+    fn test_retention_plan_selects_by_age() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mem = Memory::new(tmp.path().to_path_buf());
+        mem.init_dirs().unwrap();
+
+        let daily = tmp.path().join("memory/daily");
+        for name in [
+            "2026-06-01.md",    // today
+            "2026-05-02.md",    // 30 days: kept as-is
+            "2026-05-01.md",    // 31 days: compress
+            "2026-01-01.md.gz", // already compressed
+            "2025-06-01.md",    // 365 days: compress
+            "2025-05-31.md",    // 366 days: delete
+            "2024-01-01.md.gz", // delete
+            "notes.md",         // not a daily log
+        ] {
+            std::fs::write(daily.join(name), "x").unwrap();
+        }
+
+        let today = NaiveDate::from_ymd_opt(2026, 6, 1).unwrap();
+        let plan = mem.retention_plan(today, &MemoryConfig::default()).unwrap();
+        assert_eq!(
+            plan,
+            vec![
+                RetentionAction::Delete(daily.join("2024-01-01.md.gz")),
+                RetentionAction::Delete(daily.join("2025-05-31.md")),
+                RetentionAction::Compress(daily.join("2025-06-01.md")),
+                RetentionAction::Compress(daily.join("2026-05-01.md")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compressed_daily_is_still_readable() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mem = Memory::new(tmp.path().to_path_buf());
+        mem.init_dirs().unwrap();
+
+        let path = tmp.path().join("memory/daily/2026-01-10.md");
+        std::fs::write(&path, "# 2026-01-10\n\n09:00 - Installed gimp\n").unwrap();
+        gzip_file(&path).unwrap();
+        assert!(!path.exists());
+        assert!(path.with_extension("md.gz").exists());
+
+        let date = NaiveDate::from_ymd_opt(2026, 1, 10).unwrap();
+        assert!(mem.load_daily(date).unwrap().contains("Installed gimp"));
+        assert_eq!(
+            mem.search_from(date, "gimp", 1),
+            vec![(date, "09:00 - Installed gimp".into())]
+        );
+    }
+
+    #[test]
+    fn test_memory_ensure_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mem = Memory::new(tmp.path().to_path_buf());
+
+        assert_eq!(mem.file_path(MemoryFile::User), tmp.path().join("memory/USER.md"));
+        assert!(!mem.file_path(MemoryFile::User).exists());
+
+        let user = mem.ensure_file(MemoryFile::User).unwrap();
+        assert_eq!(std::fs::read_to_string(&user).unwrap(), "");
+
+        // Existing content is left alone
+        mem.update_user("browser=firefox").unwrap();
+        mem.ensure_file(MemoryFile::User).unwrap();
+        assert_eq!(mem.load_user().unwrap(), "browser=firefox");
+
+        let today = mem.ensure_file(MemoryFile::Today).unwrap();
+        assert!(today.starts_with(tmp.path().join("memory/daily")));
+        assert!(mem.load_today().unwrap().starts_with("# "));
+    }
+
+    #[test]
+    fn test_memory_file_parse() {
+        use clap::ValueEnum;
+        assert_eq!(MemoryFile::from_str("USER", true), Ok(MemoryFile::User));
+        assert_eq!(MemoryFile::from_str("today", true), Ok(MemoryFile::Today));
+        assert!(MemoryFile::from_str("SECRETS", true).is_err());
+    }
+
+    #[test]
+    fn test_memory_append_today_timestamps() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mem = Memory::new(tmp.path().to_path_buf());
+        mem.init_dirs().unwrap();
+
+        mem.append_today("[09:00] User asked: disk usage?").unwrap();
+        mem.append_today("Checked system status").unwrap();
+        mem.append_today("[not a time] kept verbatim").unwrap();
+
+        let today = mem.load_today().unwrap();
+        let entries: Vec<&str> = today.lines().skip(2).collect();
+        assert_eq!(entries[0], "09:00 - User asked: disk usage?");
+        // Raw entries get the current time prepended exactly once
+        let (time, rest) = entries[1].split_once(" - ").unwrap();
+        assert!(chrono::NaiveTime::parse_from_str(time, "%H:%M").is_ok());
+        assert_eq!(rest, "Checked system status");
+        assert!(entries[2].ends_with(" - [not a time] kept verbatim"));
+    }
+
     #[test]
     fn test_memory_build_context_empty() {
         let tmp = tempfile::tempdir().unwrap();
@@ -391,19 +879,104 @@ mod tests {
         assert!(ctx.is_empty() || ctx.trim().is_empty() || !ctx.contains("error"));
     }
 
+    #[test]
+    fn test_build_context_carries_over_yesterday_after_midnight() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mem = Memory::new(tmp.path().to_path_buf()).with_carry_over(60);
+        mem.init_dirs().unwrap();
+        let daily = tmp.path().join("memory/daily");
+        let entries: String = (0..25).map(|i| format!("23:{i:02} - entry {i}\n")).collect();
+        std::fs::write(daily.join("2026-10-15.md"), format!("# 2026-10-15\n\n{entries}")).unwrap();
+        std::fs::write(daily.join("2026-10-16.md"), "# 2026-10-16\n\n00:01 - after midnight\n").unwrap();
+        let at = |h, m| NaiveDate::from_ymd_opt(2026, 10, 16).unwrap().and_hms_opt(h, m, 0).unwrap();
+
+        let ctx = mem.build_context_at(at(0, 5)).unwrap();
+        assert!(ctx.contains("## Yesterday's Session (2026-10-15, last entries)\n23:05 - entry 5\n"), "{ctx}");
+        assert!(ctx.contains("23:24 - entry 24\n\n## Today's Session (2026-10-16)\n"), "{ctx}");
+        assert!(!ctx.contains("entry 4\n"), "only the tail: {ctx}");
+        assert!(!ctx.contains("# 2026-10-15"), "{ctx}");
+
+        // Past the window, and with carry-over off, only today is included
+        let ctx = mem.build_context_at(at(1, 0)).unwrap();
+        assert!(!ctx.contains("Yesterday") && ctx.contains("after midnight"), "{ctx}");
+        let off = Memory::new(tmp.path().to_path_buf());
+        assert!(!off.build_context_at(at(0, 5)).unwrap().contains("Yesterday"));
+    }
+
+    #[test]
+    fn test_refresh_merges_recorded_hardware() {
+        let tmp = tempfile::tempdir().unwrap();
+        let blunux_toml = tmp.path().join("config.toml");
+        let mut cfg = BlunuxConfig::minimal("nux", "blu");
+        cfg.hardware = Some(Hardware {
+            gpu: "nvidia".into(),
+            drivers: vec!["nvidia-open".into(), "nvidia-utils".into()],
+        });
+        cfg.save(&blunux_toml).unwrap();
+
+        let mem = Memory::new(tmp.path().to_path_buf()).with_blunux_config(&blunux_toml);
+        mem.refresh_system_info().unwrap();
+        let system = mem.load_system().unwrap();
+        assert!(system.starts_with("# System Information\n"), "{system}");
+        assert!(system.contains("- RAM: "), "{system}");
+        assert!(
+            system.ends_with("- GPU (recorded at install): nvidia\n- GPU drivers: nvidia-open, nvidia-utils\n"),
+            "{system}"
+        );
+
+        // Without a [hardware] section SYSTEM.md is just what was detected
+        cfg.hardware = None;
+        cfg.save(&blunux_toml).unwrap();
+        mem.refresh_system_info().unwrap();
+        assert!(!mem.load_system().unwrap().contains("GPU"));
+    }
+
     #[test]
     fn test_memory_log_command() {
         let tmp = tempfile::tempdir().unwrap();
         let mem = Memory::new(tmp.path().to_path_buf());
         mem.init_dirs().unwrap();
 
-        mem.log_command("SAFE", "df -h").unwrap();
-        mem.log_command("CONFIRMED", "yay -S chrome").unwrap();
+        mem.log_command("SAFE", "df -h", "local").unwrap();
+        mem.log_command("CONFIRMED", "yay -S chrome", "remote +821012345678").unwrap();
+
+        let log = std::fs::read_to_string(tmp.path().join("logs/commands.log")).unwrap();
+        assert!(log.contains("SAFE        [local] df -h\n"), "{log}");
+        assert!(log.contains("CONFIRMED   [remote +821012345678] yay -S chrome\n"), "{log}");
+    }
+
+    #[test]
+    fn test_memory_concurrent_appends() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mem = std::sync::Arc::new(Memory::new(tmp.path().to_path_buf()));
+        mem.init_dirs().unwrap();
+
+        let handles: Vec<_> = (0..64)
+            .map(|i| {
+                let mem = std::sync::Arc::clone(&mem);
+                std::thread::spawn(move || {
+                    mem.log_command("SAFE", &format!("cmd-{i}"), "local").unwrap();
+                    mem.append_today(&format!("entry-{i}")).unwrap();
+                    mem.append_long_term(&format!("fact-{i}")).unwrap();
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
 
         let log = std::fs::read_to_string(tmp.path().join("logs/commands.log")).unwrap();
-        assert!(log.contains("SAFE"));
-        assert!(log.contains("df -h"));
-        assert!(log.contains("CONFIRMED"));
+        assert_eq!(log.lines().count(), 64);
+        for i in 0..64 {
+            assert!(log.contains(&format!("cmd-{i}\n")), "missing cmd-{i}");
+        }
+
+        let today = mem.load_today().unwrap();
+        assert_eq!(today.matches("# ").count(), 1, "header written once");
+        assert_eq!(today.lines().filter(|l| l.contains(" - entry-")).count(), 64);
+
+        let facts = mem.load_long_term().unwrap();
+        assert_eq!(facts.lines().filter(|l| l.starts_with("- fact-")).count(), 64);
     }
 
     #[test]
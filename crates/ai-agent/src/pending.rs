@@ -0,0 +1,171 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// ── Confirm via reply ────────────────────────────────────────────────────────
+//
+// With `[whatsapp] confirm_via_reply`, a remote tool call that needs
+// confirmation isn't run straight away. It is parked here under the sender's
+// phone number and runs only if their next message is an affirmative reply
+// that arrives before the timeout.
+
+/// A tool call waiting for its remote user to reply YES.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingAction {
+    pub tool: String,
+    pub input: serde_json::Value,
+    /// What will run, as shown to the user.
+    pub preview: String,
+    created: Instant,
+}
+
+impl PendingAction {
+    pub fn new(tool: &str, input: serde_json::Value, preview: String, now: Instant) -> Self {
+        Self {
+            tool: tool.to_string(),
+            input,
+            preview,
+            created: now,
+        }
+    }
+}
+
+/// What a user's message did to their pending action.
+#[derive(Debug, PartialEq)]
+pub enum Resolution {
+    /// Nothing was waiting; handle the message normally.
+    NoneWaiting,
+    /// The user said yes in time: run the action.
+    Confirmed(PendingAction),
+    /// The user replied with something else; the action is dropped and the
+    /// message handled normally.
+    Declined(PendingAction),
+    /// The reply came after the timeout; the action is dropped.
+    Expired(PendingAction),
+}
+
+/// Pending actions by phone number, at most one per user.
+#[derive(Debug)]
+pub struct PendingActions {
+    timeout: Duration,
+    actions: Mutex<HashMap<String, PendingAction>>,
+}
+
+impl PendingActions {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            actions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Park `action` for `phone` unless one is already waiting, so a second
+    /// call in the same turn can't take the place of the one the user was
+    /// shown. Returns whether `action` was parked.
+    pub fn stash(&self, phone: &str, action: PendingAction) -> bool {
+        match self.actions.lock().unwrap().entry(phone.to_string()) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(slot) => {
+                slot.insert(action);
+                true
+            }
+        }
+    }
+
+    /// Preview of the action waiting for `phone`, if any.
+    pub fn preview(&self, phone: &str) -> Option<String> {
+        self.actions
+            .lock()
+            .unwrap()
+            .get(phone)
+            .map(|a| a.preview.clone())
+    }
+
+    /// Settle `phone`'s pending action with their `reply`, received at `now`.
+    /// Whatever the outcome, the action is no longer pending afterwards.
+    pub fn resolve(&self, phone: &str, reply: &str, now: Instant) -> Resolution {
+        let Some(action) = self.actions.lock().unwrap().remove(phone) else {
+            return Resolution::NoneWaiting;
+        };
+        if now.duration_since(action.created) > self.timeout {
+            Resolution::Expired(action)
+        } else if is_affirmative(reply) {
+            Resolution::Confirmed(action)
+        } else {
+            Resolution::Declined(action)
+        }
+    }
+}
+
+/// `yes` in either supported language, ignoring case and punctuation.
+pub fn is_affirmative(reply: &str) -> bool {
+    let word = reply
+        .trim()
+        .trim_end_matches(['.', '!'])
+        .to_lowercase();
+    matches!(word.as_str(), "yes" | "y" | "네" | "예" | "응")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PHONE: &str = "+821012345678";
+
+    fn action(now: Instant) -> PendingAction {
+        PendingAction::new(
+            "remove_package",
+            serde_json::json!({"package": "vlc"}),
+            "remove_package vlc".into(),
+            now,
+        )
+    }
+
+    #[test]
+    fn test_pending_action_lifecycle() {
+        let pending = PendingActions::new(Duration::from_secs(300));
+        let t0 = Instant::now();
+        assert_eq!(pending.resolve(PHONE, "yes", t0), Resolution::NoneWaiting);
+
+        // Confirmed within the timeout, then gone
+        pending.stash(PHONE, action(t0));
+        assert_eq!(pending.preview(PHONE).as_deref(), Some("remove_package vlc"));
+        assert_eq!(pending.preview("+821099999999"), None);
+        let t1 = t0 + Duration::from_secs(60);
+        assert!(matches!(pending.resolve(PHONE, " YES! ", t1), Resolution::Confirmed(a) if a.tool == "remove_package"));
+        assert_eq!(pending.resolve(PHONE, "yes", t1), Resolution::NoneWaiting);
+
+        // Anything else declines
+        pending.stash(PHONE, action(t0));
+        assert!(matches!(pending.resolve(PHONE, "actually, check disk", t1), Resolution::Declined(_)));
+        assert_eq!(pending.preview(PHONE), None);
+
+        // Too late, even for a yes
+        pending.stash(PHONE, action(t0));
+        let late = t0 + Duration::from_secs(301);
+        assert!(matches!(pending.resolve(PHONE, "네", late), Resolution::Expired(_)));
+        assert_eq!(pending.resolve(PHONE, "네", late), Resolution::NoneWaiting);
+    }
+
+    #[test]
+    fn test_second_stash_is_refused() {
+        let pending = PendingActions::new(Duration::from_secs(300));
+        let t0 = Instant::now();
+        assert!(pending.stash(PHONE, action(t0)));
+        let mut newer = action(t0);
+        newer.preview = "update_system".into();
+        assert!(!pending.stash(PHONE, newer));
+        assert_eq!(pending.preview(PHONE).as_deref(), Some("remove_package vlc"));
+    }
+
+    #[test]
+    fn test_is_affirmative() {
+        for yes in ["yes", "Y", "Yes.", "네", "예"] {
+            assert!(is_affirmative(yes), "{yes}");
+        }
+        for no in ["no", "yes please run something else", "", "아니요"] {
+            assert!(!is_affirmative(no), "{no}");
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+
+use crate::config::Language;
+use crate::error::ConfigError;
+
+// ── Canned prompts ───────────────────────────────────────────────────────────
+//
+// Named prompt templates for `blunux-ai run <name>` and `/run <name>`. The
+// built-ins below can be overridden, and more added, in the `[prompts]` table
+// of config.toml. Templates may use `{date}` and `{hostname}`.
+
+const BUILTIN_NAMES: [&str; 3] = ["disk", "health", "security"];
+
+fn builtin(name: &str, lang: &Language) -> Option<&'static str> {
+    let text = match (name, lang) {
+        ("health", Language::Korean) => {
+            "{hostname}의 시스템 전체 상태를 확인하고 CPU, RAM, 디스크, 업타임, 보류 중인 업데이트 수를 요약해줘. 문제가 있으면 명확히 알려줘."
+        }
+        ("health", Language::English) => {
+            "Check the overall state of {hostname} and summarize CPU, RAM, disk, uptime and the number of pending updates. Point out any problems clearly."
+        }
+        ("security", Language::Korean) => {
+            "보안 업데이트가 있는지 확인해줘. 있으면 패키지 목록과 함께 알려줘. 없으면 '보안 업데이트 없음'이라고 짧게 답해줘."
+        }
+        ("security", Language::English) => {
+            "Check for security updates. If there are any, list the packages; otherwise just answer 'no security updates'."
+        }
+        ("disk", Language::Korean) => {
+            "디스크 사용률을 확인해서 80% 이상인 파티션이 있으면 경고해줘. 모두 안전하면 '디스크 공간 정상'이라고 짧게 답해줘."
+        }
+        ("disk", Language::English) => {
+            "Check disk usage and warn about any partition at 80% or more. If all are fine, just answer 'disk space OK'."
+        }
+        _ => return None,
+    };
+    Some(text)
+}
+
+/// Values substituted into templates.
+#[derive(Debug, Clone)]
+pub struct PromptVars {
+    /// Today's date, `YYYY-MM-DD`.
+    pub date: String,
+    pub hostname: String,
+}
+
+impl PromptVars {
+    /// Today's local date and this machine's hostname.
+    pub fn current() -> Self {
+        let hostname = std::fs::read_to_string("/proc/sys/kernel/hostname")
+            .or_else(|_| std::fs::read_to_string("/etc/hostname"))
+            .map(|h| h.trim().to_string())
+            .unwrap_or_else(|_| "localhost".into());
+        Self {
+            date: chrono::Local::now().format("%Y-%m-%d").to_string(),
+            hostname,
+        }
+    }
+}
+
+/// Replace `{date}` and `{hostname}` in `template`; other text is kept as is.
+pub fn expand(template: &str, vars: &PromptVars) -> String {
+    template
+        .replace("{date}", &vars.date)
+        .replace("{hostname}", &vars.hostname)
+}
+
+/// Names of every available prompt, built-in and configured, sorted.
+pub fn names(custom: &BTreeMap<String, String>) -> Vec<String> {
+    let mut names: Vec<String> = custom.keys().cloned().collect();
+    names.extend(BUILTIN_NAMES.iter().map(|n| n.to_string()));
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Template for `name`: from `custom` (config.toml `[prompts]`) if present,
+/// otherwise a built-in in `lang`.
+pub fn lookup(
+    custom: &BTreeMap<String, String>,
+    lang: &Language,
+    name: &str,
+) -> Result<String, ConfigError> {
+    custom
+        .get(name)
+        .cloned()
+        .or_else(|| builtin(name, lang).map(str::to_string))
+        .ok_or_else(|| ConfigError::UnknownPrompt {
+            name: name.to_string(),
+            available: names(custom).join(", "),
+        })
+}
+
+/// Look up `name` and expand it with `vars`.
+pub fn render(
+    custom: &BTreeMap<String, String>,
+    lang: &Language,
+    name: &str,
+    vars: &PromptVars,
+) -> Result<String, ConfigError> {
+    Ok(expand(&lookup(custom, lang, name)?, vars))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars() -> PromptVars {
+        PromptVars {
+            date: "2026-10-16".into(),
+            hostname: "nux".into(),
+        }
+    }
+
+    #[test]
+    fn test_expand_substitutes_variables() {
+        assert_eq!(
+            expand("Report for {hostname} on {date}; {hostname} again. {unknown}", &vars()),
+            "Report for nux on 2026-10-16; nux again. {unknown}"
+        );
+    }
+
+    #[test]
+    fn test_custom_prompt_overrides_builtin() {
+        let mut custom = BTreeMap::new();
+        custom.insert("health".to_string(), "Quick check of {hostname}".to_string());
+        custom.insert("gpu".to_string(), "GPU status on {date}".to_string());
+
+        let health = render(&custom, &Language::English, "health", &vars()).unwrap();
+        assert_eq!(health, "Quick check of nux");
+        let gpu = render(&custom, &Language::English, "gpu", &vars()).unwrap();
+        assert_eq!(gpu, "GPU status on 2026-10-16");
+        let disk = render(&custom, &Language::English, "disk", &vars()).unwrap();
+        assert!(disk.contains("80%"));
+        assert_eq!(names(&custom), ["disk", "gpu", "health", "security"]);
+    }
+
+    #[test]
+    fn test_unknown_prompt_lists_available() {
+        let err = lookup(&BTreeMap::new(), &Language::Korean, "nope").unwrap_err();
+        assert!(matches!(err, ConfigError::UnknownPrompt { ref name, .. } if name == "nope"));
+        assert!(err.to_string().contains("disk, health, security"), "{err}");
+    }
+}
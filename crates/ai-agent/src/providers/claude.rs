@@ -1,6 +1,12 @@
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+
 use async_trait::async_trait;
+use tokio::io::AsyncWriteExt;
 use serde::{Deserialize, Serialize};
 
+use crate::capabilities::install_hint;
 use crate::config::ModelId;
 use crate::error::ProviderError;
 use crate::providers::{
@@ -18,7 +24,7 @@ pub struct ClaudeApiProvider {
 impl ClaudeApiProvider {
     pub fn new(api_key: String, model: ModelId) -> Self {
         let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(120))
+            .timeout(Duration::from_secs(120))
             .build()
             .expect("failed to build HTTP client");
         Self {
@@ -39,6 +45,20 @@ struct ClaudeApiRequest<'a> {
     tools: Vec<serde_json::Value>,
 }
 
+#[derive(Serialize)]
+struct ClaudeCountTokensRequest<'a> {
+    model: &'a str,
+    system: &'a str,
+    messages: Vec<ClaudeApiMessage>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct ClaudeCountTokensResponse {
+    input_tokens: u32,
+}
+
 #[derive(Serialize)]
 struct ClaudeApiMessage {
     role: String,
@@ -129,34 +149,15 @@ fn convert_tools(tools: &[ToolDefinition]) -> Vec<serde_json::Value> {
         .collect()
 }
 
-#[async_trait]
-impl Provider for ClaudeApiProvider {
-    fn name(&self) -> &str {
-        "Claude API"
-    }
-
-    async fn complete(
-        &self,
-        system_prompt: &str,
-        messages: &[Message],
-        tools: &[ToolDefinition],
-        max_tokens: u32,
-    ) -> Result<CompletionResult, ProviderError> {
-        let body = ClaudeApiRequest {
-            model: self.model.api_name(),
-            max_tokens,
-            system: system_prompt,
-            messages: convert_messages(messages),
-            tools: convert_tools(tools),
-        };
-
+impl ClaudeApiProvider {
+    async fn post<B: Serialize>(&self, url: &str, body: &B) -> Result<reqwest::Response, ProviderError> {
         let resp = self
             .client
-            .post("https://api.anthropic.com/v1/messages")
+            .post(url)
             .header("x-api-key", &self.api_key)
             .header("anthropic-version", "2023-06-01")
             .header("content-type", "application/json")
-            .json(&body)
+            .json(body)
             .send()
             .await?;
 
@@ -176,6 +177,58 @@ impl Provider for ClaudeApiProvider {
                 .unwrap_or(text);
             return Err(ProviderError::ApiError { status, message });
         }
+        Ok(resp)
+    }
+}
+
+#[async_trait]
+impl Provider for ClaudeApiProvider {
+    fn name(&self) -> &str {
+        "Claude API"
+    }
+
+    /// Token counting is free and checks the key without generating anything.
+    async fn health_check(&self) -> Result<(), ProviderError> {
+        self.count_tokens("", &[Message::user("ping")], &[]).await?;
+        Ok(())
+    }
+
+    async fn count_tokens(
+        &self,
+        system_prompt: &str,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+    ) -> Result<u32, ProviderError> {
+        let body = ClaudeCountTokensRequest {
+            model: self.model.api_name(),
+            system: system_prompt,
+            messages: convert_messages(messages),
+            tools: convert_tools(tools),
+        };
+        let resp = self
+            .post("https://api.anthropic.com/v1/messages/count_tokens", &body)
+            .await?;
+        let counted: ClaudeCountTokensResponse =
+            resp.json().await.map_err(|e| ProviderError::Parse(e.to_string()))?;
+        Ok(counted.input_tokens)
+    }
+
+    async fn complete(
+        &self,
+        system_prompt: &str,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        max_tokens: u32,
+    ) -> Result<CompletionResult, ProviderError> {
+        let body = ClaudeApiRequest {
+            model: self.model.api_name(),
+            max_tokens,
+            system: system_prompt,
+            messages: convert_messages(messages),
+            tools: convert_tools(tools),
+        };
+
+        let resp = self.post("https://api.anthropic.com/v1/messages", &body).await?;
 
         let api_resp: ClaudeApiResponse =
             resp.json().await.map_err(|e| ProviderError::Parse(e.to_string()))?;
@@ -212,11 +265,23 @@ impl Provider for ClaudeApiProvider {
 
 pub struct ClaudeOAuthProvider {
     model: ModelId,
+    program: PathBuf,
+    timeout: Duration,
 }
 
 impl ClaudeOAuthProvider {
     pub fn new(model: ModelId) -> Self {
-        Self { model }
+        Self {
+            model,
+            program: PathBuf::from("claude"),
+            timeout: Duration::from_secs(120),
+        }
+    }
+
+    /// How long to wait for the CLI before killing it. Default: 120s.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
     }
 }
 
@@ -258,6 +323,11 @@ impl Provider for ClaudeOAuthProvider {
         "Claude OAuth"
     }
 
+    /// The `claude -p` subprocess only returns text.
+    fn supports_tools(&self) -> bool {
+        false
+    }
+
     async fn complete(
         &self,
         system_prompt: &str,
@@ -267,18 +337,44 @@ impl Provider for ClaudeOAuthProvider {
     ) -> Result<CompletionResult, ProviderError> {
         let prompt = flatten_conversation(system_prompt, messages);
 
-        let output = tokio::process::Command::new("claude")
+        // The prompt goes over stdin rather than argv: long sessions would hit
+        // ARG_MAX, and argv is visible to every user in the process list.
+        // kill_on_drop: a timed-out child is killed when its future is dropped.
+        let mut child = tokio::process::Command::new(&self.program)
             .arg("-p")
-            .arg(&prompt)
+            .arg("--input-format")
+            .arg("text")
             .arg("--output-format")
             .arg("text")
             .arg("--model")
             .arg(self.model.api_name())
-            .output()
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| ProviderError::SubprocessError {
+                exit_code: -1,
+                stderr: if e.kind() == std::io::ErrorKind::NotFound {
+                    format!("claude CLI is not installed — {}", install_hint("claude"))
+                } else {
+                    format!("Failed to spawn claude CLI: {e}")
+                },
+            })?;
+
+        // Write from a separate task so a child that starts printing before
+        // reading all of its input can't deadlock us.
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        tokio::spawn(async move {
+            let _ = stdin.write_all(prompt.as_bytes()).await;
+        });
+
+        let output = tokio::time::timeout(self.timeout, child.wait_with_output())
             .await
+            .map_err(|_| ProviderError::Timeout)?
             .map_err(|e| ProviderError::SubprocessError {
                 exit_code: -1,
-                stderr: format!("Failed to spawn claude CLI: {e}"),
+                stderr: format!("Failed to read claude CLI output: {e}"),
             })?;
 
         if !output.status.success() {
@@ -302,3 +398,82 @@ impl Provider for ClaudeOAuthProvider {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
+
+    use super::*;
+
+    /// Write an executable shell script standing in for the `claude` CLI.
+    fn fake_cli(dir: &Path, body: &str) -> PathBuf {
+        let path = dir.join("claude");
+        std::fs::write(&path, format!("#!/bin/sh\n{body}\n")).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    fn oauth_provider(program: PathBuf) -> ClaudeOAuthProvider {
+        ClaudeOAuthProvider {
+            program,
+            ..ClaudeOAuthProvider::new(ModelId::ClaudeSonnet46)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_oauth_timeout_kills_hung_cli() {
+        let tmp = tempfile::tempdir().unwrap();
+        let provider = oauth_provider(fake_cli(tmp.path(), "sleep 30"))
+            .with_timeout(Duration::from_millis(200));
+
+        let start = std::time::Instant::now();
+        let err = provider
+            .complete("system", &[Message::user("hi")], &[], 1024)
+            .await
+            .unwrap_err();
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert!(matches!(err, ProviderError::Timeout), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_oauth_missing_cli_says_how_to_install() {
+        let tmp = tempfile::tempdir().unwrap();
+        let provider = oauth_provider(tmp.path().join("no-such-claude"));
+        let err = provider
+            .complete("system", &[Message::user("hi")], &[], 1024)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("claude CLI is not installed"), "{err}");
+        assert!(err.to_string().contains("npm install -g"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_oauth_returns_cli_output() {
+        let tmp = tempfile::tempdir().unwrap();
+        let provider = oauth_provider(fake_cli(tmp.path(), "echo '  pong  '"));
+        let result = provider
+            .complete("system", &[Message::user("ping")], &[], 1024)
+            .await
+            .unwrap();
+        assert_eq!(result.text(), "pong");
+    }
+
+    #[tokio::test]
+    async fn test_oauth_long_prompt_goes_over_stdin() {
+        let tmp = tempfile::tempdir().unwrap();
+        // Prints its argument count, then the number of bytes read on stdin.
+        let provider = oauth_provider(fake_cli(tmp.path(), "printf '%s ' \"$#\"; wc -c"));
+        let long = "x".repeat(200 * 1024);
+        let result = provider
+            .complete("system", &[Message::user(long.clone())], &[], 1024)
+            .await
+            .unwrap();
+
+        let text = result.text();
+        let (argc, bytes) = text.split_once(' ').unwrap();
+        assert_eq!(argc, "7", "prompt must not be passed as an argument");
+        let bytes: usize = bytes.trim().parse().unwrap();
+        assert!(bytes > long.len(), "stdin carried {bytes} bytes");
+    }
+}
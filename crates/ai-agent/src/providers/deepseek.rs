@@ -138,12 +138,45 @@ fn convert_tools(tools: &[ToolDefinition]) -> Vec<serde_json::Value> {
         .collect()
 }
 
+/// Map HTTP error statuses to [`ProviderError`]s, passing successful responses through.
+async fn check_status(resp: reqwest::Response) -> Result<reqwest::Response, ProviderError> {
+    let status = resp.status().as_u16();
+    if status == 401 {
+        return Err(ProviderError::AuthenticationFailed);
+    }
+    if status == 429 {
+        return Err(ProviderError::RateLimit {
+            retry_after_secs: 60,
+        });
+    }
+    if status >= 400 {
+        let text = resp.text().await.unwrap_or_default();
+        let message = serde_json::from_str::<OpenAIError>(&text)
+            .map(|e| e.error.message)
+            .unwrap_or(text);
+        return Err(ProviderError::ApiError { status, message });
+    }
+    Ok(resp)
+}
+
 #[async_trait]
 impl Provider for DeepSeekProvider {
     fn name(&self) -> &str {
         "DeepSeek"
     }
 
+    /// Listing models is authenticated but generates nothing.
+    async fn health_check(&self) -> Result<(), ProviderError> {
+        let resp = self
+            .client
+            .get("https://api.deepseek.com/models")
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .send()
+            .await?;
+        check_status(resp).await?;
+        Ok(())
+    }
+
     async fn complete(
         &self,
         system_prompt: &str,
@@ -166,23 +199,7 @@ impl Provider for DeepSeekProvider {
             .json(&body)
             .send()
             .await?;
-
-        let status = resp.status().as_u16();
-        if status == 401 {
-            return Err(ProviderError::AuthenticationFailed);
-        }
-        if status == 429 {
-            return Err(ProviderError::RateLimit {
-                retry_after_secs: 60,
-            });
-        }
-        if status >= 400 {
-            let text = resp.text().await.unwrap_or_default();
-            let message = serde_json::from_str::<OpenAIError>(&text)
-                .map(|e| e.error.message)
-                .unwrap_or(text);
-            return Err(ProviderError::ApiError { status, message });
-        }
+        let resp = check_status(resp).await?;
 
         let api_resp: OpenAIResponse =
             resp.json().await.map_err(|e| ProviderError::Parse(e.to_string()))?;
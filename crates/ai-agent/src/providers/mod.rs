@@ -1,6 +1,8 @@
 pub mod claude;
 pub mod deepseek;
 
+use std::time::Duration;
+
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
@@ -66,6 +68,31 @@ impl Message {
             content: results,
         }
     }
+
+    /// A user message carrying one tool result.
+    pub fn single_tool_result(result: ContentBlock) -> Self {
+        Self::tool_results(vec![result])
+    }
+}
+
+impl ContentBlock {
+    /// Successful result for the tool call `tool_use_id`.
+    pub fn tool_result_ok(tool_use_id: impl Into<String>, content: impl Into<String>) -> Self {
+        Self::ToolResult {
+            tool_use_id: tool_use_id.into(),
+            content: content.into(),
+            is_error: false,
+        }
+    }
+
+    /// Failed result for the tool call `tool_use_id`; the model sees `is_error`.
+    pub fn tool_result_err(tool_use_id: impl Into<String>, content: impl Into<String>) -> Self {
+        Self::ToolResult {
+            tool_use_id: tool_use_id.into(),
+            content: content.into(),
+            is_error: true,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -82,12 +109,20 @@ pub enum StopReason {
     MaxTokens,
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Usage {
     pub input_tokens: u32,
     pub output_tokens: u32,
 }
 
+impl Usage {
+    /// Accumulate another request's usage into a running total.
+    pub fn add(&mut self, other: &Usage) {
+        self.input_tokens = self.input_tokens.saturating_add(other.input_tokens);
+        self.output_tokens = self.output_tokens.saturating_add(other.output_tokens);
+    }
+}
+
 impl CompletionResult {
     pub fn text(&self) -> String {
         self.content
@@ -132,6 +167,39 @@ pub trait Provider: Send + Sync {
         tools: &[ToolDefinition],
         max_tokens: u32,
     ) -> Result<CompletionResult, ProviderError>;
+
+    /// Whether the provider can execute tool calls. When false the agent
+    /// sends no tools and asks the model to describe steps instead.
+    fn supports_tools(&self) -> bool {
+        true
+    }
+
+    /// Cheap check that the provider is reachable and the credentials work.
+    /// Defaults to a one-token completion; providers with a lighter endpoint
+    /// override it.
+    async fn health_check(&self) -> Result<(), ProviderError> {
+        self.complete("", &[Message::user("ping")], &[], 1).await?;
+        Ok(())
+    }
+
+    /// Input tokens a request with this context would use.
+    /// Defaults to a rough estimate; providers with a counting endpoint override it.
+    async fn count_tokens(
+        &self,
+        system_prompt: &str,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+    ) -> Result<u32, ProviderError> {
+        Ok(estimate_tokens(system_prompt, messages, tools))
+    }
+}
+
+/// Approximate token count at ~4 characters per token of the serialized request.
+pub fn estimate_tokens(system_prompt: &str, messages: &[Message], tools: &[ToolDefinition]) -> u32 {
+    let messages_len = serde_json::to_string(messages).map(|s| s.len()).unwrap_or(0);
+    let tools_len = serde_json::to_string(tools).map(|s| s.len()).unwrap_or(0);
+    let chars = system_prompt.len() + messages_len + tools_len;
+    (chars / 4).try_into().unwrap_or(u32::MAX)
 }
 
 // ── Factory ──────────────────────────────────────────────────────────────────
@@ -147,7 +215,10 @@ pub fn build_provider(config: &AgentConfig) -> Result<Box<dyn Provider>, ConfigE
             )))
         }
         (ProviderType::Claude, ClaudeMode::OAuth) => {
-            Ok(Box::new(ClaudeOAuthProvider::new(config.model.clone())))
+            Ok(Box::new(
+                ClaudeOAuthProvider::new(config.model.clone())
+                    .with_timeout(Duration::from_secs(config.oauth_timeout_secs)),
+            ))
         }
         (ProviderType::DeepSeek, _) => {
             let key_path = config.config_dir.join("credentials/deepseek");
@@ -171,6 +242,28 @@ mod tests {
         assert_eq!(msg.content.len(), 1);
     }
 
+    #[test]
+    fn test_tool_result_constructors() {
+        let ok = ContentBlock::tool_result_ok("t1", "42% used");
+        assert!(matches!(
+            &ok,
+            ContentBlock::ToolResult { tool_use_id, content, is_error: false }
+                if tool_use_id == "t1" && content == "42% used"
+        ));
+        let err = ContentBlock::tool_result_err("t2", "Unknown tool: foo");
+        assert!(matches!(
+            &err,
+            ContentBlock::ToolResult { tool_use_id, is_error: true, .. } if tool_use_id == "t2"
+        ));
+
+        let msg = Message::single_tool_result(err);
+        assert_eq!(msg.role, Role::User);
+        assert_eq!(msg.content.len(), 1);
+        let json = serde_json::to_value(&msg).unwrap();
+        assert_eq!(json["content"][0]["type"], "tool_result");
+        assert_eq!(json["content"][0]["is_error"], true);
+    }
+
     #[test]
     fn test_completion_result_text() {
         let result = CompletionResult {
@@ -188,6 +281,72 @@ mod tests {
         assert_eq!(result.text(), "Hello\nWorld");
     }
 
+    struct EstimateOnly;
+
+    #[async_trait]
+    impl Provider for EstimateOnly {
+        fn name(&self) -> &str {
+            "estimate-only"
+        }
+
+        async fn complete(
+            &self,
+            _system_prompt: &str,
+            _messages: &[Message],
+            _tools: &[ToolDefinition],
+            _max_tokens: u32,
+        ) -> Result<CompletionResult, ProviderError> {
+            Err(ProviderError::EmptyResponse)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_count_tokens_defaults_to_estimate() {
+        let system = "s".repeat(400);
+        let messages = vec![Message::user("hello world")];
+        let counted = EstimateOnly.count_tokens(&system, &messages, &[]).await.unwrap();
+        assert_eq!(counted, estimate_tokens(&system, &messages, &[]));
+        assert!(counted >= 100, "system prompt alone is ~100 tokens, got {counted}");
+    }
+
+    struct CountingProvider {
+        calls: std::sync::Mutex<Vec<u32>>,
+    }
+
+    #[async_trait]
+    impl Provider for CountingProvider {
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        async fn complete(
+            &self,
+            _system_prompt: &str,
+            _messages: &[Message],
+            _tools: &[ToolDefinition],
+            max_tokens: u32,
+        ) -> Result<CompletionResult, ProviderError> {
+            self.calls.lock().unwrap().push(max_tokens);
+            Ok(CompletionResult {
+                content: vec![ContentBlock::Text { text: "p".into() }],
+                stop_reason: StopReason::MaxTokens,
+                usage: Usage::default(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_health_check_defaults_to_tiny_completion() {
+        let provider = CountingProvider {
+            calls: std::sync::Mutex::new(Vec::new()),
+        };
+        provider.health_check().await.unwrap();
+        assert_eq!(*provider.calls.lock().unwrap(), vec![1]);
+
+        let err = EstimateOnly.health_check().await.unwrap_err();
+        assert!(matches!(err, ProviderError::EmptyResponse));
+    }
+
     #[test]
     fn test_completion_result_has_tool_use() {
         let with_tool = CompletionResult {
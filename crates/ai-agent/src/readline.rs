@@ -0,0 +1,226 @@
+use std::path::PathBuf;
+
+use blunux_config::Packages;
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::FileHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+
+// ── Interactive prompt ───────────────────────────────────────────────────────
+//
+// Line editing for `blunux-ai chat`, with up-arrow history that persists
+// across sessions in `<config_dir>/history` and Tab completion of slash
+// commands, canned prompt names and package names.
+
+pub const HISTORY_FILE: &str = "history";
+
+/// Slash commands understood by the interactive loop.
+pub const SLASH_COMMANDS: [&str; 1] = ["/run"];
+/// Words after which Tab completes package names.
+const PACKAGE_VERBS: [&str; 2] = ["install", "remove"];
+
+/// Everything Tab can complete besides the slash commands themselves.
+#[derive(Debug, Clone, Default)]
+pub struct Completions {
+    /// Canned prompt names, for `/run <Tab>`.
+    pub prompts: Vec<String>,
+    /// Package names, for `install <Tab>` and `remove <Tab>`.
+    pub packages: Vec<String>,
+}
+
+impl Completions {
+    /// The given prompt names, plus the `[packages.*]` option names from the
+    /// Blunux config (`chrome`, `vlc`, `docker`, ...) as package names.
+    pub fn new(prompts: Vec<String>) -> Self {
+        Self {
+            prompts,
+            packages: package_aliases(),
+        }
+    }
+}
+
+/// Option names of every `[packages.*]` group, sorted. The audio group only
+/// has an on/off switch, so it's left out.
+fn package_aliases() -> Vec<String> {
+    let Ok(toml::Value::Table(groups)) = toml::Value::try_from(Packages::default()) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = groups
+        .iter()
+        .filter(|(group, _)| group.as_str() != "audio")
+        .filter_map(|(_, options)| options.as_table())
+        .flat_map(|options| options.keys().cloned())
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Completions for the word ending at `pos` in `line`: where that word
+/// starts, and the candidates that could replace it.
+pub fn complete(line: &str, pos: usize, words: &Completions) -> (usize, Vec<String>) {
+    let before = &line[..pos];
+    let start = before
+        .char_indices()
+        .rev()
+        .find(|(_, c)| c.is_whitespace())
+        .map_or(0, |(i, c)| i + c.len_utf8());
+    let word = &before[start..];
+    let preceding: Vec<&str> = before[..start].split_whitespace().collect();
+
+    let pool: Vec<&str> = match preceding.as_slice() {
+        [] if word.starts_with('/') => SLASH_COMMANDS.to_vec(),
+        ["/run"] => words.prompts.iter().map(String::as_str).collect(),
+        _ if preceding
+            .iter()
+            .any(|w| PACKAGE_VERBS.contains(&w.to_lowercase().as_str())) =>
+        {
+            words.packages.iter().map(String::as_str).collect()
+        }
+        _ => Vec::new(),
+    };
+    let matches = pool
+        .into_iter()
+        .filter(|candidate| candidate.starts_with(word))
+        .map(str::to_string)
+        .collect();
+    (start, matches)
+}
+
+/// rustyline glue for [`complete`].
+struct PromptHelper {
+    words: Completions,
+}
+
+impl Completer for PromptHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(complete(line, pos, &self.words))
+    }
+}
+
+impl Hinter for PromptHelper {
+    type Hint = String;
+}
+
+impl Highlighter for PromptHelper {}
+
+impl Validator for PromptHelper {}
+
+impl Helper for PromptHelper {}
+
+/// Line editor whose submitted lines are saved as they are entered.
+pub struct LineEditor {
+    editor: Editor<PromptHelper, FileHistory>,
+    history_path: PathBuf,
+}
+
+impl LineEditor {
+    /// Open an editor with the history saved at `history_path`, completing
+    /// from `words`.
+    pub fn new(history_path: PathBuf, words: Completions) -> Result<Self, ReadlineError> {
+        let mut editor = Editor::new()?;
+        editor.set_helper(Some(PromptHelper { words }));
+        // A missing or unreadable history just starts empty
+        let _ = editor.load_history(&history_path);
+        Ok(Self {
+            editor,
+            history_path,
+        })
+    }
+
+    /// Read one line after `prompt`. `None` on EOF (Ctrl+D), Ctrl+C at the
+    /// prompt, or a terminal error — all of which end the session.
+    pub fn read_line(&mut self, prompt: &str) -> Option<String> {
+        self.editor.readline(prompt).ok()
+    }
+
+    /// Add `line` to the history and append it to the history file.
+    pub fn remember(&mut self, line: &str) {
+        // Repeats of the previous line aren't added, so there's nothing to save
+        if matches!(self.editor.add_history_entry(line), Ok(true)) {
+            let _ = self.editor.append_history(&self.history_path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustyline::history::History;
+
+    #[test]
+    fn test_submitted_lines_are_appended_to_history_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join(HISTORY_FILE);
+        let mut editor = LineEditor::new(path.clone(), Completions::default()).unwrap();
+        editor.remember("check disk usage");
+        editor.remember("check disk usage");
+        editor.remember("/run health");
+
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(saved.matches("check disk usage").count(), 1, "{saved}");
+        assert!(saved.contains("/run health"), "{saved}");
+
+        // A later session picks the history up and keeps appending
+        let mut editor = LineEditor::new(path.clone(), Completions::default()).unwrap();
+        assert_eq!(editor.editor.history().len(), 2);
+        editor.remember("install vlc");
+        let saved = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = saved.lines().filter(|l| !l.starts_with('#')).collect();
+        assert_eq!(lines, ["check disk usage", "/run health", "install vlc"]);
+    }
+
+    fn words() -> Completions {
+        Completions {
+            prompts: vec!["disk".into(), "gpu".into(), "health".into()],
+            packages: vec!["chrome".into(), "docker".into(), "firefox".into(), "vlc".into()],
+        }
+    }
+
+    fn candidates(line: &str) -> (usize, Vec<String>) {
+        complete(line, line.len(), &words())
+    }
+
+    #[test]
+    fn test_complete_slash_commands() {
+        assert_eq!(candidates("/r"), (0, vec!["/run".to_string()]));
+        assert_eq!(candidates("/"), (0, vec!["/run".to_string()]));
+        assert_eq!(candidates("/x"), (0, vec![]));
+        assert_eq!(candidates("/run h"), (5, vec!["health".to_string()]));
+        assert_eq!(candidates("/run ").1, ["disk", "gpu", "health"]);
+        // Only at the start of the line
+        assert_eq!(candidates("what does /r").1, Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_complete_package_names() {
+        assert_eq!(candidates("install ").1, ["chrome", "docker", "firefox", "vlc"]);
+        assert_eq!(candidates("install f"), (8, vec!["firefox".to_string()]));
+        assert_eq!(candidates("please Remove d"), (14, vec!["docker".to_string()]));
+        assert_eq!(candidates("install vlc c").1, ["chrome"]);
+        assert_eq!(candidates("check f").1, Vec::<String>::new());
+        // The cursor position, not the line end, picks the word
+        assert_eq!(complete("install fi vlc", 10, &words()), (8, vec!["firefox".to_string()]));
+        // Multi-byte whitespace (an ideographic space from a Korean IME)
+        assert_eq!(candidates("install\u{3000}f"), (10, vec!["firefox".to_string()]));
+    }
+
+    #[test]
+    fn test_package_aliases_come_from_config_options() {
+        let aliases = package_aliases();
+        for name in ["chrome", "docker", "firefox", "kde", "vlc"] {
+            assert!(aliases.contains(&name.to_string()), "{name}: {aliases:?}");
+        }
+        assert!(!aliases.contains(&"enabled".to_string()));
+    }
+}
@@ -0,0 +1,177 @@
+use std::io::IsTerminal;
+
+// ── Color mode ───────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum ColorMode {
+    /// Color when stdout is a terminal and NO_COLOR is unset
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    pub fn enabled(self) -> bool {
+        match self {
+            Self::Always => true,
+            Self::Never => false,
+            Self::Auto => {
+                std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+                    && std::io::stdout().is_terminal()
+            }
+        }
+    }
+}
+
+// ── ANSI rendering ───────────────────────────────────────────────────────────
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const CYAN: &str = "\x1b[36m";
+const YELLOW: &str = "\x1b[33m";
+
+/// Wrap `text` in the dim style (used for tool output).
+pub fn dim(text: &str) -> String {
+    format!("{DIM}{text}{RESET}")
+}
+
+/// Render a markdown reply as ANSI-styled terminal text.
+///
+/// Handles the subset models actually produce: headers, bullet lists,
+/// `**bold**`, `` `inline code` `` and fenced code blocks. Code blocks lose
+/// their fences, are indented, and get light highlighting (strings, comments).
+pub fn render_markdown(text: &str) -> String {
+    let mut out = Vec::new();
+    let mut in_code = false;
+
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            out.push(format!("  {}", highlight_code_line(line)));
+            continue;
+        }
+
+        let hashes = trimmed.chars().take_while(|&c| c == '#').count();
+        if (1..=6).contains(&hashes) && trimmed[hashes..].starts_with(' ') {
+            out.push(format!("{BOLD}{}{RESET}", trimmed[hashes..].trim()));
+            continue;
+        }
+
+        let indent = &line[..line.len() - trimmed.len()];
+        if let Some(item) = trimmed.strip_prefix("- ").or_else(|| trimmed.strip_prefix("* ")) {
+            out.push(format!("{indent}• {}", render_inline(item)));
+            continue;
+        }
+
+        out.push(render_inline(line));
+    }
+
+    out.join("\n")
+}
+
+/// Style `**bold**` and `` `code` `` spans within a single line.
+fn render_inline(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut rest = line;
+    loop {
+        let bold = rest.find("**");
+        let code = rest.find('`');
+        match (bold, code) {
+            (Some(b), c) if c.is_none_or(|c| b < c) => {
+                let Some(end) = rest[b + 2..].find("**") else { break };
+                out.push_str(&rest[..b]);
+                out.push_str(&format!("{BOLD}{}{RESET}", &rest[b + 2..b + 2 + end]));
+                rest = &rest[b + 4 + end..];
+            }
+            (_, Some(c)) => {
+                let Some(end) = rest[c + 1..].find('`') else { break };
+                out.push_str(&rest[..c]);
+                out.push_str(&format!("{CYAN}{}{RESET}", &rest[c + 1..c + 1 + end]));
+                rest = &rest[c + 2 + end..];
+            }
+            _ => break,
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Light highlighting for a code line: comments dimmed, quoted strings
+/// yellow, everything else cyan.
+fn highlight_code_line(line: &str) -> String {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with('#') || trimmed.starts_with("//") {
+        return dim(line);
+    }
+
+    let mut out = String::from(CYAN);
+    let mut quote: Option<char> = None;
+    for ch in line.chars() {
+        match quote {
+            None if ch == '"' || ch == '\'' => {
+                quote = Some(ch);
+                out.push_str(YELLOW);
+                out.push(ch);
+            }
+            Some(q) if ch == q => {
+                quote = None;
+                out.push(ch);
+                out.push_str(CYAN);
+            }
+            _ => out.push(ch),
+        }
+    }
+    out.push_str(RESET);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "## Disk usage\n\
+                          Run **this** to check `/home`:\n\
+                          ```bash\n\
+                          # show sizes\n\
+                          du -sh \"$HOME\"\n\
+                          ```\n\
+                          - first\n\
+                          - second";
+
+    #[test]
+    fn test_render_code_block() {
+        let out = render_markdown(SAMPLE);
+        assert!(!out.contains("```"), "fences should be removed: {out}");
+        assert!(out.contains(&format!("  {DIM}# show sizes{RESET}")));
+        assert!(out.contains(&format!("  {CYAN}du -sh {YELLOW}\"$HOME\"{CYAN}{RESET}")));
+    }
+
+    #[test]
+    fn test_render_headers_lists_and_inline() {
+        let out = render_markdown(SAMPLE);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], format!("{BOLD}Disk usage{RESET}"));
+        assert_eq!(
+            lines[1],
+            format!("Run {BOLD}this{RESET} to check {CYAN}/home{RESET}:")
+        );
+        assert_eq!(lines[lines.len() - 2], "• first");
+        assert_eq!(lines[lines.len() - 1], "• second");
+    }
+
+    #[test]
+    fn test_render_unterminated_spans_left_alone() {
+        assert_eq!(render_markdown("a ** b ` c"), "a ** b ` c");
+    }
+
+    #[test]
+    fn test_color_mode_fixed() {
+        assert!(ColorMode::Always.enabled());
+        assert!(!ColorMode::Never.enabled());
+    }
+}
@@ -1,8 +1,12 @@
 use std::path::PathBuf;
 
+use blunux_config::util::command_exists;
 use dialoguer::{Input, Password, Select};
 
-use crate::config::{AgentConfig, ClaudeMode, Language, ModelId, ProviderType, WhatsAppConfig};
+use crate::config::{
+    AgentConfig, ClaudeMode, Language, ModelId, ProviderType,
+    WhatsAppConfig,
+};
 use crate::error::AgentError;
 use crate::memory::Memory;
 use crate::strings;
@@ -10,11 +14,24 @@ use crate::strings;
 pub struct SetupWizard {
     lang: Language,
     config_dir: PathBuf,
+    /// The current config when reconfiguring: its values become the default
+    /// in each prompt and saved credentials are kept unless replaced.
+    existing: Option<AgentConfig>,
 }
 
 impl SetupWizard {
     pub fn new(lang: Language, config_dir: PathBuf) -> Self {
-        Self { lang, config_dir }
+        Self {
+            lang,
+            config_dir,
+            existing: None,
+        }
+    }
+
+    /// Reconfigure `existing` instead of starting from scratch.
+    pub fn reconfigure(mut self, existing: AgentConfig) -> Self {
+        self.existing = Some(existing);
+        self
     }
 
     pub fn run(&self) -> Result<AgentConfig, AgentError> {
@@ -52,16 +69,28 @@ impl SetupWizard {
         // Step 5: WhatsApp bridge setup
         let (whatsapp_enabled, whatsapp_cfg) = self.setup_whatsapp()?;
 
-        // Step 6: Build and save config
-        let config = AgentConfig {
-            provider,
-            claude_mode,
-            model,
-            whatsapp_enabled,
-            language: self.lang.clone(),
-            safe_mode: true,
-            config_dir: self.config_dir.clone(),
-            whatsapp: whatsapp_cfg,
+        // Step 6: Build and save config. Reconfiguring keeps the settings
+        // the wizard doesn't ask about.
+        let config = match &self.existing {
+            Some(existing) => AgentConfig {
+                provider,
+                claude_mode,
+                model,
+                whatsapp_enabled,
+                whatsapp: whatsapp_cfg,
+                config_dir: self.config_dir.clone(),
+                ..existing.clone()
+            },
+            None => AgentConfig {
+                provider,
+                claude_mode,
+                model,
+                whatsapp_enabled,
+                language: self.lang.clone(),
+                config_dir: self.config_dir.clone(),
+                whatsapp: whatsapp_cfg,
+                ..AgentConfig::default()
+            },
         };
         config.save().map_err(AgentError::Config)?;
 
@@ -91,10 +120,14 @@ impl SetupWizard {
             "Claude (Anthropic) — Recommended",
             "DeepSeek — Alternative",
         ];
+        let current = match self.existing.as_ref().map(|c| &c.provider) {
+            Some(ProviderType::DeepSeek) => 1,
+            _ => 0,
+        };
         let selection = Select::new()
             .with_prompt(strings::setup_provider_prompt(&self.lang))
             .items(&items)
-            .default(0)
+            .default(current)
             .interact()
             .map_err(|_| AgentError::UserCancelled)?;
 
@@ -109,10 +142,14 @@ impl SetupWizard {
             "OAuth — Claude Pro/Max subscription (no API key needed)",
             "API Key — Direct HTTP (pay per token)",
         ];
+        let current = match self.existing.as_ref().map(|c| &c.claude_mode) {
+            Some(ClaudeMode::Api) => 1,
+            _ => 0,
+        };
         let selection = Select::new()
             .with_prompt(strings::setup_claude_mode_prompt(&self.lang))
             .items(&items)
-            .default(0)
+            .default(current)
             .interact()
             .map_err(|_| AgentError::UserCancelled)?;
 
@@ -123,27 +160,21 @@ impl SetupWizard {
     }
 
     fn select_model(&self, provider: &ProviderType) -> Result<ModelId, AgentError> {
-        let (items, models) = match provider {
-            ProviderType::Claude => (
-                vec![
-                    "claude-sonnet-4-6 — Fast & balanced (Recommended)",
-                    "claude-opus-4-6 — More capable, slower",
-                ],
-                vec![ModelId::ClaudeSonnet46, ModelId::ClaudeOpus46],
-            ),
-            ProviderType::DeepSeek => (
-                vec![
-                    "deepseek-chat — General purpose (Recommended)",
-                    "deepseek-coder — Code-focused",
-                ],
-                vec![ModelId::DeepSeekChat, ModelId::DeepSeekCoder],
-            ),
-        };
+        let models = ModelId::all_for(provider);
+        let items: Vec<String> = models
+            .iter()
+            .map(|m| format!("{} — {}", m.api_name(), m.tagline()))
+            .collect();
 
+        let current = self
+            .existing
+            .as_ref()
+            .and_then(|c| models.iter().position(|m| m == &c.model))
+            .unwrap_or(0);
         let selection = Select::new()
             .with_prompt(strings::setup_model_prompt(&self.lang))
             .items(&items)
-            .default(0)
+            .default(current)
             .interact()
             .map_err(|_| AgentError::UserCancelled)?;
 
@@ -151,43 +182,35 @@ impl SetupWizard {
     }
 
     fn setup_claude_oauth(&self) -> Result<(), AgentError> {
-        // Check if claude CLI is installed
-        let claude_check = std::process::Command::new("which")
-            .arg("claude")
-            .output();
-
-        match claude_check {
-            Ok(output) if output.status.success() => {
-                println!("  Claude CLI found.");
-            }
-            _ => {
-                println!("  Claude CLI not found. Please install it:");
-                println!("    npm install -g @anthropic-ai/claude-code");
-                println!("  Then run: claude login");
-                println!();
-
-                // Try to install
-                let msg = match self.lang {
-                    Language::Korean => "Claude CLI를 지금 설치하시겠습니까?",
-                    Language::English => "Install Claude CLI now?",
-                };
-                let install: String = Input::new()
-                    .with_prompt(format!("{msg} (y/n)"))
-                    .default("y".into())
-                    .interact_text()
-                    .map_err(|_| AgentError::UserCancelled)?;
-
-                if install.starts_with('y') || install.starts_with('Y') {
-                    println!("  Installing Claude CLI...");
-                    let result = std::process::Command::new("npm")
-                        .args(["install", "-g", "@anthropic-ai/claude-code"])
-                        .status();
-
-                    match result {
-                        Ok(s) if s.success() => println!("  Claude CLI installed."),
-                        _ => {
-                            println!("  Failed to install. Please install manually.");
-                        }
+        if command_exists("claude") {
+            println!("  Claude CLI found.");
+        } else {
+            println!("  Claude CLI not found. Please install it:");
+            println!("    npm install -g @anthropic-ai/claude-code");
+            println!("  Then run: claude login");
+            println!();
+
+            // Try to install
+            let msg = match self.lang {
+                Language::Korean => "Claude CLI를 지금 설치하시겠습니까?",
+                Language::English => "Install Claude CLI now?",
+            };
+            let install: String = Input::new()
+                .with_prompt(format!("{msg} (y/n)"))
+                .default("y".into())
+                .interact_text()
+                .map_err(|_| AgentError::UserCancelled)?;
+
+            if install.starts_with('y') || install.starts_with('Y') {
+                println!("  Installing Claude CLI...");
+                let result = std::process::Command::new("npm")
+                    .args(["install", "-g", "@anthropic-ai/claude-code"])
+                    .status();
+
+                match result {
+                    Ok(s) if s.success() => println!("  Claude CLI installed."),
+                    _ => {
+                        println!("  Failed to install. Please install manually.");
                     }
                 }
             }
@@ -210,28 +233,30 @@ impl SetupWizard {
             strings::setup_whatsapp_skip_label(&self.lang),
             strings::setup_whatsapp_enable_label(&self.lang),
         ];
+        let current = usize::from(self.existing.as_ref().is_some_and(|c| c.whatsapp_enabled));
         let selection = Select::new()
             .with_prompt(strings::setup_whatsapp_enable_prompt(&self.lang))
             .items(&items)
-            .default(0)
+            .default(current)
             .interact()
             .map_err(|_| AgentError::UserCancelled)?;
 
         if selection == 0 {
             println!("  {}", strings::setup_whatsapp_skipped(&self.lang));
-            return Ok((false, WhatsAppConfig {
-                allowed_numbers: vec![],
-                max_messages_per_minute: 5,
-                require_prefix: false,
-                session_timeout: 3600,
-            }));
+            return Ok((false, WhatsAppConfig::default()));
         }
 
         // Collect allowed phone numbers
         println!();
         println!("  {}", strings::setup_whatsapp_phone_hint(&self.lang));
+        let current_numbers = self
+            .existing
+            .as_ref()
+            .map(|c| c.whatsapp.allowed_numbers.join(", "))
+            .unwrap_or_default();
         let phones_raw: String = Input::new()
             .with_prompt(strings::setup_whatsapp_phone_prompt(&self.lang))
+            .with_initial_text(current_numbers)
             .allow_empty(true)
             .interact_text()
             .map_err(|_| AgentError::UserCancelled)?;
@@ -251,20 +276,62 @@ impl SetupWizard {
             }
         }
 
+        // The remote tool policy and confirmation are only edited in config.toml; keep them
+        let existing = self.existing.as_ref().map(|c| &c.whatsapp);
         Ok((true, WhatsAppConfig {
             allowed_numbers,
             max_messages_per_minute: 5,
             require_prefix: false,
             session_timeout: 3600,
+            allowed_tools: existing.map(|w| w.allowed_tools.clone()).unwrap_or_default(),
+            blocked_tools: existing.map(|w| w.blocked_tools.clone()).unwrap_or_default(),
+            confirm_via_reply: existing.is_some_and(|w| w.confirm_via_reply),
+            confirm_timeout_secs: existing.map_or(300, |w| w.confirm_timeout_secs),
         }))
     }
 
     fn setup_api_key(&self, provider_name: &str) -> Result<(), AgentError> {
-        let key: String = Password::new()
-            .with_prompt(strings::setup_api_key_prompt(&self.lang))
-            .interact()
-            .map_err(|_| AgentError::UserCancelled)?;
+        self.ensure_api_key(
+            provider_name,
+            || {
+                let items = [
+                    strings::setup_keep_api_key_label(&self.lang),
+                    strings::setup_replace_api_key_label(&self.lang),
+                ];
+                let selection = Select::new()
+                    .with_prompt(strings::setup_api_key_exists_prompt(&self.lang))
+                    .items(&items)
+                    .default(0)
+                    .interact()
+                    .map_err(|_| AgentError::UserCancelled)?;
+                Ok(selection == 1)
+            },
+            || {
+                Password::new()
+                    .with_prompt(strings::setup_api_key_prompt(&self.lang))
+                    .interact()
+                    .map_err(|_| AgentError::UserCancelled)
+            },
+        )
+    }
 
+    /// Save an API key for `provider_name`, read with `read_key`. When
+    /// reconfiguring and a key is already saved, `replace_existing` is asked
+    /// first and the saved key is kept unless it returns true.
+    fn ensure_api_key(
+        &self,
+        provider_name: &str,
+        replace_existing: impl FnOnce() -> Result<bool, AgentError>,
+        read_key: impl FnOnce() -> Result<String, AgentError>,
+    ) -> Result<(), AgentError> {
+        let cred_dir = self.config_dir.join("credentials");
+        let cred_path = cred_dir.join(provider_name);
+        if self.existing.is_some() && cred_path.exists() && !replace_existing()? {
+            println!("  {}", strings::setup_api_key_kept(&self.lang));
+            return Ok(());
+        }
+
+        let key = read_key()?;
         if key.trim().is_empty() {
             return Err(AgentError::Config(crate::error::ConfigError::MissingField {
                 field: "API key".into(),
@@ -272,7 +339,6 @@ impl SetupWizard {
         }
 
         // Save credential
-        let cred_dir = self.config_dir.join("credentials");
         std::fs::create_dir_all(&cred_dir).map_err(AgentError::Io)?;
 
         // Restrict credentials directory to owner-only (rwx------)
@@ -283,8 +349,7 @@ impl SetupWizard {
                 .map_err(AgentError::Io)?;
         }
 
-        let cred_path = cred_dir.join(provider_name);
-        std::fs::write(&cred_path, key.trim()).map_err(AgentError::Io)?;
+        blunux_config::write_atomic(&cred_path, key.trim()).map_err(AgentError::Io)?;
 
         // Set credential file permissions to owner-read-only (rw-------)
         #[cfg(unix)]
@@ -302,3 +367,59 @@ impl SetupWizard {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn existing_config(dir: &std::path::Path) -> AgentConfig {
+        AgentConfig {
+            provider: ProviderType::DeepSeek,
+            claude_mode: ClaudeMode::Api,
+            model: ModelId::DeepSeekChat,
+            ..crate::test_support::test_config(dir)
+        }
+    }
+
+    #[test]
+    fn test_reconfigure_keeps_credential_when_declined() {
+        let tmp = tempfile::tempdir().unwrap();
+        let cred = tmp.path().join("credentials/deepseek");
+        std::fs::create_dir_all(cred.parent().unwrap()).unwrap();
+        std::fs::write(&cred, "sk-original").unwrap();
+
+        let wizard = SetupWizard::new(Language::English, tmp.path().to_path_buf())
+            .reconfigure(existing_config(tmp.path()));
+        wizard
+            .ensure_api_key(
+                "deepseek",
+                || Ok(false),
+                || panic!("must not prompt for a new key"),
+            )
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(&cred).unwrap(), "sk-original");
+
+        wizard
+            .ensure_api_key("deepseek", || Ok(true), || Ok("sk-new\n".into()))
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(&cred).unwrap(), "sk-new");
+    }
+
+    #[test]
+    fn test_fresh_setup_prompts_for_key() {
+        let tmp = tempfile::tempdir().unwrap();
+        let cred = tmp.path().join("credentials/claude");
+        std::fs::create_dir_all(cred.parent().unwrap()).unwrap();
+        std::fs::write(&cred, "sk-old").unwrap();
+
+        let wizard = SetupWizard::new(Language::English, tmp.path().to_path_buf());
+        wizard
+            .ensure_api_key(
+                "claude",
+                || panic!("fresh setup doesn't offer to keep the key"),
+                || Ok("sk-fresh".into()),
+            )
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(&cred).unwrap(), "sk-fresh");
+    }
+}
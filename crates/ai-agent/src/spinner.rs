@@ -0,0 +1,100 @@
+use std::io::IsTerminal;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+
+const TICK: Duration = Duration::from_millis(120);
+
+/// "Thinking..." indicator for interactive chat.
+///
+/// Ticks on its own thread and erases its line when stopped, so it can be
+/// paused around confirmation prompts and tool output without leaving stray
+/// text behind. Disabled entirely when stdout isn't a terminal.
+pub struct Spinner {
+    message: String,
+    enabled: bool,
+    bar: Mutex<Option<ProgressBar>>,
+}
+
+impl Spinner {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self::with_enabled(message, std::io::stdout().is_terminal())
+    }
+
+    pub fn with_enabled(message: impl Into<String>, enabled: bool) -> Self {
+        Self {
+            message: message.into(),
+            enabled,
+            bar: Mutex::new(None),
+        }
+    }
+
+    /// Start ticking. No-op if disabled or already running.
+    pub fn start(&self) {
+        let mut bar = self.bar.lock().unwrap();
+        if !self.enabled || bar.is_some() {
+            return;
+        }
+        let pb = ProgressBar::with_draw_target(None, ProgressDrawTarget::stdout());
+        pb.set_style(
+            ProgressStyle::with_template("  {spinner} {msg}")
+                .expect("valid spinner template")
+                .tick_chars("⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏ "),
+        );
+        pb.set_message(self.message.clone());
+        pb.enable_steady_tick(TICK);
+        *bar = Some(pb);
+    }
+
+    /// Stop ticking and erase the spinner line.
+    /// Returns whether it was running, so callers can restart it afterwards.
+    pub fn stop(&self) -> bool {
+        match self.bar.lock().unwrap().take() {
+            Some(pb) => {
+                pb.finish_and_clear();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.bar.lock().unwrap().is_some()
+    }
+}
+
+impl Drop for Spinner {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spinner_start_stop() {
+        let spinner = Spinner::with_enabled("Thinking...", true);
+        assert!(!spinner.is_running());
+        assert!(!spinner.stop(), "stopping an idle spinner is a no-op");
+
+        spinner.start();
+        assert!(spinner.is_running());
+        spinner.start();
+        assert!(spinner.is_running(), "double start keeps a single spinner");
+
+        assert!(spinner.stop());
+        assert!(!spinner.is_running());
+        assert!(!spinner.stop());
+    }
+
+    #[test]
+    fn test_disabled_spinner_never_runs() {
+        let spinner = Spinner::with_enabled("Thinking...", false);
+        spinner.start();
+        assert!(!spinner.is_running());
+        assert!(!spinner.stop());
+    }
+}
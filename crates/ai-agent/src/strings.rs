@@ -1,4 +1,6 @@
 use crate::config::Language;
+use crate::doctor::Check;
+use crate::tools::{SafeAlternative, SafetyReason};
 
 pub fn welcome(lang: &Language) -> &'static str {
     match lang {
@@ -42,52 +44,155 @@ pub fn blocked(lang: &Language) -> &'static str {
     }
 }
 
-pub fn goodbye(lang: &Language) -> &'static str {
+pub fn read_only_blocked(lang: &Language) -> &'static str {
     match lang {
-        Language::Korean => "Blunux AI Agent를 종료합니다. 안녕히 계세요!",
-        Language::English => "Goodbye! Blunux AI Agent stopped.",
+        Language::Korean => "읽기 전용 모드: 시스템을 변경하는 도구는 실행할 수 없습니다.",
+        Language::English => "Read-only mode: tools that change the system are disabled.",
     }
 }
 
-pub fn error_prefix(lang: &Language) -> &'static str {
+pub fn remote_tool_blocked(lang: &Language, tool: &str) -> String {
     match lang {
-        Language::Korean => "오류",
-        Language::English => "Error",
+        Language::Korean => format!("원격(WhatsApp) 요청에서는 '{tool}' 도구를 사용할 수 없습니다."),
+        Language::English => format!("The '{tool}' tool is not available to remote (WhatsApp) requests."),
     }
 }
 
-pub fn exit_hint(lang: &Language) -> &'static str {
+pub fn remote_confirm_prompt(lang: &Language, preview: &str) -> String {
+    match lang {
+        Language::Korean => format!("실행하려면 '네'라고 답장하세요: {preview}"),
+        Language::English => format!("Reply YES to run: {preview}"),
+    }
+}
+
+pub fn remote_confirm_pending(lang: &Language) -> &'static str {
     match lang {
-        Language::Korean => "종료: Ctrl+C",
-        Language::English => "Exit: Ctrl+C",
+        Language::Korean => "아직 실행되지 않았습니다. 사용자가 WhatsApp에서 '네'라고 답장해야 실행됩니다. 무엇이 실행될지 알려주세요.",
+        Language::English => "Not run yet: it runs once the user replies YES on WhatsApp. Tell them what will run.",
     }
 }
 
-pub fn confirm_install(lang: &Language, package: &str) -> String {
+pub fn remote_confirm_busy(lang: &Language) -> &'static str {
     match lang {
-        Language::Korean => format!("{package} 패키지를 설치합니다."),
-        Language::English => format!("Installing package: {package}"),
+        Language::Korean => "실행되지 않았습니다. 다른 작업이 이미 확인을 기다리고 있습니다. 그 작업이 처리된 뒤에 다시 요청하세요.",
+        Language::English => "Not run: another action is already waiting for the user's YES. Ask again once that one is settled.",
     }
 }
 
-pub fn confirm_remove(lang: &Language, package: &str) -> String {
+pub fn remote_confirm_expired(lang: &Language) -> &'static str {
     match lang {
-        Language::Korean => format!("{package} 패키지를 삭제합니다."),
-        Language::English => format!("Removing package: {package}"),
+        Language::Korean => "확인 시간이 지나 실행하지 않았습니다. 다시 요청해주세요.",
+        Language::English => "The confirmation timed out, so nothing was run. Please ask again.",
     }
 }
 
-pub fn confirm_service(lang: &Language, action: &str, service: &str) -> String {
+pub fn safety_reason(lang: &Language, reason: SafetyReason) -> &'static str {
+    use SafetyReason::*;
     match lang {
-        Language::Korean => format!("{service} 서비스를 {action}합니다."),
-        Language::English => format!("{action} service: {service}"),
+        Language::Korean => match reason {
+            RootDeletion => "루트 파일시스템 재귀 삭제",
+            RecursiveRootDeletion => "루트 경로 강제 재귀 삭제",
+            DdWrite => "dd를 이용한 디스크 직접 쓰기",
+            DiskFormat => "디스크 포맷 작업",
+            BlockDeviceWrite => "블록 장치에 직접 쓰기",
+            BlockDevicePipe => "블록 장치로 파이프 출력",
+            ForkBomb => "포크 폭탄 감지",
+            RootChmod => "루트 경로의 위험한 권한 변경",
+            RecursiveChmod => "위험한 재귀 권한 변경",
+            DecodeAndExecute => "base64 디코딩 후 실행",
+            RemotePython => "인터넷에서 받은 내용을 Python으로 실행",
+            CredentialFileWrite => "시스템 인증 파일에 쓰기",
+            CredentialFileTee => "tee를 이용한 시스템 인증 파일 쓰기",
+            SudoersEdit => "sudoers 설정 변경",
+            DiskShred => "shred를 이용한 디스크 완전 삭제",
+            PackageRemoval => "패키지 삭제",
+            SystemUpdate => "시스템 업데이트",
+            PackageInstall => "패키지 설치",
+            ServiceChange => "서비스 상태 변경",
+            RootPrivileges => "root 권한이 필요한 명령",
+            RemoteInstall => "인터넷에서 받은 스크립트 실행",
+            PowerState => "시스템 전원 상태 변경",
+            UserAccount => "사용자 계정 변경",
+            PasswordChange => "비밀번호 변경",
+        },
+        Language::English => match reason {
+            RootDeletion => "Recursive deletion of root filesystem",
+            RecursiveRootDeletion => "Recursive forced deletion from root",
+            DdWrite => "Raw disk write with dd",
+            DiskFormat => "Disk format operation",
+            BlockDeviceWrite => "Raw write to block device",
+            BlockDevicePipe => "Pipe to block device",
+            ForkBomb => "Fork bomb detected",
+            RootChmod => "Dangerous permission change on root",
+            RecursiveChmod => "Recursive dangerous permission change",
+            DecodeAndExecute => "Decode-and-execute via base64",
+            RemotePython => "Pipe from internet to Python interpreter",
+            CredentialFileWrite => "Write to sensitive system credentials file",
+            CredentialFileTee => "Write to sensitive system credentials file via tee",
+            SudoersEdit => "Modification of sudoers configuration",
+            DiskShred => "Destructive disk wipe with shred",
+            PackageRemoval => "Package removal",
+            SystemUpdate => "System update",
+            PackageInstall => "Package installation",
+            ServiceChange => "Service state change",
+            RootPrivileges => "Command requires root privileges",
+            RemoteInstall => "Pipe install from internet",
+            PowerState => "System power state change",
+            UserAccount => "User account modification",
+            PasswordChange => "Password change",
+        },
     }
 }
 
-pub fn confirm_update(lang: &Language) -> &'static str {
+pub fn safe_alternative(lang: &Language, alternative: SafeAlternative) -> &'static str {
+    use SafeAlternative::*;
     match lang {
-        Language::Korean => "시스템 전체 업데이트를 실행합니다.",
-        Language::English => "Running full system update.",
+        Language::Korean => match alternative {
+            DeleteSpecificPath => "루트 대신 삭제할 디렉터리를 정확히 지정하세요 (예: rm -r ~/old-project).",
+            VerifyDevice => "lsblk로 대상 장치를 먼저 확인한 뒤 터미널에서 직접 실행하세요.",
+            NarrowPermissions => "권한이 필요한 파일이나 디렉터리에만 적용하세요 (예: chmod 755 ~/bin/script.sh).",
+            InspectFirst => "스크립트를 파일로 저장해 내용을 확인한 뒤 실행하세요.",
+            UseAccountTools => "인증 파일을 직접 수정하지 말고 useradd, usermod, passwd를 사용하세요.",
+            RunManually => "꼭 필요하다면 터미널에서 직접 실행하세요.",
+            ShredFiles => "디스크 전체 대신 개별 파일을 지정하세요 (예: shred -u secret.txt).",
+        },
+        Language::English => match alternative {
+            DeleteSpecificPath => "Name the exact directory to delete instead of the root (e.g. rm -r ~/old-project).",
+            VerifyDevice => "Check the target device with lsblk first, then run the command yourself in a terminal.",
+            NarrowPermissions => "Change permissions only on the file or directory that needs it (e.g. chmod 755 ~/bin/script.sh).",
+            InspectFirst => "Save the script to a file and read it before running it.",
+            UseAccountTools => "Use useradd, usermod or passwd instead of editing credential files directly.",
+            RunManually => "If you really need this, run it yourself in a terminal.",
+            ShredFiles => "Shred individual files instead of a whole disk (e.g. shred -u secret.txt).",
+        },
+    }
+}
+
+pub fn goodbye(lang: &Language) -> &'static str {
+    match lang {
+        Language::Korean => "Blunux AI Agent를 종료합니다. 안녕히 계세요!",
+        Language::English => "Goodbye! Blunux AI Agent stopped.",
+    }
+}
+
+pub fn error_prefix(lang: &Language) -> &'static str {
+    match lang {
+        Language::Korean => "오류",
+        Language::English => "Error",
+    }
+}
+
+pub fn session_resumed(lang: &Language, messages: usize) -> String {
+    match lang {
+        Language::Korean => format!("이전 대화를 이어갑니다 (메시지 {messages}개)"),
+        Language::English => format!("Resumed previous session ({messages} messages)"),
+    }
+}
+
+pub fn exit_hint(lang: &Language) -> &'static str {
+    match lang {
+        Language::Korean => "종료: Ctrl+C | 저장된 질문: /run <이름>",
+        Language::English => "Exit: Ctrl+C | Canned prompts: /run <name>",
     }
 }
 
@@ -105,6 +210,31 @@ pub fn tool_executing(lang: &Language, tool_name: &str) -> String {
     }
 }
 
+pub fn daemon_running_warning(lang: &Language) -> &'static str {
+    match lang {
+        Language::Korean => {
+            "⚠ blunux-ai 데몬이 실행 중입니다. 대화 기록과 메모리를 함께 기록하므로 내용이 섞일 수 있습니다."
+        }
+        Language::English => {
+            "⚠ The blunux-ai daemon is running. It writes the same memory files, so entries may interleave."
+        }
+    }
+}
+
+pub fn provider_check_ok(lang: &Language, provider: &str) -> String {
+    match lang {
+        Language::Korean => format!("프로바이더 연결 확인: {provider} 정상"),
+        Language::English => format!("Provider check: {provider} is reachable"),
+    }
+}
+
+pub fn provider_check_failed(lang: &Language) -> &'static str {
+    match lang {
+        Language::Korean => "프로바이더 연결 확인 실패",
+        Language::English => "Provider check failed",
+    }
+}
+
 // ── Setup wizard strings ─────────────────────────────────────────────────────
 
 pub fn setup_welcome(lang: &Language) -> &'static str {
@@ -142,6 +272,34 @@ pub fn setup_api_key_prompt(lang: &Language) -> &'static str {
     }
 }
 
+pub fn setup_api_key_exists_prompt(lang: &Language) -> &'static str {
+    match lang {
+        Language::Korean => "저장된 API 키가 있습니다",
+        Language::English => "An API key is already saved",
+    }
+}
+
+pub fn setup_keep_api_key_label(lang: &Language) -> &'static str {
+    match lang {
+        Language::Korean => "유지 — 저장된 키 사용",
+        Language::English => "Keep — use the saved key",
+    }
+}
+
+pub fn setup_replace_api_key_label(lang: &Language) -> &'static str {
+    match lang {
+        Language::Korean => "교체 — 새 키 입력",
+        Language::English => "Replace — enter a new key",
+    }
+}
+
+pub fn setup_api_key_kept(lang: &Language) -> &'static str {
+    match lang {
+        Language::Korean => "저장된 API 키를 유지합니다.",
+        Language::English => "Keeping the saved API key.",
+    }
+}
+
 pub fn setup_done(lang: &Language) -> &'static str {
     match lang {
         Language::Korean => "설정 완료! 'blunux-ai chat'으로 시작하세요.",
@@ -242,6 +400,49 @@ pub fn status_automations_none(lang: &Language) -> &'static str {
     }
 }
 
+pub fn timers_installed(lang: &Language, n: usize, dir: &std::path::Path) -> String {
+    match lang {
+        Language::Korean => format!("systemd 타이머 {n}개를 설치했습니다: {}", dir.display()),
+        Language::English => format!("Installed {n} systemd timers in {}", dir.display()),
+    }
+}
+
+pub fn timers_daemon_running(lang: &Language) -> &'static str {
+    match lang {
+        Language::Korean => {
+            "blunux-ai 데몬이 실행 중입니다. 데몬도 자동화를 실행하므로 타이머를 설치하면 두 번 실행됩니다. 데몬을 중지한 뒤 다시 시도하세요."
+        }
+        Language::English => {
+            "The blunux-ai daemon is running and already runs automations; timers would run them twice. Stop the daemon first."
+        }
+    }
+}
+
+pub fn timers_no_whatsapp(lang: &Language) -> &'static str {
+    match lang {
+        Language::Korean => {
+            "참고: 타이머 실행 결과는 저널에만 기록됩니다. notify = \"whatsapp\" 알림은 데몬에서만 전송됩니다."
+        }
+        Language::English => {
+            "Note: timer runs only log to the journal. notify = \"whatsapp\" is only sent by the daemon."
+        }
+    }
+}
+
+pub fn ask_no_prompt(lang: &Language) -> &'static str {
+    match lang {
+        Language::Korean => "질문을 입력하세요: blunux-ai ask \"질문\" 또는 echo \"질문\" | blunux-ai ask",
+        Language::English => "No prompt given: use blunux-ai ask \"question\" or echo \"question\" | blunux-ai ask",
+    }
+}
+
+pub fn automation_not_found(lang: &Language, name: &str) -> String {
+    match lang {
+        Language::Korean => format!("자동화를 찾을 수 없습니다: {name}"),
+        Language::English => format!("Automation not found: {name}"),
+    }
+}
+
 pub fn status_whatsapp_enabled(lang: &Language, n: usize) -> String {
     match lang {
         Language::Korean => format!("활성화 (허용된 번호 {n}개)"),
@@ -255,3 +456,110 @@ pub fn status_whatsapp_disabled(lang: &Language) -> &'static str {
         Language::English => "Disabled",
     }
 }
+
+// ── Doctor strings ───────────────────────────────────────────────────────────
+
+pub fn doctor_title(lang: &Language) -> &'static str {
+    match lang {
+        Language::Korean => "Blunux AI 진단",
+        Language::English => "Blunux AI Doctor",
+    }
+}
+
+pub fn doctor_check_name(lang: &Language, check: Check) -> &'static str {
+    match (check, lang) {
+        (Check::Config, Language::Korean) => "설정",
+        (Check::Config, Language::English) => "Config",
+        (Check::Credentials, Language::Korean) => "인증 정보",
+        (Check::Credentials, Language::English) => "Credentials",
+        (Check::Provider, Language::Korean) => "프로바이더",
+        (Check::Provider, Language::English) => "Provider",
+        (Check::Yay, _) => "yay",
+        (Check::Distro, Language::Korean) => "배포판",
+        (Check::Distro, Language::English) => "Distro",
+    }
+}
+
+pub fn doctor_run_setup(lang: &Language) -> &'static str {
+    match lang {
+        Language::Korean => "'blunux-ai setup'을 실행하세요",
+        Language::English => "Run 'blunux-ai setup'",
+    }
+}
+
+pub fn doctor_save_api_key(lang: &Language) -> &'static str {
+    match lang {
+        Language::Korean => "'blunux-ai setup'을 실행해 API 키를 저장하세요",
+        Language::English => "Run 'blunux-ai setup' to save your API key",
+    }
+}
+
+pub fn doctor_claude_cli_found(lang: &Language, path: &std::path::Path) -> String {
+    match lang {
+        Language::Korean => format!("claude CLI 위치: {}", path.display()),
+        Language::English => format!("claude CLI at {}", path.display()),
+    }
+}
+
+pub fn doctor_claude_cli_missing(lang: &Language) -> &'static str {
+    match lang {
+        Language::Korean => "claude CLI를 찾을 수 없습니다 (OAuth 모드에 필요)",
+        Language::English => "claude CLI not found (needed for OAuth mode)",
+    }
+}
+
+pub fn doctor_claude_cli_hint(lang: &Language) -> &'static str {
+    match lang {
+        Language::Korean => "npm install -g @anthropic-ai/claude-code 후 claude login 을 실행하세요",
+        Language::English => "npm install -g @anthropic-ai/claude-code, then run: claude login",
+    }
+}
+
+pub fn doctor_provider_reachable(lang: &Language, provider: &str) -> String {
+    match lang {
+        Language::Korean => format!("{provider} 연결 정상"),
+        Language::English => format!("{provider} is reachable"),
+    }
+}
+
+pub fn doctor_check_network(lang: &Language) -> &'static str {
+    match lang {
+        Language::Korean => "네트워크 연결과 인증 정보를 확인하세요",
+        Language::English => "Check your network connection and credentials",
+    }
+}
+
+pub fn doctor_yay_missing(lang: &Language) -> &'static str {
+    match lang {
+        Language::Korean => "찾을 수 없음; 패키지 설치/제거 도구가 동작하지 않습니다",
+        Language::English => "not found; package install/remove tools won't work",
+    }
+}
+
+pub fn doctor_yay_hint(lang: &Language) -> &'static str {
+    match lang {
+        Language::Korean => "AUR에서 yay를 설치하세요: https://github.com/Jguer/yay",
+        Language::English => "Install yay from the AUR: https://github.com/Jguer/yay",
+    }
+}
+
+pub fn doctor_os_release_unreadable(lang: &Language) -> &'static str {
+    match lang {
+        Language::Korean => "/etc/os-release 를 읽을 수 없습니다",
+        Language::English => "/etc/os-release not readable",
+    }
+}
+
+pub fn doctor_not_arch(lang: &Language, name: &str) -> String {
+    match lang {
+        Language::Korean => format!("{name}은(는) Arch 기반이 아닙니다; pacman/yay 도구가 동작하지 않습니다"),
+        Language::English => format!("{name} is not Arch-based; pacman/yay tools won't work"),
+    }
+}
+
+pub fn doctor_arch_hint(lang: &Language) -> &'static str {
+    match lang {
+        Language::Korean => "Blunux AI는 Blunux 및 Arch 기반 시스템용입니다",
+        Language::English => "Blunux AI is built for Blunux and other Arch-based systems",
+    }
+}
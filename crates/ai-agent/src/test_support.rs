@@ -0,0 +1,82 @@
+// ── Shared test fixtures ─────────────────────────────────────────────────────
+//
+// Config and mock providers used by the unit tests of several modules.
+
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::config::{AgentConfig, Language};
+use crate::error::ProviderError;
+use crate::providers::{CompletionResult, Message, Provider};
+use crate::tools::ToolDefinition;
+
+/// The default config in English, with everything kept under `dir`.
+pub(crate) fn test_config(dir: &Path) -> AgentConfig {
+    AgentConfig {
+        language: Language::English,
+        config_dir: dir.to_path_buf(),
+        ..Default::default()
+    }
+}
+
+/// For tests that never reach the provider.
+pub(crate) struct NullProvider;
+
+#[async_trait::async_trait]
+impl Provider for NullProvider {
+    fn name(&self) -> &str {
+        "null"
+    }
+
+    async fn complete(
+        &self,
+        _system_prompt: &str,
+        _messages: &[Message],
+        _tools: &[ToolDefinition],
+        _max_tokens: u32,
+    ) -> Result<CompletionResult, ProviderError> {
+        unreachable!("this test never calls the provider")
+    }
+}
+
+/// Returns the queued completions in order.
+pub(crate) struct ScriptedProvider {
+    pub(crate) replies: Mutex<Vec<CompletionResult>>,
+}
+
+#[async_trait::async_trait]
+impl Provider for ScriptedProvider {
+    fn name(&self) -> &str {
+        "scripted"
+    }
+
+    async fn complete(
+        &self,
+        _system_prompt: &str,
+        _messages: &[Message],
+        _tools: &[ToolDefinition],
+        _max_tokens: u32,
+    ) -> Result<CompletionResult, ProviderError> {
+        Ok(self.replies.lock().unwrap().remove(0))
+    }
+}
+
+/// Provider that always fails authentication.
+pub(crate) struct FailingProvider;
+
+#[async_trait::async_trait]
+impl Provider for FailingProvider {
+    fn name(&self) -> &str {
+        "failing"
+    }
+
+    async fn complete(
+        &self,
+        _system_prompt: &str,
+        _messages: &[Message],
+        _tools: &[ToolDefinition],
+        _max_tokens: u32,
+    ) -> Result<CompletionResult, ProviderError> {
+        Err(ProviderError::AuthenticationFailed)
+    }
+}
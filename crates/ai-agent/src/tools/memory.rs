@@ -0,0 +1,193 @@
+use async_trait::async_trait;
+use std::path::PathBuf;
+
+use crate::error::ToolError;
+use crate::memory::Memory;
+use crate::tools::{PermissionLevel, SystemTool};
+
+const DEFAULT_DAYS: u32 = 7;
+const MAX_DAYS: u32 = 365;
+
+// ── search_memory ────────────────────────────────────────────────────────────
+
+pub struct SearchMemoryTool {
+    pub config_dir: PathBuf,
+}
+
+#[async_trait]
+impl SystemTool for SearchMemoryTool {
+    fn name(&self) -> &str {
+        "search_memory"
+    }
+    fn description(&self) -> &str {
+        "Search past daily session logs for lines containing a word or phrase (case-insensitive). Use for questions like 'what did I install last week?'."
+    }
+    fn input_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "query": {
+                    "type": "string",
+                    "description": "Text to search for, e.g. 'install'"
+                },
+                "days": {
+                    "type": "integer",
+                    "description": "How many days back to search, including today (default: 7, max: 365)"
+                }
+            },
+            "required": ["query"]
+        })
+    }
+    fn permission_level(&self) -> PermissionLevel {
+        PermissionLevel::Safe
+    }
+    async fn execute(&self, input: serde_json::Value) -> Result<String, ToolError> {
+        let query = input
+            .get("query")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|q| !q.is_empty())
+            .ok_or_else(|| ToolError::InvalidInput("'query' is required".into()))?;
+        let days = input
+            .get("days")
+            .and_then(|v| v.as_u64())
+            .map(|d| d.clamp(1, MAX_DAYS as u64) as u32)
+            .unwrap_or(DEFAULT_DAYS);
+
+        let hits = Memory::new(self.config_dir.clone()).search(query, days);
+        if hits.is_empty() {
+            return Ok(format!("No matches for '{query}' in the last {days} days."));
+        }
+        Ok(hits
+            .iter()
+            .map(|(date, line)| format!("{date}  {line}"))
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+}
+
+// ── update_user_preference ───────────────────────────────────────────────────
+
+pub struct UpdateUserPreferenceTool {
+    pub config_dir: PathBuf,
+}
+
+#[async_trait]
+impl SystemTool for UpdateUserPreferenceTool {
+    fn name(&self) -> &str {
+        "update_user_preference"
+    }
+    fn description(&self) -> &str {
+        "Remember a user preference (e.g. 'prefers vim over nano') by adding it to USER.md. Existing preferences are kept."
+    }
+    fn input_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "preference": {
+                    "type": "string",
+                    "description": "One short preference, e.g. 'browser: firefox'"
+                }
+            },
+            "required": ["preference"]
+        })
+    }
+    fn permission_level(&self) -> PermissionLevel {
+        PermissionLevel::Safe
+    }
+    fn writes_memory(&self) -> bool {
+        true
+    }
+    async fn execute(&self, input: serde_json::Value) -> Result<String, ToolError> {
+        let preference = input
+            .get("preference")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|p| !p.is_empty() && !p.contains('\n'))
+            .ok_or_else(|| {
+                ToolError::InvalidInput("'preference' must be a single non-empty line".into())
+            })?;
+
+        let added = Memory::new(self.config_dir.clone())
+            .append_user(&format!("- {preference}"))
+            .map_err(|e| ToolError::Io(std::io::Error::other(e.to_string())))?;
+        Ok(if added {
+            format!("Saved preference: {preference}")
+        } else {
+            format!("Preference already saved: {preference}")
+        })
+    }
+}
+
+// ── remember ─────────────────────────────────────────────────────────────────
+
+pub struct RememberTool {
+    pub config_dir: PathBuf,
+}
+
+#[async_trait]
+impl SystemTool for RememberTool {
+    fn name(&self) -> &str {
+        "remember"
+    }
+    fn description(&self) -> &str {
+        "Save an important fact to long-term memory (MEMORY.md) so it is known in future sessions, e.g. 'main disk is /dev/nvme0n1'. Use update_user_preference for preferences."
+    }
+    fn input_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "fact": {
+                    "type": "string",
+                    "description": "One short fact, e.g. 'external backup drive is mounted at /mnt/backup'"
+                }
+            },
+            "required": ["fact"]
+        })
+    }
+    fn permission_level(&self) -> PermissionLevel {
+        PermissionLevel::Safe
+    }
+    fn writes_memory(&self) -> bool {
+        true
+    }
+    async fn execute(&self, input: serde_json::Value) -> Result<String, ToolError> {
+        let fact = input
+            .get("fact")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|f| !f.is_empty() && !f.contains('\n'))
+            .ok_or_else(|| ToolError::InvalidInput("'fact' must be a single non-empty line".into()))?;
+
+        let added = Memory::new(self.config_dir.clone())
+            .append_long_term(fact)
+            .map_err(|e| ToolError::Io(std::io::Error::other(e.to_string())))?;
+        Ok(if added {
+            format!("Remembered: {fact}")
+        } else {
+            format!("Already remembered: {fact}")
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_remember_persists_fact() {
+        let tmp = tempfile::tempdir().unwrap();
+        let tool = RememberTool {
+            config_dir: tmp.path().to_path_buf(),
+        };
+        let input = serde_json::json!({"fact": "main disk is /dev/nvme0n1"});
+        assert_eq!(tool.execute(input.clone()).await.unwrap(), "Remembered: main disk is /dev/nvme0n1");
+        assert!(tool.execute(input).await.unwrap().starts_with("Already remembered"));
+
+        let err = tool.execute(serde_json::json!({"fact": "a\nb"})).await.unwrap_err();
+        assert!(matches!(err, ToolError::InvalidInput(_)));
+
+        let ctx = Memory::new(tmp.path().to_path_buf()).build_context().unwrap();
+        assert!(ctx.contains("- main disk is /dev/nvme0n1"), "{ctx}");
+    }
+}
@@ -1,15 +1,18 @@
+pub mod memory;
 pub mod packages;
+pub mod runner;
 pub mod safety;
 pub mod services;
 pub mod system;
 
 use std::collections::HashMap;
+use std::path::Path;
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
 use crate::error::ToolError;
-pub use safety::{PermissionLevel, SafetyChecker, SafetyResult};
+pub use safety::{PermissionLevel, SafeAlternative, SafetyChecker, SafetyReason, SafetyResult};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolDefinition {
@@ -24,6 +27,17 @@ pub trait SystemTool: Send + Sync {
     fn description(&self) -> &str;
     fn input_schema(&self) -> serde_json::Value;
     fn permission_level(&self) -> PermissionLevel;
+    /// Permission for one call with `input`. Defaults to
+    /// [`permission_level`](Self::permission_level); tools that only change
+    /// the system for some inputs (e.g. `apply: true`) relax it for the rest.
+    fn permission_for(&self, _input: &serde_json::Value) -> PermissionLevel {
+        self.permission_level()
+    }
+    /// Whether the tool saves what it is given into memory that every later
+    /// session reads. Remote callers need confirmation for these.
+    fn writes_memory(&self) -> bool {
+        false
+    }
     async fn execute(&self, input: serde_json::Value) -> Result<String, ToolError>;
 
     fn definition(&self) -> ToolDefinition {
@@ -35,55 +49,93 @@ pub trait SystemTool: Send + Sync {
     }
 }
 
+#[derive(Default)]
 pub struct ToolRegistry {
     tools: HashMap<String, Box<dyn SystemTool>>,
 }
 
 impl ToolRegistry {
-    pub fn default_tools() -> Self {
-        let mut tools: HashMap<String, Box<dyn SystemTool>> = HashMap::new();
+    /// An empty registry, for embedders that pick their own tool set.
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-        // System tools
-        tools.insert("check_disk".into(), Box::new(system::CheckDiskTool));
-        tools.insert("check_memory".into(), Box::new(system::CheckMemoryTool));
-        tools.insert(
-            "check_processes".into(),
+    pub fn default_tools() -> Self {
+        let tools: Vec<Box<dyn SystemTool>> = vec![
+            // System tools
+            Box::new(system::CheckDiskTool),
+            Box::new(system::CheckMemoryTool),
             Box::new(system::CheckProcessesTool),
-        );
-        tools.insert("read_logs".into(), Box::new(system::ReadLogsTool));
-        tools.insert(
-            "check_network".into(),
+            Box::new(system::ReadLogsTool),
+            Box::new(system::CheckBootTool),
             Box::new(system::CheckNetworkTool),
-        );
-
-        // Package tools
-        tools.insert(
-            "list_packages".into(),
-            Box::new(packages::ListPackagesTool),
-        );
-        tools.insert(
-            "install_package".into(),
-            Box::new(packages::InstallPackageTool),
-        );
-        tools.insert(
-            "remove_package".into(),
-            Box::new(packages::RemovePackageTool),
-        );
-        tools.insert(
-            "update_system".into(),
-            Box::new(packages::UpdateSystemTool),
-        );
-
-        // Service tools
-        tools.insert(
-            "manage_service".into(),
+            // Package tools
+            Box::new(packages::ListPackagesTool::default()),
+            Box::new(packages::IsInstalledTool::default()),
+            Box::new(packages::InstallPackageTool::default()),
+            Box::new(packages::RemovePackageTool::default()),
+            Box::new(packages::UpdateSystemTool::default()),
+            Box::new(packages::CleanCacheTool::default()),
+            // Service tools
             Box::new(services::ManageServiceTool),
-        );
+            Box::new(services::CheckTimersTool),
+            // Generic command
+            Box::new(system::RunCommandTool),
+        ];
 
-        // Generic command
-        tools.insert("run_command".into(), Box::new(system::RunCommandTool));
+        let mut registry = Self::new();
+        for tool in tools {
+            registry
+                .register(tool)
+                .expect("built-in tool names are unique and valid");
+        }
+        registry
+    }
 
-        Self { tools }
+    /// The default tools plus the memory tools bound to `config_dir`:
+    /// everything an [`Agent`](crate::agent::Agent) exposes before
+    /// `with_blunux_config`.
+    pub fn for_agent(config_dir: &Path) -> Self {
+        let mut registry = Self::default_tools();
+        for tool in [
+            Box::new(memory::SearchMemoryTool {
+                config_dir: config_dir.to_path_buf(),
+            }) as Box<dyn SystemTool>,
+            Box::new(memory::UpdateUserPreferenceTool {
+                config_dir: config_dir.to_path_buf(),
+            }),
+            Box::new(memory::RememberTool {
+                config_dir: config_dir.to_path_buf(),
+            }),
+        ] {
+            registry.register(tool).expect("memory tool names are unique");
+        }
+        registry
+    }
+
+    /// Names of every tool an agent can have: those of
+    /// [`for_agent`](Self::for_agent) plus `check_blunux_packages`, which is
+    /// added once a blunux config is given.
+    pub fn builtin_names() -> Vec<String> {
+        let mut names: Vec<String> = Self::for_agent(Path::new("")).tools.into_keys().collect();
+        names.push(packages::CheckBlunuxPackagesTool::new(Default::default()).name().to_string());
+        names.sort();
+        names
+    }
+
+    /// Add a tool. Used for tools that need construction-time state (e.g. a
+    /// config path) and for embedders adding their own. Names must be
+    /// `[a-z0-9_]+` and not already taken.
+    pub fn register(&mut self, tool: Box<dyn SystemTool>) -> Result<(), ToolError> {
+        let name = tool.name();
+        if !is_valid_tool_name(name) {
+            return Err(ToolError::InvalidName { name: name.into() });
+        }
+        if self.tools.contains_key(name) {
+            return Err(ToolError::DuplicateName { name: name.into() });
+        }
+        self.tools.insert(name.to_string(), tool);
+        Ok(())
     }
 
     pub fn get(&self, name: &str) -> Option<&dyn SystemTool> {
@@ -93,4 +145,148 @@ impl ToolRegistry {
     pub fn definitions(&self) -> Vec<ToolDefinition> {
         self.tools.values().map(|t| t.definition()).collect()
     }
+
+    /// Human-readable listing of every tool, sorted by name: permission
+    /// level, description and pretty-printed input schema.
+    pub fn catalog(&self) -> String {
+        let mut names: Vec<&String> = self.tools.keys().collect();
+        names.sort();
+        names
+            .into_iter()
+            .map(|name| {
+                let tool = &self.tools[name];
+                let schema = serde_json::to_string_pretty(&tool.input_schema())
+                    .unwrap_or_default()
+                    .lines()
+                    .map(|l| format!("    {l}"))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                format!(
+                    "{name} [{}]\n  {}\n{schema}\n",
+                    tool.permission_level().label(),
+                    tool.description()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Schema for the optional `dry_run` input shared by the destructive tools.
+fn dry_run_schema() -> serde_json::Value {
+    serde_json::json!({
+        "type": "boolean",
+        "description": "If true, only report the command that would run, without running it"
+    })
+}
+
+/// Whether the tool input asks for a dry run (`"dry_run": true`).
+fn is_dry_run(input: &serde_json::Value) -> bool {
+    input.get("dry_run").and_then(|v| v.as_bool()).unwrap_or(false)
+}
+
+/// Tool output for a dry run: the command that would have been executed.
+fn dry_run_report(cmd: &str, args: &[&str]) -> String {
+    format!("[dry run] Would run: {cmd} {}", args.join(" "))
+}
+
+fn is_valid_tool_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct DeployTool;
+
+    #[async_trait]
+    impl SystemTool for DeployTool {
+        fn name(&self) -> &str {
+            "deploy"
+        }
+        fn description(&self) -> &str {
+            "Deploy the given service"
+        }
+        fn input_schema(&self) -> serde_json::Value {
+            serde_json::json!({"type": "object", "properties": {}, "required": []})
+        }
+        fn permission_level(&self) -> PermissionLevel {
+            PermissionLevel::RequiresConfirmation
+        }
+        async fn execute(&self, input: serde_json::Value) -> Result<String, ToolError> {
+            Ok(format!("deployed {}", input["service"].as_str().unwrap_or("?")))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_register_custom_tool() {
+        let mut registry = ToolRegistry::new();
+        assert!(registry.definitions().is_empty());
+
+        registry.register(Box::new(DeployTool)).unwrap();
+        assert_eq!(registry.definitions().len(), 1);
+        let out = registry
+            .get("deploy")
+            .unwrap()
+            .execute(serde_json::json!({"service": "web"}))
+            .await
+            .unwrap();
+        assert_eq!(out, "deployed web");
+    }
+
+    #[test]
+    fn test_default_tools_extendable() {
+        let mut registry = ToolRegistry::default_tools();
+        let count = registry.definitions().len();
+        assert!(registry.get("check_disk").is_some());
+        registry.register(Box::new(DeployTool)).unwrap();
+        assert_eq!(registry.definitions().len(), count + 1);
+    }
+
+    #[test]
+    fn test_register_rejects_duplicates() {
+        let mut registry = ToolRegistry::default_tools();
+        let err = registry.register(Box::new(system::CheckDiskTool)).unwrap_err();
+        assert!(matches!(err, ToolError::DuplicateName { name } if name == "check_disk"));
+    }
+
+    #[test]
+    fn test_tool_name_validation() {
+        assert!(is_valid_tool_name("check_disk"));
+        assert!(is_valid_tool_name("deploy2"));
+        assert!(!is_valid_tool_name(""));
+        assert!(!is_valid_tool_name("Check-Disk"));
+        assert!(!is_valid_tool_name("check disk"));
+    }
+
+    #[test]
+    fn test_catalog_lists_every_tool() {
+        let tmp = tempfile::tempdir().unwrap();
+        let registry = ToolRegistry::for_agent(tmp.path());
+        let catalog = registry.catalog();
+        for def in registry.definitions() {
+            assert!(
+                catalog.contains(&format!("{} [", def.name)),
+                "{} missing from catalog",
+                def.name
+            );
+        }
+        assert!(catalog.contains("run_command [requires confirmation]"));
+        assert!(catalog.contains("\"type\": \"object\""));
+    }
+
+    #[test]
+    fn test_default_tools_self_check() {
+        let registry = ToolRegistry::default_tools();
+        let defs = registry.definitions();
+        assert_eq!(defs.len(), 15);
+        for def in &defs {
+            assert!(is_valid_tool_name(&def.name), "bad name {}", def.name);
+            assert_eq!(registry.get(&def.name).unwrap().name(), def.name);
+        }
+    }
 }
@@ -1,20 +1,23 @@
 use async_trait::async_trait;
+use blunux_config::util::{command_exists, human_size};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
-use tokio::process::Command;
 
 use crate::error::ToolError;
-use crate::tools::{PermissionLevel, SystemTool};
+use crate::tools::runner::{CommandRunner, RealRunner};
+use crate::tools::{dry_run_report, dry_run_schema, is_dry_run, PermissionLevel, SystemTool};
 
-async fn run_pkg_cmd(cmd: &str, args: &[&str], timeout_secs: u64) -> Result<String, ToolError> {
-    let result = tokio::time::timeout(
-        Duration::from_secs(timeout_secs),
-        Command::new(cmd).args(args).output(),
-    )
-    .await
-    .map_err(|_| ToolError::Timeout {
-        secs: timeout_secs,
-    })?
-    .map_err(ToolError::Io)?;
+async fn run_pkg_cmd(
+    runner: &dyn CommandRunner,
+    cmd: &str,
+    args: &[&str],
+    timeout_secs: u64,
+) -> Result<String, ToolError> {
+    let result = runner
+        .run(cmd, args, Duration::from_secs(timeout_secs))
+        .await?;
 
     let stdout = String::from_utf8_lossy(&result.stdout).to_string();
     let stderr = String::from_utf8_lossy(&result.stderr).to_string();
@@ -30,9 +33,136 @@ async fn run_pkg_cmd(cmd: &str, args: &[&str], timeout_secs: u64) -> Result<Stri
     }
 }
 
+/// Declares a package tool that runs its commands through a
+/// [`CommandRunner`]: `with_runner` for tests, `Default` for real processes.
+macro_rules! runner_tool {
+    ($name:ident) => {
+        pub struct $name {
+            runner: Arc<dyn CommandRunner>,
+        }
+
+        impl $name {
+            pub fn with_runner(runner: Arc<dyn CommandRunner>) -> Self {
+                Self { runner }
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::with_runner(Arc::new(RealRunner))
+            }
+        }
+    };
+}
+
+/// The `package` input, checked to be a plain package name (alphanumeric,
+/// dash, underscore, dot only).
+fn package_arg(input: &serde_json::Value) -> Result<&str, ToolError> {
+    let package = input
+        .get("package")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ToolError::InvalidInput("Missing 'package' field".into()))?;
+    if !package
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == '.')
+    {
+        return Err(ToolError::InvalidInput(format!(
+            "Invalid package name: {package}"
+        )));
+    }
+    Ok(package)
+}
+
 // ── list_packages ────────────────────────────────────────────────────────────
 
-pub struct ListPackagesTool;
+/// An installed package as reported by `pacman -Q` or `pacman -Qs`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstalledPackage {
+    pub name: String,
+    /// Full version string, including any `epoch:` prefix and `-pkgrel`.
+    pub version: String,
+    /// One-line description; only `pacman -Qs` prints one.
+    pub description: Option<String>,
+}
+
+/// Parse `pacman -Q` output (`name version` per line). Also accepts
+/// `pacman -Qs` output, whose `local/name version (groups)` lines are followed
+/// by an indented description. Malformed lines are skipped.
+pub fn parse_pacman_q(output: &str) -> Vec<InstalledPackage> {
+    let mut packages: Vec<InstalledPackage> = Vec::new();
+    for line in output.lines() {
+        if line.starts_with(char::is_whitespace) {
+            let text = line.trim();
+            if let (Some(package), false) = (packages.last_mut(), text.is_empty()) {
+                package.description = Some(match package.description.take() {
+                    Some(earlier) => format!("{earlier} {text}"),
+                    None => text.to_string(),
+                });
+            }
+            continue;
+        }
+        let mut fields = line.split_whitespace();
+        let (Some(name), Some(version)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        packages.push(InstalledPackage {
+            name: name.rsplit('/').next().unwrap_or(name).to_string(),
+            version: version.to_string(),
+            description: None,
+        });
+    }
+    packages
+}
+
+runner_tool!(ListPackagesTool);
+
+impl ListPackagesTool {
+
+    /// Installed packages, or with `search` only those `pacman -Qs` matches.
+    pub async fn packages(&self, search: Option<&str>) -> Result<Vec<InstalledPackage>, ToolError> {
+        let output = match search {
+            Some(query) => {
+                match run_pkg_cmd(self.runner.as_ref(), "pacman", &["-Qs", query], 60).await {
+                    // pacman -Qs exits 1 when nothing matches
+                    Err(ToolError::ExecutionFailed { exit_code: 1, .. }) => String::new(),
+                    other => other?,
+                }
+            }
+            None => run_pkg_cmd(self.runner.as_ref(), "pacman", &["-Q"], 60).await?,
+        };
+        Ok(parse_pacman_q(&output))
+    }
+}
+
+const DEFAULT_LIST_LIMIT: u64 = 100;
+const MAX_LIST_LIMIT: u64 = 1000;
+
+/// `limit` packages from `offset` as `name version` lines, each followed by
+/// its indented description if it has one, with a note on where the page
+/// sits when it isn't the whole list.
+fn package_page(packages: &[InstalledPackage], offset: usize, limit: usize) -> String {
+    let total = packages.len();
+    if offset >= total && total > 0 {
+        return format!("No packages at offset {offset}; there are {total}.");
+    }
+    let page = &packages[offset.min(total)..(offset + limit).min(total)];
+    let mut out: String = page
+        .iter()
+        .map(|p| match &p.description {
+            Some(description) => format!("{} {}\n    {description}\n", p.name, p.version),
+            None => format!("{} {}\n", p.name, p.version),
+        })
+        .collect();
+    if page.len() < total {
+        let end = offset + page.len();
+        out.push_str(&format!("(showing {}-{end} of {total} packages", offset + 1));
+        if end < total {
+            out.push_str(&format!("; use offset {end} for more"));
+        }
+        out.push_str(")\n");
+    }
+    out
+}
 
 #[async_trait]
 impl SystemTool for ListPackagesTool {
@@ -40,7 +170,7 @@ impl SystemTool for ListPackagesTool {
         "list_packages"
     }
     fn description(&self) -> &str {
-        "List installed packages. Optionally search for a specific package."
+        "List installed packages as 'name version' lines, 100 at a time by default. Optionally search for a specific package (matches also show their description); use offset to page through long lists."
     }
     fn input_schema(&self) -> serde_json::Value {
         serde_json::json!({
@@ -49,6 +179,14 @@ impl SystemTool for ListPackagesTool {
                 "search": {
                     "type": "string",
                     "description": "Optional search query to filter packages"
+                },
+                "limit": {
+                    "type": "integer",
+                    "description": "Most packages to return (default: 100, max: 1000)"
+                },
+                "offset": {
+                    "type": "integer",
+                    "description": "Packages to skip, for the next page (default: 0)"
                 }
             },
             "required": []
@@ -58,17 +196,81 @@ impl SystemTool for ListPackagesTool {
         PermissionLevel::Safe
     }
     async fn execute(&self, input: serde_json::Value) -> Result<String, ToolError> {
-        if let Some(query) = input.get("search").and_then(|v| v.as_str()) {
-            run_pkg_cmd("pacman", &["-Qs", query], 60).await
-        } else {
-            run_pkg_cmd("pacman", &["-Q"], 60).await
+        let search = input.get("search").and_then(|v| v.as_str());
+        let limit = input
+            .get("limit")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_LIST_LIMIT)
+            .clamp(1, MAX_LIST_LIMIT) as usize;
+        let offset = input.get("offset").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+
+        let packages = self.packages(search).await?;
+        if packages.is_empty() {
+            return Ok(match search {
+                Some(query) => format!("No installed packages match '{query}'."),
+                None => "No packages installed.".into(),
+            });
+        }
+        Ok(package_page(&packages, offset, limit))
+    }
+}
+
+// ── is_installed ─────────────────────────────────────────────────────────────
+
+runner_tool!(IsInstalledTool);
+
+#[async_trait]
+impl SystemTool for IsInstalledTool {
+    fn name(&self) -> &str {
+        "is_installed"
+    }
+    fn description(&self) -> &str {
+        "Check whether one package is installed and get its version. Cheaper than list_packages; use it before suggesting an install."
+    }
+    fn input_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "package": {
+                    "type": "string",
+                    "description": "Exact package name (e.g. 'firefox')"
+                }
+            },
+            "required": ["package"]
+        })
+    }
+    fn permission_level(&self) -> PermissionLevel {
+        PermissionLevel::Safe
+    }
+    async fn execute(&self, input: serde_json::Value) -> Result<String, ToolError> {
+        let package = package_arg(&input)?;
+        let args = ["-Q", package];
+        let result = self
+            .runner
+            .run("pacman", &args, Duration::from_secs(30))
+            .await?;
+        // pacman -Q exits 1 for a package that isn't installed
+        match result.status.code() {
+            Some(0) => {
+                let stdout = String::from_utf8_lossy(&result.stdout);
+                Ok(match parse_pacman_q(&stdout).first() {
+                    Some(p) => format!("{} {} is installed.", p.name, p.version),
+                    None => format!("{package} is installed."),
+                })
+            }
+            Some(1) => Ok(format!("{package} is not installed.")),
+            code => Err(ToolError::ExecutionFailed {
+                command: format!("pacman {}", args.join(" ")),
+                exit_code: code.unwrap_or(-1),
+                stderr: String::from_utf8_lossy(&result.stderr).to_string(),
+            }),
         }
     }
 }
 
 // ── install_package ──────────────────────────────────────────────────────────
 
-pub struct InstallPackageTool;
+runner_tool!(InstallPackageTool);
 
 #[async_trait]
 impl SystemTool for InstallPackageTool {
@@ -85,7 +287,8 @@ impl SystemTool for InstallPackageTool {
                 "package": {
                     "type": "string",
                     "description": "Package name to install (e.g. 'google-chrome', 'vlc')"
-                }
+                },
+                "dry_run": dry_run_schema()
             },
             "required": ["package"]
         })
@@ -94,28 +297,19 @@ impl SystemTool for InstallPackageTool {
         PermissionLevel::RequiresConfirmation
     }
     async fn execute(&self, input: serde_json::Value) -> Result<String, ToolError> {
-        let package = input
-            .get("package")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| ToolError::InvalidInput("Missing 'package' field".into()))?;
+        let package = package_arg(&input)?;
 
-        // Validate package name (alphanumeric, dash, underscore, dot only)
-        if !package
-            .chars()
-            .all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == '.')
-        {
-            return Err(ToolError::InvalidInput(format!(
-                "Invalid package name: {package}"
-            )));
+        let args = ["-S", "--noconfirm", package];
+        if is_dry_run(&input) {
+            return Ok(dry_run_report("yay", &args));
         }
-
-        run_pkg_cmd("yay", &["-S", "--noconfirm", package], 300).await
+        run_pkg_cmd(self.runner.as_ref(), "yay", &args, 300).await
     }
 }
 
 // ── remove_package ───────────────────────────────────────────────────────────
 
-pub struct RemovePackageTool;
+runner_tool!(RemovePackageTool);
 
 #[async_trait]
 impl SystemTool for RemovePackageTool {
@@ -132,7 +326,8 @@ impl SystemTool for RemovePackageTool {
                 "package": {
                     "type": "string",
                     "description": "Package name to remove"
-                }
+                },
+                "dry_run": dry_run_schema()
             },
             "required": ["package"]
         })
@@ -141,27 +336,19 @@ impl SystemTool for RemovePackageTool {
         PermissionLevel::RequiresConfirmation
     }
     async fn execute(&self, input: serde_json::Value) -> Result<String, ToolError> {
-        let package = input
-            .get("package")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| ToolError::InvalidInput("Missing 'package' field".into()))?;
+        let package = package_arg(&input)?;
 
-        if !package
-            .chars()
-            .all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == '.')
-        {
-            return Err(ToolError::InvalidInput(format!(
-                "Invalid package name: {package}"
-            )));
+        let args = ["-Rns", "--noconfirm", package];
+        if is_dry_run(&input) {
+            return Ok(dry_run_report("yay", &args));
         }
-
-        run_pkg_cmd("yay", &["-Rns", "--noconfirm", package], 120).await
+        run_pkg_cmd(self.runner.as_ref(), "yay", &args, 120).await
     }
 }
 
 // ── update_system ────────────────────────────────────────────────────────────
 
-pub struct UpdateSystemTool;
+runner_tool!(UpdateSystemTool);
 
 #[async_trait]
 impl SystemTool for UpdateSystemTool {
@@ -174,14 +361,520 @@ impl SystemTool for UpdateSystemTool {
     fn input_schema(&self) -> serde_json::Value {
         serde_json::json!({
             "type": "object",
-            "properties": {},
+            "properties": {
+                "dry_run": dry_run_schema()
+            },
             "required": []
         })
     }
     fn permission_level(&self) -> PermissionLevel {
         PermissionLevel::RequiresConfirmation
     }
+    async fn execute(&self, input: serde_json::Value) -> Result<String, ToolError> {
+        let args = ["pacman", "-Syu", "--noconfirm"];
+        if is_dry_run(&input) {
+            return Ok(dry_run_report("sudo", &args));
+        }
+        run_pkg_cmd(self.runner.as_ref(), "sudo", &args, 600).await
+    }
+}
+
+// ── clean_cache ──────────────────────────────────────────────────────────────
+
+const PACMAN_CACHE: &str = "/var/cache/pacman/pkg";
+
+/// Reports the pacman package cache size; with `apply`, prunes it with
+/// `paccache -r` (keeps the three most recent versions of each package).
+pub struct CleanCacheTool {
+    runner: Arc<dyn CommandRunner>,
+    /// Whether a command is on `PATH`; swapped out in tests.
+    command_exists: fn(&str) -> bool,
+}
+
+impl CleanCacheTool {
+    pub fn with_runner(runner: Arc<dyn CommandRunner>) -> Self {
+        Self {
+            runner,
+            command_exists,
+        }
+    }
+
+    async fn cache_bytes(&self) -> Result<u64, ToolError> {
+        let du = run_pkg_cmd(self.runner.as_ref(), "du", &["-sb", PACMAN_CACHE], 60).await?;
+        parse_du_bytes(&du).ok_or_else(|| ToolError::ExecutionFailed {
+            command: format!("du -sb {PACMAN_CACHE}"),
+            exit_code: 0,
+            stderr: format!("unexpected output: {du}"),
+        })
+    }
+}
+
+impl Default for CleanCacheTool {
+    fn default() -> Self {
+        Self::with_runner(Arc::new(RealRunner))
+    }
+}
+
+#[async_trait]
+impl SystemTool for CleanCacheTool {
+    fn name(&self) -> &str {
+        "clean_cache"
+    }
+    fn description(&self) -> &str {
+        "Report the size of the pacman package cache (/var/cache/pacman/pkg). With apply=true, \
+         remove old package versions with paccache -r (keeping the 3 most recent) and report the space freed."
+    }
+    fn input_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "apply": {
+                    "type": "boolean",
+                    "description": "If true, prune the cache; otherwise only report its size (default: false)"
+                },
+                "dry_run": dry_run_schema()
+            },
+            "required": []
+        })
+    }
+    fn permission_level(&self) -> PermissionLevel {
+        PermissionLevel::RequiresConfirmation
+    }
+    fn permission_for(&self, input: &serde_json::Value) -> PermissionLevel {
+        if is_apply(input) {
+            PermissionLevel::RequiresConfirmation
+        } else {
+            PermissionLevel::Safe
+        }
+    }
+    async fn execute(&self, input: serde_json::Value) -> Result<String, ToolError> {
+        let before = self.cache_bytes().await?;
+        if !is_apply(&input) {
+            return Ok(format!("Pacman cache ({PACMAN_CACHE}): {}", human_size(before)));
+        }
+
+        let args = ["paccache", "-r"];
+        if is_dry_run(&input) {
+            return Ok(dry_run_report("sudo", &args));
+        }
+        if !(self.command_exists)("paccache") {
+            return Err(ToolError::InvalidInput(
+                "paccache is not installed; install the pacman-contrib package first".into(),
+            ));
+        }
+
+        run_pkg_cmd(self.runner.as_ref(), "sudo", &args, 300).await?;
+        let after = self.cache_bytes().await?;
+        Ok(format!(
+            "Pacman cache: {} -> {} (freed {})",
+            human_size(before),
+            human_size(after),
+            human_size(before.saturating_sub(after))
+        ))
+    }
+}
+
+fn is_apply(input: &serde_json::Value) -> bool {
+    input.get("apply").and_then(|v| v.as_bool()).unwrap_or(false)
+}
+
+/// Byte count from `du -sb` output (`<bytes>\t<path>`).
+fn parse_du_bytes(du: &str) -> Option<u64> {
+    du.split_whitespace().next()?.parse().ok()
+}
+
+// ── check_blunux_packages ────────────────────────────────────────────────────
+
+/// Compares the packages selected in the blunux `config.toml` with what
+/// `pacman -Q` reports as installed.
+pub struct CheckBlunuxPackagesTool {
+    blunux_config: PathBuf,
+    runner: Arc<dyn CommandRunner>,
+}
+
+impl CheckBlunuxPackagesTool {
+    pub fn new(blunux_config: PathBuf) -> Self {
+        Self {
+            blunux_config,
+            runner: Arc::new(RealRunner),
+        }
+    }
+
+    pub fn with_runner(mut self, runner: Arc<dyn CommandRunner>) -> Self {
+        self.runner = runner;
+        self
+    }
+}
+
+#[async_trait]
+impl SystemTool for CheckBlunuxPackagesTool {
+    fn name(&self) -> &str {
+        "check_blunux_packages"
+    }
+    fn description(&self) -> &str {
+        "Check whether every package selected in the Blunux config.toml is actually installed, and list any that are missing."
+    }
+    fn input_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {},
+            "required": []
+        })
+    }
+    fn permission_level(&self) -> PermissionLevel {
+        PermissionLevel::Safe
+    }
     async fn execute(&self, _input: serde_json::Value) -> Result<String, ToolError> {
-        run_pkg_cmd("sudo", &["pacman", "-Syu", "--noconfirm"], 600).await
+        let config = blunux_config::BlunuxConfig::load(&self.blunux_config).map_err(|e| {
+            ToolError::Io(std::io::Error::other(format!(
+                "failed to load {}: {e}",
+                self.blunux_config.display()
+            )))
+        })?;
+        let selected = blunux_config::resolve_packages(&config);
+        let installed = installed_set(&run_pkg_cmd(self.runner.as_ref(), "pacman", &["-Q"], 60).await?);
+        Ok(selection_report(&selected, &installed))
+    }
+}
+
+/// Package names from `pacman -Q` output (`name version` per line).
+fn installed_set(pacman_q: &str) -> HashSet<String> {
+    parse_pacman_q(pacman_q).into_iter().map(|p| p.name).collect()
+}
+
+fn selection_report(selected: &[String], installed: &HashSet<String>) -> String {
+    let missing: Vec<&str> = selected
+        .iter()
+        .filter(|pkg| !installed.contains(pkg.as_str()))
+        .map(String::as_str)
+        .collect();
+
+    if missing.is_empty() {
+        return format!("All {} selected packages are installed.", selected.len());
+    }
+    format!(
+        "{} of {} selected packages are missing:\n{}",
+        missing.len(),
+        selected.len(),
+        missing.iter().map(|p| format!("  - {p}")).collect::<Vec<_>>().join("\n")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::runner::MockRunner;
+
+    #[test]
+    fn test_installed_set_parses_pacman_q() {
+        let set = installed_set("firefox 131.0-1\nvlc 3.0.21-2\n");
+        assert!(set.contains("firefox"));
+        assert!(set.contains("vlc"));
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_pacman_q() {
+        let q = "\
+bluez 5.78-1
+lib32-mesa 1:24.2.5-1
+python-dateutil 2.9.0-6
+ttf-dejavu 2.37+18+g9b5d1b2f-7
+
+garbage
+";
+        let pkg = |name: &str, version: &str| InstalledPackage {
+            name: name.into(),
+            version: version.into(),
+            description: None,
+        };
+        assert_eq!(
+            parse_pacman_q(q),
+            [
+                pkg("bluez", "5.78-1"),
+                pkg("lib32-mesa", "1:24.2.5-1"),
+                pkg("python-dateutil", "2.9.0-6"),
+                pkg("ttf-dejavu", "2.37+18+g9b5d1b2f-7"),
+            ]
+        );
+
+        let qs = "\
+local/mesa 1:24.2.5-1
+    Open-source OpenGL drivers
+local/xorg-server 21.1.14-1 (xorg)
+    Xorg X server
+";
+        let described = |name: &str, version: &str, description: &str| InstalledPackage {
+            description: Some(description.into()),
+            ..pkg(name, version)
+        };
+        assert_eq!(
+            parse_pacman_q(qs),
+            [
+                described("mesa", "1:24.2.5-1", "Open-source OpenGL drivers"),
+                described("xorg-server", "21.1.14-1", "Xorg X server"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_package_page_slices_list() {
+        let packages: Vec<InstalledPackage> = (1..=5)
+            .map(|i| InstalledPackage {
+                name: format!("pkg{i}"),
+                version: "1.0-1".into(),
+                description: None,
+            })
+            .collect();
+
+        assert_eq!(
+            package_page(&packages, 0, 2),
+            "pkg1 1.0-1\npkg2 1.0-1\n(showing 1-2 of 5 packages; use offset 2 for more)\n"
+        );
+        assert_eq!(
+            package_page(&packages, 3, 2),
+            "pkg4 1.0-1\npkg5 1.0-1\n(showing 4-5 of 5 packages)\n"
+        );
+        assert_eq!(package_page(&packages, 4, 100), "pkg5 1.0-1\n(showing 5-5 of 5 packages)\n");
+        assert_eq!(package_page(&packages, 0, 100).lines().count(), 5, "whole list, no note");
+        assert_eq!(package_page(&packages, 5, 2), "No packages at offset 5; there are 5.");
+    }
+
+    #[tokio::test]
+    async fn test_list_packages_limits_search_results() {
+        let qs = "local/lib32-mesa 1:24.2.5-1\n    32-bit drivers\nlocal/mesa 1:24.2.5-1\n    drivers\nlocal/mesa-utils 9.0.0-5\n    utilities\n";
+        let mock = Arc::new(MockRunner::new(0, qs));
+        let tool = ListPackagesTool::with_runner(mock.clone());
+        let reply = tool
+            .execute(serde_json::json!({"search": "mesa", "limit": 1, "offset": 1}))
+            .await
+            .unwrap();
+        assert_eq!(
+            reply,
+            "mesa 1:24.2.5-1\n    drivers\n(showing 2-2 of 3 packages; use offset 2 for more)\n"
+        );
+        assert_eq!(mock.calls(), ["pacman -Qs mesa"]);
+
+        let none = ListPackagesTool::with_runner(Arc::new(MockRunner::new(1, "")));
+        let reply = none.execute(serde_json::json!({"search": "nope"})).await.unwrap();
+        assert_eq!(reply, "No installed packages match 'nope'.");
+    }
+
+    #[tokio::test]
+    async fn test_list_packages_structured_and_text() {
+        let mock = Arc::new(MockRunner::new(0, "firefox 131.0-1\nlib32-mesa 1:24.2.5-1\n"));
+        let tool = ListPackagesTool::with_runner(mock.clone());
+        let packages = tool.packages(None).await.unwrap();
+        assert_eq!(packages.len(), 2);
+        assert_eq!(packages[1].version, "1:24.2.5-1");
+        let text = tool.execute(serde_json::json!({})).await.unwrap();
+        assert_eq!(text, "firefox 131.0-1\nlib32-mesa 1:24.2.5-1\n");
+        assert_eq!(mock.calls(), ["pacman -Q", "pacman -Q"]);
+    }
+
+    #[tokio::test]
+    async fn test_is_installed() {
+        let mock = Arc::new(MockRunner::new(0, "firefox 131.0-1\n"));
+        let tool = IsInstalledTool::with_runner(mock.clone());
+        let reply = tool.execute(serde_json::json!({"package": "firefox"})).await.unwrap();
+        assert_eq!(reply, "firefox 131.0-1 is installed.");
+        assert_eq!(mock.calls(), ["pacman -Q firefox"]);
+
+        // Exit 1 is an answer, not a failure
+        let tool = IsInstalledTool::with_runner(Arc::new(MockRunner::new(1, "")));
+        let reply = tool.execute(serde_json::json!({"package": "vlc"})).await.unwrap();
+        assert_eq!(reply, "vlc is not installed.");
+
+        let tool = IsInstalledTool::with_runner(Arc::new(MockRunner::new(2, "")));
+        let err = tool.execute(serde_json::json!({"package": "vlc"})).await.unwrap_err();
+        assert!(matches!(err, ToolError::ExecutionFailed { exit_code: 2, .. }), "{err}");
+
+        let err = tool.execute(serde_json::json!({"package": "vlc; reboot"})).await.unwrap_err();
+        assert!(matches!(err, ToolError::InvalidInput(_)));
+    }
+
+    #[test]
+    fn test_selection_report_lists_missing() {
+        let selected: Vec<String> = ["firefox", "vlc", "julia"].map(str::to_string).to_vec();
+        let installed = installed_set("firefox 131.0-1\njulia 1.11.0-1\n");
+        let report = selection_report(&selected, &installed);
+        assert!(report.starts_with("1 of 3 selected packages are missing"));
+        assert!(report.contains("  - vlc"));
+        assert!(!report.contains("firefox"));
+    }
+
+    #[test]
+    fn test_selection_report_all_installed() {
+        let selected: Vec<String> = vec!["firefox".into()];
+        let installed = installed_set("firefox 131.0-1\n");
+        assert_eq!(
+            selection_report(&selected, &installed),
+            "All 1 selected packages are installed."
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_blunux_packages_uses_runner() {
+        let tmp = tempfile::tempdir().unwrap();
+        let blunux_toml = tmp.path().join("config.toml");
+        std::fs::write(&blunux_toml, include_str!("../../../../config.toml")).unwrap();
+
+        let mock = Arc::new(MockRunner::new(0, "firefox 131.0-1\n"));
+        let tool = CheckBlunuxPackagesTool::new(blunux_toml).with_runner(mock.clone());
+        let report = tool.execute(serde_json::json!({})).await.unwrap();
+        assert!(report.contains("selected packages are missing"), "{report}");
+        assert_eq!(mock.calls(), vec!["pacman -Q"]);
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_reports_without_running() {
+        let mock = Arc::new(MockRunner::new(0, ""));
+        let cases: [(Box<dyn SystemTool>, serde_json::Value, &str); 3] = [
+            (
+                Box::new(InstallPackageTool::with_runner(mock.clone())),
+                serde_json::json!({"package": "vlc", "dry_run": true}),
+                "yay -S --noconfirm vlc",
+            ),
+            (
+                Box::new(RemovePackageTool::with_runner(mock.clone())),
+                serde_json::json!({"package": "vlc", "dry_run": true}),
+                "yay -Rns --noconfirm vlc",
+            ),
+            (
+                Box::new(UpdateSystemTool::with_runner(mock.clone())),
+                serde_json::json!({"dry_run": true}),
+                "sudo pacman -Syu --noconfirm",
+            ),
+        ];
+        for (tool, input, command) in cases {
+            let out = tool.execute(input).await.unwrap();
+            assert_eq!(out, format!("[dry run] Would run: {command}"));
+        }
+        assert!(mock.calls().is_empty(), "dry run ran {:?}", mock.calls());
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_still_validates_input() {
+        let err = InstallPackageTool::with_runner(Arc::new(MockRunner::new(0, "")))
+            .execute(serde_json::json!({"package": "vlc; rm -rf ~", "dry_run": true}))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ToolError::InvalidInput(_)));
+    }
+
+    #[tokio::test]
+    async fn test_package_tools_run_expected_commands() {
+        let mock = Arc::new(MockRunner::new(0, "ok"));
+        let input = serde_json::json!({"package": "vlc"});
+        InstallPackageTool::with_runner(mock.clone())
+            .execute(input.clone())
+            .await
+            .unwrap();
+        RemovePackageTool::with_runner(mock.clone())
+            .execute(input)
+            .await
+            .unwrap();
+        UpdateSystemTool::with_runner(mock.clone())
+            .execute(serde_json::json!({}))
+            .await
+            .unwrap();
+        ListPackagesTool::with_runner(mock.clone())
+            .execute(serde_json::json!({"search": "vlc"}))
+            .await
+            .unwrap();
+        assert_eq!(
+            mock.calls(),
+            vec![
+                "yay -S --noconfirm vlc",
+                "yay -Rns --noconfirm vlc",
+                "sudo pacman -Syu --noconfirm",
+                "pacman -Qs vlc",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_du_bytes() {
+        assert_eq!(parse_du_bytes("1288490189\t/var/cache/pacman/pkg\n"), Some(1_288_490_189));
+        assert_eq!(parse_du_bytes("du: cannot access"), None);
+        assert_eq!(parse_du_bytes(""), None);
+    }
+
+    #[tokio::test]
+    async fn test_clean_cache_reports_size_without_cleaning() {
+        let mock = Arc::new(MockRunner::new(0, "1288490189\t/var/cache/pacman/pkg\n"));
+        let tool = CleanCacheTool::with_runner(mock.clone());
+        assert_eq!(tool.permission_for(&serde_json::json!({})), PermissionLevel::Safe);
+        let out = tool.execute(serde_json::json!({})).await.unwrap();
+        assert_eq!(out, "Pacman cache (/var/cache/pacman/pkg): 1.2 GiB");
+        assert_eq!(mock.calls(), vec!["du -sb /var/cache/pacman/pkg"]);
+    }
+
+    #[tokio::test]
+    async fn test_clean_cache_apply_runs_paccache() {
+        let mock = Arc::new(MockRunner::new(0, "1024\t/var/cache/pacman/pkg\n"));
+        let tool = CleanCacheTool {
+            command_exists: |cmd| cmd == "paccache",
+            ..CleanCacheTool::with_runner(mock.clone())
+        };
+        let input = serde_json::json!({"apply": true});
+        assert_eq!(tool.permission_for(&input), PermissionLevel::RequiresConfirmation);
+        let out = tool.execute(input).await.unwrap();
+        assert_eq!(out, "Pacman cache: 1.0 KiB -> 1.0 KiB (freed 0 B)");
+        assert_eq!(
+            mock.calls(),
+            vec![
+                "du -sb /var/cache/pacman/pkg",
+                "sudo paccache -r",
+                "du -sb /var/cache/pacman/pkg",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_clean_cache_apply_needs_paccache() {
+        let mock = Arc::new(MockRunner::new(0, "1024\t/var/cache/pacman/pkg\n"));
+        let tool = CleanCacheTool {
+            command_exists: |_| false,
+            ..CleanCacheTool::with_runner(mock.clone())
+        };
+        let err = tool.execute(serde_json::json!({"apply": true})).await.unwrap_err();
+        assert!(err.to_string().contains("pacman-contrib"), "{err}");
+        assert_eq!(mock.calls(), vec!["du -sb /var/cache/pacman/pkg"]);
+    }
+
+    #[tokio::test]
+    async fn test_list_packages_returns_stdout() {
+        let mock = Arc::new(MockRunner::new(0, "vlc 3.0.21-2\n"));
+        let out = ListPackagesTool::with_runner(mock)
+            .execute(serde_json::json!({}))
+            .await
+            .unwrap();
+        assert_eq!(out, "vlc 3.0.21-2\n");
+    }
+
+    #[tokio::test]
+    async fn test_install_failure_reports_exit_code() {
+        let mock = Arc::new(MockRunner::new(1, ""));
+        let err = InstallPackageTool::with_runner(mock)
+            .execute(serde_json::json!({"package": "no-such-pkg"}))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ToolError::ExecutionFailed { exit_code: 1, ref command, .. }
+                if command == "yay -S --noconfirm no-such-pkg"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_package_name_never_runs() {
+        let mock = Arc::new(MockRunner::new(0, ""));
+        let err = RemovePackageTool::with_runner(mock.clone())
+            .execute(serde_json::json!({"package": "$(reboot)"}))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ToolError::InvalidInput(_)));
+        assert!(mock.calls().is_empty());
     }
 }
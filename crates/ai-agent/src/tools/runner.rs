@@ -0,0 +1,122 @@
+use std::process::Output;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::process::Command;
+
+use crate::capabilities::spawn_error;
+use crate::error::ToolError;
+
+/// Runs external commands for tools. Tools take one instead of calling
+/// `tokio::process::Command` directly so they can be tested without a live
+/// Arch system.
+#[async_trait]
+pub trait CommandRunner: Send + Sync {
+    /// Run `cmd` with `args`, giving up with [`ToolError::Timeout`] after
+    /// `timeout`. A non-zero exit is not an error here; callers decide.
+    async fn run(&self, cmd: &str, args: &[&str], timeout: Duration) -> Result<Output, ToolError>;
+}
+
+/// Spawns real processes.
+pub struct RealRunner;
+
+#[async_trait]
+impl CommandRunner for RealRunner {
+    async fn run(&self, cmd: &str, args: &[&str], timeout: Duration) -> Result<Output, ToolError> {
+        // kill_on_drop so a timed-out command doesn't keep running behind us
+        let output = Command::new(cmd).args(args).kill_on_drop(true).output();
+        tokio::time::timeout(timeout, output)
+            .await
+            .map_err(|_| ToolError::Timeout {
+                secs: timeout.as_secs(),
+            })?
+            .map_err(|e| spawn_error(cmd, e))
+    }
+}
+
+#[cfg(test)]
+pub(crate) use mock::MockRunner;
+
+#[cfg(test)]
+mod mock {
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+    use std::sync::Mutex;
+
+    use super::*;
+
+    /// Records every command line and answers with a canned exit code and stdout.
+    pub(crate) struct MockRunner {
+        exit_code: i32,
+        stdout: String,
+        calls: Mutex<Vec<String>>,
+    }
+
+    impl MockRunner {
+        pub(crate) fn new(exit_code: i32, stdout: &str) -> Self {
+            Self {
+                exit_code,
+                stdout: stdout.to_string(),
+                calls: Mutex::new(Vec::new()),
+            }
+        }
+
+        /// Command lines run so far, as `cmd arg1 arg2`.
+        pub(crate) fn calls(&self) -> Vec<String> {
+            self.calls.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl CommandRunner for MockRunner {
+        async fn run(&self, cmd: &str, args: &[&str], _timeout: Duration) -> Result<Output, ToolError> {
+            let mut line = cmd.to_string();
+            for arg in args {
+                line.push(' ');
+                line.push_str(arg);
+            }
+            self.calls.lock().unwrap().push(line);
+            Ok(Output {
+                status: ExitStatus::from_raw(self.exit_code << 8),
+                stdout: self.stdout.clone().into_bytes(),
+                stderr: if self.exit_code == 0 {
+                    Vec::new()
+                } else {
+                    b"mock failure".to_vec()
+                },
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_real_runner_captures_output() {
+        let out = RealRunner
+            .run("sh", &["-c", "echo hi; exit 3"], Duration::from_secs(10))
+            .await
+            .unwrap();
+        assert_eq!(out.status.code(), Some(3));
+        assert_eq!(String::from_utf8_lossy(&out.stdout), "hi\n");
+    }
+
+    #[tokio::test]
+    async fn test_real_runner_times_out() {
+        let err = RealRunner
+            .run("sleep", &["5"], Duration::from_millis(100))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ToolError::Timeout { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_mock_runner_records_calls() {
+        let mock = MockRunner::new(1, "");
+        let out = mock.run("yay", &["-S", "vlc"], Duration::from_secs(1)).await.unwrap();
+        assert_eq!(out.status.code(), Some(1));
+        assert_eq!(mock.calls(), vec!["yay -S vlc"]);
+    }
+}
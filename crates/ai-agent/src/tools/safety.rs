@@ -7,16 +7,84 @@ pub enum PermissionLevel {
     Blocked,
 }
 
+impl PermissionLevel {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Safe => "safe",
+            Self::RequiresConfirmation => "requires confirmation",
+            Self::Blocked => "blocked",
+        }
+    }
+}
+
+/// Why a command was blocked or needs confirmation. Rendered for the user in
+/// their language by [`strings::safety_reason`](crate::strings::safety_reason).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SafetyReason {
+    // Blocked
+    RootDeletion,
+    RecursiveRootDeletion,
+    DdWrite,
+    DiskFormat,
+    BlockDeviceWrite,
+    BlockDevicePipe,
+    ForkBomb,
+    RootChmod,
+    RecursiveChmod,
+    DecodeAndExecute,
+    RemotePython,
+    CredentialFileWrite,
+    CredentialFileTee,
+    SudoersEdit,
+    DiskShred,
+    // Requires confirmation
+    PackageRemoval,
+    SystemUpdate,
+    PackageInstall,
+    ServiceChange,
+    RootPrivileges,
+    RemoteInstall,
+    PowerState,
+    UserAccount,
+    PasswordChange,
+}
+
+/// A constructive next step offered alongside a block, rendered by
+/// [`strings::safe_alternative`](crate::strings::safe_alternative).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SafeAlternative {
+    /// Delete the specific directory meant instead of `/`.
+    DeleteSpecificPath,
+    /// Identify the target device with `lsblk` and run the command by hand.
+    VerifyDevice,
+    /// Change permissions on the specific path that needs it.
+    NarrowPermissions,
+    /// Save the script to a file and read it before running it.
+    InspectFirst,
+    /// Use the account tools instead of editing credential files.
+    UseAccountTools,
+    /// Do it yourself in a terminal if you really mean it.
+    RunManually,
+    /// Shred individual files rather than a whole disk.
+    ShredFiles,
+}
+
 #[derive(Debug)]
 pub enum SafetyResult {
     Safe,
-    RequiresConfirmation { reason: String },
-    Blocked { reason: String },
+    RequiresConfirmation {
+        reason: SafetyReason,
+    },
+    Blocked {
+        reason: SafetyReason,
+        suggestion: Option<SafeAlternative>,
+    },
 }
 
 pub struct SafetyChecker {
-    blocked_patterns: Vec<(Regex, &'static str)>,
-    confirm_patterns: Vec<(Regex, &'static str)>,
+    blocked_patterns: Vec<(Regex, SafetyReason, Option<SafeAlternative>)>,
+    confirm_patterns: Vec<(Regex, SafetyReason)>,
+    separators: Regex,
 }
 
 impl SafetyChecker {
@@ -24,137 +92,164 @@ impl SafetyChecker {
         let blocked_patterns = vec![
             (
                 Regex::new(r"rm\s+(-[a-zA-Z]*f[a-zA-Z]*\s+)?/\s*$").unwrap(),
-                "Recursive deletion of root filesystem",
+                SafetyReason::RootDeletion,
+                Some(SafeAlternative::DeleteSpecificPath),
             ),
             (
                 Regex::new(r"rm\s+-[a-zA-Z]*r[a-zA-Z]*f[a-zA-Z]*\s+/").unwrap(),
-                "Recursive forced deletion from root",
+                SafetyReason::RecursiveRootDeletion,
+                Some(SafeAlternative::DeleteSpecificPath),
             ),
             (
                 Regex::new(r"rm\s+-[a-zA-Z]*f[a-zA-Z]*r[a-zA-Z]*\s+/").unwrap(),
-                "Recursive forced deletion from root",
+                SafetyReason::RecursiveRootDeletion,
+                Some(SafeAlternative::DeleteSpecificPath),
             ),
             (
                 Regex::new(r"dd\s+.*if=").unwrap(),
-                "Raw disk write with dd",
+                SafetyReason::DdWrite,
+                Some(SafeAlternative::VerifyDevice),
             ),
             (
                 Regex::new(r"mkfs\.\w+\s+/dev/").unwrap(),
-                "Disk format operation",
+                SafetyReason::DiskFormat,
+                Some(SafeAlternative::VerifyDevice),
             ),
             (
                 Regex::new(r">\s*/dev/(sd|nvme|vd|hd)").unwrap(),
-                "Raw write to block device",
+                SafetyReason::BlockDeviceWrite,
+                Some(SafeAlternative::VerifyDevice),
             ),
             (
                 Regex::new(r"\|\s*/dev/(sd|nvme|vd|hd)").unwrap(),
-                "Pipe to block device",
+                SafetyReason::BlockDevicePipe,
+                Some(SafeAlternative::VerifyDevice),
             ),
             (
                 Regex::new(r":\(\)\s*\{").unwrap(),
-                "Fork bomb detected",
+                SafetyReason::ForkBomb,
+                None,
             ),
             (
                 Regex::new(r"chmod\s+777\s+/\s*$").unwrap(),
-                "Dangerous permission change on root",
+                SafetyReason::RootChmod,
+                Some(SafeAlternative::NarrowPermissions),
             ),
             (
                 Regex::new(r"chmod\s+-R\s+777\s+/").unwrap(),
-                "Recursive dangerous permission change",
+                SafetyReason::RecursiveChmod,
+                Some(SafeAlternative::NarrowPermissions),
             ),
             // Decode-and-execute patterns
             (
                 Regex::new(r"base64\s+-d.*\|\s*(ba)?sh").unwrap(),
-                "Decode-and-execute via base64",
+                SafetyReason::DecodeAndExecute,
+                Some(SafeAlternative::InspectFirst),
             ),
             (
                 Regex::new(r"(curl|wget)\s+.*\|\s*python[23]?").unwrap(),
-                "Pipe from internet to Python interpreter",
+                SafetyReason::RemotePython,
+                Some(SafeAlternative::InspectFirst),
             ),
             // Sensitive file modification
             (
                 Regex::new(r"(>>?)\s*/etc/(passwd|shadow|sudoers|gshadow|group)\b").unwrap(),
-                "Write to sensitive system credentials file",
+                SafetyReason::CredentialFileWrite,
+                Some(SafeAlternative::UseAccountTools),
             ),
             (
                 Regex::new(r"\btee\s+/etc/(passwd|shadow|sudoers|gshadow|group)\b").unwrap(),
-                "Write to sensitive system credentials file via tee",
+                SafetyReason::CredentialFileTee,
+                Some(SafeAlternative::UseAccountTools),
             ),
             (
                 Regex::new(r"\bvisudo\b").unwrap(),
-                "Modification of sudoers configuration",
+                SafetyReason::SudoersEdit,
+                Some(SafeAlternative::RunManually),
             ),
             // Disk wiping
             (
                 Regex::new(r"\bshred\b.*/dev/(sd|nvme|vd|hd)").unwrap(),
-                "Destructive disk wipe with shred",
+                SafetyReason::DiskShred,
+                Some(SafeAlternative::ShredFiles),
             ),
         ];
 
         let confirm_patterns = vec![
             (
                 Regex::new(r"(pacman|yay)\s+.*-[a-zA-Z]*R").unwrap(),
-                "Package removal",
+                SafetyReason::PackageRemoval,
             ),
             (
                 Regex::new(r"(pacman|yay)\s+.*-[a-zA-Z]*S[a-zA-Z]*y[a-zA-Z]*u").unwrap(),
-                "System update",
+                SafetyReason::SystemUpdate,
             ),
             (
                 Regex::new(r"(pacman|yay)\s+.*-S\s").unwrap(),
-                "Package installation",
+                SafetyReason::PackageInstall,
             ),
             (
                 Regex::new(r"systemctl\s+(enable|disable|start|stop|restart|mask)").unwrap(),
-                "Service state change",
+                SafetyReason::ServiceChange,
             ),
             (
                 Regex::new(r"sudo\s+").unwrap(),
-                "Command requires root privileges",
+                SafetyReason::RootPrivileges,
             ),
             (
                 Regex::new(r"(curl|wget)\s+.*\|\s*(ba)?sh").unwrap(),
-                "Pipe install from internet",
+                SafetyReason::RemoteInstall,
             ),
             (
                 Regex::new(r"reboot|shutdown|poweroff|halt").unwrap(),
-                "System power state change",
+                SafetyReason::PowerState,
             ),
             // User account management
             (
                 Regex::new(r"\b(useradd|userdel|usermod|groupadd|groupdel)\b").unwrap(),
-                "User account modification",
+                SafetyReason::UserAccount,
             ),
             (
                 Regex::new(r"\bpasswd\b").unwrap(),
-                "Password change",
+                SafetyReason::PasswordChange,
             ),
         ];
 
         Self {
             blocked_patterns,
             confirm_patterns,
+            separators: Regex::new(r"&&|\|\||;|\||\n").unwrap(),
         }
     }
 
     pub fn check(&self, command: &str) -> SafetyResult {
         let trimmed = command.trim();
+        // The whole command, then each part of a `;`, `&&`, `||`, `|` or newline chain,
+        // so a benign prefix can't hide a dangerous suffix. The whole string is
+        // still checked because some patterns (`| sh`, fork bombs) span a pipe.
+        let segments: Vec<&str> = std::iter::once(trimmed)
+            .chain(
+                self.separators
+                    .split(trimmed)
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty()),
+            )
+            .collect();
 
         // Check blocked patterns first
-        for (pattern, reason) in &self.blocked_patterns {
-            if pattern.is_match(trimmed) {
+        for (pattern, reason, suggestion) in &self.blocked_patterns {
+            if segments.iter().any(|s| pattern.is_match(s)) {
                 return SafetyResult::Blocked {
-                    reason: reason.to_string(),
+                    reason: *reason,
+                    suggestion: *suggestion,
                 };
             }
         }
 
         // Check confirmation patterns
         for (pattern, reason) in &self.confirm_patterns {
-            if pattern.is_match(trimmed) {
-                return SafetyResult::RequiresConfirmation {
-                    reason: reason.to_string(),
-                };
+            if segments.iter().any(|s| pattern.is_match(s)) {
+                return SafetyResult::RequiresConfirmation { reason: *reason };
             }
         }
 
@@ -162,6 +257,12 @@ impl SafetyChecker {
     }
 }
 
+impl Default for SafetyChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -343,4 +444,64 @@ mod tests {
             SafetyResult::RequiresConfirmation { .. }
         ));
     }
+
+    #[test]
+    fn test_blocked_patterns_suggest_alternatives() {
+        for (command, expected) in [
+            ("rm -rf /", SafeAlternative::DeleteSpecificPath),
+            ("dd if=/dev/zero of=/dev/sda", SafeAlternative::VerifyDevice),
+            ("mkfs.ext4 /dev/sda1", SafeAlternative::VerifyDevice),
+            ("chmod -R 777 /", SafeAlternative::NarrowPermissions),
+            ("echo aGVsbG8= | base64 -d | sh", SafeAlternative::InspectFirst),
+        ] {
+            match checker().check(command) {
+                SafetyResult::Blocked { suggestion, .. } => {
+                    assert_eq!(suggestion, Some(expected), "{command}")
+                }
+                other => panic!("{command} should be blocked, got {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_fork_bomb_has_no_alternative() {
+        assert!(matches!(
+            checker().check(":(){ :|:& };:"),
+            SafetyResult::Blocked { suggestion: None, .. }
+        ));
+    }
+
+    // Chained commands
+    #[test]
+    fn test_blocked_dangerous_suffix_in_chain() {
+        for command in [
+            "echo hi && rm -rf /",
+            "ls; dd if=/dev/zero of=/dev/sda",
+            "ls; chmod 777 / ; echo done",
+            "cd /tmp && rm -f / && echo ok",
+            "false || mkfs.ext4 /dev/sda1",
+            "cat notes.txt | visudo",
+        ] {
+            assert!(
+                matches!(checker().check(command), SafetyResult::Blocked { .. }),
+                "{command} should be blocked"
+            );
+        }
+    }
+
+    #[test]
+    fn test_confirm_suffix_in_chain() {
+        assert!(matches!(
+            checker().check("df -h; systemctl restart sshd"),
+            SafetyResult::RequiresConfirmation { .. }
+        ));
+    }
+
+    #[test]
+    fn test_safe_chain() {
+        assert!(matches!(
+            checker().check("df -h && free -h; ps aux | head -5"),
+            SafetyResult::Safe
+        ));
+    }
 }
@@ -2,8 +2,9 @@ use async_trait::async_trait;
 use std::time::Duration;
 use tokio::process::Command;
 
+use crate::capabilities::spawn_error;
 use crate::error::ToolError;
-use crate::tools::{PermissionLevel, SystemTool};
+use crate::tools::{dry_run_report, dry_run_schema, is_dry_run, PermissionLevel, SystemTool};
 
 pub struct ManageServiceTool;
 
@@ -27,7 +28,8 @@ impl SystemTool for ManageServiceTool {
                 "service": {
                     "type": "string",
                     "description": "Service name (e.g. 'sshd', 'docker', 'bluetooth')"
-                }
+                },
+                "dry_run": dry_run_schema()
             },
             "required": ["action", "service"]
         })
@@ -68,6 +70,9 @@ impl SystemTool for ManageServiceTool {
         } else {
             ("sudo", vec!["systemctl", action, service])
         };
+        if is_dry_run(&input) {
+            return Ok(dry_run_report(cmd, &args));
+        }
 
         let result = tokio::time::timeout(
             Duration::from_secs(30),
@@ -75,7 +80,7 @@ impl SystemTool for ManageServiceTool {
         )
         .await
         .map_err(|_| ToolError::Timeout { secs: 30 })?
-        .map_err(ToolError::Io)?;
+        .map_err(|e| spawn_error(cmd, e))?;
 
         let stdout = String::from_utf8_lossy(&result.stdout).to_string();
         let stderr = String::from_utf8_lossy(&result.stderr).to_string();
@@ -96,3 +101,76 @@ impl SystemTool for ManageServiceTool {
         }
     }
 }
+
+// ── check_timers ─────────────────────────────────────────────────────────────
+
+pub struct CheckTimersTool;
+
+#[async_trait]
+impl SystemTool for CheckTimersTool {
+    fn name(&self) -> &str {
+        "check_timers"
+    }
+    fn description(&self) -> &str {
+        "List systemd timers with their next and last trigger times. Includes Blunux AI automation timers installed with 'blunux-ai install-timers'."
+    }
+    fn input_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "scope": {
+                    "type": "string",
+                    "enum": ["user", "system"],
+                    "description": "List user timers or system timers (default: user)"
+                }
+            },
+            "required": []
+        })
+    }
+    fn permission_level(&self) -> PermissionLevel {
+        PermissionLevel::Safe
+    }
+    async fn execute(&self, input: serde_json::Value) -> Result<String, ToolError> {
+        let scope = input
+            .get("scope")
+            .and_then(|v| v.as_str())
+            .unwrap_or("user");
+
+        let mut args = vec!["list-timers", "--all", "--no-pager"];
+        if scope != "system" {
+            args.insert(0, "--user");
+        }
+
+        let result = tokio::time::timeout(
+            Duration::from_secs(30),
+            Command::new("systemctl").args(&args).output(),
+        )
+        .await
+        .map_err(|_| ToolError::Timeout { secs: 30 })?
+        .map_err(|e| spawn_error("systemctl", e))?;
+
+        if result.status.success() {
+            Ok(String::from_utf8_lossy(&result.stdout).to_string())
+        } else {
+            Err(ToolError::ExecutionFailed {
+                command: format!("systemctl {}", args.join(" ")),
+                exit_code: result.status.code().unwrap_or(-1),
+                stderr: String::from_utf8_lossy(&result.stderr).to_string(),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_manage_service_dry_run() {
+        let out = ManageServiceTool
+            .execute(serde_json::json!({"action": "restart", "service": "sshd", "dry_run": true}))
+            .await
+            .unwrap();
+        assert_eq!(out, "[dry run] Would run: sudo systemctl restart sshd");
+    }
+}
@@ -2,6 +2,7 @@ use async_trait::async_trait;
 use std::time::Duration;
 use tokio::process::Command;
 
+use crate::capabilities::spawn_error;
 use crate::error::ToolError;
 use crate::tools::{PermissionLevel, SystemTool};
 
@@ -14,7 +15,7 @@ async fn run_cmd(cmd: &str, args: &[&str], timeout_secs: u64) -> Result<String,
     .map_err(|_| ToolError::Timeout {
         secs: timeout_secs,
     })?
-    .map_err(ToolError::Io)?;
+    .map_err(|e| spawn_error(cmd, e))?;
 
     let stdout = String::from_utf8_lossy(&result.stdout).to_string();
     let stderr = String::from_utf8_lossy(&result.stderr).to_string();
@@ -45,23 +46,69 @@ impl SystemTool for CheckDiskTool {
         "check_disk"
     }
     fn description(&self) -> &str {
-        "Check disk usage on all mounted filesystems. Returns human-readable output from df -h."
+        "Check disk usage on all mounted filesystems. Returns human-readable output from df -h, \
+         or with 'threshold' set, a JSON verdict listing filesystems at or above that usage percent."
     }
     fn input_schema(&self) -> serde_json::Value {
         serde_json::json!({
             "type": "object",
-            "properties": {},
+            "properties": {
+                "threshold": {
+                    "type": "integer",
+                    "description": "Optional usage percent (1-100) to alert on, e.g. 80"
+                }
+            },
             "required": []
         })
     }
     fn permission_level(&self) -> PermissionLevel {
         PermissionLevel::Safe
     }
-    async fn execute(&self, _input: serde_json::Value) -> Result<String, ToolError> {
-        run_cmd("df", &["-h"], 60).await
+    async fn execute(&self, input: serde_json::Value) -> Result<String, ToolError> {
+        let Some(threshold) = input.get("threshold").and_then(|v| v.as_u64()) else {
+            return run_cmd("df", &["-h"], 60).await;
+        };
+        if !(1..=100).contains(&threshold) {
+            return Err(ToolError::InvalidInput(format!(
+                "threshold must be between 1 and 100, got {threshold}"
+            )));
+        }
+        // -P keeps each filesystem on one line so the columns are stable
+        let df = run_cmd("df", &["-hP"], 60).await?;
+        Ok(disk_verdict(&df, threshold as u8).to_string())
     }
 }
 
+/// Parse `df -hP` output and report filesystems whose Use% is >= `threshold`.
+fn disk_verdict(df: &str, threshold: u8) -> serde_json::Value {
+    let over: Vec<serde_json::Value> = df
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let cols: Vec<&str> = line.split_whitespace().collect();
+            if cols.len() < 6 {
+                return None;
+            }
+            let percent: u8 = cols[4].trim_end_matches('%').parse().ok()?;
+            (percent >= threshold).then(|| {
+                serde_json::json!({
+                    "filesystem": cols[0],
+                    "mount": cols[5..].join(" "),
+                    "size": cols[1],
+                    "available": cols[3],
+                    "use_percent": percent,
+                })
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "threshold": threshold,
+        "status": if over.is_empty() { "ok" } else { "alert" },
+        "over_threshold": over,
+    })
+}
+
 // ── check_memory ─────────────────────────────────────────────────────────────
 
 pub struct CheckMemoryTool;
@@ -137,7 +184,7 @@ impl SystemTool for ReadLogsTool {
         "read_logs"
     }
     fn description(&self) -> &str {
-        "Read system logs using journalctl. Supports filtering by time, priority, and unit."
+        "Read system logs using journalctl. Supports filtering by time or boot, priority, and unit."
     }
     fn input_schema(&self) -> serde_json::Value {
         serde_json::json!({
@@ -145,7 +192,19 @@ impl SystemTool for ReadLogsTool {
             "properties": {
                 "since": {
                     "type": "string",
-                    "description": "Start time (e.g. 'today', '1 hour ago', '2026-02-20')"
+                    "description": "Start time (e.g. 'today', '1 hour ago', '2026-02-20'). Not with 'boot'."
+                },
+                "boot": {
+                    "type": "boolean",
+                    "description": "Only logs from the current boot (journalctl -b), e.g. for 'errors since boot'. Not with 'since'."
+                },
+                "boot_offset": {
+                    "type": "integer",
+                    "description": "Which boot with 'boot', from -100 to 0: 0 is the current one, -1 the previous one, and so on (default: 0)"
+                },
+                "grep": {
+                    "type": "string",
+                    "description": "Only return messages matching this regex (journalctl -g; case-insensitive unless it has capitals), e.g. 'usb|firmware'"
                 },
                 "priority": {
                     "type": "string",
@@ -168,36 +227,218 @@ impl SystemTool for ReadLogsTool {
         PermissionLevel::Safe
     }
     async fn execute(&self, input: serde_json::Value) -> Result<String, ToolError> {
-        let mut args: Vec<String> = vec!["--no-pager".into()];
+        let args = journal_args(&input)?;
+        let arg_refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+        run_cmd("journalctl", &arg_refs, 60).await
+    }
+}
 
-        if let Some(since) = input.get("since").and_then(|v| v.as_str()) {
-            args.push("--since".into());
-            args.push(since.into());
-        } else {
+/// Furthest back `boot_offset` reaches. Positive offsets would count from the
+/// first recorded boot instead, so they are rejected.
+const MAX_BOOT_OFFSET: i64 = 100;
+/// Longest `grep` pattern accepted.
+const MAX_GREP_LEN: usize = 200;
+
+/// `journalctl` arguments for a `read_logs` call.
+fn journal_args(input: &serde_json::Value) -> Result<Vec<String>, ToolError> {
+    let mut args: Vec<String> = vec!["--no-pager".into()];
+
+    let since = input.get("since").and_then(|v| v.as_str());
+    let offset = match input.get("boot_offset") {
+        None | Some(serde_json::Value::Null) => None,
+        Some(v) => Some(
+            v.as_i64()
+                .filter(|n| (-MAX_BOOT_OFFSET..=0).contains(n))
+                .ok_or_else(|| {
+                    ToolError::InvalidInput(format!(
+                        "'boot_offset' must be an integer from -{MAX_BOOT_OFFSET} to 0"
+                    ))
+                })?,
+        ),
+    };
+    let boot = input.get("boot").and_then(|v| v.as_bool()).unwrap_or(false) || offset.is_some();
+
+    match (boot, since) {
+        (true, Some(_)) => {
+            return Err(ToolError::InvalidInput(
+                "'since' and 'boot' can't be combined".into(),
+            ))
+        }
+        (true, None) => {
+            args.push("-b".into());
+            if let Some(offset) = offset {
+                args.push(offset.to_string());
+            }
+        }
+        (false, since) => {
             args.push("--since".into());
-            args.push("today".into());
+            args.push(since.unwrap_or("today").into());
         }
+    }
 
-        if let Some(priority) = input.get("priority").and_then(|v| v.as_str()) {
-            args.push("-p".into());
-            args.push(priority.into());
+    if let Some(priority) = input.get("priority").and_then(|v| v.as_str()) {
+        args.push("-p".into());
+        args.push(priority.into());
+    }
+
+    if let Some(unit) = input.get("unit").and_then(|v| v.as_str()) {
+        args.push("-u".into());
+        args.push(unit.into());
+    }
+
+    if let Some(pattern) = input.get("grep").and_then(|v| v.as_str()) {
+        // Passed to journalctl as one argument, never through a shell, so only
+        // patterns that couldn't be meant as a single line are refused
+        if pattern.is_empty()
+            || pattern.len() > MAX_GREP_LEN
+            || pattern.contains(['\n', '\r', '\0'])
+        {
+            return Err(ToolError::InvalidInput(format!(
+                "'grep' must be a single-line pattern of 1 to {MAX_GREP_LEN} bytes"
+            )));
         }
+        args.push("-g".into());
+        args.push(pattern.into());
+    }
+
+    let lines = input
+        .get("lines")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(50);
+    args.push("-n".into());
+    args.push(lines.to_string());
+
+    Ok(args)
+}
+
+// ── check_boot ───────────────────────────────────────────────────────────────
 
-        if let Some(unit) = input.get("unit").and_then(|v| v.as_str()) {
-            args.push("-u".into());
-            args.push(unit.into());
+/// Most distinct error messages and slowest units reported by `check_boot`.
+const BOOT_REPORT_LIMIT: usize = 15;
+
+pub struct CheckBootTool;
+
+#[async_trait]
+impl SystemTool for CheckBootTool {
+    fn name(&self) -> &str {
+        "check_boot"
+    }
+    fn description(&self) -> &str {
+        "Summarize problems from the current boot: error-level journal messages (deduplicated, \
+         with counts) and the slowest units from systemd-analyze blame. Use this for questions \
+         like 'why did my boot have errors?' or 'why is boot slow?'."
+    }
+    fn input_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {},
+            "required": []
+        })
+    }
+    fn permission_level(&self) -> PermissionLevel {
+        PermissionLevel::Safe
+    }
+    async fn execute(&self, _input: serde_json::Value) -> Result<String, ToolError> {
+        let journal = run_cmd("journalctl", &["-b", "-p", "err", "--no-pager", "-q"], 60).await?;
+        let errors = summarize_boot_errors(&journal);
+
+        let mut out = format!("Boot errors ({} distinct):\n", errors.len());
+        if errors.is_empty() {
+            out.push_str("  none\n");
+        }
+        for (message, count) in errors.iter().take(BOOT_REPORT_LIMIT) {
+            match count {
+                1 => out.push_str(&format!("  {message}\n")),
+                n => out.push_str(&format!("  {message} (x{n})\n")),
+            }
+        }
+        if errors.len() > BOOT_REPORT_LIMIT {
+            out.push_str(&format!("  ... and {} more\n", errors.len() - BOOT_REPORT_LIMIT));
         }
 
-        let lines = input
-            .get("lines")
-            .and_then(|v| v.as_u64())
-            .unwrap_or(50);
-        args.push("-n".into());
-        args.push(lines.to_string());
+        // systemd-analyze is missing in containers and fails before boot finishes
+        if let Ok(blame) = run_cmd("systemd-analyze", &["blame", "--no-pager"], 60).await {
+            out.push_str("\nSlowest units:\n");
+            for entry in parse_blame(&blame).iter().take(BOOT_REPORT_LIMIT) {
+                out.push_str(&format!("  {:>8.3}s {}\n", entry.millis as f64 / 1000.0, entry.unit));
+            }
+        }
+        Ok(out)
+    }
+}
 
-        let arg_refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
-        run_cmd("journalctl", &arg_refs, 60).await
+/// One line of `systemd-analyze blame`.
+#[derive(Debug, PartialEq)]
+struct BlameEntry {
+    unit: String,
+    millis: u64,
+}
+
+/// Parse `systemd-analyze blame` lines such as `1min 2.345s foo.service`,
+/// slowest first.
+fn parse_blame(output: &str) -> Vec<BlameEntry> {
+    let mut entries: Vec<BlameEntry> = output
+        .lines()
+        .filter_map(|line| {
+            let mut tokens: Vec<&str> = line.split_whitespace().collect();
+            let unit = tokens.pop()?;
+            if tokens.is_empty() {
+                return None;
+            }
+            let millis = tokens
+                .iter()
+                .map(|t| parse_time_span(t))
+                .sum::<Option<u64>>()?;
+            Some(BlameEntry {
+                unit: unit.to_string(),
+                millis,
+            })
+        })
+        .collect();
+    entries.sort_by_key(|e| std::cmp::Reverse(e.millis));
+    entries
+}
+
+/// Milliseconds in one systemd time span token (`1h`, `2min`, `3.5s`, `120ms`, `40us`).
+fn parse_time_span(token: &str) -> Option<u64> {
+    // Not just ASCII letters: newer systemd prints microseconds as `µs`
+    let split = token.find(|c: char| !c.is_ascii_digit() && c != '.')?;
+    let (number, suffix) = token.split_at(split);
+    let value: f64 = number.parse().ok()?;
+    let scale = match suffix {
+        "h" => 3_600_000.0,
+        "min" => 60_000.0,
+        "s" => 1_000.0,
+        "ms" => 1.0,
+        "us" | "µs" => 0.001,
+        _ => return None,
+    };
+    Some((value * scale).round() as u64)
+}
+
+/// Collapse `journalctl` short-format lines into distinct `process: message`
+/// entries with repeat counts, in first-seen order. The timestamp, host and
+/// PID are dropped so repeats of the same error group together.
+fn summarize_boot_errors(journal: &str) -> Vec<(String, usize)> {
+    let mut summary: Vec<(String, usize)> = Vec::new();
+    for line in journal.lines() {
+        // "Oct 16 09:12:01 host process[123]: message"
+        let Some(rest) = line.splitn(5, ' ').nth(4) else {
+            continue;
+        };
+        let message = match rest.split_once(": ") {
+            Some((process, text)) => {
+                let process = process.split('[').next().unwrap_or(process);
+                format!("{process}: {text}")
+            }
+            None => rest.to_string(),
+        };
+        match summary.iter_mut().find(|(m, _)| *m == message) {
+            Some((_, count)) => *count += 1,
+            None => summary.push((message, 1)),
+        }
     }
+    summary
 }
 
 // ── check_network ────────────────────────────────────────────────────────────
@@ -299,3 +540,145 @@ impl SystemTool for RunCommandTool {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const DF_SAMPLE: &str = "\
+Filesystem      Size  Used Avail Use% Mounted on
+/dev/nvme0n1p2  468G  398G   47G  90% /
+tmpfs           7.8G  120M  7.7G   2% /dev/shm
+/dev/nvme0n1p1  511M  409M  103M  80% /boot
+/dev/sda1       1.8T  900G  900G  50% /mnt/My Backup
+tmpfs           1.6G   64K  1.6G   1% /run/user/1000
+";
+
+    #[test]
+    fn test_disk_verdict_over_threshold() {
+        let verdict = disk_verdict(DF_SAMPLE, 80);
+        assert_eq!(verdict["status"], "alert");
+        let over = verdict["over_threshold"].as_array().unwrap();
+        let mounts: Vec<&str> = over.iter().map(|p| p["mount"].as_str().unwrap()).collect();
+        assert_eq!(mounts, vec!["/", "/boot"]);
+        assert_eq!(over[0]["use_percent"], 90);
+        assert_eq!(over[0]["available"], "47G");
+    }
+
+    #[test]
+    fn test_disk_verdict_all_ok() {
+        let verdict = disk_verdict(DF_SAMPLE, 95);
+        assert_eq!(verdict["status"], "ok");
+        assert!(verdict["over_threshold"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_disk_verdict_mount_with_spaces() {
+        let verdict = disk_verdict(DF_SAMPLE, 50);
+        let over = verdict["over_threshold"].as_array().unwrap();
+        assert!(over.iter().any(|p| p["mount"] == "/mnt/My Backup"));
+    }
+
+    const BLAME_SAMPLE: &str = "\
+     5.213s NetworkManager-wait-online.service
+1min 2.002s plymouth-quit-wait.service
+      812ms systemd-journal-flush.service
+    1.004s dev-nvme0n1p2.device
+       95us blunux-firstboot.service
+";
+
+    #[test]
+    fn test_parse_blame_sorted_slowest_first() {
+        let entries = parse_blame(BLAME_SAMPLE);
+        assert_eq!(entries.len(), 5);
+        assert_eq!(
+            entries[0],
+            BlameEntry {
+                unit: "plymouth-quit-wait.service".into(),
+                millis: 62_002,
+            }
+        );
+        assert_eq!(entries[1].unit, "NetworkManager-wait-online.service");
+        assert_eq!(entries[1].millis, 5_213);
+        assert_eq!(entries[3].millis, 812);
+        assert_eq!(entries[4].millis, 0);
+    }
+
+    #[test]
+    fn test_parse_blame_skips_garbage() {
+        assert!(parse_blame("").is_empty());
+        assert!(parse_blame("Bootup is not yet finished.\n").is_empty());
+    }
+
+    #[test]
+    fn test_parse_time_span_units() {
+        assert_eq!(parse_time_span("1h"), Some(3_600_000));
+        assert_eq!(parse_time_span("2min"), Some(120_000));
+        assert_eq!(parse_time_span("3.5s"), Some(3_500));
+        assert_eq!(parse_time_span("1500us"), Some(2));
+        assert_eq!(parse_time_span("1500µs"), Some(2));
+        assert_eq!(parse_time_span("95µs"), Some(0));
+        assert_eq!(parse_time_span("12"), None);
+        assert_eq!(parse_time_span("5d"), None);
+    }
+
+    #[test]
+    fn test_journal_args_since_and_boot() {
+        let args = |input| journal_args(&input).unwrap().join(" ");
+        assert_eq!(args(json!({})), "--no-pager --since today -n 50");
+        assert_eq!(
+            args(json!({"since": "1 hour ago", "unit": "sshd", "lines": 10})),
+            "--no-pager --since 1 hour ago -u sshd -n 10"
+        );
+        assert_eq!(args(json!({"boot": true, "priority": "err"})), "--no-pager -b -p err -n 50");
+        assert_eq!(args(json!({"boot": true, "boot_offset": -1})), "--no-pager -b -1 -n 50");
+        assert_eq!(args(json!({"boot_offset": 0})), "--no-pager -b 0 -n 50");
+        assert_eq!(args(json!({"boot_offset": -100})), "--no-pager -b -100 -n 50");
+        assert_eq!(args(json!({"boot": false})), "--no-pager --since today -n 50");
+    }
+
+    #[test]
+    fn test_journal_args_rejects_bad_boot_input() {
+        for input in [
+            json!({"boot": true, "since": "today"}),
+            json!({"boot_offset": -1, "since": "today"}),
+            json!({"boot": true, "boot_offset": -1000}),
+            json!({"boot": true, "boot_offset": 1}),
+            json!({"boot": true, "boot_offset": "-1; rm -rf /"}),
+            json!({"boot": true, "boot_offset": 1.5}),
+        ] {
+            let err = journal_args(&input).unwrap_err();
+            assert!(matches!(err, ToolError::InvalidInput(_)), "{input}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_journal_args_grep() {
+        let args = journal_args(&json!({"boot": true, "grep": "usb|firmware; rm -rf /"})).unwrap();
+        assert_eq!(args, ["--no-pager", "-b", "-g", "usb|firmware; rm -rf /", "-n", "50"]);
+        assert!(!journal_args(&json!({})).unwrap().contains(&"-g".to_string()));
+
+        for bad in [json!(""), json!("a\nb"), json!("x".repeat(MAX_GREP_LEN + 1))] {
+            let err = journal_args(&json!({"grep": bad})).unwrap_err();
+            assert!(matches!(err, ToolError::InvalidInput(_)), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_summarize_boot_errors_groups_repeats() {
+        let journal = "\
+Oct 16 09:12:01 nux kernel: ACPI BIOS Error (bug): Could not resolve symbol
+Oct 16 09:12:03 nux bluetoothd[612]: Failed to set mode: Blocked through rfkill
+Oct 16 09:12:09 nux bluetoothd[612]: Failed to set mode: Blocked through rfkill
+";
+        let summary = summarize_boot_errors(journal);
+        assert_eq!(
+            summary,
+            vec![
+                ("kernel: ACPI BIOS Error (bug): Could not resolve symbol".to_string(), 1),
+                ("bluetoothd: Failed to set mode: Blocked through rfkill".to_string(), 2),
+            ]
+        );
+    }
+}
@@ -0,0 +1,345 @@
+// ── WhatsApp delivery ────────────────────────────────────────────────────────
+//
+// Replies and notifications the daemon hands the WhatsApp bridge. Markdown is
+// rewritten into WhatsApp's own formatting, and long bodies are split so no
+// single message hits WhatsApp's length limit.
+
+/// Room kept in each chunk for its `(i/n)` marker line.
+const MARKER_RESERVE: usize = 16;
+/// Smallest chunk body, so a tiny configured limit still makes progress.
+const MIN_CHUNK: usize = 64;
+const FENCE: &str = "```";
+
+/// Split `body` into messages of at most `max_chars` characters, each
+/// labelled `(i/n)`. Breaks fall between paragraphs, then lines, then words;
+/// a fenced code block is kept whole when it fits in one chunk and is
+/// otherwise closed and reopened around each break.
+pub fn split_message(body: &str, max_chars: usize) -> Vec<String> {
+    if char_len(body) <= max_chars {
+        return vec![body.to_string()];
+    }
+    let budget = max_chars.saturating_sub(MARKER_RESERVE).max(MIN_CHUNK);
+
+    let mut pieces = Vec::new();
+    for block in blocks(body) {
+        split_block(block, budget, &mut pieces);
+    }
+
+    let mut chunks: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for piece in pieces {
+        if !current.is_empty() && char_len(&current) + char_len(&piece) > budget {
+            chunks.push(std::mem::take(&mut current));
+        }
+        current.push_str(&piece);
+    }
+    chunks.push(current);
+
+    let total = chunks.len();
+    chunks
+        .iter()
+        .enumerate()
+        .map(|(i, chunk)| format!("({}/{total})\n{}", i + 1, chunk.trim_matches('\n')))
+        .collect()
+}
+
+fn char_len(s: &str) -> usize {
+    s.chars().count()
+}
+
+#[derive(Debug, PartialEq)]
+enum Block {
+    /// A paragraph including its trailing blank line(s).
+    Text(String),
+    /// A fenced code block from the opening to the closing fence line.
+    Code(String),
+}
+
+/// Cut `body` into paragraphs and fenced code blocks, keeping every character.
+fn blocks(body: &str) -> Vec<Block> {
+    let mut out = Vec::new();
+    let mut text = String::new();
+    let mut code: Option<String> = None;
+
+    for line in body.split_inclusive('\n') {
+        let is_fence = line.trim_start().starts_with(FENCE);
+        match code.as_mut() {
+            Some(block) => {
+                block.push_str(line);
+                if is_fence {
+                    out.push(Block::Code(code.take().unwrap_or_default()));
+                }
+            }
+            None if is_fence => {
+                if !text.is_empty() {
+                    out.push(Block::Text(std::mem::take(&mut text)));
+                }
+                code = Some(line.to_string());
+            }
+            None => {
+                text.push_str(line);
+                if line.trim().is_empty() {
+                    out.push(Block::Text(std::mem::take(&mut text)));
+                }
+            }
+        }
+    }
+    // An unclosed fence runs to the end of the message
+    if let Some(block) = code {
+        out.push(Block::Code(block));
+    }
+    if !text.is_empty() {
+        out.push(Block::Text(text));
+    }
+    out
+}
+
+/// Push `block` as pieces of at most `budget` characters.
+fn split_block(block: Block, budget: usize, pieces: &mut Vec<String>) {
+    match block {
+        Block::Text(text) if char_len(&text) <= budget => pieces.push(text),
+        Block::Code(code) if char_len(&code) <= budget => pieces.push(code),
+        Block::Text(text) => {
+            for line in text.split_inclusive('\n') {
+                pieces.extend(split_line(line, budget));
+            }
+        }
+        Block::Code(code) => {
+            let mut lines = code.split_inclusive('\n');
+            let open = lines.next().unwrap_or(FENCE).trim_end().to_string();
+            let close = format!("{FENCE}\n");
+            let inner_budget = budget
+                .saturating_sub(char_len(&open) + 1 + char_len(&close))
+                .max(1);
+
+            let mut current = String::new();
+            let mut flush = |current: &mut String| {
+                if !current.is_empty() {
+                    pieces.push(format!("{open}\n{current}{close}"));
+                    current.clear();
+                }
+            };
+            for line in lines {
+                if line.trim_start().starts_with(FENCE) {
+                    break;
+                }
+                for part in split_line(line, inner_budget) {
+                    if char_len(&current) + char_len(&part) > inner_budget {
+                        flush(&mut current);
+                    }
+                    current.push_str(&part);
+                }
+            }
+            flush(&mut current);
+        }
+    }
+}
+
+/// Split one line into parts of at most `budget` characters, breaking after
+/// whitespace when there is some in the back half of a part.
+fn split_line(line: &str, budget: usize) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut rest = line;
+    while char_len(rest) > budget {
+        let hard = rest
+            .char_indices()
+            .nth(budget)
+            .map_or(rest.len(), |(i, _)| i);
+        let cut = rest[..hard]
+            .rfind(char::is_whitespace)
+            .filter(|&i| char_len(&rest[..i]) >= budget / 2)
+            .map_or(hard, |i| i + rest[i..].chars().next().map_or(1, char::len_utf8));
+        parts.push(rest[..cut].to_string());
+        rest = &rest[cut..];
+    }
+    if !rest.is_empty() {
+        parts.push(rest.to_string());
+    }
+    parts
+}
+
+/// Rewrite the markdown models produce into WhatsApp formatting: headers and
+/// `**bold**` become `*bold*`, `*italic*` becomes `_italic_`, `~~strike~~`
+/// becomes `~strike~`, bullets become `•`, links become `text (url)`. Code
+/// blocks keep their fences (WhatsApp's monospace) minus the language tag;
+/// code is never rewritten.
+pub fn to_whatsapp(markdown: &str) -> String {
+    let mut out = Vec::new();
+    let mut in_code = false;
+
+    for line in markdown.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with(FENCE) {
+            in_code = !in_code;
+            out.push(FENCE.to_string());
+            continue;
+        }
+        if in_code {
+            out.push(line.to_string());
+            continue;
+        }
+
+        let hashes = trimmed.chars().take_while(|&c| c == '#').count();
+        if (1..=6).contains(&hashes) && trimmed[hashes..].starts_with(' ') {
+            let title = trimmed[hashes..].trim().replace("**", "");
+            out.push(format!("*{title}*"));
+            continue;
+        }
+
+        let indent = &line[..line.len() - trimmed.len()];
+        if let Some(item) = trimmed.strip_prefix("- ").or_else(|| trimmed.strip_prefix("* ")) {
+            out.push(format!("{indent}• {}", inline(item)));
+            continue;
+        }
+
+        out.push(inline(line));
+    }
+
+    out.join("\n")
+}
+
+/// Rewrite inline spans, leaving `` `code` `` untouched.
+fn inline(line: &str) -> String {
+    line.split('`')
+        .enumerate()
+        .map(|(i, part)| if i % 2 == 1 { part.to_string() } else { emphasis(&links(part)) })
+        .collect::<Vec<_>>()
+        .join("`")
+}
+
+/// `[text](url)` → `text (url)`.
+fn links(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(open) = rest.find('[') {
+        let Some(close) = rest[open..].find("](").map(|i| open + i) else { break };
+        let Some(end) = rest[close + 2..].find(')').map(|i| close + 2 + i) else { break };
+        out.push_str(&rest[..open]);
+        out.push_str(&format!("{} ({})", &rest[open + 1..close], &rest[close + 2..end]));
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Bold, italic and strikethrough markers.
+fn emphasis(text: &str) -> String {
+    // Park bold on a placeholder so its `*` isn't read as italic
+    const BOLD: char = '\u{0}';
+    let text = text.replace("**", &BOLD.to_string()).replace("~~", "~");
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text.as_str();
+    while let Some(start) = rest.find('*') {
+        let inner_start = start + 1;
+        let closed = rest[inner_start..].find('*').map(|i| inner_start + i);
+        match closed {
+            Some(end)
+                if end > inner_start
+                    && !rest[inner_start..end].starts_with(' ')
+                    && !rest[inner_start..end].ends_with(' ') =>
+            {
+                out.push_str(&rest[..start]);
+                out.push('_');
+                out.push_str(&rest[inner_start..end]);
+                out.push('_');
+                rest = &rest[end + 1..];
+            }
+            _ => {
+                out.push_str(&rest[..inner_start]);
+                rest = &rest[inner_start..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out.replace(BOLD, "*")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_body_is_untouched() {
+        assert_eq!(split_message("hello", 4000), ["hello"]);
+    }
+
+    #[test]
+    fn test_long_body_splits_into_marked_chunks() {
+        // Ten 101-char paragraphs; two fit in each 300-char message
+        let body = format!("{}\n\n", "x".repeat(99)).repeat(10);
+        let chunks = split_message(&body, 300);
+        assert_eq!(chunks.len(), 5);
+        for (i, chunk) in chunks.iter().enumerate() {
+            assert!(chunk.starts_with(&format!("({}/5)\n", i + 1)), "{chunk}");
+            assert!(char_len(chunk) <= 300);
+        }
+        let xs: usize = chunks.iter().map(|c| c.matches('x').count()).sum();
+        assert_eq!(xs, 990, "nothing lost");
+    }
+
+    #[test]
+    fn test_code_block_is_not_split_when_it_fits() {
+        let code = format!("```\n{}```\n", "let x = 1;\n".repeat(10));
+        let body = format!("{}\n\n{code}\n{}", "a".repeat(150), "b".repeat(150));
+        let chunks = split_message(&body, 300);
+        let holder = chunks.iter().find(|c| c.contains("let x")).unwrap();
+        assert!(holder.contains(&code.trim_end().to_string()), "{holder}");
+        assert_eq!(chunks.iter().filter(|c| c.contains("let x")).count(), 1);
+    }
+
+    #[test]
+    fn test_oversized_code_block_reopens_fence() {
+        let body = format!("```sh\n{}```\n", "echo 0123456789\n".repeat(40));
+        let chunks = split_message(&body, 200);
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            let fences = chunk.matches("```").count();
+            assert_eq!(fences, 2, "each chunk is a closed block: {chunk}");
+            assert!(chunk.contains("```sh\n"), "{chunk}");
+            assert!(char_len(chunk) <= 200);
+        }
+    }
+
+    #[test]
+    fn test_long_line_breaks_at_whitespace() {
+        let body = "word ".repeat(100);
+        let chunks = split_message(&body, 150);
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|c| !c.ends_with("wor")), "{chunks:?}");
+        assert!(chunks.iter().all(|c| char_len(c) <= 150));
+    }
+
+    #[test]
+    fn test_to_whatsapp_converts_markdown() {
+        let markdown = "\
+## Disk report
+
+**Root** is at *91%*, see [docs](https://wiki.archlinux.org).
+
+- Clean the `pacman` cache
+* Remove ~~old~~ orphans
+
+```bash
+echo \"**not bold**\" # *kept*
+```";
+        let expected = "\
+*Disk report*
+
+*Root* is at _91%_, see docs (https://wiki.archlinux.org).
+
+• Clean the `pacman` cache
+• Remove ~old~ orphans
+
+```
+echo \"**not bold**\" # *kept*
+```";
+        assert_eq!(to_whatsapp(markdown), expected);
+    }
+
+    #[test]
+    fn test_to_whatsapp_leaves_stray_asterisks() {
+        assert_eq!(to_whatsapp("2 * 3 * 4 = 24"), "2 * 3 * 4 = 24");
+        assert_eq!(to_whatsapp("`a*b*c` and **x**"), "`a*b*c` and *x*");
+    }
+}
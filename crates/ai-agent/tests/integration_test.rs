@@ -15,7 +15,10 @@
 
 use tempfile::tempdir;
 
-use ai_agent::config::{AgentConfig, ClaudeMode, Language, ModelId, ProviderType, WhatsAppConfig};
+use ai_agent::config::{
+    AgentConfig, ClaudeMode, Language, ModelId, ProviderType,
+    WhatsAppConfig,
+};
 use ai_agent::memory::Memory;
 use ai_agent::providers::{ClaudeApiProvider, DeepSeekProvider, Message, Provider, StopReason};
 use ai_agent::tools::ToolRegistry;
@@ -70,7 +73,7 @@ async fn test_tool_memory_check() {
     );
 }
 
-/// Extra: verify the tool registry contains all 11 expected tools.
+/// Extra: verify the tool registry contains all 15 expected tools.
 #[test]
 fn test_tool_registry_has_all_tools() {
     let registry = ToolRegistry::default_tools();
@@ -79,12 +82,16 @@ fn test_tool_registry_has_all_tools() {
         "check_memory",
         "check_processes",
         "read_logs",
+        "check_boot",
         "check_network",
         "list_packages",
+        "is_installed",
         "install_package",
         "remove_package",
         "update_system",
+        "clean_cache",
         "manage_service",
+        "check_timers",
         "run_command",
     ];
     for name in &expected {
@@ -134,10 +141,10 @@ fn test_memory_lifecycle() {
     mem.append_today("[09:01] Agent ran: df -h — output: / 40G 20G")
         .expect("append_today #2 failed");
 
-    // 5. Verify both entries are present
+    // 5. Verify both entries are present, keeping their own timestamps
     let today = mem.load_today().expect("load_today failed");
     assert!(
-        today.contains("disk usage"),
+        today.contains("09:00 - User asked: disk usage?"),
         "today log should contain first entry"
     );
     assert!(
@@ -180,7 +187,9 @@ fn test_setup_config_write() {
             max_messages_per_minute: 10,
             require_prefix: true,
             session_timeout: 1800,
+            ..Default::default()
         },
+        ..Default::default()
     };
 
     // Write config.toml
@@ -261,6 +270,34 @@ async fn test_claude_api_provider() {
     );
 }
 
+/// Run with:
+///   ANTHROPIC_API_KEY=<key> cargo test test_claude_count_tokens -- --ignored
+#[tokio::test]
+#[ignore = "requires ANTHROPIC_API_KEY environment variable"]
+async fn test_claude_count_tokens() {
+    let api_key = std::env::var("ANTHROPIC_API_KEY")
+        .expect("ANTHROPIC_API_KEY must be set to run this test");
+
+    let provider = ClaudeApiProvider::new(api_key, ModelId::ClaudeSonnet46);
+    let messages = vec![Message::user("How much disk space is free?")];
+    let tools = ToolRegistry::default_tools().definitions();
+
+    let without_tools = provider
+        .count_tokens("You are a test assistant.", &messages, &[])
+        .await
+        .expect("count_tokens call failed");
+    let with_tools = provider
+        .count_tokens("You are a test assistant.", &messages, &tools)
+        .await
+        .expect("count_tokens call failed");
+
+    assert!(without_tools > 0, "expected a non-zero token count");
+    assert!(
+        with_tools > without_tools,
+        "tool definitions should add tokens ({with_tools} <= {without_tools})"
+    );
+}
+
 /// TDD §15.2: test_deepseek_provider
 ///
 /// Run with:
@@ -294,3 +331,26 @@ async fn test_deepseek_provider() {
         "expected 'pong' in response, got: {text}"
     );
 }
+
+/// Run with:
+///   ANTHROPIC_API_KEY=<key> DEEPSEEK_API_KEY=<key> cargo test test_provider_health_checks -- --ignored
+#[tokio::test]
+#[ignore = "requires ANTHROPIC_API_KEY and DEEPSEEK_API_KEY environment variables"]
+async fn test_provider_health_checks() {
+    let claude_key = std::env::var("ANTHROPIC_API_KEY")
+        .expect("ANTHROPIC_API_KEY must be set to run this test");
+    let deepseek_key = std::env::var("DEEPSEEK_API_KEY")
+        .expect("DEEPSEEK_API_KEY must be set to run this test");
+
+    ClaudeApiProvider::new(claude_key, ModelId::ClaudeSonnet46)
+        .health_check()
+        .await
+        .expect("Claude health check failed");
+    DeepSeekProvider::new(deepseek_key, ModelId::DeepSeekChat)
+        .health_check()
+        .await
+        .expect("DeepSeek health check failed");
+
+    let bad = ClaudeApiProvider::new("sk-ant-invalid".into(), ModelId::ClaudeSonnet46);
+    assert!(bad.health_check().await.is_err(), "an invalid key must fail the check");
+}
@@ -0,0 +1,577 @@
+//! Hardware detection for the live session and installer, via /sys and /proc.
+//!
+//! This is the single copy of the detection logic: the wizard, toml2cal,
+//! setup and the AI agent all use it rather than keeping their own.
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::util::command_exists;
+
+/// Detected GPU vendor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GpuVendor {
+    Nvidia,
+    Amd,
+    Intel,
+    Unknown,
+}
+
+impl GpuVendor {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Nvidia => "NVIDIA",
+            Self::Amd => "AMD",
+            Self::Intel => "Intel",
+            Self::Unknown => "Unknown",
+        }
+    }
+}
+
+impl fmt::Display for GpuVendor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Detected CPU vendor, from `/proc/cpuinfo`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CpuVendor {
+    Intel,
+    Amd,
+    Unknown,
+}
+
+impl CpuVendor {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Intel => "Intel",
+            Self::Amd => "AMD",
+            Self::Unknown => "Unknown",
+        }
+    }
+}
+
+/// Detected hypervisor, when running inside a virtual machine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VmType {
+    Kvm,
+    VirtualBox,
+    Vmware,
+    HyperV,
+}
+
+impl VmType {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Kvm => "KVM/QEMU",
+            Self::VirtualBox => "VirtualBox",
+            Self::Vmware => "VMware",
+            Self::HyperV => "Hyper-V",
+        }
+    }
+
+    /// Match DMI `sys_vendor` + `product_name` text or a
+    /// `systemd-detect-virt` id. Surface laptops share Hyper-V's vendor
+    /// string, so Microsoft only counts with a "Virtual Machine" product.
+    fn from_ident(ident: &str) -> Option<Self> {
+        let ident = ident.trim().to_ascii_lowercase();
+        if ident.contains("qemu") || ident.contains("kvm") {
+            Some(Self::Kvm)
+        } else if ident.contains("virtualbox") || ident.contains("innotek") || ident == "oracle" {
+            Some(Self::VirtualBox)
+        } else if ident.contains("vmware") {
+            Some(Self::Vmware)
+        } else if ident == "microsoft"
+            || (ident.contains("microsoft") && ident.contains("virtual machine"))
+        {
+            Some(Self::HyperV)
+        } else {
+            None
+        }
+    }
+}
+
+/// Detected audio backend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioBackend {
+    Pipewire,
+    PulseAudio,
+    None,
+}
+
+impl AudioBackend {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Pipewire => "Pipewire",
+            Self::PulseAudio => "PulseAudio",
+            Self::None => "None",
+        }
+    }
+}
+
+/// Detect primary GPU vendor by scanning /sys/class/drm/card*/device/vendor.
+pub fn detect_gpu() -> GpuVendor {
+    let drm_path = Path::new("/sys/class/drm");
+    if !drm_path.exists() {
+        return GpuVendor::Unknown;
+    }
+
+    let entries = match fs::read_dir(drm_path) {
+        Ok(e) => e,
+        Err(_) => return GpuVendor::Unknown,
+    };
+
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let name_str = name.to_string_lossy();
+        if !name_str.starts_with("card") || name_str.contains('-') {
+            continue;
+        }
+
+        let vendor_path = entry.path().join("device/vendor");
+        if let Ok(vendor_id) = fs::read_to_string(&vendor_path) {
+            let vendor_id = vendor_id.trim();
+            match vendor_id {
+                "0x10de" => return GpuVendor::Nvidia,
+                "0x1002" => return GpuVendor::Amd,
+                "0x8086" => return GpuVendor::Intel,
+                _ => {}
+            }
+        }
+    }
+
+    GpuVendor::Unknown
+}
+
+/// Detect the CPU vendor from `/proc/cpuinfo`.
+pub fn detect_cpu_vendor() -> CpuVendor {
+    fs::read_to_string("/proc/cpuinfo")
+        .map(|cpuinfo| parse_cpu_vendor(&cpuinfo))
+        .unwrap_or(CpuVendor::Unknown)
+}
+
+/// CPU vendor from the first `vendor_id` line of cpuinfo text.
+pub fn parse_cpu_vendor(cpuinfo: &str) -> CpuVendor {
+    let vendor_id = cpuinfo.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        (key.trim() == "vendor_id").then(|| value.trim())
+    });
+    match vendor_id {
+        Some("GenuineIntel") => CpuVendor::Intel,
+        Some("AuthenticAMD") => CpuVendor::Amd,
+        _ => CpuVendor::Unknown,
+    }
+}
+
+/// Microcode package Arch needs for early CPU microcode updates.
+pub fn cpu_microcode_package(vendor: CpuVendor) -> Option<&'static str> {
+    match vendor {
+        CpuVendor::Intel => Some("intel-ucode"),
+        CpuVendor::Amd => Some("amd-ucode"),
+        CpuVendor::Unknown => None,
+    }
+}
+
+/// Detect the hypervisor from DMI, falling back to `systemd-detect-virt`.
+pub fn detect_virtualization() -> Option<VmType> {
+    detect_virtualization_in(Path::new("/sys/class/dmi/id")).or_else(|| {
+        let out = std::process::Command::new("systemd-detect-virt")
+            .arg("--vm")
+            .output()
+            .ok()?;
+        VmType::from_ident(&String::from_utf8_lossy(&out.stdout))
+    })
+}
+
+/// Detect the hypervisor from `sys_vendor` and `product_name` under `dmi_dir`.
+pub fn detect_virtualization_in(dmi_dir: &Path) -> Option<VmType> {
+    let ident: Vec<String> = ["sys_vendor", "product_name"]
+        .iter()
+        .filter_map(|f| fs::read_to_string(dmi_dir.join(f)).ok())
+        .map(|s| s.trim().to_string())
+        .collect();
+    VmType::from_ident(&ident.join(" "))
+}
+
+/// Return the list of driver packages to install for the detected GPU.
+/// Drivers are auto-selected: NVIDIA → proprietary, AMD/Intel → mesa.
+/// Inside a VM the emulated GPU gets mesa plus the hypervisor's guest tools,
+/// never the proprietary NVIDIA driver.
+pub fn gpu_driver_packages(vendor: GpuVendor, vm: Option<VmType>) -> Vec<&'static str> {
+    if let Some(vm) = vm {
+        return match vm {
+            VmType::Kvm => vec!["mesa", "qemu-guest-agent", "spice-vdagent"],
+            VmType::VirtualBox => vec!["mesa", "virtualbox-guest-utils"],
+            VmType::Vmware => vec!["mesa", "open-vm-tools", "xf86-video-vmware"],
+            VmType::HyperV => vec!["mesa", "hyperv"],
+        };
+    }
+    match vendor {
+        GpuVendor::Nvidia => vec![
+            "nvidia-dkms",
+            "nvidia-utils",
+            "lib32-nvidia-utils",
+            "nvidia-settings",
+        ],
+        GpuVendor::Amd => vec![
+            "mesa",
+            "vulkan-radeon",
+            "lib32-mesa",
+            "lib32-vulkan-radeon",
+            "xf86-video-amdgpu",
+        ],
+        GpuVendor::Intel => vec![
+            "mesa",
+            "vulkan-intel",
+            "lib32-mesa",
+            "lib32-vulkan-intel",
+            "intel-media-driver",
+        ],
+        GpuVendor::Unknown => vec!["mesa"],
+    }
+}
+
+/// Detect the audio backend: `None` without a sound card, otherwise the
+/// running (or else installed) sound server, defaulting to PipeWire.
+pub fn detect_audio() -> AudioBackend {
+    let cards = fs::read_to_string("/proc/asound/cards").unwrap_or_default();
+    select_audio_backend(has_sound_card(&cards), process_running, command_exists)
+}
+
+/// Whether `/proc/asound/cards` text lists at least one card. The file
+/// exists but reads "--- no soundcards ---" when there are none.
+pub fn has_sound_card(cards: &str) -> bool {
+    cards
+        .lines()
+        .any(|line| line.trim_start().starts_with(|c: char| c.is_ascii_digit()))
+}
+
+/// Pick the backend from probes: a running server wins over an installed
+/// one, and PipeWire wins over PulseAudio.
+pub fn select_audio_backend(
+    has_card: bool,
+    is_running: impl Fn(&str) -> bool,
+    is_installed: impl Fn(&str) -> bool,
+) -> AudioBackend {
+    if !has_card {
+        AudioBackend::None
+    } else if is_running("pipewire") {
+        AudioBackend::Pipewire
+    } else if is_running("pulseaudio")
+        || (!is_installed("pipewire") && is_installed("pulseaudio"))
+    {
+        AudioBackend::PulseAudio
+    } else {
+        AudioBackend::Pipewire
+    }
+}
+
+/// Packages providing the audio stack for `backend`. The bash fallback in
+/// `scripts/blunux-setup` repeats this list and [`select_audio_backend`].
+pub fn audio_packages(backend: AudioBackend) -> Vec<&'static str> {
+    match backend {
+        AudioBackend::Pipewire => vec![
+            "pipewire",
+            "wireplumber",
+            "pipewire-pulse",
+            "pipewire-alsa",
+        ],
+        AudioBackend::PulseAudio => vec!["pulseaudio", "pulseaudio-alsa"],
+        AudioBackend::None => vec![],
+    }
+}
+
+/// Whether a process named `name` is running, from `/proc/<pid>/comm`.
+fn process_running(name: &str) -> bool {
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return false;
+    };
+    entries.flatten().any(|entry| {
+        fs::read_to_string(entry.path().join("comm")).is_ok_and(|comm| comm.trim() == name)
+    })
+}
+
+/// Check if the system is booted in UEFI mode.
+pub fn is_uefi() -> bool {
+    Path::new("/sys/firmware/efi").exists()
+}
+
+/// Whether `device` (e.g. `sda`, `/dev/nvme0n1p2`) is on a spinning disk.
+pub fn is_rotational(device: &str) -> bool {
+    is_rotational_in(Path::new("/sys"), device)
+}
+
+/// [`is_rotational`] against the sysfs tree at `sys_root`. A partition is
+/// judged by the disk it's on. Unknown devices count as rotational, so
+/// callers only apply SSD tuning when they're sure.
+pub fn is_rotational_in(sys_root: &Path, device: &str) -> bool {
+    let name = device.trim_start_matches("/dev/");
+    let block = sys_root.join("block");
+    // Only whole disks (and dm devices) are listed in /sys/block; a
+    // partition is a subdirectory of its disk there
+    let disk = if block.join(name).exists() {
+        Some(block.join(name))
+    } else {
+        fs::read_dir(&block).ok().and_then(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .find(|disk| disk.join(name).is_dir())
+        })
+    };
+    disk.and_then(|disk| fs::read_to_string(disk.join("queue/rotational")).ok())
+        .map(|v| v.trim() != "0")
+        .unwrap_or(true)
+}
+
+/// Whether the root filesystem is on an SSD/NVMe drive.
+pub fn root_on_ssd() -> bool {
+    let mounts = fs::read_to_string("/proc/self/mounts").unwrap_or_default();
+    let Some(source) = root_source(&mounts) else {
+        return false;
+    };
+    // /dev/mapper/* and /dev/disk/by-* are symlinks to the kernel name
+    let device = fs::canonicalize(source).unwrap_or_else(|_| source.into());
+    !is_rotational(&device.to_string_lossy())
+}
+
+/// The device mounted on `/` in `/proc/self/mounts` text. The last entry
+/// wins, since a later mount covers an earlier one.
+fn root_source(mounts: &str) -> Option<&str> {
+    mounts
+        .lines()
+        .rev()
+        .find_map(|line| {
+            let mut fields = line.split_whitespace();
+            let source = fields.next()?;
+            (fields.next()? == "/").then_some(source)
+        })
+}
+
+/// Memory and swap figures from `/proc/meminfo`, in megabytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemInfo {
+    pub total_mb: u64,
+    pub available_mb: u64,
+    pub swap_total_mb: u64,
+    pub swap_free_mb: u64,
+}
+
+/// Read `/proc/meminfo`. All fields are 0 if it can't be read.
+pub fn read_meminfo() -> MemInfo {
+    fs::read_to_string("/proc/meminfo")
+        .map(|meminfo| parse_meminfo(&meminfo))
+        .unwrap_or_default()
+}
+
+/// Parse meminfo text; missing lines leave their field at 0.
+pub fn parse_meminfo(meminfo: &str) -> MemInfo {
+    let mut info = MemInfo::default();
+    for line in meminfo.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let Some(kb) = value
+            .trim()
+            .trim_end_matches("kB")
+            .trim()
+            .parse::<u64>()
+            .ok()
+        else {
+            continue;
+        };
+        let field = match key {
+            "MemTotal" => &mut info.total_mb,
+            "MemAvailable" => &mut info.available_mb,
+            "SwapTotal" => &mut info.swap_total_mb,
+            "SwapFree" => &mut info.swap_free_mb,
+            _ => continue,
+        };
+        *field = kb / 1024;
+    }
+    info
+}
+
+/// Get total system RAM in megabytes.
+pub fn total_ram_mb() -> u64 {
+    read_meminfo().total_mb
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dmi_fixture(sys_vendor: &str, product_name: &str) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("sys_vendor"), format!("{sys_vendor}\n")).unwrap();
+        fs::write(dir.path().join("product_name"), format!("{product_name}\n")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_detect_virtualization_from_dmi() {
+        let cases = [
+            ("QEMU", "Standard PC (Q35 + ICH9, 2009)", Some(VmType::Kvm)),
+            ("innotek GmbH", "VirtualBox", Some(VmType::VirtualBox)),
+            ("VMware, Inc.", "VMware Virtual Platform", Some(VmType::Vmware)),
+            ("Microsoft Corporation", "Virtual Machine", Some(VmType::HyperV)),
+            ("LENOVO", "20XW0026GE", None),
+            ("Microsoft Corporation", "Surface Laptop 5", None),
+        ];
+        for (vendor, product, expected) in cases {
+            let dir = dmi_fixture(vendor, product);
+            assert_eq!(detect_virtualization_in(dir.path()), expected, "{vendor}");
+        }
+    }
+
+    #[test]
+    fn test_detect_virtualization_missing_dmi() {
+        assert_eq!(detect_virtualization_in(Path::new("/nonexistent/dmi")), None);
+    }
+
+    const INTEL_CPUINFO: &str = "processor\t: 0\n\
+vendor_id\t: GenuineIntel\n\
+cpu family\t: 6\n\
+model name\t: 13th Gen Intel(R) Core(TM) i7-1360P\n\
+flags\t\t: fpu vme de pse tsc msr\n\n\
+processor\t: 1\n\
+vendor_id\t: GenuineIntel\n";
+
+    const AMD_CPUINFO: &str = "processor\t: 0\n\
+vendor_id\t: AuthenticAMD\n\
+cpu family\t: 25\n\
+model name\t: AMD Ryzen 7 7840U w/ Radeon 780M Graphics\n";
+
+    #[test]
+    fn test_parse_cpu_vendor() {
+        assert_eq!(parse_cpu_vendor(INTEL_CPUINFO), CpuVendor::Intel);
+        assert_eq!(parse_cpu_vendor(AMD_CPUINFO), CpuVendor::Amd);
+        assert_eq!(parse_cpu_vendor("processor\t: 0\n"), CpuVendor::Unknown);
+    }
+
+    #[test]
+    fn test_cpu_microcode_package() {
+        assert_eq!(cpu_microcode_package(parse_cpu_vendor(INTEL_CPUINFO)), Some("intel-ucode"));
+        assert_eq!(cpu_microcode_package(parse_cpu_vendor(AMD_CPUINFO)), Some("amd-ucode"));
+        assert_eq!(cpu_microcode_package(CpuVendor::Unknown), None);
+    }
+
+    /// A sysfs tree with `disks` (name, rotational) in `block/`, each
+    /// followed by its `partitions`.
+    fn sysfs_fixture(disks: &[(&str, &str, &[&str])]) -> tempfile::TempDir {
+        let root = tempfile::tempdir().unwrap();
+        for (dev, rotational, partitions) in disks {
+            let disk = root.path().join("block").join(dev);
+            fs::create_dir_all(disk.join("queue")).unwrap();
+            fs::write(disk.join("queue/rotational"), format!("{rotational}\n")).unwrap();
+            for part in *partitions {
+                fs::create_dir_all(disk.join(part)).unwrap();
+            }
+        }
+        root
+    }
+
+    #[test]
+    fn test_is_rotational() {
+        let root = sysfs_fixture(&[("sda", "1", &["sda1"]), ("nvme0n1", "0", &["nvme0n1p2"]), ("dm-0", "0", &[])]);
+        let root = root.path();
+        assert!(is_rotational_in(root, "sda"));
+        assert!(is_rotational_in(root, "/dev/sda1"));
+        assert!(!is_rotational_in(root, "/dev/nvme0n1"));
+        assert!(!is_rotational_in(root, "/dev/nvme0n1p2"), "partitions follow their disk");
+        assert!(!is_rotational_in(root, "/dev/dm-0"));
+        assert!(is_rotational_in(root, "sdz"), "unknown devices count as rotational");
+        assert!(is_rotational_in(root, "airootfs"));
+    }
+
+    #[test]
+    fn test_root_source() {
+        let mounts = "proc /proc proc rw 0 0\n\
+/dev/nvme0n1p2 / btrfs rw,relatime,subvol=/@ 0 0\n\
+/dev/nvme0n1p1 /boot/efi vfat rw 0 0\n\
+/dev/sdb1 /mnt/data ext4 rw 0 0\n";
+        assert_eq!(root_source(mounts), Some("/dev/nvme0n1p2"));
+        // Only the disk the root filesystem is on counts, not any disk
+        let root = sysfs_fixture(&[("sdb", "1", &["sdb1"]), ("nvme0n1", "0", &["nvme0n1p2"])]);
+        assert!(!is_rotational_in(root.path(), root_source(mounts).unwrap()));
+
+        let overmounted = "airootfs / overlay rw 0 0\n/dev/sda2 / ext4 rw 0 0\n";
+        assert_eq!(root_source(overmounted), Some("/dev/sda2"));
+        assert_eq!(root_source("proc /proc proc rw 0 0\n"), None);
+    }
+
+    const MEMINFO: &str = "MemTotal:       16303428 kB
+MemFree:         1022244 kB
+MemAvailable:    9437184 kB
+Buffers:          512344 kB
+Cached:          7784532 kB
+SwapCached:        10240 kB
+Active:          6512000 kB
+SwapTotal:       8388604 kB
+SwapFree:        8126460 kB
+Dirty:               244 kB
+HugePages_Total:       0
+Hugepagesize:       2048 kB
+";
+
+    #[test]
+    fn test_parse_meminfo() {
+        assert_eq!(
+            parse_meminfo(MEMINFO),
+            MemInfo {
+                total_mb: 15921,
+                available_mb: 9216,
+                swap_total_mb: 8191,
+                swap_free_mb: 7935,
+            }
+        );
+        assert_eq!(parse_meminfo(""), MemInfo::default());
+    }
+
+    #[test]
+    fn test_has_sound_card() {
+        let cards = " 0 [PCH            ]: HDA-Intel - HDA Intel PCH\n                      HDA Intel PCH at 0x6001120000 irq 147\n";
+        assert!(has_sound_card(cards));
+        assert!(!has_sound_card("--- no soundcards ---\n"));
+        assert!(!has_sound_card(""));
+    }
+
+    #[test]
+    fn test_select_audio_backend() {
+        let none = |_: &str| false;
+        let only = |name: &'static str| move |p: &str| p == name;
+
+        assert_eq!(select_audio_backend(false, only("pipewire"), none), AudioBackend::None);
+        assert_eq!(select_audio_backend(true, only("pipewire"), none), AudioBackend::Pipewire);
+        assert_eq!(select_audio_backend(true, only("pulseaudio"), none), AudioBackend::PulseAudio);
+        assert_eq!(
+            select_audio_backend(true, none, only("pulseaudio")),
+            AudioBackend::PulseAudio
+        );
+        assert_eq!(
+            select_audio_backend(true, only("pipewire"), only("pulseaudio")),
+            AudioBackend::Pipewire,
+            "a running server beats an installed one"
+        );
+        assert_eq!(select_audio_backend(true, none, none), AudioBackend::Pipewire);
+    }
+
+    #[test]
+    fn test_audio_packages() {
+        assert!(audio_packages(AudioBackend::Pipewire).contains(&"wireplumber"));
+        assert_eq!(audio_packages(AudioBackend::PulseAudio)[0], "pulseaudio");
+        assert!(audio_packages(AudioBackend::None).is_empty());
+    }
+
+    #[test]
+    fn test_vm_never_gets_proprietary_nvidia() {
+        let pkgs = gpu_driver_packages(GpuVendor::Nvidia, Some(VmType::Kvm));
+        assert!(pkgs.contains(&"mesa"));
+        assert!(!pkgs.iter().any(|p| p.starts_with("nvidia")));
+
+        let bare_metal = gpu_driver_packages(GpuVendor::Nvidia, None);
+        assert!(bare_metal.contains(&"nvidia-dkms"));
+    }
+}
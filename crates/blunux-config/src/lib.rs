@@ -1,18 +1,83 @@
+mod atomic;
+pub mod hwdetect;
+pub mod log;
+mod packages;
+pub mod util;
+
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 
+pub use atomic::write_atomic;
+pub use packages::{is_valid_unit_name, resolve_audio_packages, resolve_packages, resolve_services};
+
 /// Root configuration — mirrors config.toml structure exactly.
 #[derive(Debug, Deserialize, Serialize)]
 pub struct BlunuxConfig {
+    #[serde(default)]
     pub blunux: BlunuxMeta,
     pub locale: Locale,
+    #[serde(default)]
     pub input_method: InputMethod,
+    #[serde(default)]
     pub kernel: Kernel,
     pub install: Install,
     pub disk: Disk,
+    /// Omitted sections and options default to not installed.
+    #[serde(default)]
     pub packages: Packages,
     #[serde(default)]
     pub ai_agent: Option<AiAgent>,
+    /// Hardware recorded when the config was written, if any.
+    #[serde(default)]
+    pub hardware: Option<Hardware>,
+}
+
+impl Default for BlunuxConfig {
+    /// Starter Blunux config: Korean locale, KDE with Firefox, systemd-boot
+    /// and the stock `linux` kernel.
+    fn default() -> Self {
+        Self::minimal("nux", "blu")
+    }
+}
+
+impl BlunuxConfig {
+    /// Default config with the given hostname and primary username.
+    pub fn minimal(hostname: &str, username: &str) -> Self {
+        let mut packages = Packages::default();
+        packages.desktop.kde = true;
+        packages.browser.firefox = true;
+
+        Self {
+            blunux: BlunuxMeta::default(),
+            locale: Locale {
+                language: vec!["ko_KR".into()],
+                timezone: "Asia/Seoul".into(),
+                keyboard: vec!["kr".into(), "us".into()],
+            },
+            input_method: InputMethod {
+                enabled: true,
+                engine: "kime".into(),
+            },
+            kernel: Kernel::default(),
+            install: Install {
+                bootloader: "systemd-boot".into(),
+                hostname: hostname.into(),
+                username: username.into(),
+                root_password: "1234".into(),
+                user_password: "1234".into(),
+                encryption: false,
+                autologin: false,
+                extra_users: Vec::new(),
+                services: Vec::new(),
+            },
+            disk: Disk {
+                swap: "suspend".into(),
+            },
+            packages,
+            ai_agent: None,
+            hardware: None,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -38,12 +103,33 @@ impl Default for AiAgent {
     }
 }
 
+/// What the machine looked like when the config was written. Informational:
+/// the installer still detects hardware on the target itself. No tool writes
+/// this section yet; blunux-ai reads it when it is present.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Hardware {
+    /// GPU vendor, e.g. `nvidia`, `amd`, `intel`.
+    pub gpu: String,
+    /// Driver packages chosen for that GPU.
+    pub drivers: Vec<String>,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct BlunuxMeta {
     pub version: String,
     pub name: String,
 }
 
+impl Default for BlunuxMeta {
+    fn default() -> Self {
+        Self {
+            version: "2.0".into(),
+            name: "blunux".into(),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Locale {
     pub language: Vec<String>,
@@ -57,12 +143,29 @@ pub struct InputMethod {
     pub engine: String,
 }
 
+impl Default for InputMethod {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            engine: "kime".into(),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Kernel {
     #[serde(rename = "type")]
     pub kernel_type: String,
 }
 
+impl Default for Kernel {
+    fn default() -> Self {
+        Self {
+            kernel_type: "linux".into(),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Install {
     pub bootloader: String,
@@ -72,6 +175,36 @@ pub struct Install {
     pub user_password: String,
     pub encryption: bool,
     pub autologin: bool,
+    /// Additional accounts created alongside the primary user.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_users: Vec<ExtraUser>,
+    /// Extra systemd units to enable (e.g. `sshd`, `cups`, `fstrim.timer`),
+    /// on top of the ones implied by the selected packages.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub services: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ExtraUser {
+    pub username: String,
+    pub password: String,
+    /// Add the account to the wheel group.
+    #[serde(default)]
+    pub sudo: bool,
+    /// Log this account in at boot. Ignored when the primary user already
+    /// logs in automatically.
+    #[serde(default)]
+    pub autologin: bool,
+}
+
+/// A login name `useradd` accepts and that is safe in a shell command:
+/// `[a-z_][a-z0-9_-]*`, at most 32 characters.
+pub fn is_valid_username(name: &str) -> bool {
+    name.len() <= 32
+        && name.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '-'))
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -79,7 +212,8 @@ pub struct Disk {
     pub swap: String,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
 pub struct Packages {
     pub desktop: DesktopPkgs,
     pub browser: BrowserPkgs,
@@ -90,14 +224,99 @@ pub struct Packages {
     pub virtualization: VirtualizationPkgs,
     pub communication: CommunicationPkgs,
     pub utility: UtilityPkgs,
+    pub audio: AudioPkgs,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
 pub struct DesktopPkgs {
     pub kde: bool,
+    pub gnome: bool,
+    pub xfce: bool,
+    pub hyprland: bool,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+/// A desktop session Blunux knows how to start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DesktopSession {
+    /// Option name in `[packages.desktop]`.
+    pub desktop: &'static str,
+    pub display_manager: &'static str,
+    /// `.desktop` file name for autologin, without the extension.
+    pub desktop_file: &'static str,
+    /// Commands that start a Wayland session.
+    pub wayland: &'static [&'static str],
+    /// Commands that start an X11 session.
+    pub x11: &'static [&'static str],
+}
+
+impl DesktopPkgs {
+    /// Every known session. When several desktops are enabled, the first
+    /// in this order wins.
+    pub const SESSIONS: [DesktopSession; 4] = [
+        DesktopSession {
+            desktop: "kde",
+            display_manager: "sddm",
+            desktop_file: "plasma",
+            wayland: &["startplasma-wayland"],
+            x11: &["startplasma-x11"],
+        },
+        DesktopSession {
+            desktop: "gnome",
+            display_manager: "gdm",
+            desktop_file: "gnome",
+            wayland: &["gnome-session"],
+            x11: &[],
+        },
+        DesktopSession {
+            desktop: "xfce",
+            display_manager: "lightdm",
+            desktop_file: "xfce",
+            wayland: &[],
+            x11: &["startxfce4"],
+        },
+        DesktopSession {
+            desktop: "hyprland",
+            display_manager: "sddm",
+            desktop_file: "hyprland",
+            wayland: &["Hyprland"],
+            x11: &[],
+        },
+    ];
+
+    /// Whether the `[packages.desktop]` option `desktop` is on.
+    pub fn is_enabled(&self, desktop: &str) -> bool {
+        match desktop {
+            "kde" => self.kde,
+            "gnome" => self.gnome,
+            "xfce" => self.xfce,
+            "hyprland" => self.hyprland,
+            _ => false,
+        }
+    }
+
+    /// The first enabled session in [`SESSIONS`](Self::SESSIONS) order.
+    fn selected(&self) -> Option<&'static DesktopSession> {
+        Self::SESSIONS.iter().find(|s| self.is_enabled(s.desktop))
+    }
+
+    /// Display manager service for the selected desktop, or `None` when no
+    /// desktop is selected.
+    pub fn display_manager(&self) -> Option<&'static str> {
+        self.selected().map(|s| s.display_manager)
+    }
+
+    /// Session executable and `.desktop` file name for autologin: the
+    /// selected desktop's first Wayland command, else its first X11 one.
+    pub fn session(&self) -> Option<(&'static str, &'static str)> {
+        let session = self.selected()?;
+        let exec = session.wayland.iter().chain(session.x11).next()?;
+        Some((exec, session.desktop_file))
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
 pub struct BrowserPkgs {
     pub firefox: bool,
     pub whale: bool,
@@ -105,14 +324,16 @@ pub struct BrowserPkgs {
     pub mullvad: bool,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
 pub struct OfficePkgs {
     pub libreoffice: bool,
     pub hoffice: bool,
     pub texlive: bool,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
 pub struct DevelopmentPkgs {
     pub vscode: bool,
     pub sublime: bool,
@@ -122,7 +343,8 @@ pub struct DevelopmentPkgs {
     pub github_cli: bool,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
 pub struct MultimediaPkgs {
     pub obs: bool,
     pub vlc: bool,
@@ -131,27 +353,31 @@ pub struct MultimediaPkgs {
     pub freetube: bool,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
 pub struct GamingPkgs {
     pub steam: bool,
     pub unciv: bool,
     pub snes9x: bool,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
 pub struct VirtualizationPkgs {
     pub virtualbox: bool,
     pub docker: bool,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
 pub struct CommunicationPkgs {
     pub teams: bool,
     pub whatsapp: bool,
     pub onenote: bool,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
 pub struct UtilityPkgs {
     pub conky: bool,
     pub vnc: bool,
@@ -159,29 +385,178 @@ pub struct UtilityPkgs {
     pub bluetooth: bool,
 }
 
+/// Audio stack, picked from the detected sound server at install time.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AudioPkgs {
+    pub enabled: bool,
+}
+
+impl Default for AudioPkgs {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
 impl BlunuxConfig {
+    /// Parse config from TOML text without touching the filesystem.
+    pub fn from_toml_str(contents: &str) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let config: BlunuxConfig = toml::from_str(contents)?;
+        Ok(config)
+    }
+
+    /// Serialize config to TOML text without touching the filesystem.
+    pub fn to_toml_string(&self) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
     /// Load config from a TOML file path.
     pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
         let contents = std::fs::read_to_string(path)?;
-        let config: BlunuxConfig = toml::from_str(&contents)?;
+        Self::from_toml_str(&contents)
+    }
+
+    /// Load config like [`load`](Self::load), but reject keys or sections the
+    /// schema doesn't know about (e.g. a misspelled `[packages.broswer]`).
+    /// `load` stays lenient so older binaries accept newer configs.
+    pub fn load_strict(path: &Path) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let contents = std::fs::read_to_string(path)?;
+        Self::from_toml_str_strict(&contents)
+    }
+
+    /// Parse TOML text, rejecting unknown keys like [`load_strict`](Self::load_strict).
+    pub fn from_toml_str_strict(
+        contents: &str,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let raw: toml::Value = toml::from_str(contents)?;
+        let config = Self::from_toml_str(contents)?;
+
+        let known = toml::Value::try_from(&config)?;
+        let mut unknown = Vec::new();
+        collect_unknown_keys(&raw, &known, "", &mut unknown);
+        if !unknown.is_empty() {
+            return Err(Box::new(UnknownKeys(unknown)));
+        }
+        Ok(config)
+    }
+
+    /// Load config and expand `${VAR}` references in its string fields from
+    /// the process environment (see [`expand_env_with`](Self::expand_env_with)).
+    pub fn load_expanded(path: &Path) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let mut config = Self::load(path)?;
+        config.expand_env_with(|var| std::env::var(var).ok())?;
         Ok(config)
     }
 
+    /// Replace `${VAR}` in hostname, usernames, passwords and timezone using
+    /// `lookup`. Fails on the first variable `lookup` can't resolve.
+    pub fn expand_env_with(
+        &mut self,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<(), UndefinedEnvVar> {
+        let install = &mut self.install;
+        let mut fields: Vec<(String, &mut String)> = vec![
+            ("locale.timezone".into(), &mut self.locale.timezone),
+            ("install.hostname".into(), &mut install.hostname),
+            ("install.username".into(), &mut install.username),
+            ("install.root_password".into(), &mut install.root_password),
+            ("install.user_password".into(), &mut install.user_password),
+        ];
+        for (i, user) in install.extra_users.iter_mut().enumerate() {
+            fields.push((format!("install.extra_users[{i}].username"), &mut user.username));
+            fields.push((format!("install.extra_users[{i}].password"), &mut user.password));
+        }
+
+        for (field, value) in fields {
+            *value = expand_vars(value, &lookup).map_err(|var| UndefinedEnvVar { var, field })?;
+        }
+        Ok(())
+    }
+
     /// Save config back to a TOML file path.
     pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let contents = toml::to_string_pretty(self)?;
-        std::fs::write(path, contents)?;
+        write_atomic(path, self.to_toml_string()?)?;
         Ok(())
     }
 }
 
+/// Keys present in a config file that the schema does not recognise.
+#[derive(Debug)]
+pub struct UnknownKeys(pub Vec<String>);
+
+impl std::fmt::Display for UnknownKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unknown config keys: {}", self.0.join(", "))
+    }
+}
+
+impl std::error::Error for UnknownKeys {}
+
+/// A `${VAR}` reference in the config that isn't set in the environment.
+#[derive(Debug)]
+pub struct UndefinedEnvVar {
+    pub var: String,
+    pub field: String,
+}
+
+impl std::fmt::Display for UndefinedEnvVar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "environment variable ${{{}}} used in {} is not set", self.var, self.field)
+    }
+}
+
+impl std::error::Error for UndefinedEnvVar {}
+
+/// Expand every `${VAR}` in `value`. Returns the name of the first unresolved
+/// variable on failure. A `$` not followed by `{` is kept as-is.
+fn expand_vars(value: &str, lookup: &impl Fn(&str) -> Option<String>) -> Result<String, String> {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start + 2..].find('}') else { break };
+        let var = &rest[start + 2..start + 2 + len];
+        out.push_str(&rest[..start]);
+        out.push_str(&lookup(var).ok_or_else(|| var.to_string())?);
+        rest = &rest[start + 3 + len..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Sections that live in config.toml but are read by other tools (build.jl,
+/// blunux-ai) rather than this schema, so strict loading must not flag them.
+const EXTERNAL_KEYS: &[&str] = &["packages.ai", "ai_agent.automations", "whatsapp"];
+
+/// Walk the raw TOML alongside the re-serialized config; anything in `raw`
+/// that didn't survive the round trip was ignored by serde.
+fn collect_unknown_keys(raw: &toml::Value, known: &toml::Value, prefix: &str, out: &mut Vec<String>) {
+    match (raw, known) {
+        (toml::Value::Table(raw), toml::Value::Table(known)) => {
+            for (key, value) in raw {
+                let path = if prefix.is_empty() { key.clone() } else { format!("{prefix}.{key}") };
+                match known.get(key) {
+                    Some(known_value) => collect_unknown_keys(value, known_value, &path, out),
+                    // Empty arrays are skipped when serializing (e.g. extra_users)
+                    None if value.as_array().is_some_and(|a| a.is_empty()) => {}
+                    None if EXTERNAL_KEYS.contains(&path.as_str()) => {}
+                    None => out.push(path),
+                }
+            }
+        }
+        (toml::Value::Array(raw), toml::Value::Array(known)) => {
+            for (i, (value, known_value)) in raw.iter().zip(known).enumerate() {
+                collect_unknown_keys(value, known_value, &format!("{prefix}[{i}]"), out);
+            }
+        }
+        _ => {}
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_parse_sample_config() {
-        let toml_str = r#"
+    const SAMPLE: &str = r#"
 [blunux]
 version = "2.0"
 name = "test-build"
@@ -259,12 +634,273 @@ vnc = false
 samba = false
 bluetooth = true
 "#;
-        let config: BlunuxConfig = toml::from_str(toml_str).unwrap();
+
+    #[test]
+    fn test_parse_sample_config() {
+        let config: BlunuxConfig = toml::from_str(SAMPLE).unwrap();
         assert_eq!(config.blunux.version, "2.0");
         assert_eq!(config.install.bootloader, "systemd-boot");
         assert_eq!(config.disk.swap, "suspend");
         assert!(config.packages.desktop.kde);
         assert!(config.packages.browser.firefox);
         assert!(!config.packages.gaming.steam);
+        assert!(config.install.extra_users.is_empty());
+    }
+
+    #[test]
+    fn test_toml_string_round_trip() {
+        let config = BlunuxConfig::from_toml_str(SAMPLE).unwrap();
+        let text = config.to_toml_string().unwrap();
+        let reparsed = BlunuxConfig::from_toml_str(&text).unwrap();
+
+        assert_eq!(reparsed.blunux.name, "test-build");
+        assert_eq!(reparsed.locale.timezone, "Europe/Stockholm");
+        assert_eq!(reparsed.install.hostname, "nux");
+        assert_eq!(resolve_packages(&reparsed), resolve_packages(&config));
+        // Serializing again is stable
+        assert_eq!(reparsed.to_toml_string().unwrap(), text);
+    }
+
+    #[test]
+    fn test_from_toml_str_rejects_invalid() {
+        assert!(BlunuxConfig::from_toml_str("[locale]\nlanguage = 1\n").is_err());
+    }
+
+    #[test]
+    fn test_default_config_validates() {
+        let config = BlunuxConfig::default();
+        let text = config.to_toml_string().unwrap();
+        let parsed = BlunuxConfig::from_toml_str_strict(&text).unwrap();
+
+        assert_eq!(parsed.install.bootloader, "systemd-boot");
+        assert_eq!(parsed.kernel.kernel_type, "linux");
+        assert_eq!(parsed.locale.language, vec!["ko_KR".to_string()]);
+        assert!(parsed.packages.desktop.kde);
+        assert!(parsed.packages.browser.firefox);
+        assert!(!parsed.packages.gaming.steam);
+    }
+
+    #[test]
+    fn test_minimal_sets_identity() {
+        let config = BlunuxConfig::minimal("desk", "alice");
+        assert_eq!(config.install.hostname, "desk");
+        assert_eq!(config.install.username, "alice");
+    }
+
+    #[test]
+    fn test_minimal_config_defaults_packages() {
+        let minimal = r#"
+[locale]
+language = ["en_US"]
+timezone = "UTC"
+keyboard = ["us"]
+
+[install]
+bootloader = "grub"
+hostname = "mini"
+username = "user"
+root_password = "root"
+user_password = "user"
+encryption = false
+autologin = false
+
+[disk]
+swap = "none"
+
+[packages.browser]
+firefox = true
+"#;
+        let config: BlunuxConfig = toml::from_str(minimal).unwrap();
+        assert_eq!(config.install.hostname, "mini");
+        assert_eq!(config.kernel.kernel_type, "linux");
+        assert!(!config.input_method.enabled);
+
+        // Listed option is honoured, missing options and sections are off
+        assert!(config.packages.browser.firefox);
+        assert!(!config.packages.browser.chrome);
+        assert!(!config.packages.desktop.kde);
+        assert!(!config.packages.gaming.steam);
+        assert!(!config.packages.development.rust);
+        assert!(!config.packages.utility.bluetooth);
+        assert_eq!(resolve_packages(&config), vec!["firefox".to_string()]);
+        assert_eq!(config.packages.desktop.display_manager(), None);
+        assert!(config.packages.audio.enabled, "audio is on unless disabled");
+    }
+
+    fn desktop_config(desktop: &str) -> BlunuxConfig {
+        let mut config = BlunuxConfig::minimal("nux", "blu");
+        config.packages.desktop = DesktopPkgs::default();
+        match desktop {
+            "kde" => config.packages.desktop.kde = true,
+            "gnome" => config.packages.desktop.gnome = true,
+            "xfce" => config.packages.desktop.xfce = true,
+            "hyprland" => config.packages.desktop.hyprland = true,
+            _ => unreachable!(),
+        }
+        config
+    }
+
+    #[test]
+    fn test_desktop_packages_and_display_manager() {
+        let cases = [
+            ("kde", "sddm", "plasma-desktop"),
+            ("gnome", "gdm", "gnome-shell"),
+            ("xfce", "lightdm", "xfce4-session"),
+            ("hyprland", "sddm", "hyprland"),
+        ];
+        for (desktop, dm, marker) in cases {
+            let config = desktop_config(desktop);
+            let pkgs = resolve_packages(&config);
+            assert_eq!(config.packages.desktop.display_manager(), Some(dm), "{desktop}");
+            assert!(pkgs.contains(&marker.to_string()), "{desktop}: {pkgs:?}");
+            assert!(pkgs.contains(&dm.to_string()), "{desktop} should install {dm}");
+            assert!(config.packages.desktop.session().is_some(), "{desktop}");
+        }
+        let xfce = desktop_config("xfce");
+        assert_eq!(xfce.packages.desktop.session(), Some(("startxfce4", "xfce")));
+    }
+
+    #[test]
+    fn test_desktop_parse_from_toml() {
+        let toml_str = SAMPLE.replace("kde = true", "kde = false\ngnome = true");
+        let config = BlunuxConfig::from_toml_str_strict(&toml_str).unwrap();
+        assert!(!config.packages.desktop.kde);
+        assert!(config.packages.desktop.gnome);
+        assert_eq!(
+            config.packages.desktop.session(),
+            Some(("gnome-session", "gnome"))
+        );
+    }
+
+    #[test]
+    fn test_audio_packages_follow_detected_backend() {
+        let config = BlunuxConfig::default();
+        let pkgs = resolve_audio_packages(&config, hwdetect::AudioBackend::Pipewire);
+        assert_eq!(pkgs, ["pipewire", "wireplumber", "pipewire-pulse", "pipewire-alsa"]);
+        assert!(resolve_audio_packages(&config, hwdetect::AudioBackend::None).is_empty());
+
+        let toml_str = SAMPLE.replace("[packages.browser]", "[packages.audio]\nenabled = false\n\n[packages.browser]");
+        let disabled = BlunuxConfig::from_toml_str_strict(&toml_str).unwrap();
+        assert!(resolve_audio_packages(&disabled, hwdetect::AudioBackend::Pipewire).is_empty());
+    }
+
+    #[test]
+    fn test_resolve_services_appends_configured_units() {
+        let toml_str = SAMPLE.replace(
+            "autologin = true",
+            "autologin = true\nservices = [\"sshd\", \"cups.service\", \"fstrim.timer\", \"bad/unit\", \"sshd\"]",
+        );
+        let config = BlunuxConfig::from_toml_str_strict(&toml_str).unwrap();
+        assert_eq!(
+            resolve_services(&config),
+            ["NetworkManager", "sddm", "bluetooth", "sshd", "cups.service", "fstrim.timer"]
+        );
+        assert!(!is_valid_unit_name("bad/unit"));
+        assert!(!is_valid_unit_name(""));
+        assert!(is_valid_unit_name("getty@tty1.service"));
+    }
+
+    #[test]
+    fn test_kde_wins_when_several_desktops_enabled() {
+        let mut config = desktop_config("gnome");
+        config.packages.desktop.kde = true;
+        assert_eq!(config.packages.desktop.display_manager(), Some("sddm"));
+        assert_eq!(BlunuxConfig::default().packages.desktop.display_manager(), Some("sddm"));
+    }
+
+    fn env(var: &str) -> Option<String> {
+        match var {
+            "HOST" => Some("lab-01".into()),
+            "ROOT_PW" => Some("s3cret".into()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_expand_env_substitutes_vars() {
+        let mut config = BlunuxConfig::minimal("${HOST}", "blu");
+        config.install.root_password = "${ROOT_PW}".into();
+        config.install.user_password = "pre-${HOST}-post".into();
+        config.expand_env_with(env).unwrap();
+
+        assert_eq!(config.install.hostname, "lab-01");
+        assert_eq!(config.install.root_password, "s3cret");
+        assert_eq!(config.install.user_password, "pre-lab-01-post");
+        // Untouched fields and bare `$` are left alone
+        assert_eq!(config.install.username, "blu");
+        assert_eq!(expand_vars("cost $5", &env).unwrap(), "cost $5");
+    }
+
+    #[test]
+    fn test_expand_env_undefined_var_errors() {
+        let mut config = BlunuxConfig::minimal("nux", "blu");
+        config.install.user_password = "${MISSING_PW}".into();
+        let err = config.expand_env_with(env).unwrap_err();
+        assert_eq!(err.var, "MISSING_PW");
+        assert_eq!(err.field, "install.user_password");
+
+        let mut config = BlunuxConfig::minimal("nux", "blu");
+        config.install.extra_users.push(ExtraUser {
+            username: "alice".into(),
+            password: "${MISSING_PW}".into(),
+            sudo: false,
+            autologin: false,
+        });
+        let err = config.expand_env_with(env).unwrap_err();
+        assert_eq!(err.field, "install.extra_users[0].password");
+    }
+
+    #[test]
+    fn test_extra_users_parse_from_toml() {
+        let toml_str = SAMPLE.replace(
+            "[disk]",
+            "[[install.extra_users]]\nusername = \"alice\"\npassword = \"pw\"\nsudo = true\n\n[disk]",
+        );
+        let config = BlunuxConfig::from_toml_str_strict(&toml_str).unwrap();
+        assert_eq!(config.install.extra_users.len(), 1);
+        assert!(config.install.extra_users[0].sudo);
+        assert!(!config.install.extra_users[0].autologin);
+    }
+
+    #[test]
+    fn test_is_valid_username() {
+        for name in ["alice", "_build", "user-2", "a_b"] {
+            assert!(is_valid_username(name), "{name}");
+        }
+        for name in ["", "Alice", "2fast", "-x", "bob smith", "eve;rm", &"a".repeat(33)] {
+            assert!(!is_valid_username(name), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_load_strict_accepts_sample() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, SAMPLE).unwrap();
+        assert!(BlunuxConfig::load_strict(&path).is_ok());
+    }
+
+    #[test]
+    fn test_load_strict_accepts_repo_config() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../config.toml");
+        if let Err(e) = BlunuxConfig::load_strict(&path) {
+            panic!("repo config.toml failed strict load: {e}");
+        }
+    }
+
+    #[test]
+    fn test_load_strict_reports_misspelled_section() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let typo = format!("{SAMPLE}\n[packages.broswer]\nfirefox = true\n");
+        std::fs::write(&path, typo.replace("autologin = true", "autologin = true\nhostnmae = \"x\"")).unwrap();
+
+        // Lenient load still succeeds
+        assert!(BlunuxConfig::load(&path).is_ok());
+
+        let err = BlunuxConfig::load_strict(&path).unwrap_err();
+        let unknown = err.downcast_ref::<UnknownKeys>().expect("UnknownKeys error");
+        assert!(unknown.0.contains(&"packages.broswer".to_string()));
+        assert!(unknown.0.contains(&"install.hostnmae".to_string()));
     }
 }
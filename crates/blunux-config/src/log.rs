@@ -0,0 +1,104 @@
+//! Progress output for the Blunux binaries. [`info!`](crate::info) lines are
+//! dropped under `--quiet`; [`warn!`](crate::warn) lines always go to stderr.
+
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+static INFO_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Where `info!` lines go when not quiet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    /// For tools whose stdout carries data, e.g. `toml2cal generate --diff`.
+    Stderr,
+}
+
+/// Set up output for this process; call once at the start of `main`.
+pub fn init(quiet: bool, info: Stream) {
+    QUIET.store(quiet, Ordering::Relaxed);
+    INFO_TO_STDERR.store(info == Stream::Stderr, Ordering::Relaxed);
+}
+
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Level {
+    Info,
+    Warn,
+}
+
+#[doc(hidden)]
+pub fn info(args: fmt::Arguments) {
+    let to_stderr = INFO_TO_STDERR.load(Ordering::Relaxed);
+    emit(Level::Info, args, is_quiet(), to_stderr, &mut io::stdout(), &mut io::stderr());
+}
+
+#[doc(hidden)]
+pub fn warn(args: fmt::Arguments) {
+    emit(Level::Warn, args, is_quiet(), false, &mut io::stdout(), &mut io::stderr());
+}
+
+fn emit(
+    level: Level,
+    args: fmt::Arguments,
+    quiet: bool,
+    info_to_stderr: bool,
+    out: &mut impl Write,
+    err: &mut impl Write,
+) {
+    let sink: &mut dyn Write = match level {
+        Level::Info if quiet => return,
+        Level::Info if !info_to_stderr => out,
+        _ => err,
+    };
+    // Nowhere left to report a failed write to the terminal
+    let _ = writeln!(sink, "{args}");
+}
+
+/// Print a progress line, unless `--quiet`.
+#[macro_export]
+macro_rules! info {
+    () => {
+        $crate::log::info(format_args!(""))
+    };
+    ($($arg:tt)*) => {
+        $crate::log::info(format_args!($($arg)*))
+    };
+}
+
+/// Print a warning to stderr, even under `--quiet`.
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)*) => {
+        $crate::log::warn(format_args!($($arg)*))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(level: Level, quiet: bool, info_to_stderr: bool) -> (String, String) {
+        let (mut out, mut err) = (Vec::new(), Vec::new());
+        emit(level, format_args!("── step {} ──", 1), quiet, info_to_stderr, &mut out, &mut err);
+        (String::from_utf8(out).unwrap(), String::from_utf8(err).unwrap())
+    }
+
+    #[test]
+    fn test_quiet_suppresses_info_only() {
+        assert_eq!(run(Level::Info, false, false), ("── step 1 ──\n".into(), String::new()));
+        assert_eq!(run(Level::Info, true, false), (String::new(), String::new()));
+        assert_eq!(run(Level::Warn, true, false), (String::new(), "── step 1 ──\n".into()));
+    }
+
+    #[test]
+    fn test_info_can_go_to_stderr() {
+        assert_eq!(run(Level::Info, false, true), (String::new(), "── step 1 ──\n".into()));
+        assert_eq!(run(Level::Info, true, true), (String::new(), String::new()));
+    }
+}
@@ -1,8 +1,9 @@
-use blunux_config::BlunuxConfig;
+use crate::hwdetect::{self, AudioBackend};
+use crate::BlunuxConfig;
 
-/// Resolve config.toml booleans into package names.
-/// All packages go through yay, which handles both official and AUR.
-pub fn resolve(config: &BlunuxConfig) -> Vec<String> {
+/// Resolve config.toml package booleans into package names.
+/// The list mixes official and AUR packages, so installers should use yay.
+pub fn resolve_packages(config: &BlunuxConfig) -> Vec<String> {
     let mut pkgs: Vec<String> = Vec::new();
     let p = &config.packages;
 
@@ -23,6 +24,50 @@ pub fn resolve(config: &BlunuxConfig) -> Vec<String> {
             .map(str::to_string),
         );
     }
+    if p.desktop.gnome {
+        pkgs.extend(
+            [
+                "gnome-shell",
+                "gdm",
+                "gnome-control-center",
+                "gnome-terminal",
+                "nautilus",
+                "gnome-text-editor",
+                "file-roller",
+                "xdg-desktop-portal-gnome",
+            ]
+            .map(str::to_string),
+        );
+    }
+    if p.desktop.xfce {
+        pkgs.extend(
+            [
+                "xfce4-session",
+                "xfwm4",
+                "xfce4-panel",
+                "xfdesktop",
+                "xfce4-settings",
+                "xfce4-terminal",
+                "thunar",
+                "lightdm",
+                "lightdm-gtk-greeter",
+            ]
+            .map(str::to_string),
+        );
+    }
+    if p.desktop.hyprland {
+        pkgs.extend(
+            [
+                "hyprland",
+                "sddm",
+                "kitty",
+                "waybar",
+                "wofi",
+                "xdg-desktop-portal-hyprland",
+            ]
+            .map(str::to_string),
+        );
+    }
 
     // Browsers
     if p.browser.firefox {
@@ -139,3 +184,48 @@ pub fn resolve(config: &BlunuxConfig) -> Vec<String> {
 
     pkgs
 }
+
+/// Audio stack packages for the detected `backend`, unless
+/// `[packages.audio] enabled = false`. Kept out of [`resolve_packages`]
+/// because it depends on the machine, not just the config.
+pub fn resolve_audio_packages(config: &BlunuxConfig, backend: AudioBackend) -> Vec<String> {
+    if !config.packages.audio.enabled {
+        return Vec::new();
+    }
+    hwdetect::audio_packages(backend)
+        .into_iter()
+        .map(str::to_string)
+        .collect()
+}
+
+/// systemd units to enable: NetworkManager, the display manager and services
+/// for selected packages, then `[install] services`. Invalid or repeated
+/// names are dropped; see [`is_valid_unit_name`].
+pub fn resolve_services(config: &BlunuxConfig) -> Vec<String> {
+    let mut services = vec!["NetworkManager".to_string()];
+    if let Some(dm) = config.packages.desktop.display_manager() {
+        services.push(dm.into());
+    }
+    if config.packages.utility.bluetooth {
+        services.push("bluetooth".into());
+    }
+    if config.packages.virtualization.docker {
+        services.push("docker".into());
+    }
+    for unit in &config.install.services {
+        if is_valid_unit_name(unit) && !services.contains(unit) {
+            services.push(unit.clone());
+        }
+    }
+    services
+}
+
+/// A systemd unit name safe to pass to `systemctl enable`: non-empty and only
+/// `[A-Za-z0-9:_.@-]`, so no paths or shell metacharacters.
+pub fn is_valid_unit_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('-')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, ':' | '_' | '.' | '@' | '-'))
+}
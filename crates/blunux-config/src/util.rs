@@ -0,0 +1,94 @@
+//! Small helpers shared by the Blunux binaries.
+
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+/// Whether `name` is an executable on `PATH`. Scans `PATH` directly rather
+/// than spawning `which`, which may itself be missing on a minimal install.
+pub fn command_exists(name: &str) -> bool {
+    find_in_path(name, std::env::var_os("PATH").as_deref()).is_some()
+}
+
+/// First executable file named `program` in the `PATH`-style list `path`.
+pub fn find_in_path(program: &str, path: Option<&OsStr>) -> Option<PathBuf> {
+    if program.is_empty() || program.contains('/') {
+        return None;
+    }
+    std::env::split_paths(path?)
+        .map(|dir| dir.join(program))
+        .find(|candidate| is_executable(candidate))
+}
+
+/// `bytes` in the largest binary unit that keeps the value at least 1, e.g.
+/// `1288490189` -> `1.2 GiB`. Plain bytes below 1 KiB.
+///
+/// IEC units and a `.` decimal read the same in every language Blunux ships,
+/// so the result is not localized.
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_exists() {
+        assert!(command_exists("sh"));
+        assert!(!command_exists("blunux-no-such-command-7f3a"));
+        assert!(!command_exists(""));
+    }
+
+    #[test]
+    fn test_human_size() {
+        // Bytes
+        assert_eq!(human_size(0), "0 B");
+        assert_eq!(human_size(1023), "1023 B");
+        assert_eq!(human_size(1536), "1.5 KiB");
+        // Megabytes
+        assert_eq!(human_size(500 * 1024 * 1024), "500.0 MiB");
+        assert_eq!(human_size(1_288_490_189), "1.2 GiB");
+        // Terabytes, and nothing larger
+        assert_eq!(human_size(2 * 1024_u64.pow(4)), "2.0 TiB");
+        assert_eq!(human_size(3 * 1024_u64.pow(5)), "3072.0 TiB");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_find_in_path_skips_non_executables() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let tool = dir.path().join("tool");
+        std::fs::write(&tool, "#!/bin/sh\n").unwrap();
+        let path = dir.path().as_os_str().to_owned();
+
+        std::fs::set_permissions(&tool, std::fs::Permissions::from_mode(0o644)).unwrap();
+        assert_eq!(find_in_path("tool", Some(&path)), None);
+
+        std::fs::set_permissions(&tool, std::fs::Permissions::from_mode(0o755)).unwrap();
+        assert_eq!(find_in_path("tool", Some(&path)), Some(tool));
+        assert_eq!(find_in_path("tool", None), None);
+    }
+}
@@ -0,0 +1,83 @@
+//! Split package installs into official-repo and AUR packages, so official
+//! packages go in one fast pacman batch and each AUR build can fail alone.
+
+use std::process::{Command, Stdio};
+
+/// Packages from `pkgs` found in the sync repos, and the rest (AUR).
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Classified {
+    pub official: Vec<String>,
+    pub aur: Vec<String>,
+}
+
+/// Classify `pkgs` with `in_repos`, which says whether the sync repos have
+/// a package.
+pub fn classify(pkgs: &[String], in_repos: impl Fn(&str) -> bool) -> Classified {
+    let (official, aur) = pkgs.iter().cloned().partition(|p| in_repos(p));
+    Classified { official, aur }
+}
+
+/// Classify `pkgs` by the exit status of `pacman -Si <pkg>`, like the bash
+/// fallback. The output is translated, so it isn't parsed. If pacman can't
+/// run at all, everything is treated as AUR so yay still gets a chance.
+pub fn classify_with_pacman(pkgs: &[String]) -> Classified {
+    classify_with("pacman", pkgs)
+}
+
+fn classify_with(pacman: &str, pkgs: &[String]) -> Classified {
+    classify(pkgs, |pkg| {
+        Command::new(pacman)
+            .args(["-Si", pkg])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pkgs(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_classify_official_and_aur() {
+        let list = pkgs(&["firefox", "naver-whale-bin", "rustup", "visual-studio-code-bin"]);
+        let classified = classify(&list, |p| p == "firefox" || p == "rustup");
+        assert_eq!(classified.official, ["firefox", "rustup"]);
+        assert_eq!(classified.aur, ["naver-whale-bin", "visual-studio-code-bin"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_classify_with_translated_pacman_output() {
+        use std::os::unix::fs::PermissionsExt;
+        // pacman on a ko_KR system: translated field names, found or not by exit status
+        let dir = tempfile::tempdir().unwrap();
+        let pacman = dir.path().join("pacman");
+        std::fs::write(
+            &pacman,
+            "#!/bin/sh\n\
+             [ \"$2\" = firefox ] || { echo \"오류: '$2' 꾸러미를 찾을 수 없습니다\" >&2; exit 1; }\n\
+             echo '저장소          : extra'\n\
+             echo '이름            : firefox'\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&pacman, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let classified = classify_with(pacman.to_str().unwrap(), &pkgs(&["firefox", "hoffice-bin"]));
+        assert_eq!(classified.official, ["firefox"]);
+        assert_eq!(classified.aur, ["hoffice-bin"]);
+    }
+
+    #[test]
+    fn test_classify_without_pacman_is_all_aur() {
+        let list = pkgs(&["firefox", "hoffice-bin"]);
+        let classified = classify_with("/nonexistent/pacman", &list);
+        assert!(classified.official.is_empty());
+        assert_eq!(classified.aur, list);
+    }
+}
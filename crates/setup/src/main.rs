@@ -1,10 +1,16 @@
-mod packages;
+mod install;
+mod preflight;
+mod yay;
 
 use anyhow::{bail, Context, Result};
-use blunux_config::BlunuxConfig;
+use blunux_config::util::command_exists;
+use blunux_config::{
+    hwdetect, info, is_valid_unit_name, log, resolve_audio_packages, resolve_packages, resolve_services,
+    warn, write_atomic, BlunuxConfig,
+};
 use clap::Parser;
 use std::path::PathBuf;
-use std::process::Command;
+use std::process::{Command, ExitCode};
 
 #[derive(Parser)]
 #[command(name = "blunux-setup")]
@@ -17,105 +23,224 @@ struct Cli {
     /// Live ISO mode: also install calamares
     #[arg(long)]
     live: bool,
+
+    /// Rank pacman mirrors by speed with reflector before installing
+    #[arg(long)]
+    rank_mirrors: bool,
+
+    /// Install official packages in one pacman batch and build AUR packages
+    /// one at a time, so a failing AUR build doesn't block the rest
+    #[arg(long)]
+    parallel: bool,
+
+    /// Only print warnings and errors
+    #[arg(short, long)]
+    quiet: bool,
 }
 
-fn main() -> Result<()> {
+const MIRRORLIST: &str = "/etc/pacman.d/mirrorlist";
+
+fn main() -> ExitCode {
     let cli = Cli::parse();
+    log::init(cli.quiet, log::Stream::Stdout);
+
+    match run(&cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(failure) => {
+            eprintln!("Error: {:?}", failure.error);
+            ExitCode::from(failure.stage.exit_code())
+        }
+    }
+}
 
-    println!("blunux-setup: loading {}", cli.config.display());
+fn run(cli: &Cli) -> Result<(), Failure> {
+    info!("blunux-setup: loading {}", cli.config.display());
     let config = BlunuxConfig::load(&cli.config)
-        .map_err(|e| anyhow::anyhow!("{}: {}", cli.config.display(), e))?;
+        .map_err(|e| anyhow::anyhow!("{}: {}", cli.config.display(), e))
+        .at(Stage::Config)?;
+
+    // 0. Network and mirror preflight
+    step_preflight(cli.rank_mirrors).at(Stage::Preflight)?;
 
     // 1. Bootstrap yay (AUR helper)
-    ensure_yay()?;
+    ensure_yay().at(Stage::Yay)?;
 
     // 2. Live mode: install calamares from AUR
     if cli.live {
-        step_install_calamares()?;
+        step_install_calamares().at(Stage::Packages)?;
     }
 
     // 3. Install user-selected packages (official + AUR, all via yay)
-    step_install_packages(&config)?;
+    step_install_packages(&config, cli.parallel).at(Stage::Packages)?;
 
     // 4. Input method
     if config.input_method.enabled {
-        step_setup_input_method(&config)?;
+        step_setup_input_method(&config).at(Stage::InputMethod)?;
     }
 
     // 5. Enable services
-    step_enable_services(&config)?;
+    step_enable_services(&config).at(Stage::Services)?;
 
-    println!("\nblunux-setup: done");
+    info!("\nblunux-setup: done");
     Ok(())
 }
 
-// ── yay bootstrap ──────────────────────────────────────────────────────────
+// ── Exit codes ─────────────────────────────────────────────────────────────
+
+/// The step `blunux-setup` failed in. Each has its own exit code so
+/// provisioning scripts can tell them apart; they start at 10 to stay clear
+/// of clap's usage error (2).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stage {
+    Config,
+    Preflight,
+    Yay,
+    Packages,
+    InputMethod,
+    Services,
+}
 
-fn ensure_yay() -> Result<()> {
-    if has_cmd("yay") {
-        println!("  yay: found");
-        return Ok(());
+impl Stage {
+    fn exit_code(self) -> u8 {
+        match self {
+            Stage::Config => 10,
+            Stage::Preflight => 11,
+            Stage::Yay => 12,
+            Stage::Packages => 13,
+            Stage::InputMethod => 14,
+            Stage::Services => 15,
+        }
     }
+}
 
-    println!("── Installing yay ──");
+struct Failure {
+    stage: Stage,
+    error: anyhow::Error,
+}
 
-    // base-devel + git needed for makepkg
-    sudo_pacman(&["base-devel", "git"])?;
+trait AtStage<T> {
+    /// Tag an error with the step it happened in.
+    fn at(self, stage: Stage) -> Result<T, Failure>;
+}
 
-    let tmp = "/tmp/blunux-yay-build";
-    let _ = std::fs::remove_dir_all(tmp);
+impl<T> AtStage<T> for Result<T> {
+    fn at(self, stage: Stage) -> Result<T, Failure> {
+        self.map_err(|error| Failure { stage, error })
+    }
+}
 
-    let status = Command::new("git")
-        .args(["clone", "https://aur.archlinux.org/yay-bin.git", tmp])
-        .status()
-        .context("git clone yay-bin")?;
-    if !status.success() {
-        bail!("Failed to clone yay-bin");
+// ── Preflight ──────────────────────────────────────────────────────────────
+
+fn step_preflight(rank_mirrors: bool) -> Result<()> {
+    info!("── Preflight ──");
+    let euid = preflight::effective_uid().unwrap_or(u32::MAX);
+    let user = preflight::check_user(euid, command_exists("sudo"));
+    let message = user.message().unwrap_or_default();
+    match user {
+        preflight::UserCheck::Ok => info!("  user: ok"),
+        preflight::UserCheck::Root => warn!("  Warning: {message}"),
+        preflight::UserCheck::NoSudo => bail!("{message}"),
     }
 
-    let status = Command::new("makepkg")
-        .args(["-si", "--noconfirm"])
-        .current_dir(tmp)
-        .status()
-        .context("makepkg yay-bin")?;
-    if !status.success() {
-        bail!("Failed to build yay-bin");
+    preflight::check_network(preflight::tcp_reachable)?;
+    info!("  network: ok");
+
+    if rank_mirrors {
+        info!("  Ranking mirrors with reflector...");
+        sudo_pacman(&["reflector"])?;
+        let status = Command::new("sudo")
+            .args([
+                "reflector", "--latest", "20", "--protocol", "https", "--sort", "rate", "--save",
+                MIRRORLIST,
+            ])
+            .status()
+            .context("reflector")?;
+        if !status.success() {
+            bail!("reflector exited {status}");
+        }
     }
 
-    let _ = std::fs::remove_dir_all(tmp);
-    println!("  yay: installed");
+    let mirrorlist = std::fs::read_to_string(MIRRORLIST)
+        .with_context(|| format!("Failed to read {MIRRORLIST}"))?;
+    preflight::check_mirror(&mirrorlist, preflight::tcp_reachable)?;
+    info!("  mirror: ok");
+    Ok(())
+}
+
+// ── yay bootstrap ──────────────────────────────────────────────────────────
+
+fn ensure_yay() -> Result<()> {
+    if command_exists("yay") {
+        info!("  yay: found");
+        return Ok(());
+    }
+
+    info!("── Installing yay ──");
+
+    // base-devel + git needed for makepkg
+    sudo_pacman(&["base-devel", "git"])?;
+
+    yay::bootstrap(&yay::SystemRunner, std::path::Path::new("/tmp/blunux-yay-build"))
+        .context("Failed to install yay")?;
+    info!("  yay: installed");
     Ok(())
 }
 
 // ── Package installation ───────────────────────────────────────────────────
 
 fn step_install_calamares() -> Result<()> {
-    println!("\n── Installing Calamares (live ISO) ──");
+    info!("\n── Installing Calamares (live ISO) ──");
     yay_install(&["calamares", "calamares-extensions"])
 }
 
-fn step_install_packages(config: &BlunuxConfig) -> Result<()> {
-    let pkgs = packages::resolve(config);
+fn step_install_packages(config: &BlunuxConfig, parallel: bool) -> Result<()> {
+    let mut pkgs = resolve_packages(config);
+    pkgs.extend(resolve_audio_packages(config, hwdetect::detect_audio()));
     if pkgs.is_empty() {
-        println!("\n── No additional packages to install ──");
+        info!("\n── No additional packages to install ──");
         return Ok(());
     }
 
-    println!("\n── Installing {} packages ──", pkgs.len());
-    for pkg in &pkgs {
-        eprint!("  {pkg} ");
-    }
-    eprintln!();
+    info!("\n── Installing {} packages ──", pkgs.len());
+    info!("  {}", pkgs.join(" "));
 
+    if parallel {
+        return install_split(&pkgs);
+    }
     let refs: Vec<&str> = pkgs.iter().map(|s| s.as_str()).collect();
     yay_install(&refs)
 }
 
+/// `--parallel`: official packages in one pacman call, then each AUR package
+/// on its own. Every AUR package is attempted; failures are reported at the end.
+fn install_split(pkgs: &[String]) -> Result<()> {
+    let classified = install::classify_with_pacman(pkgs);
+
+    if !classified.official.is_empty() {
+        info!("  {} official packages via pacman", classified.official.len());
+        let refs: Vec<&str> = classified.official.iter().map(|s| s.as_str()).collect();
+        sudo_pacman(&refs)?;
+    }
+
+    let mut failed = Vec::new();
+    for pkg in &classified.aur {
+        info!("  AUR: {pkg}");
+        if let Err(e) = yay_install(&[pkg.as_str()]) {
+            warn!("  Warning: {pkg} failed: {e}");
+            failed.push(pkg.as_str());
+        }
+    }
+    if !failed.is_empty() {
+        bail!("{} AUR package(s) failed: {}", failed.len(), failed.join(" "));
+    }
+    Ok(())
+}
+
 // ── Input method ───────────────────────────────────────────────────────────
 
 fn step_setup_input_method(config: &BlunuxConfig) -> Result<()> {
     let engine = &config.input_method.engine;
-    println!("\n── Configuring input method: {engine} ──");
+    info!("\n── Configuring input method: {engine} ──");
 
     match engine.as_str() {
         "kime" => setup_kime()?,
@@ -158,9 +283,9 @@ engine:
     hanja_keys: [F9, Hangul_Hanja]
 "#;
 
-    std::fs::write(config_dir.join("config.yaml"), kime_yaml)
+    write_atomic(&config_dir.join("config.yaml"), kime_yaml)
         .context("write kime config.yaml")?;
-    println!("  Wrote kime config.yaml");
+    info!("  Wrote kime config.yaml");
 
     // Environment variables
     write_input_env("kime")?;
@@ -168,12 +293,12 @@ engine:
     // Autostart desktop entry
     let autostart_dir = dirs_config().join("autostart");
     std::fs::create_dir_all(&autostart_dir)?;
-    std::fs::write(
-        autostart_dir.join("kime.desktop"),
+    write_atomic(
+        &autostart_dir.join("kime.desktop"),
         "[Desktop Entry]\nName=Kime\nExec=kime\nType=Application\nX-GNOME-Autostart-enabled=true\n",
     )
     .context("write kime autostart")?;
-    println!("  Created kime autostart entry");
+    info!("  Created kime autostart entry");
 
     Ok(())
 }
@@ -210,7 +335,7 @@ fn write_input_env(module: &str) -> Result<()> {
             .args(["cp", tmp, "/etc/environment.d/input-method.conf"])
             .status();
         let _ = std::fs::remove_file(tmp);
-        println!("  Wrote /etc/environment.d/input-method.conf");
+        info!("  Wrote /etc/environment.d/input-method.conf");
     }
 
     // Also write to user profile for Xorg sessions
@@ -225,9 +350,9 @@ fn write_input_env(module: &str) -> Result<()> {
         if !existing.contains("GTK_IM_MODULE") {
             let mut full = existing;
             full.push_str(&profile_content);
-            std::fs::write(&path, full)
+            write_atomic(&path, full)
                 .with_context(|| format!("write {}", path.display()))?;
-            println!("  Updated ~/{file}");
+            info!("  Updated ~/{file}");
         }
     }
 
@@ -237,45 +362,46 @@ fn write_input_env(module: &str) -> Result<()> {
 // ── Services ───────────────────────────────────────────────────────────────
 
 fn step_enable_services(config: &BlunuxConfig) -> Result<()> {
-    println!("\n── Enabling services ──");
-
-    let mut services = vec!["NetworkManager"];
+    info!("\n── Enabling services ──");
 
-    if config.packages.desktop.kde {
-        services.push("sddm");
-    }
-    if config.packages.utility.bluetooth {
-        services.push("bluetooth");
-    }
-    if config.packages.virtualization.docker {
-        services.push("docker");
+    for unit in &config.install.services {
+        if !is_valid_unit_name(unit) {
+            warn!("  Warning: skipping invalid unit name {unit:?} in [install] services");
+        }
     }
+    let services = services_to_enable(config, hwdetect::root_on_ssd());
 
+    let mut failed = Vec::new();
     for svc in &services {
         let status = Command::new("sudo")
             .args(["systemctl", "enable", svc])
             .status();
         match status {
-            Ok(s) if s.success() => println!("  Enabled {svc}"),
-            _ => eprintln!("  Warning: could not enable {svc}"),
+            Ok(s) if s.success() => info!("  Enabled {svc}"),
+            _ => {
+                warn!("  Warning: could not enable {svc}");
+                failed.push(svc.as_str());
+            }
         }
     }
-
+    if !failed.is_empty() {
+        bail!("{} service(s) could not be enabled: {}", failed.len(), failed.join(" "));
+    }
     Ok(())
 }
 
-// ── Helpers ────────────────────────────────────────────────────────────────
-
-fn has_cmd(cmd: &str) -> bool {
-    Command::new("which")
-        .arg(cmd)
-        .stdout(std::process::Stdio::null())
-        .stderr(std::process::Stdio::null())
-        .status()
-        .map(|s| s.success())
-        .unwrap_or(false)
+/// Units for `systemctl enable`: [`resolve_services`] plus `fstrim.timer`
+/// (periodic TRIM) when the machine has an SSD.
+fn services_to_enable(config: &BlunuxConfig, ssd: bool) -> Vec<String> {
+    let mut services = resolve_services(config);
+    if ssd && !services.iter().any(|s| s == "fstrim.timer") {
+        services.push("fstrim.timer".into());
+    }
+    services
 }
 
+// ── Helpers ────────────────────────────────────────────────────────────────
+
 fn sudo_pacman(pkgs: &[&str]) -> Result<()> {
     let status = Command::new("sudo")
         .args(["pacman", "-S", "--noconfirm", "--needed"])
@@ -325,3 +451,43 @@ fn dirs_config() -> PathBuf {
         .map(PathBuf::from)
         .unwrap_or_else(|_| home_dir().join(".config"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stage_exit_codes_are_distinct() {
+        let stages = [
+            Stage::Config,
+            Stage::Preflight,
+            Stage::Yay,
+            Stage::Packages,
+            Stage::InputMethod,
+            Stage::Services,
+        ];
+        let mut codes: Vec<u8> = stages.iter().map(|s| s.exit_code()).collect();
+        assert!(codes.iter().all(|&c| c >= 10), "clear of success and usage errors");
+        codes.sort();
+        codes.dedup();
+        assert_eq!(codes.len(), stages.len());
+
+        let failure = Err::<(), _>(anyhow::anyhow!("yay exited 1"))
+            .at(Stage::Yay)
+            .unwrap_err();
+        assert_eq!(failure.stage.exit_code(), 12);
+        assert_eq!(failure.error.to_string(), "yay exited 1");
+    }
+
+    #[test]
+    fn test_services_to_enable_includes_configured_units() {
+        let mut config = BlunuxConfig::default();
+        config.install.services = vec!["sshd".into(), "cups".into(), "fstrim.timer".into()];
+
+        let services = services_to_enable(&config, true);
+        assert_eq!(services, ["NetworkManager", "sddm", "sshd", "cups", "fstrim.timer"]);
+
+        config.install.services.clear();
+        assert_eq!(services_to_enable(&config, false), ["NetworkManager", "sddm"]);
+    }
+}
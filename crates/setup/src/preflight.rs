@@ -0,0 +1,190 @@
+//! Network and mirror checks run before `git clone` and `yay`, which fail
+//! with far less helpful errors. `reachable(host, port)` is the network probe.
+
+use anyhow::{bail, Result};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+/// Host every install needs: yay and the AUR packages come from here.
+const AUR_HOST: &str = "aur.archlinux.org";
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Check that the network is up by reaching the AUR.
+pub fn check_network(reachable: impl Fn(&str, u16) -> bool) -> Result<()> {
+    if !reachable(AUR_HOST, 443) {
+        bail!(
+            "No network connection ({AUR_HOST} is unreachable).\n  \
+             Connect first, e.g. with `nmtui` or `iwctl`, then run blunux-setup again."
+        );
+    }
+    Ok(())
+}
+
+/// Check that the first mirror in `mirrorlist` (the text of
+/// /etc/pacman.d/mirrorlist) answers.
+pub fn check_mirror(mirrorlist: &str, reachable: impl Fn(&str, u16) -> bool) -> Result<()> {
+    let Some((host, port)) = first_mirror(mirrorlist) else {
+        bail!(
+            "No active `Server =` line in /etc/pacman.d/mirrorlist.\n  \
+             Uncomment a mirror or rerun with --rank-mirrors."
+        );
+    };
+    if !reachable(&host, port) {
+        bail!(
+            "Pacman mirror {host} is not responding.\n  \
+             Rerun with --rank-mirrors to pick working mirrors, or edit /etc/pacman.d/mirrorlist."
+        );
+    }
+    Ok(())
+}
+
+/// Outcome of the who-is-running-this check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserCheck {
+    /// Regular user with sudo: the expected setup.
+    Ok,
+    /// Running as root: pacman works, but makepkg (and so yay's AUR builds)
+    /// refuses to run as root.
+    Root,
+    /// Regular user without sudo: every pacman/systemctl step will fail.
+    NoSudo,
+}
+
+impl UserCheck {
+    pub fn message(self) -> Option<&'static str> {
+        match self {
+            Self::Ok => None,
+            Self::Root => Some(
+                "Running as root: makepkg refuses to build AUR packages as root.\n  \
+                 Create a build user (useradd -m -G wheel builder), give wheel sudo rights, \
+                 and run blunux-setup as that user.",
+            ),
+            Self::NoSudo => Some(
+                "sudo not found: blunux-setup needs it for pacman and systemctl.\n  \
+                 Install it as root (pacman -S sudo) and add your user to the wheel group.",
+            ),
+        }
+    }
+}
+
+/// Decide from the effective uid and whether `sudo` is on PATH.
+pub fn check_user(euid: u32, has_sudo: bool) -> UserCheck {
+    if euid == 0 {
+        UserCheck::Root
+    } else if !has_sudo {
+        UserCheck::NoSudo
+    } else {
+        UserCheck::Ok
+    }
+}
+
+/// Effective uid from `/proc/self/status` (`Uid:` real, effective, ...).
+pub fn effective_uid() -> Option<u32> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("Uid:"))?
+        .split_whitespace()
+        .nth(1)?
+        .parse()
+        .ok()
+}
+
+/// Host and port of the first uncommented `Server = ...` line.
+pub fn first_mirror(mirrorlist: &str) -> Option<(String, u16)> {
+    let url = mirrorlist.lines().find_map(|line| {
+        let (key, value) = line.trim().split_once('=')?;
+        (key.trim() == "Server").then(|| value.trim())
+    })?;
+
+    let (default_port, rest) = if let Some(rest) = url.strip_prefix("https://") {
+        (443, rest)
+    } else if let Some(rest) = url.strip_prefix("http://") {
+        (80, rest)
+    } else {
+        return None;
+    };
+    let authority = rest.split('/').next()?;
+    match authority.rsplit_once(':') {
+        Some((host, port)) => Some((host.to_string(), port.parse().ok()?)),
+        None if !authority.is_empty() => Some((authority.to_string(), default_port)),
+        None => None,
+    }
+}
+
+/// Real probe: a TCP connect to `host:port` within a few seconds.
+pub fn tcp_reachable(host: &str, port: u16) -> bool {
+    let Ok(addrs) = (host, port).to_socket_addrs() else {
+        return false;
+    };
+    addrs
+        .into_iter()
+        .any(|addr| TcpStream::connect_timeout(&addr, PROBE_TIMEOUT).is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIRRORLIST: &str = "\
+## Sweden
+#Server = https://ftp.acc.umu.se/mirror/archlinux/$repo/os/$arch
+Server = https://mirror.example.se/archlinux/$repo/os/$arch
+Server = http://fallback.example.org:8080/arch/$repo/os/$arch
+";
+
+    #[test]
+    fn test_first_mirror_skips_comments() {
+        assert_eq!(
+            first_mirror(MIRRORLIST),
+            Some(("mirror.example.se".to_string(), 443))
+        );
+        assert_eq!(
+            first_mirror("Server = http://fallback.example.org:8080/arch/$repo/os/$arch"),
+            Some(("fallback.example.org".to_string(), 8080))
+        );
+        assert_eq!(first_mirror("#Server = https://x/$repo\n"), None);
+    }
+
+    #[test]
+    fn test_check_offline() {
+        let err = check_network(|_, _| false).unwrap_err().to_string();
+        assert!(err.contains("No network connection"), "{err}");
+    }
+
+    #[test]
+    fn test_check_mirror_down() {
+        let reachable = |host: &str, _| host == AUR_HOST;
+        assert!(check_network(reachable).is_ok());
+        let err = check_mirror(MIRRORLIST, reachable).unwrap_err().to_string();
+        assert!(err.contains("mirror.example.se is not responding"), "{err}");
+        assert!(err.contains("--rank-mirrors"));
+    }
+
+    #[test]
+    fn test_check_no_mirror_configured() {
+        let err = check_mirror("", |_, _| true).unwrap_err().to_string();
+        assert!(err.contains("No active `Server =` line"), "{err}");
+    }
+
+    #[test]
+    fn test_check_user() {
+        assert_eq!(check_user(1000, true), UserCheck::Ok);
+        assert_eq!(check_user(0, true), UserCheck::Root);
+        assert_eq!(check_user(0, false), UserCheck::Root);
+        assert_eq!(check_user(1000, false), UserCheck::NoSudo);
+        assert!(UserCheck::Ok.message().is_none());
+        assert!(UserCheck::Root.message().unwrap().contains("build user"));
+    }
+
+    #[test]
+    fn test_effective_uid_readable() {
+        assert!(effective_uid().is_some());
+    }
+
+    #[test]
+    fn test_check_online() {
+        assert!(check_network(|_, _| true).is_ok());
+        assert!(check_mirror(MIRRORLIST, |_, _| true).is_ok());
+    }
+}
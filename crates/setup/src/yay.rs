@@ -0,0 +1,205 @@
+//! Bootstrapping yay from the AUR: clone yay-bin, build it with makepkg and
+//! check the result runs. Commands go through [`Runner`] so the retry and
+//! cleanup logic can be tested without touching the system.
+
+use std::fmt;
+use std::path::Path;
+use std::process::Command;
+
+use blunux_config::warn;
+
+const YAY_BIN_REPO: &str = "https://aur.archlinux.org/yay-bin.git";
+/// Clone + build attempts before giving up.
+const ATTEMPTS: u32 = 2;
+
+/// Runs an external command, returning whether it exited successfully.
+/// A command that can't be spawned counts as a failure.
+pub trait Runner {
+    fn run(&self, program: &str, args: &[&str], cwd: Option<&Path>) -> bool;
+}
+
+/// Runs commands for real, inheriting stdio so the user sees progress.
+pub struct SystemRunner;
+
+impl Runner for SystemRunner {
+    fn run(&self, program: &str, args: &[&str], cwd: Option<&Path>) -> bool {
+        let mut cmd = Command::new(program);
+        cmd.args(args);
+        if let Some(dir) = cwd {
+            cmd.current_dir(dir);
+        }
+        cmd.status().map(|s| s.success()).unwrap_or(false)
+    }
+}
+
+/// Which step of the bootstrap failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum YayError {
+    /// `git clone` of yay-bin failed on every attempt (network or AUR down).
+    Clone,
+    /// `makepkg` failed on every attempt (missing base-devel, sudo, ...).
+    Build,
+    /// yay installed but `yay --version` doesn't run.
+    Verify,
+}
+
+impl fmt::Display for YayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Clone => write!(
+                f,
+                "could not clone {YAY_BIN_REPO} after {ATTEMPTS} attempts; check your network"
+            ),
+            Self::Build => write!(
+                f,
+                "makepkg failed to build yay-bin after {ATTEMPTS} attempts; check base-devel and sudo"
+            ),
+            Self::Verify => write!(f, "yay was installed but `yay --version` failed"),
+        }
+    }
+}
+
+impl std::error::Error for YayError {}
+
+impl YayError {
+    /// What went wrong in a single attempt, for the retry warnings. The
+    /// [`Display`](fmt::Display) form is for the final error and counts the
+    /// attempts.
+    fn attempt_failure(&self) -> &'static str {
+        match self {
+            Self::Clone => "git clone failed",
+            Self::Build => "makepkg failed",
+            Self::Verify => "`yay --version` failed",
+        }
+    }
+}
+
+/// Clone and build yay-bin in `build_dir`, retrying the whole sequence once.
+/// `build_dir` is removed before each attempt and after it, whatever the
+/// outcome, so a half-built tree never survives.
+pub fn bootstrap(runner: &impl Runner, build_dir: &Path) -> Result<(), YayError> {
+    let mut last_err = YayError::Clone;
+    for attempt in 1..=ATTEMPTS {
+        let _ = std::fs::remove_dir_all(build_dir);
+        let result = clone_and_build(runner, build_dir);
+        let _ = std::fs::remove_dir_all(build_dir);
+
+        match result {
+            Ok(()) => {
+                return if runner.run("yay", &["--version"], None) {
+                    Ok(())
+                } else {
+                    Err(YayError::Verify)
+                };
+            }
+            Err(e) => {
+                warn!("  yay attempt {attempt}/{ATTEMPTS}: {}", e.attempt_failure());
+                last_err = e;
+            }
+        }
+    }
+    Err(last_err)
+}
+
+fn clone_and_build(runner: &impl Runner, build_dir: &Path) -> Result<(), YayError> {
+    let dir = build_dir.to_string_lossy();
+    if !runner.run("git", &["clone", YAY_BIN_REPO, &dir], None) {
+        return Err(YayError::Clone);
+    }
+    if !runner.run("makepkg", &["-si", "--noconfirm"], Some(build_dir)) {
+        return Err(YayError::Build);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::path::PathBuf;
+
+    /// Answers each command from a script of results (`true` once the script
+    /// runs out) and records what was run. `git clone` creates the target
+    /// directory, like a real partial checkout would.
+    struct MockRunner {
+        results: RefCell<Vec<bool>>,
+        calls: RefCell<Vec<String>>,
+    }
+
+    impl MockRunner {
+        fn new(results: &[bool]) -> Self {
+            Self {
+                results: RefCell::new(results.iter().rev().copied().collect()),
+                calls: RefCell::new(Vec::new()),
+            }
+        }
+
+        fn calls(&self) -> Vec<String> {
+            self.calls.borrow().clone()
+        }
+    }
+
+    impl Runner for MockRunner {
+        fn run(&self, program: &str, args: &[&str], _cwd: Option<&Path>) -> bool {
+            self.calls.borrow_mut().push(program.to_string());
+            if program == "git" {
+                std::fs::create_dir_all(args[2]).unwrap();
+            }
+            self.results.borrow_mut().pop().unwrap_or(true)
+        }
+    }
+
+    /// A build dir that doesn't exist yet, inside a temp dir.
+    fn build_dir() -> (tempfile::TempDir, PathBuf) {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().join("yay");
+        (tmp, dir)
+    }
+
+    #[test]
+    fn test_bootstrap_success_verifies_yay() {
+        let (_tmp, dir) = build_dir();
+        let runner = MockRunner::new(&[true, true, true]);
+        assert_eq!(bootstrap(&runner, &dir), Ok(()));
+        assert_eq!(runner.calls(), ["git", "makepkg", "yay"]);
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn test_bootstrap_retries_failed_build() {
+        let (_tmp, dir) = build_dir();
+        let runner = MockRunner::new(&[true, false, true, true, true]);
+        assert_eq!(bootstrap(&runner, &dir), Ok(()));
+        assert_eq!(runner.calls(), ["git", "makepkg", "git", "makepkg", "yay"]);
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn test_bootstrap_clone_failure_cleans_up() {
+        let (_tmp, dir) = build_dir();
+        let runner = MockRunner::new(&[false, false]);
+        assert_eq!(bootstrap(&runner, &dir), Err(YayError::Clone));
+        assert_eq!(runner.calls(), ["git", "git"]);
+        assert!(!dir.exists(), "partial clone must be removed");
+    }
+
+    #[test]
+    fn test_bootstrap_build_failure_reported_as_build() {
+        let (_tmp, dir) = build_dir();
+        let runner = MockRunner::new(&[true, false, true, false]);
+        let err = bootstrap(&runner, &dir).unwrap_err();
+        assert_eq!(err, YayError::Build);
+        assert!(err.to_string().contains("makepkg"));
+        // Only the final error counts the attempts
+        assert!(err.to_string().contains("after 2 attempts"));
+        assert!(!err.attempt_failure().contains("attempts"));
+        assert!(!dir.exists(), "half-built tree must be removed");
+    }
+
+    #[test]
+    fn test_bootstrap_verify_failure() {
+        let (_tmp, dir) = build_dir();
+        let runner = MockRunner::new(&[true, true, false]);
+        assert_eq!(bootstrap(&runner, &dir), Err(YayError::Verify));
+    }
+}
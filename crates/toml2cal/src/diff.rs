@@ -0,0 +1,158 @@
+//! Line-based unified diffs, used by `generate --diff` to show what a
+//! regeneration would change in existing Calamares files.
+
+/// Lines of unchanged context around each change.
+const CONTEXT: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// One line of the edit script, with its position in the old and new text
+/// (0-based, pointing at the line or where it would go).
+struct Edit<'a> {
+    op: Op,
+    line: &'a str,
+    old_pos: usize,
+    new_pos: usize,
+}
+
+/// Unified diff from `old` to `new` with `--- old_label` / `+++ new_label`
+/// headers. Empty when the texts are identical.
+pub fn unified_diff(old: &str, new: &str, old_label: &str, new_label: &str) -> String {
+    let edits = edit_script(old, new);
+    let changed: Vec<usize> = edits
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| e.op != Op::Equal)
+        .map(|(i, _)| i)
+        .collect();
+    if changed.is_empty() {
+        return String::new();
+    }
+
+    // Group changes whose context windows touch into one hunk
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for &i in &changed {
+        let start = i.saturating_sub(CONTEXT);
+        let end = (i + CONTEXT + 1).min(edits.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    let mut out = format!("--- {old_label}\n+++ {new_label}\n");
+    for (start, end) in hunks {
+        let hunk = &edits[start..end];
+        let old_len = hunk.iter().filter(|e| e.op != Op::Insert).count();
+        let new_len = hunk.iter().filter(|e| e.op != Op::Delete).count();
+        out.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            range(hunk[0].old_pos, old_len),
+            range(hunk[0].new_pos, new_len)
+        ));
+        for edit in hunk {
+            let sign = match edit.op {
+                Op::Equal => ' ',
+                Op::Delete => '-',
+                Op::Insert => '+',
+            };
+            out.push(sign);
+            out.push_str(edit.line);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// `start,len` in unified-diff form: 1-based, except that an empty range
+/// names the line before it.
+fn range(pos: usize, len: usize) -> String {
+    let start = if len == 0 { pos } else { pos + 1 };
+    format!("{start},{len}")
+}
+
+/// Shortest edit script from `old` to `new` via the longest common
+/// subsequence of their lines. Generated configs are small, so the
+/// quadratic table is fine.
+fn edit_script<'a>(old: &'a str, new: &'a str) -> Vec<Edit<'a>> {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+
+    // lcs[i][j] = LCS length of a[i..] and b[j..]
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut edits = Vec::with_capacity(a.len() + b.len());
+    while i < a.len() || j < b.len() {
+        let (op, line) = if i < a.len() && j < b.len() && a[i] == b[j] {
+            (Op::Equal, a[i])
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            // Prefer deletions so removed lines print before their replacements
+            (Op::Delete, a[i])
+        } else {
+            (Op::Insert, b[j])
+        };
+        edits.push(Edit {
+            op,
+            line,
+            old_pos: i,
+            new_pos: j,
+        });
+        if op != Op::Insert {
+            i += 1;
+        }
+        if op != Op::Delete {
+            j += 1;
+        }
+    }
+    edits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate;
+    use blunux_config::BlunuxConfig;
+
+    #[test]
+    fn test_identical_is_empty() {
+        assert_eq!(unified_diff("a\nb\n", "a\nb\n", "a", "b"), "");
+    }
+
+    #[test]
+    fn test_locale_conf_change_diffs() {
+        let mut config = BlunuxConfig::default();
+        config.locale.timezone = "Asia/Seoul".into();
+        let old = generate::locale_conf(&config);
+        config.locale.timezone = "Europe/Stockholm".into();
+        let new = generate::locale_conf(&config);
+
+        let diff = unified_diff(&old, &new, "a/locale.conf", "b/locale.conf");
+        assert!(diff.starts_with("--- a/locale.conf\n+++ b/locale.conf\n@@ -"), "{diff}");
+        assert!(
+            diff.contains("\n-region: \"Asia\"\n-zone: \"Seoul\"\n+region: \"Europe\"\n+zone: \"Stockholm\"\n"),
+            "{diff}"
+        );
+        assert!(!diff.contains("localeGenPath"), "context is limited: {diff}");
+    }
+
+    #[test]
+    fn test_new_file_diff() {
+        let diff = unified_diff("", "one\ntwo\n", "/dev/null", "b/new.conf");
+        assert_eq!(diff, "--- /dev/null\n+++ b/new.conf\n@@ -0,0 +1,2 @@\n+one\n+two\n");
+    }
+}
@@ -1,6 +1,4 @@
-use blunux_config::BlunuxConfig;
-
-use crate::packages;
+use blunux_config::{is_valid_username, resolve_packages, resolve_services, BlunuxConfig};
 
 // ---------------------------------------------------------------------------
 // settings.conf — Calamares module pipeline
@@ -28,6 +26,7 @@ pub fn settings_conf(config: &BlunuxConfig) -> String {
         "networkcfg",
         "hwclock",
         "services-systemd",
+        "packages",
         "shellprocess",
     ]);
 
@@ -278,6 +277,36 @@ unpack:
     .to_string()
 }
 
+// ---------------------------------------------------------------------------
+// packages.conf — hardware packages
+// ---------------------------------------------------------------------------
+
+/// `hardware_pkgs` are the packages picked for the installing machine (CPU
+/// microcode, audio stack). The packages module runs before shellprocess, so
+/// microcode is in place when the initramfs is regenerated.
+pub fn packages_conf(hardware_pkgs: &[String]) -> String {
+    let operations = if hardware_pkgs.is_empty() {
+        " []".to_string()
+    } else {
+        let list = hardware_pkgs
+            .iter()
+            .map(|p| format!("      - {p}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        format!("\n  - install:\n{list}")
+    };
+    format!(
+        r#"# Auto-generated by blunux-toml2cal
+
+backend: pacman
+update_db: true
+skip_if_no_internet: false
+
+operations:{operations}
+"#
+    )
+}
+
 // ---------------------------------------------------------------------------
 // shellprocess.conf — post-install commands
 // ---------------------------------------------------------------------------
@@ -315,8 +344,11 @@ pub fn shellprocess_conf(config: &BlunuxConfig) -> String {
         );
     }
 
+    // Extra user accounts; Calamares' users module only creates the primary one
+    scripts.extend(extra_user_commands(config));
+
     // Install user-selected packages
-    let pkgs = packages::resolve(config);
+    let pkgs = resolve_packages(config);
     if !pkgs.is_empty() {
         scripts.push(format!(
             r#"  - command: "chroot $ROOT pacman -S --noconfirm --needed {}""#,
@@ -351,6 +383,15 @@ pub fn shellprocess_conf(config: &BlunuxConfig) -> String {
         }
     }
 
+    // Periodic TRIM when the target disk is an SSD. Only the installer
+    // knows which disk that is, so it's checked at install time
+    if !resolve_services(config).iter().any(|u| u == "fstrim.timer") {
+        scripts.push(
+            r#"  - command: "lsblk -ndo ROTA $(findmnt -nvo SOURCE $ROOT) | grep -qw 0 && chroot $ROOT systemctl enable fstrim.timer || true""#
+                .to_string(),
+        );
+    }
+
     // Copy live session theme to installed system
     scripts
         .push(r#"  - command: "cp -r /home/liveuser/.config/plasma* $ROOT/etc/skel/.config/ 2>/dev/null || true""#.to_string());
@@ -379,36 +420,114 @@ script:
     )
 }
 
-// ---------------------------------------------------------------------------
-// services-systemd.conf
-// ---------------------------------------------------------------------------
+/// Groups every account gets, as in users.conf `defaultGroups`.
+const DEFAULT_GROUPS: &str = "video,audio,storage,optical,network,lp,scanner";
 
-pub fn services_systemd_conf(config: &BlunuxConfig) -> String {
-    let mut services = vec!["NetworkManager"];
+/// shellprocess steps creating `[[install.extra_users]]`. They run after the
+/// users module, so the default groups and the wheel sudoers rule exist.
+fn extra_user_commands(config: &BlunuxConfig) -> Vec<String> {
+    let mut scripts = Vec::new();
+    // Only one account can log in automatically, and the primary user comes first
+    let mut autologin_taken = config.install.autologin;
+    for user in &config.install.extra_users {
+        // Names end up in shell commands; cmd_generate rejects bad ones first
+        if !is_valid_username(&user.username) {
+            continue;
+        }
+        let name = &user.username;
+        let autologin = user.autologin && !autologin_taken;
+        autologin_taken |= autologin;
 
-    if config.packages.desktop.kde {
-        services.push("sddm");
+        let mut groups = DEFAULT_GROUPS.to_string();
+        if user.sudo {
+            groups.push_str(",wheel");
+        }
+        if autologin {
+            scripts.push(r#"  - command: "chroot $ROOT groupadd -f autologin""#.to_string());
+            groups.push_str(",autologin");
+        }
+        scripts.push(format!(
+            r#"  - command: "chroot $ROOT useradd -m -s /bin/bash -G {groups} {name}""#
+        ));
+        scripts.push(format!(
+            r#"  - command: "printf '%s\\n' {} | chroot $ROOT chpasswd""#,
+            yaml_escape(&shell_quote(&format!("{name}:{}", user.password)))
+        ));
+        if autologin {
+            scripts.extend(autologin_command(config, name));
+        }
     }
+    scripts
+}
 
-    if config.packages.utility.bluetooth {
-        services.push("bluetooth");
-    }
+/// Display manager drop-in logging `name` in at boot. The displaymanager
+/// module only does this for the primary user.
+fn autologin_command(config: &BlunuxConfig, name: &str) -> Option<String> {
+    let desktop = config.packages.desktop.session().map(|(_, file)| file)?;
+    let (dir, file, contents) = match config.packages.desktop.display_manager()? {
+        "sddm" => (
+            "/etc/sddm.conf.d",
+            "autologin.conf",
+            format!("[Autologin]\\nUser={name}\\nSession={desktop}.desktop\\n"),
+        ),
+        "gdm" => (
+            "/etc/gdm",
+            "custom.conf",
+            format!("[daemon]\\nAutomaticLoginEnable=True\\nAutomaticLogin={name}\\n"),
+        ),
+        "lightdm" => (
+            "/etc/lightdm/lightdm.conf.d",
+            "autologin.conf",
+            format!("[Seat:*]\\nautologin-user={name}\\nautologin-session={desktop}\\n"),
+        ),
+        _ => return None,
+    };
+    Some(format!(
+        r#"  - command: "mkdir -p $ROOT{dir} && printf '{contents}' > $ROOT{dir}/{file}""#
+    ))
+}
 
-    if config.packages.virtualization.docker {
-        services.push("docker");
-    }
+/// Wrap `s` in single quotes for the shell.
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
 
-    let svc_list = services
-        .iter()
-        .map(|s| format!("  - name: {}\n    mandatory: false", s))
+/// Escape `s` for a double-quoted YAML scalar.
+fn yaml_escape(s: &str) -> String {
+    s.replace('\\', r"\\").replace('"', r#"\""#)
+}
+
+// ---------------------------------------------------------------------------
+// services-systemd.conf
+// ---------------------------------------------------------------------------
+
+/// systemd unit types, as name suffixes.
+const UNIT_TYPES: [&str; 11] = [
+    ".service", ".socket", ".device", ".mount", ".automount", ".swap", ".target", ".path",
+    ".timer", ".slice", ".scope",
+];
+
+pub fn services_systemd_conf(config: &BlunuxConfig) -> String {
+    // `units` takes full unit names, so sockets, timers etc. keep their type;
+    // bare names are services, as with `systemctl enable`
+    let unit_list = resolve_services(config)
+        .into_iter()
+        .map(|unit| {
+            let name = if UNIT_TYPES.iter().any(|t| unit.ends_with(t)) {
+                unit
+            } else {
+                format!("{unit}.service")
+            };
+            format!("  - name: {name}\n    action: enable\n    mandatory: false")
+        })
         .collect::<Vec<_>>()
         .join("\n");
 
     format!(
         r#"# Auto-generated by blunux-toml2cal
 
-services:
-{svc_list}
+units:
+{unit_list}
 "#
     )
 }
@@ -418,18 +537,13 @@ services:
 // ---------------------------------------------------------------------------
 
 pub fn displaymanager_conf(config: &BlunuxConfig) -> String {
-    let dm = if config.packages.desktop.kde {
-        "sddm"
-    } else {
-        "lightdm"
-    };
+    let dm = config.packages.desktop.display_manager().unwrap_or("lightdm");
 
-    let autologin = if config.install.autologin {
-        format!(
-            "defaultDesktopEnvironment:\n  executable: \"startplasma-wayland\"\n  desktopFile: \"plasma\"\n"
-        )
-    } else {
-        String::new()
+    let autologin = match config.packages.desktop.session() {
+        Some((executable, desktop_file)) if config.install.autologin => format!(
+            "defaultDesktopEnvironment:\n  executable: \"{executable}\"\n  desktopFile: \"{desktop_file}\"\n"
+        ),
+        _ => String::new(),
     };
 
     format!(
@@ -440,3 +554,244 @@ displaymanagers:
 {autologin}"#
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blunux_config::ExtraUser;
+
+    const SAMPLE: &str = r#"
+[blunux]
+version = "2.0"
+name = "test"
+
+[locale]
+language = ["ko_KR"]
+timezone = "Europe/Stockholm"
+keyboard = ["kr", "us"]
+
+[input_method]
+enabled = false
+engine = "kime"
+
+[kernel]
+type = "linux"
+
+[install]
+bootloader = "grub"
+hostname = "nux"
+username = "blu"
+root_password = "1234"
+user_password = "1234"
+encryption = false
+autologin = true
+
+[disk]
+swap = "small"
+
+[packages.desktop]
+kde = true
+
+[packages.browser]
+firefox = false
+whale = false
+chrome = false
+mullvad = false
+
+[packages.office]
+libreoffice = false
+hoffice = false
+texlive = false
+
+[packages.development]
+vscode = false
+sublime = false
+rust = false
+julia = false
+nodejs = false
+github_cli = false
+
+[packages.multimedia]
+obs = false
+vlc = false
+freetv = false
+ytdlp = false
+freetube = false
+
+[packages.gaming]
+steam = false
+unciv = false
+snes9x = false
+
+[packages.virtualization]
+virtualbox = false
+docker = false
+
+[packages.communication]
+teams = false
+whatsapp = false
+onenote = false
+
+[packages.utility]
+conky = false
+vnc = false
+samba = false
+bluetooth = false
+"#;
+
+    fn sample_config() -> BlunuxConfig {
+        BlunuxConfig::from_toml_str(SAMPLE).unwrap()
+    }
+
+    #[test]
+    fn test_users_conf_single_user() {
+        let out = users_conf(&sample_config());
+        assert!(out.contains("name: \"blu\""));
+    }
+
+    fn extra_user(username: &str, password: &str, sudo: bool, autologin: bool) -> ExtraUser {
+        ExtraUser {
+            username: username.into(),
+            password: password.into(),
+            sudo,
+            autologin,
+        }
+    }
+
+    /// The shell commands in shellprocess.conf, as Calamares reads them.
+    fn shell_commands(config: &BlunuxConfig) -> Vec<String> {
+        let parsed: serde_yaml::Value = serde_yaml::from_str(&shellprocess_conf(config)).unwrap();
+        parsed["script"]
+            .as_sequence()
+            .unwrap()
+            .iter()
+            .map(|step| step["command"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_two_extra_users_are_created() {
+        let mut config = sample_config();
+        config.install.extra_users = vec![
+            extra_user("alice", "pw1", true, false),
+            extra_user("bob", "it's \"q\"", false, false),
+        ];
+        let commands = shell_commands(&config);
+        let groups = "video,audio,storage,optical,network,lp,scanner";
+        assert!(commands.contains(&format!("chroot $ROOT useradd -m -s /bin/bash -G {groups},wheel alice")));
+        assert!(commands.contains(&format!("chroot $ROOT useradd -m -s /bin/bash -G {groups} bob")));
+        assert!(commands.contains(&r"printf '%s\n' 'alice:pw1' | chroot $ROOT chpasswd".to_string()));
+        // Quotes in the password survive YAML and the shell
+        assert!(commands.contains(&r#"printf '%s\n' 'bob:it'\''s "q"' | chroot $ROOT chpasswd"#.to_string()));
+        // The primary user is still Calamares' own
+        assert!(users_conf(&config).contains("name: \"blu\""));
+        assert!(!commands.iter().any(|c| c.contains("autologin")), "primary user logs in");
+    }
+
+    #[test]
+    fn test_extra_user_autologin() {
+        let toml_str = SAMPLE.replace("autologin = true", "autologin = false");
+        let mut config = BlunuxConfig::from_toml_str(&toml_str).unwrap();
+        config.install.extra_users = vec![
+            extra_user("alice", "pw", false, true),
+            extra_user("bob", "pw", false, true),
+        ];
+        let commands = shell_commands(&config);
+        assert!(commands.iter().any(|c| c.ends_with("-G video,audio,storage,optical,network,lp,scanner,autologin alice")));
+        let dropin = commands.iter().find(|c| c.contains("sddm.conf.d")).unwrap();
+        assert!(dropin.contains("User=alice\nSession=plasma.desktop"), "{dropin}");
+        // Only the first one gets it
+        assert_eq!(commands.iter().filter(|c| c.contains("sddm.conf.d")).count(), 1);
+        assert!(users_conf(&config).contains("doAutologin: false"));
+    }
+
+    #[test]
+    fn test_packages_module_installs_microcode_before_initramfs() {
+        let hardware = ["amd-ucode".to_string(), "pipewire".to_string()];
+        let out = packages_conf(&hardware);
+        assert!(out.contains("operations:\n  - install:\n      - amd-ucode\n      - pipewire\n"));
+        let parsed: serde_yaml::Value = serde_yaml::from_str(&out).unwrap();
+        assert_eq!(parsed["backend"], "pacman");
+        assert!(!shellprocess_conf(&sample_config()).contains("ucode"));
+
+        // The packages module runs before shellprocess regenerates the initramfs
+        let settings = settings_conf(&sample_config());
+        assert!(settings.find("- packages").unwrap() < settings.find("- shellprocess").unwrap());
+
+        let empty: serde_yaml::Value = serde_yaml::from_str(&packages_conf(&[])).unwrap();
+        assert_eq!(empty["operations"], serde_yaml::Value::Sequence(vec![]));
+    }
+
+    #[test]
+    fn test_fstrim_depends_on_install_target_disk() {
+        let out = shellprocess_conf(&sample_config());
+        let trim = out.lines().find(|l| l.contains("fstrim")).unwrap();
+        assert!(trim.contains("findmnt -nvo SOURCE $ROOT"), "{trim}");
+        assert!(trim.contains("&& chroot $ROOT systemctl enable fstrim.timer"), "{trim}");
+        assert!(!services_systemd_conf(&sample_config()).contains("fstrim"));
+        let parsed: serde_yaml::Value = serde_yaml::from_str(&out).unwrap();
+        assert!(parsed["script"].as_sequence().is_some());
+
+        // Already enabled unconditionally through `services`
+        let toml_str = SAMPLE.replace("autologin = true", "autologin = true\nservices = [\"fstrim.timer\"]");
+        let config = BlunuxConfig::from_toml_str(&toml_str).unwrap();
+        assert!(!shellprocess_conf(&config).contains("fstrim"));
+    }
+
+    #[test]
+    fn test_services_include_configured_units() {
+        let toml_str = SAMPLE.replace(
+            "autologin = true",
+            "autologin = true\nservices = [\"sshd.socket\", \"cups\", \"fstrim.timer\"]",
+        );
+        let config = BlunuxConfig::from_toml_str(&toml_str).unwrap();
+        let out = services_systemd_conf(&config);
+        let parsed: serde_yaml::Value = serde_yaml::from_str(&out).unwrap();
+        let names: Vec<&str> = parsed["units"]
+            .as_sequence()
+            .unwrap()
+            .iter()
+            .map(|unit| unit["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "NetworkManager.service",
+                "sddm.service",
+                "sshd.socket",
+                "cups.service",
+                "fstrim.timer"
+            ]
+        );
+        assert_eq!(parsed["units"][3]["action"], "enable");
+    }
+
+    #[test]
+    fn test_display_manager_per_desktop() {
+        let cases = [
+            ("kde", "sddm", "startplasma-wayland"),
+            ("gnome", "gdm", "gnome-session"),
+            ("xfce", "lightdm", "startxfce4"),
+            ("hyprland", "sddm", "Hyprland"),
+        ];
+        for (desktop, dm, executable) in cases {
+            let toml_str = SAMPLE.replace("kde = true", &format!("{desktop} = true"));
+            let config = BlunuxConfig::from_toml_str(&toml_str).unwrap();
+
+            let dm_conf = displaymanager_conf(&config);
+            assert!(dm_conf.contains(&format!("  - {dm}\n")), "{desktop}: {dm_conf}");
+            assert!(dm_conf.contains(&format!("executable: \"{executable}\"")));
+
+            let services = services_systemd_conf(&config);
+            assert!(services.contains(&format!("- name: {dm}.service\n")), "{desktop}: {services}");
+        }
+    }
+
+    #[test]
+    fn test_no_desktop_skips_display_manager_service() {
+        let config = BlunuxConfig::from_toml_str(&SAMPLE.replace("kde = true", "")).unwrap();
+        let services = services_systemd_conf(&config);
+        assert!(!services.contains("sddm") && !services.contains("lightdm"));
+        assert!(!displaymanager_conf(&config).contains("defaultDesktopEnvironment"));
+    }
+}
@@ -1,8 +1,12 @@
+mod diff;
 mod generate;
-mod packages;
 
 use anyhow::{Context, Result};
-use blunux_config::BlunuxConfig;
+use blunux_config::util::command_exists;
+use blunux_config::{
+    hwdetect, info, is_valid_unit_name, is_valid_username, log, resolve_audio_packages,
+    resolve_packages, write_atomic, BlunuxConfig,
+};
 use clap::{Parser, Subcommand};
 use std::path::{Path, PathBuf};
 
@@ -10,6 +14,14 @@ use std::path::{Path, PathBuf};
 #[command(name = "blunux-toml2cal")]
 #[command(about = "Translate config.toml into Calamares YAML configuration files")]
 struct Cli {
+    /// Expand ${VAR} in hostname, usernames, passwords and timezone from the environment
+    #[arg(long, global = true)]
+    expand_env: bool,
+
+    /// Only print warnings and errors
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -29,6 +41,40 @@ enum Commands {
         /// Path to write settings.conf
         #[arg(short, long, default_value = "/etc/calamares/settings.conf")]
         settings: PathBuf,
+
+        /// Print a unified diff of every file that changes
+        #[arg(long)]
+        diff: bool,
+
+        /// Like --diff, but write nothing
+        #[arg(long)]
+        diff_only: bool,
+    },
+
+    /// Write a starter config.toml with Blunux defaults
+    Init {
+        /// Where to write the new config
+        #[arg(short, long, default_value = "config.toml")]
+        out: PathBuf,
+
+        /// Hostname for the installed system
+        #[arg(long, default_value = "nux")]
+        hostname: String,
+
+        /// Primary user account name
+        #[arg(long, default_value = "blu")]
+        username: String,
+
+        /// Overwrite the output file if it already exists
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Check config.toml for errors and unknown (e.g. misspelled) keys
+    Validate {
+        /// Path to config.toml
+        #[arg(short, long)]
+        input: PathBuf,
     },
 
     /// Install packages listed in config.toml [packages.*] sections via pacman
@@ -48,40 +94,71 @@ enum Commands {
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    // stdout is reserved for `generate --diff` output
+    log::init(cli.quiet, log::Stream::Stderr);
 
     match cli.command {
         Commands::Generate {
             input,
             output_dir,
             settings,
-        } => cmd_generate(&input, &output_dir, &settings),
-        Commands::ApplyPackages { input } => cmd_apply_packages(&input),
-        Commands::ApplyInputMethod { input } => cmd_apply_input_method(&input),
+            diff,
+            diff_only,
+        } => cmd_generate(
+            &input,
+            &output_dir,
+            &settings,
+            cli.expand_env,
+            diff || diff_only,
+            !diff_only,
+        ),
+        Commands::Init {
+            out,
+            hostname,
+            username,
+            force,
+        } => cmd_init(&out, &hostname, &username, force),
+        Commands::Validate { input } => cmd_validate(&input),
+        Commands::ApplyPackages { input } => cmd_apply_packages(&input, cli.expand_env),
+        Commands::ApplyInputMethod { input } => cmd_apply_input_method(&input, cli.expand_env),
     }
 }
 
-fn load_config(input: &Path) -> Result<BlunuxConfig> {
-    BlunuxConfig::load(input)
-        .map_err(|e| anyhow::anyhow!("Failed to load config from {}: {}", input.display(), e))
+fn load_config(input: &Path, expand_env: bool) -> Result<BlunuxConfig> {
+    let loaded = if expand_env {
+        BlunuxConfig::load_expanded(input)
+    } else {
+        BlunuxConfig::load(input)
+    };
+    loaded.map_err(|e| anyhow::anyhow!("Failed to load config from {}: {}", input.display(), e))
 }
 
-fn cmd_generate(input: &Path, output_dir: &Path, settings_path: &Path) -> Result<()> {
-    let config = load_config(input)?;
-
-    std::fs::create_dir_all(output_dir)
-        .with_context(|| format!("Failed to create output dir {}", output_dir.display()))?;
-
-    if let Some(parent) = settings_path.parent() {
-        std::fs::create_dir_all(parent)?;
+fn cmd_generate(
+    input: &Path,
+    output_dir: &Path,
+    settings_path: &Path,
+    expand_env: bool,
+    show_diff: bool,
+    write: bool,
+) -> Result<()> {
+    let config = load_config(input, expand_env)?;
+    check_extra_users(&config).with_context(|| format!("{} is invalid", input.display()))?;
+
+    // Fail before writing anything rather than leave a half-written set
+    if write {
+        check_writable(output_dir, settings_path)?;
     }
 
-    // Generate settings.conf (module pipeline)
-    let settings_yaml = generate::settings_conf(&config);
-    std::fs::write(settings_path, &settings_yaml)
-        .with_context(|| format!("Failed to write {}", settings_path.display()))?;
-    eprintln!("Wrote {}", settings_path.display());
+    // Hardware of the machine we're installing on
+    let mut hardware_pkgs: Vec<String> = hwdetect::cpu_microcode_package(hwdetect::detect_cpu_vendor())
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+    hardware_pkgs.extend(resolve_audio_packages(&config, hwdetect::detect_audio()));
 
-    // Generate per-module configs
+    // settings.conf (module pipeline) first, then the per-module configs
+    let mut outputs: Vec<(PathBuf, String)> =
+        vec![(settings_path.to_path_buf(), generate::settings_conf(&config))];
     let modules: Vec<(&str, String)> = vec![
         ("locale.conf", generate::locale_conf(&config)),
         ("keyboard.conf", generate::keyboard_conf(&config)),
@@ -89,45 +166,144 @@ fn cmd_generate(input: &Path, output_dir: &Path, settings_path: &Path) -> Result
         ("users.conf", generate::users_conf(&config)),
         ("bootloader.conf", generate::bootloader_conf(&config)),
         ("unpackfs.conf", generate::unpackfs_conf()),
+        ("packages.conf", generate::packages_conf(&hardware_pkgs)),
         ("shellprocess.conf", generate::shellprocess_conf(&config)),
-        (
-            "services-systemd.conf",
-            generate::services_systemd_conf(&config),
-        ),
+        ("services-systemd.conf", generate::services_systemd_conf(&config)),
         (
             "displaymanager.conf",
             generate::displaymanager_conf(&config),
         ),
     ];
+    outputs.extend(
+        modules
+            .into_iter()
+            .map(|(filename, content)| (output_dir.join(filename), content)),
+    );
 
-    for (filename, content) in &modules {
-        let path = output_dir.join(filename);
-        std::fs::write(&path, content)
-            .with_context(|| format!("Failed to write {}", path.display()))?;
-        eprintln!("Wrote {}", path.display());
+    let mut changed = 0;
+    for (path, content) in &outputs {
+        if show_diff {
+            let existing = std::fs::read_to_string(path).ok();
+            let old_label = match existing {
+                Some(_) => path.display().to_string(),
+                None => "/dev/null".to_string(),
+            };
+            let patch = diff::unified_diff(
+                existing.as_deref().unwrap_or(""),
+                content,
+                &old_label,
+                &path.display().to_string(),
+            );
+            if !patch.is_empty() {
+                changed += 1;
+                print!("{patch}");
+            }
+        }
+        if write {
+            write_atomic(path, content)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            info!("Wrote {}", path.display());
+        }
     }
 
-    eprintln!(
-        "Generated {} config files from {}",
-        modules.len() + 1,
-        input.display()
-    );
+    if write {
+        info!(
+            "Generated {} config files from {}",
+            outputs.len(),
+            input.display()
+        );
+    } else {
+        info!(
+            "{changed} of {} config files would change (nothing written)",
+            outputs.len()
+        );
+    }
+    Ok(())
+}
+
+/// Make sure `output_dir` and the directory of `settings_path` exist and are
+/// writable (and `settings_path` itself, if it exists).
+fn check_writable(output_dir: &Path, settings_path: &Path) -> Result<()> {
+    let settings_dir = match settings_path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    for dir in [output_dir, settings_dir] {
+        let probe = dir.join(".blunux-toml2cal-write-test");
+        std::fs::create_dir_all(dir)
+            .and_then(|()| std::fs::write(&probe, ""))
+            .map_err(|e| not_writable(dir, e))?;
+        let _ = std::fs::remove_file(&probe);
+    }
+    if settings_path.exists() {
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(settings_path)
+            .map_err(|e| not_writable(settings_path, e))?;
+    }
+    Ok(())
+}
+
+fn not_writable(path: &Path, err: std::io::Error) -> anyhow::Error {
+    anyhow::anyhow!(
+        "{} is not writable ({err}). Run with sudo, or pass --output-dir/--settings \
+         pointing somewhere writable.",
+        path.display()
+    )
+}
+
+fn cmd_init(out: &Path, hostname: &str, username: &str, force: bool) -> Result<()> {
+    if out.exists() && !force {
+        anyhow::bail!("{} already exists (use --force to overwrite)", out.display());
+    }
+    BlunuxConfig::minimal(hostname, username)
+        .save(out)
+        .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", out.display(), e))?;
+    info!("Wrote {} — change the passwords before installing", out.display());
+    Ok(())
+}
+
+fn cmd_validate(input: &Path) -> Result<()> {
+    let config = BlunuxConfig::load_strict(input)
+        .map_err(|e| anyhow::anyhow!("{} is invalid: {}", input.display(), e))?;
+    if let Some(unit) = config.install.services.iter().find(|u| !is_valid_unit_name(u)) {
+        anyhow::bail!("{} is invalid: bad unit name {unit:?} in [install] services", input.display());
+    }
+    check_extra_users(&config).with_context(|| format!("{} is invalid", input.display()))?;
+    info!("{} is valid", input.display());
+    Ok(())
+}
+
+/// `[[install.extra_users]]` names must be valid and distinct from every
+/// other account.
+fn check_extra_users(config: &BlunuxConfig) -> Result<()> {
+    let mut seen = vec![config.install.username.as_str()];
+    for user in &config.install.extra_users {
+        if !is_valid_username(&user.username) {
+            anyhow::bail!("bad username {:?} in [[install.extra_users]]", user.username);
+        }
+        if seen.contains(&user.username.as_str()) {
+            anyhow::bail!("user {:?} is defined twice", user.username);
+        }
+        seen.push(&user.username);
+    }
     Ok(())
 }
 
-fn cmd_apply_packages(input: &Path) -> Result<()> {
-    let config = load_config(input)?;
+fn cmd_apply_packages(input: &Path, expand_env: bool) -> Result<()> {
+    let config = load_config(input, expand_env)?;
 
-    let pkgs = packages::resolve(&config);
+    let mut pkgs = resolve_packages(&config);
+    pkgs.extend(resolve_audio_packages(&config, hwdetect::detect_audio()));
     if pkgs.is_empty() {
-        eprintln!("No additional packages to install.");
+        info!("No additional packages to install.");
         return Ok(());
     }
 
-    eprintln!("Installing {} packages: {}", pkgs.len(), pkgs.join(" "));
+    info!("Installing {} packages: {}", pkgs.len(), pkgs.join(" "));
 
     // Use yay if available (handles AUR), fall back to pacman
-    let pkg_mgr = if has_cmd("yay") { "yay" } else { "pacman" };
+    let pkg_mgr = if command_exists("yay") { "yay" } else { "pacman" };
     let status = std::process::Command::new(pkg_mgr)
         .args(["-S", "--noconfirm", "--needed"])
         .args(&pkgs)
@@ -140,11 +316,11 @@ fn cmd_apply_packages(input: &Path) -> Result<()> {
     Ok(())
 }
 
-fn cmd_apply_input_method(input: &Path) -> Result<()> {
-    let config = load_config(input)?;
+fn cmd_apply_input_method(input: &Path, expand_env: bool) -> Result<()> {
+    let config = load_config(input, expand_env)?;
 
     if !config.input_method.enabled {
-        eprintln!("Input method disabled in config, skipping.");
+        info!("Input method disabled in config, skipping.");
         return Ok(());
     }
 
@@ -157,9 +333,9 @@ fn cmd_apply_input_method(input: &Path) -> Result<()> {
         }
     };
 
-    eprintln!("Installing input method ({}): {}", config.input_method.engine, im_pkgs.join(" "));
+    info!("Installing input method ({}): {}", config.input_method.engine, im_pkgs.join(" "));
 
-    let pkg_mgr = if has_cmd("yay") { "yay" } else { "pacman" };
+    let pkg_mgr = if command_exists("yay") { "yay" } else { "pacman" };
     let status = std::process::Command::new(pkg_mgr)
         .args(["-S", "--noconfirm", "--needed"])
         .args(&im_pkgs)
@@ -179,20 +355,84 @@ fn cmd_apply_input_method(input: &Path) -> Result<()> {
     };
 
     if !env_content.is_empty() {
-        std::fs::write("/etc/environment.d/input-method.conf", env_content)
+        write_atomic(Path::new("/etc/environment.d/input-method.conf"), env_content)
             .context("Failed to write input method environment config")?;
-        eprintln!("Wrote /etc/environment.d/input-method.conf");
+        info!("Wrote /etc/environment.d/input-method.conf");
     }
 
     Ok(())
 }
 
-fn has_cmd(cmd: &str) -> bool {
-    std::process::Command::new("which")
-        .arg(cmd)
-        .stdout(std::process::Stdio::null())
-        .stderr(std::process::Stdio::null())
-        .status()
-        .map(|s| s.success())
-        .unwrap_or(false)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn test_check_extra_users() {
+        let extra = |username: &str| blunux_config::ExtraUser {
+            username: username.into(),
+            password: "pw".into(),
+            sudo: false,
+            autologin: false,
+        };
+        let mut config = BlunuxConfig::minimal("nux", "blu");
+        config.install.extra_users = vec![extra("alice"), extra("bob")];
+        assert!(check_extra_users(&config).is_ok());
+
+        config.install.extra_users = vec![extra("alice"), extra("alice; reboot")];
+        let err = check_extra_users(&config).unwrap_err();
+        assert!(err.to_string().contains("bad username"), "{err}");
+
+        config.install.extra_users = vec![extra("blu")];
+        let err = check_extra_users(&config).unwrap_err();
+        assert!(err.to_string().contains("defined twice"), "{err}");
+    }
+
+    #[test]
+    fn test_check_writable_ok() {
+        let tmp = tempfile::tempdir().unwrap();
+        let tmp = tmp.path();
+        let modules = tmp.join("modules");
+        assert!(check_writable(&modules, &tmp.join("settings.conf")).is_ok());
+        assert!(modules.is_dir());
+        assert_eq!(std::fs::read_dir(&modules).unwrap().count(), 0, "probe file removed");
+    }
+
+    #[test]
+    fn test_check_writable_read_only_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let tmp = dir.path();
+        std::fs::set_permissions(tmp, std::fs::Permissions::from_mode(0o555)).unwrap();
+
+        // root (or CAP_DAC_OVERRIDE) ignores directory permissions; the
+        // test below covers failures that hold for root too
+        let privileged = std::fs::write(tmp.join("probe"), "").is_ok();
+        let result = check_writable(tmp, &tmp.join("settings.conf"));
+        std::fs::set_permissions(tmp, std::fs::Permissions::from_mode(0o755)).unwrap();
+        if privileged {
+            eprintln!("skipping read-only directory check: running as root");
+            return;
+        }
+        let err = result.unwrap_err();
+        assert!(err.to_string().contains("is not writable"), "{err}");
+        assert!(err.to_string().contains("sudo"));
+    }
+
+    #[test]
+    fn test_check_writable_fails_even_as_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let tmp = dir.path();
+
+        // A file where the output dir should go
+        std::fs::write(tmp.join("not-a-dir"), "").unwrap();
+        let err = check_writable(&tmp.join("not-a-dir/modules"), &tmp.join("settings.conf"))
+            .unwrap_err();
+        assert!(err.to_string().contains("not-a-dir/modules is not writable"), "{err}");
+
+        // A directory where settings.conf should go
+        std::fs::create_dir(tmp.join("settings.conf")).unwrap();
+        let err = check_writable(&tmp.join("modules"), &tmp.join("settings.conf")).unwrap_err();
+        assert!(err.to_string().contains("settings.conf is not writable"), "{err}");
+    }
 }
@@ -1,18 +1,19 @@
-mod hwdetect;
-
 use anyhow::Result;
-use blunux_config::BlunuxConfig;
+use blunux_config::{hwdetect, info, log, warn, BlunuxConfig, DesktopPkgs, DesktopSession};
 use std::path::Path;
 use std::process::Command;
 
 const CONFIG_PATH: &str = "/usr/share/blunux/config.toml";
 
 fn main() -> Result<()> {
-    println!("╔══════════════════════════════════════╗");
-    println!("║     blunux2 Setup Wizard v2.0        ║");
-    println!("║     Rust + C  (no Python)            ║");
-    println!("╚══════════════════════════════════════╝");
-    println!();
+    let quiet = std::env::args().any(|a| a == "--quiet" || a == "-q");
+    log::init(quiet, log::Stream::Stdout);
+
+    info!("╔══════════════════════════════════════╗");
+    info!("║     blunux2 Setup Wizard v2.0        ║");
+    info!("║     Rust + C  (no Python)            ║");
+    info!("╚══════════════════════════════════════╝");
+    info!();
 
     let no_desktop = std::env::args().any(|a| a == "--no-desktop");
 
@@ -28,52 +29,68 @@ fn main() -> Result<()> {
 
     // 4. Launch desktop session (unless --no-desktop)
     if !no_desktop {
-        step_launch_desktop();
+        step_launch_desktop(&config);
     } else {
-        println!("\n── Skipping desktop launch (--no-desktop) ──");
+        info!("\n── Skipping desktop launch (--no-desktop) ──");
     }
 
     Ok(())
 }
 
 fn step_hardware_detect() {
-    println!("── Hardware Detection ──");
+    info!("── Hardware Detection ──");
 
     let gpu = hwdetect::detect_gpu();
-    println!("  GPU: {}", gpu.name());
+    info!("  GPU: {}", gpu.name());
+
+    let cpu = hwdetect::detect_cpu_vendor();
+    match hwdetect::cpu_microcode_package(cpu) {
+        Some(ucode) => info!("  CPU: {} (microcode: {ucode})", cpu.name()),
+        None => info!("  CPU: {}", cpu.name()),
+    }
+
+    let vm = hwdetect::detect_virtualization();
+    if let Some(vm) = vm {
+        info!("  Virtual machine: {}", vm.name());
+    }
 
-    let drivers = hwdetect::gpu_driver_packages(gpu);
-    println!("  Auto-selected drivers: {}", drivers.join(", "));
+    let drivers = hwdetect::gpu_driver_packages(gpu, vm);
+    info!("  Auto-selected drivers: {}", drivers.join(", "));
 
     let audio = hwdetect::detect_audio();
-    println!("  Audio: {}", audio.name());
+    info!("  Audio: {}", audio.name());
+    let audio_pkgs = hwdetect::audio_packages(audio);
+    if !audio_pkgs.is_empty() {
+        info!("  Audio packages: {}", audio_pkgs.join(", "));
+    }
 
     let uefi = hwdetect::is_uefi();
-    println!("  Boot mode: {}", if uefi { "UEFI" } else { "BIOS" });
+    info!("  Boot mode: {}", if uefi { "UEFI" } else { "BIOS" });
 
-    let ram = hwdetect::total_ram_mb();
-    println!("  RAM: {} MB", ram);
+    let mem = hwdetect::read_meminfo();
+    info!("  RAM: {} MB ({} MB available)", mem.total_mb, mem.available_mb);
+    info!("  Swap: {} MB ({} MB free)", mem.swap_total_mb, mem.swap_free_mb);
 }
 
 fn step_load_config(path: &str) -> Result<BlunuxConfig> {
-    println!("\n── Loading Configuration ──");
+    info!("\n── Loading Configuration ──");
 
     let config = BlunuxConfig::load(Path::new(path))
         .map_err(|e| anyhow::anyhow!("Failed to load {}: {}", path, e))?;
 
-    println!("  Language: {:?}", config.locale.language);
-    println!("  Timezone: {}", config.locale.timezone);
-    println!("  Hostname: {}", config.install.hostname);
-    println!("  Username: {}", config.install.username);
-    println!("  Bootloader: {}", config.install.bootloader);
-    println!("  Swap: {}", config.disk.swap);
-    println!("  Kernel: {}", config.kernel.kernel_type);
+    info!("  Language: {:?}", config.locale.language);
+    info!("  Timezone: {}", config.locale.timezone);
+    info!("  Hostname: {}", config.install.hostname);
+    info!("  Username: {}", config.install.username);
+    info!("  Bootloader: {}", config.install.bootloader);
+    info!("  Swap: {}", config.disk.swap);
+    info!("  Kernel: {}", config.kernel.kernel_type);
 
     Ok(config)
 }
 
 fn step_apply_locale(config: &BlunuxConfig) {
-    println!("\n── Applying Locale ──");
+    info!("\n── Applying Locale ──");
 
     let lang = config
         .locale
@@ -92,41 +109,212 @@ fn step_apply_locale(config: &BlunuxConfig) {
         .args(["set-timezone", tz])
         .status();
 
-    println!("  Applied: LANG={}.UTF-8, TZ={}", lang, tz);
+    info!("  Applied: LANG={}.UTF-8, TZ={}", lang, tz);
 }
 
 fn step_apply_keyboard(config: &BlunuxConfig) {
-    println!("\n── Applying Keyboard Layout ──");
+    info!("\n── Applying Keyboard Layout ──");
 
     if let Some(layout) = config.locale.keyboard.first() {
         let _ = Command::new("localectl")
             .args(["set-x11-keymap", layout])
             .status();
-        println!("  Applied: {}", layout);
+        info!("  Applied: {}", layout);
     } else {
-        println!("  No keyboard layout specified, using default");
+        info!("  No keyboard layout specified, using default");
     }
 }
 
-fn step_launch_desktop() {
-    println!("\n── Launching Desktop ──");
-    println!("  Starting Plasma Wayland session...");
+fn step_launch_desktop(config: &BlunuxConfig) {
+    info!("\n── Launching Desktop ──");
 
-    // Replace this process with the desktop session.
-    // exec replaces the current process — this binary exits, Plasma takes over.
-    let err = exec_replace("startplasma-wayland");
+    let wayland = std::env::var("XDG_SESSION_TYPE").as_deref() != Ok("x11");
+    let has_bus = std::env::var_os("DBUS_SESSION_BUS_ADDRESS").is_some();
+    for launch in launch_commands(&config.packages.desktop, wayland) {
+        let sessions_dir = if launch.wayland {
+            "/usr/share/wayland-sessions"
+        } else {
+            "/usr/share/xsessions"
+        };
+        let entry = Path::new(sessions_dir).join(format!("{}.desktop", launch.desktop_file));
+        let entry = std::fs::read_to_string(entry).unwrap_or_default();
+        let argv = session_argv(launch.cmd, exec_line(&entry), launch.wayland && !has_bus);
+        info!("  Starting {}...", argv.join(" "));
+        // gnome-session picks Wayland or X11 from this, as logind would set it
+        std::env::set_var("XDG_SESSION_TYPE", if launch.wayland { "wayland" } else { "x11" });
 
-    // If exec returns, Wayland failed — try X11
-    eprintln!("  Wayland failed ({}), falling back to X11...", err);
-    let err = exec_replace("startplasma-x11");
+        // Replace this process with the desktop session.
+        // exec replaces the current process — this binary exits, the desktop takes over.
+        let err = exec_replace(&argv);
 
-    eprintln!("  X11 also failed ({}). No desktop session available.", err);
+        // If exec returns, this session isn't available — try the next one
+        warn!("  {} failed ({err}), trying next session...", launch.cmd);
+    }
+
+    warn!("  No desktop session available.");
     std::process::exit(1);
 }
 
-/// Replace the current process with the given command (unix exec).
-fn exec_replace(cmd: &str) -> std::io::Error {
+/// One way to start a desktop session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Launch {
+    /// Session command from [`DesktopPkgs::SESSIONS`].
+    cmd: &'static str,
+    /// `.desktop` file name of the session, without the extension.
+    desktop_file: &'static str,
+    wayland: bool,
+}
+
+/// Sessions to try, in order: the configured desktops first, then every
+/// other known session as a fallback. Within each desktop the session type
+/// matching `wayland` comes first.
+fn launch_commands(desktop: &DesktopPkgs, wayland: bool) -> Vec<Launch> {
+    let (configured, others): (Vec<&DesktopSession>, Vec<_>) = DesktopPkgs::SESSIONS
+        .iter()
+        .partition(|session| desktop.is_enabled(session.desktop));
+
+    configured
+        .into_iter()
+        .chain(others)
+        .flat_map(|session| {
+            let launches = move |cmds: &'static [&'static str], wayland: bool| {
+                cmds.iter().map(move |&cmd| Launch {
+                    cmd,
+                    desktop_file: session.desktop_file,
+                    wayland,
+                })
+            };
+            let wayland_cmds = launches(session.wayland, true);
+            let x11_cmds = launches(session.x11, false);
+            if wayland {
+                wayland_cmds.chain(x11_cmds).collect::<Vec<_>>()
+            } else {
+                x11_cmds.chain(wayland_cmds).collect()
+            }
+        })
+        .collect()
+}
+
+/// The `Exec=` command of a `.desktop` session entry.
+fn exec_line(entry: &str) -> Option<&str> {
+    entry
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Exec="))
+        .map(str::trim)
+        .filter(|exec| !exec.is_empty())
+}
+
+/// Command line that starts `cmd` the way a display manager would: the
+/// session entry's `Exec=` line when it runs `cmd` (it may add a wrapper
+/// such as `gnome-session --session=gnome`), else `cmd` alone. With
+/// `needs_bus`, the session gets its own D-Bus session bus through
+/// `dbus-run-session`, which a display manager would otherwise provide.
+fn session_argv(cmd: &str, exec: Option<&str>, needs_bus: bool) -> Vec<String> {
+    let runs_cmd = |exec: &&str| {
+        exec.split_whitespace()
+            .any(|arg| arg.rsplit('/').next() == Some(cmd))
+    };
+    let command = match exec.filter(runs_cmd) {
+        // Field codes (%U, ...) have no meaning for a session
+        Some(exec) => exec
+            .split_whitespace()
+            .filter(|arg| !arg.starts_with('%'))
+            .map(String::from)
+            .collect(),
+        None => vec![cmd.to_string()],
+    };
+    if needs_bus {
+        ["dbus-run-session", "--"]
+            .into_iter()
+            .map(String::from)
+            .chain(command)
+            .collect()
+    } else {
+        command
+    }
+}
+
+/// Replace the current process with the given command line (unix exec).
+fn exec_replace(argv: &[String]) -> std::io::Error {
     use std::os::unix::process::CommandExt;
     // This only returns if exec fails
-    Command::new(cmd).exec()
+    Command::new(&argv[0]).args(&argv[1..]).exec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commands(desktop: &DesktopPkgs, wayland: bool) -> Vec<&'static str> {
+        launch_commands(desktop, wayland).iter().map(|l| l.cmd).collect()
+    }
+
+    #[test]
+    fn test_launch_commands_kde() {
+        let desktop = DesktopPkgs {
+            kde: true,
+            ..Default::default()
+        };
+        let cmds = commands(&desktop, true);
+        assert_eq!(&cmds[..2], ["startplasma-wayland", "startplasma-x11"]);
+        assert!(cmds.contains(&"gnome-session"), "other sessions are fallbacks");
+
+        let cmds = commands(&desktop, false);
+        assert_eq!(&cmds[..2], ["startplasma-x11", "startplasma-wayland"]);
+    }
+
+    #[test]
+    fn test_launch_commands_gnome() {
+        let desktop = DesktopPkgs {
+            gnome: true,
+            ..Default::default()
+        };
+        let cmds = commands(&desktop, true);
+        assert_eq!(cmds[0], "gnome-session");
+        assert_eq!(cmds[1], "startplasma-wayland");
+        assert_eq!(cmds.len(), 5);
+    }
+
+    #[test]
+    fn test_launch_commands_carry_session_type() {
+        let launches = launch_commands(&DesktopPkgs::default(), true);
+        assert_eq!(
+            launches[1],
+            Launch { cmd: "startplasma-x11", desktop_file: "plasma", wayland: false }
+        );
+        assert!(launches.iter().any(|l| l.cmd == "gnome-session" && l.wayland));
+    }
+
+    #[test]
+    fn test_session_argv_uses_desktop_entry_and_bus() {
+        let gnome = "[Desktop Entry]\nName=GNOME\nExec=gnome-session --session=gnome\n";
+        assert_eq!(
+            session_argv("gnome-session", exec_line(gnome), true),
+            ["dbus-run-session", "--", "gnome-session", "--session=gnome"]
+        );
+        assert_eq!(
+            session_argv("gnome-session", exec_line(gnome), false),
+            ["gnome-session", "--session=gnome"]
+        );
+
+        // No entry installed: the bare command
+        assert_eq!(session_argv("startxfce4", exec_line(""), false), ["startxfce4"]);
+
+        // An entry for some other program isn't used
+        let other = "[Desktop Entry]\nExec=/usr/bin/startplasma-wayland %U\n";
+        assert_eq!(session_argv("Hyprland", exec_line(other), false), ["Hyprland"]);
+        assert_eq!(
+            session_argv("startplasma-wayland", exec_line(other), false),
+            ["/usr/bin/startplasma-wayland"]
+        );
+    }
+
+    #[test]
+    fn test_launch_commands_no_desktop_falls_back_to_all() {
+        let cmds = commands(&DesktopPkgs::default(), true);
+        assert_eq!(
+            cmds,
+            ["startplasma-wayland", "startplasma-x11", "gnome-session", "startxfce4", "Hyprland"]
+        );
+    }
 }